@@ -0,0 +1,111 @@
+use std::fmt::Write;
+
+use crate::volatile::{VolatileValue, VOLATILE_VALUES};
+
+/// A volatile value is only recommended to be filtered if it shows up in at
+/// least this many snapshots.
+const MIN_OCCURRENCES: usize = 2;
+
+/// A filter recommended for the snapshots of a project.
+pub(crate) struct Recommendation {
+    pub(crate) value: &'static VolatileValue,
+    pub(crate) snapshots: usize,
+}
+
+/// Finds the volatile values that appear in many of `snapshots`.
+pub(crate) fn recommend_filters<'a>(
+    snapshots: impl IntoIterator<Item = &'a str> + Clone,
+) -> Vec<Recommendation> {
+    VOLATILE_VALUES
+        .iter()
+        .filter_map(|value| {
+            let regex = value.regex();
+            let snapshots = snapshots
+                .clone()
+                .into_iter()
+                .filter(|contents| regex.is_match(contents))
+                .count();
+            (snapshots >= MIN_OCCURRENCES).then_some(Recommendation { value, snapshots })
+        })
+        .collect()
+}
+
+/// Renders the initial `insta.yaml` of a project.
+///
+/// Filters are settings and cannot be set in `insta.yaml`, so recommended
+/// filters are written as comments with the code to add them.
+pub(crate) fn config_template(recommendations: &[Recommendation], has_nextest: bool) -> String {
+    let mut rv = String::new();
+    writeln!(
+        rv,
+        "# Generated by `cargo insta bootstrap`.  For all options see"
+    )
+    .unwrap();
+    writeln!(rv, "# https://insta.rs/docs/settings/").unwrap();
+    writeln!(rv, "behavior:").unwrap();
+    writeln!(rv, "  # write .snap.new files for review, fail on CI").unwrap();
+    writeln!(rv, "  update: auto").unwrap();
+    writeln!(rv, "test:").unwrap();
+    if has_nextest {
+        writeln!(rv, "  # cargo-nextest is installed").unwrap();
+        writeln!(rv, "  runner: nextest").unwrap();
+    } else {
+        writeln!(rv, "  runner: auto").unwrap();
+    }
+    writeln!(rv, "  # warn about snapshots no test refers to anymore").unwrap();
+    writeln!(rv, "  unreferenced: warn").unwrap();
+
+    if !recommendations.is_empty() {
+        writeln!(rv).unwrap();
+        writeln!(
+            rv,
+            "# Values that likely change between runs were found in the snapshots."
+        )
+        .unwrap();
+        writeln!(
+            rv,
+            "# Consider filtering them, for instance in a helper used by the tests:"
+        )
+        .unwrap();
+        writeln!(rv, "#").unwrap();
+        writeln!(
+            rv,
+            "#   let mut settings = insta::Settings::clone_current();"
+        )
+        .unwrap();
+        for recommendation in recommendations {
+            writeln!(
+                rv,
+                "#   // {} in {} snapshots",
+                recommendation.value.name, recommendation.snapshots
+            )
+            .unwrap();
+            writeln!(rv, "#   {}", recommendation.value.filter_code()).unwrap();
+        }
+        writeln!(rv, "#   let _guard = settings.bind_to_scope();").unwrap();
+    }
+    rv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recommend_filters() {
+        let snapshots = [
+            "created_at: 2024-01-02T03:04:05Z",
+            "created_at: 2024-01-02 03:04:05.123+01:00\nid: 67e55044-10b1-426f-9247-bb680e5fe0c8",
+            "path: /tmp/.tmpAbC/out.txt",
+            "count: 2024",
+        ];
+        let recommendations = recommend_filters(snapshots.iter().copied());
+        assert_eq!(
+            recommendations
+                .iter()
+                .map(|x| (x.value.name, x.snapshots))
+                .collect::<Vec<_>>(),
+            [("timestamps", 2)]
+        );
+    }
+}
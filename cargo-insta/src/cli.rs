@@ -1,14 +1,17 @@
 use std::borrow::{Borrow, Cow};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::error::Error;
+use std::fmt;
 use std::path::{Path, PathBuf};
-use std::{collections::HashSet, fmt};
-use std::{env, fs};
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::{env, fs, thread};
 use std::{io, process};
 
 use console::{set_colors_enabled, style, Key, Term};
 use insta::_cargo_insta_support::{
-    get_cargo, get_pending_dir, is_ci, SnapshotPrinter, SnapshotUpdate, TestRunner, ToolConfig,
-    UnreferencedSnapshots,
+    get_cargo, get_pending_dir, is_ci, matches_pattern, predict_snapshot_file,
+    sanitize_snapshot_name, target_pending_dir, SnapshotPrinter, SnapshotUpdate, TestRunner,
+    ToolConfig, UnreferencedSnapshots,
 };
 use insta::{internals::SnapshotContents, Snapshot};
 use itertools::Itertools;
@@ -16,11 +19,22 @@ use semver::Version;
 use serde::Serialize;
 use uuid::Uuid;
 
+use crate::bootstrap;
 use crate::cargo::{find_snapshot_roots, Package};
-use crate::container::{Operation, SnapshotContainer};
+use crate::container::{
+    find_base, is_conflicted, snapshot_sort_key, Operation, SnapshotContainer, TextSnapshotKind,
+};
+use crate::debug;
+use crate::gc;
+use crate::merge;
+use crate::migrate;
+use crate::quick::{Durations, Runner, Timings};
+use crate::trace;
 use crate::utils::cargo_insta_version;
 use crate::utils::{err_msg, QuietExit};
-use crate::walk::{find_pending_snapshots, make_snapshot_walker, FindFlags};
+use crate::volatile;
+use crate::walk::{find_pending_snapshot_files, make_snapshot_walker, FindFlags};
+use crate::watch;
 
 use clap::{Args, Parser, Subcommand, ValueEnum};
 
@@ -36,6 +50,17 @@ struct Opts {
     #[arg(long, global = true, value_name = "WHEN", env = "CARGO_TERM_COLOR")]
     color: Option<ColorWhen>,
 
+    /// Explain which snapshots are found, read and written.
+    ///
+    /// Pass twice for details, such as the directories that are skipped when
+    /// searching snapshots.
+    #[arg(short = 'v', long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Write all messages of `--verbose` to a file as JSON lines.
+    #[arg(long, global = true, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Command,
 }
@@ -78,6 +103,24 @@ enum Command {
     PendingSnapshots(PendingSnapshotsCommand),
     /// Shows a specific snapshot
     Show(ShowCommand),
+    /// Snapshot macro expansions of a package
+    Expand(ExpandCommand),
+    /// Rewrite assertions from other snapshot libraries to insta
+    Migrate(MigrateCommand),
+    /// Re-run tests whenever sources or fixtures change
+    Watch(WatchCommand),
+    /// Set up snapshots and an insta.yaml for an existing test suite
+    Bootstrap(BootstrapCommand),
+    /// Check the snapshots of a workspace for problems
+    Doctor(DoctorCommand),
+    /// Delete the snapshots of tests that were removed since a git revision
+    Gc(GcCommand),
+    /// Print statistics about the snapshots of a workspace
+    Stats(StatsCommand),
+    /// Edit the metadata of many snapshot files at once
+    Meta(MetaCommand),
+    /// Print the path of the snapshot file a test asserts against
+    Locate(LocateCommand),
 }
 
 #[derive(Args, Debug, Clone)]
@@ -117,6 +160,9 @@ struct ProcessCommand {
     /// Do not print to stdout.
     #[arg(short = 'q', long)]
     quiet: bool,
+    /// Run rustfmt on the source files of accepted inline snapshots.
+    #[arg(long)]
+    and_fmt: bool,
 }
 
 #[derive(Args, Debug)]
@@ -128,7 +174,7 @@ struct ReviewCommand {
     diff_tool: Option<String>,
 }
 
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Clone)]
 #[command(rename_all = "kebab-case", next_help_heading = "Test Runner Options")]
 struct TestRunnerOptions {
     /// Test only this package's library unit tests
@@ -184,7 +230,7 @@ struct TestRunnerOptions {
     target: Option<String>,
 }
 
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Clone)]
 #[command(rename_all = "kebab-case")]
 struct TestCommand {
     /// Accept all snapshots after test.
@@ -196,12 +242,19 @@ struct TestCommand {
     /// Follow up with review.
     #[arg(long)]
     review: bool,
+    /// Run rustfmt on the source files of accepted inline snapshots.
+    #[arg(long)]
+    and_fmt: bool,
     /// Accept all new (previously unseen).
     #[arg(long, hide = true)]
     accept_unseen: bool,
     /// Do not reject pending snapshots before run (deprecated).
     #[arg(long, hide = true)]
     keep_pending: bool,
+    /// With `--accept`, accept snapshots into an overlay directory in the
+    /// target directory instead of the source tree and summarize the changes.
+    #[arg(long, requires = "accept")]
+    ephemeral: bool,
     /// Update all snapshots even if they are still matching; implies `--accept`.
     #[arg(long)]
     force_update_snapshots: bool,
@@ -214,6 +267,24 @@ struct TestCommand {
     /// Require metadata as well as snapshots' contents to match.
     #[arg(long)]
     require_full_match: bool,
+    /// Only run the fastest tests, and the tests whose snapshots or source
+    /// files changed since the previous run.
+    ///
+    /// Takes the percentage of the tests to run, which is 50 by default.
+    /// Runs of `cargo insta test` store how long the tests took in
+    /// `.insta/timings.json` in the workspace, and only tests in there are
+    /// selected.  With nextest every run records the times it reports.  With
+    /// libtest, runs with `--quick` or `--no-quiet` record the time from the
+    /// start of each test binary until a test finished, or the time libtest
+    /// reports on nightly with `-- -Zunstable-options --report-time`.
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        num_args = 0..=1,
+        default_missing_value = "50",
+        value_parser = clap::value_parser!(u8).range(1..=100)
+    )]
+    quick: Option<u8>,
     /// Prevent running all tests regardless of failure
     #[arg(long)]
     fail_fast: bool,
@@ -272,6 +343,127 @@ impl TestCommand {
     }
 }
 
+#[derive(Args, Debug)]
+#[command(rename_all = "kebab-case")]
+struct WatchCommand {
+    /// How often to check for changes, in milliseconds.
+    #[arg(long, default_value = "500", value_name = "MS")]
+    interval: u64,
+    /// Additional paths to watch, such as fixtures outside of the packages.
+    #[arg(long, value_name = "PATH")]
+    watch: Vec<PathBuf>,
+    #[command(flatten)]
+    test: TestCommand,
+}
+
+#[derive(Args, Debug)]
+#[command(rename_all = "kebab-case")]
+struct BootstrapCommand {
+    /// Do not write an insta.yaml.
+    #[arg(long)]
+    no_config: bool,
+    #[command(flatten)]
+    test: TestCommand,
+}
+
+#[derive(Args, Debug)]
+#[command(rename_all = "kebab-case")]
+struct DoctorCommand {
+    #[command(flatten)]
+    target_args: TargetArgs,
+    /// The longest path that is reported as fine, which defaults to the
+    /// `MAX_PATH` limit of Windows.
+    #[arg(long, value_name = "LEN", default_value = "259")]
+    max_path_len: usize,
+}
+
+#[derive(Args, Debug)]
+#[command(rename_all = "kebab-case")]
+struct GcCommand {
+    #[command(flatten)]
+    target_args: TargetArgs,
+    /// The git revision to compare the working tree against.
+    #[arg(long, value_name = "REV")]
+    since: String,
+    /// Delete the snapshots instead of only listing them.
+    #[arg(long)]
+    delete: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(rename_all = "kebab-case")]
+struct StatsCommand {
+    #[command(flatten)]
+    target_args: TargetArgs,
+    /// List the quarantined snapshots.
+    #[arg(long)]
+    quarantined: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(rename_all = "kebab-case")]
+struct MetaCommand {
+    #[command(subcommand)]
+    action: MetaAction,
+    #[command(flatten)]
+    target_args: TargetArgs,
+    /// Only edit the snapshot files whose path matches one of these patterns.
+    ///
+    /// In patterns `*` stands for any text.  A pattern matches the path
+    /// relative to the workspace root or the end of it after a `/`, so
+    /// `api__*` matches all snapshot files whose name starts with `api__`.
+    /// Without patterns all snapshot files are edited.
+    #[arg(long, value_name = "PATTERN", global = true)]
+    glob: Vec<String>,
+    /// Only report which snapshot files would be changed.
+    #[arg(long, global = true)]
+    dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(rename_all = "kebab-case")]
+struct LocateCommand {
+    #[command(flatten)]
+    target_args: TargetArgs,
+    /// The path of the test function, such as `my_crate::tests::test_parse`.
+    ///
+    /// The first segment is the name of the library, binary or test target.
+    /// Modules are looked up in the conventional files; modules that are not
+    /// found are assumed to be inline modules.
+    #[arg(long, value_name = "PATH")]
+    test: String,
+    /// The name of the snapshot, for assertions with an explicit name.
+    #[arg(long)]
+    name: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum MetaAction {
+    /// Set a metadata field
+    Set {
+        /// The field to set.
+        field: MetaField,
+        /// The new value of the field.
+        value: String,
+    },
+    /// Remove a metadata field
+    Strip {
+        /// The field to remove.
+        #[arg(long)]
+        field: MetaField,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+#[value(rename_all = "snake_case")]
+enum MetaField {
+    Description,
+    Expression,
+    InputFile,
+    Info,
+    Schema,
+}
+
 #[derive(Args, Debug)]
 #[command(rename_all = "kebab-case")]
 struct PendingSnapshotsCommand {
@@ -291,11 +483,81 @@ struct ShowCommand {
     path: PathBuf,
 }
 
+#[derive(ValueEnum, Copy, Clone, Debug)]
+enum ExpandBackend {
+    /// Use `cargo expand` if it is installed, otherwise `rustc`.
+    Auto,
+    /// Use `cargo expand`.
+    CargoExpand,
+    /// Use `rustc -Zunpretty=expanded`.
+    Rustc,
+}
+
+#[derive(Args, Debug)]
+#[command(rename_all = "kebab-case")]
+struct ExpandCommand {
+    #[command(flatten)]
+    target_args: TargetArgs,
+    /// Package to expand
+    #[arg(short = 'p', long)]
+    package: Option<String>,
+    /// Expand the specified binary instead of the library
+    #[arg(long, conflicts_with_all = ["test", "example"])]
+    bin: Option<String>,
+    /// Expand the specified test target instead of the library
+    #[arg(long, conflicts_with = "example")]
+    test: Option<String>,
+    /// Expand the specified example instead of the library
+    #[arg(long)]
+    example: Option<String>,
+    /// Space-separated list of features to activate
+    #[arg(short = 'F', long, value_name = "FEATURES")]
+    features: Option<String>,
+    /// The tool used to expand macros.
+    #[arg(long, default_value = "auto")]
+    backend: ExpandBackend,
+    /// Directory for the snapshots, relative to the package root.
+    #[arg(long, value_name = "PATH", default_value = "snapshots")]
+    snapshot_path: PathBuf,
+    /// Write changed expansions directly instead of leaving them for review.
+    #[arg(long, conflicts_with = "check")]
+    accept: bool,
+    /// Fail if an expansion changed instead of leaving it for review.
+    #[arg(long)]
+    check: bool,
+    /// Modules or items to expand (e.g. `parser::ast`); requires `cargo expand`.
+    items: Vec<String>,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum MigrateFrom {
+    /// `expect![[...]].assert_eq(..)` assertions from `expect-test`.
+    ExpectTest,
+}
+
+#[derive(Args, Debug)]
+#[command(rename_all = "kebab-case")]
+struct MigrateCommand {
+    #[command(flatten)]
+    target_args: TargetArgs,
+    /// Package to migrate
+    #[arg(short = 'p', long)]
+    package: Vec<String>,
+    /// The library to migrate from.
+    #[arg(long, value_name = "LIBRARY")]
+    from: MigrateFrom,
+    /// Only report what would be changed.
+    #[arg(long)]
+    dry_run: bool,
+    /// Files or directories to migrate instead of the selected packages.
+    paths: Vec<PathBuf>,
+}
+
 #[allow(clippy::too_many_arguments)]
 fn query_snapshot(
     workspace_root: &Path,
     term: &Term,
-    new: &Snapshot,
+    new: &mut Snapshot,
     old: Option<&Snapshot>,
     pkg: &Package,
     line: Option<u32>,
@@ -318,6 +580,17 @@ fn query_snapshot(
         ));
     }
 
+    // the contents of the three-way merge of a conflict, if the reference
+    // when the test ran is in the git history
+    let merge = match (new.as_text(), old.and_then(|x| x.as_text())) {
+        (Some(ours), Some(theirs)) if is_conflicted(old, new) => snapshot_file
+            .and_then(|path| find_base(path, new))
+            .map(|base| (base, ours.to_string(), theirs.to_string())),
+        _ => None,
+    };
+    let mut show_merge = false;
+    let mut notice = None;
+
     loop {
         term.clear_screen()?;
 
@@ -336,6 +609,22 @@ fn query_snapshot(
         printer.set_show_info(*show_info);
         printer.set_show_diff(*show_diff);
         printer.print();
+        if *show_diff {
+            print_volatile_hints(old, new);
+        }
+        if let (true, Some((base, ours, theirs))) = (show_merge, &merge) {
+            print_merge_view(base, ours, theirs);
+        }
+        if let Some(notice) = notice.take() {
+            println!("{notice}");
+        }
+        if is_conflicted(old, new) {
+            println!(
+                "{} the reference snapshot changed after the test ran and accepting \
+                 overwrites it; reject and re-run the test to compare against it",
+                style("conflict:").bold().red()
+            );
+        }
 
         println!();
         println!(
@@ -375,6 +664,19 @@ fn query_snapshot(
             if *show_diff { "hide" } else { "show" },
             style("toggle snapshot diff").dim()
         );
+        if merge.is_some() {
+            println!(
+                "  {} {} merge {}",
+                style("m").magenta().bold(),
+                if show_merge { "hide" } else { "show" },
+                style("toggle changes since the reference the test ran against").dim()
+            );
+            println!(
+                "  {} edit merge {}",
+                style("e").magenta().bold(),
+                style("resolve the conflict in an editor and accept the result").dim()
+            );
+        }
 
         let new_is_binary = new.contents().is_binary();
         let old_is_binary = old.map(|o| o.contents().is_binary()).unwrap_or(false);
@@ -417,6 +719,24 @@ fn query_snapshot(
                     *show_diff = !*show_diff;
                     break;
                 }
+                Key::Char('m') if merge.is_some() => {
+                    show_merge = !show_merge;
+                    break;
+                }
+                Key::Char('e') => {
+                    if let Some((base, ours, theirs)) = &merge {
+                        match edit_merge(base, ours, theirs)? {
+                            Ok(merged) => {
+                                *new = new.with_text(merged);
+                                return Ok(Operation::Accept);
+                            }
+                            Err(msg) => {
+                                notice = Some(msg);
+                                break;
+                            }
+                        }
+                    }
+                }
                 Key::Char('o') => {
                     if let Some(old) = old {
                         if let Some(path) = old.build_binary_path(snapshot_file.unwrap()) {
@@ -438,6 +758,88 @@ fn query_snapshot(
     }
 }
 
+/// Shows how the new snapshot and the current reference each changed the
+/// reference that the test ran against.
+fn print_merge_view(base: &str, ours: &str, theirs: &str) {
+    println!(
+        "{} {}",
+        style("Merge:").bold(),
+        style("reference when the test ran → new snapshot").dim()
+    );
+    print!("{}", insta::diff(base, ours, None));
+    println!(
+        "{} {}",
+        style("Merge:").bold(),
+        style("reference when the test ran → current reference").dim()
+    );
+    print!("{}", insta::diff(base, theirs, None));
+}
+
+/// Opens the three-way merge of a conflicting snapshot in `$VISUAL` or
+/// `$EDITOR`, with conflict markers where both sides changed the same lines.
+///
+/// Returns the edited text, or why it cannot be accepted.
+fn edit_merge(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+) -> Result<Result<String, String>, Box<dyn Error>> {
+    let (merged, _) = merge::merge3(base, ours, theirs);
+    let file = tempfile::Builder::new()
+        .prefix("insta-merge-")
+        .suffix(".txt")
+        .tempfile()?;
+    fs::write(file.path(), merged)?;
+
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".into());
+    let mut args = editor.split_whitespace();
+    let program = args.next().unwrap_or("vi");
+    let status = process::Command::new(program)
+        .args(args)
+        .arg(file.path())
+        .status()
+        .map_err(|err| err_msg(format!("failed to run editor `{editor}`: {err}")))?;
+    if !status.success() {
+        return Ok(Err(format!(
+            "{} the editor exited with {status}; the snapshot was not changed",
+            style("merge:").bold().red()
+        )));
+    }
+
+    let edited = fs::read_to_string(file.path())?;
+    if merge::has_conflict_markers(&edited) {
+        return Ok(Err(format!(
+            "{} the merge still has conflict markers; the snapshot was not changed",
+            style("merge:").bold().red()
+        )));
+    }
+    Ok(Ok(edited))
+}
+
+/// Points out changes that only consist of values such as timestamps that
+/// differ between runs, and how to filter them.
+fn print_volatile_hints(old: Option<&Snapshot>, new: &Snapshot) {
+    let (Some(old), Some(new)) = (old.and_then(|x| x.as_text()), new.as_text()) else {
+        return;
+    };
+    for change in volatile::find_volatile_changes(&old.to_string(), &new.to_string()) {
+        println!(
+            "{} only {} changed in {} {}, they likely differ between runs. Filter them with:",
+            style("hint:").yellow().bold(),
+            change.value.name,
+            if change.lines.len() == 1 {
+                "line"
+            } else {
+                "lines"
+            },
+            change.lines.iter().join(", "),
+        );
+        println!("  {}", style(change.value.filter_code()).cyan());
+    }
+}
+
 fn handle_color(color: Option<ColorWhen>) {
     match color {
         Some(ColorWhen::Always) => {
@@ -454,6 +856,10 @@ fn handle_color(color: Option<ColorWhen>) {
 struct LocationInfo<'a> {
     tool_config: ToolConfig,
     workspace_root: PathBuf,
+    /// Where pending snapshots are stored if not next to their destination
+    pending_dir: Option<PathBuf>,
+    /// The cargo target directory
+    target_dir: PathBuf,
     /// Packages to test
     packages: Vec<Package>,
     exts: Vec<&'a str>,
@@ -500,6 +906,11 @@ fn handle_target_args<'a>(
         .map_err(|e| format!("failed to load cargo metadata: {e}. Command details: {cmd:?}"))?;
     let workspace_root = metadata.workspace_root.as_std_path().to_path_buf();
     let tool_config = ToolConfig::from_workspace(&workspace_root)?;
+    let pending_dir = get_pending_dir().or_else(|| {
+        tool_config
+            .pending_in_target()
+            .then(|| target_pending_dir(metadata.target_directory.as_std_path(), &workspace_root))
+    });
 
     let insta_version = metadata
         .packages
@@ -538,6 +949,8 @@ fn handle_target_args<'a>(
 
     Ok(LocationInfo {
         workspace_root,
+        pending_dir,
+        target_dir: metadata.target_directory.as_std_path().to_path_buf(),
         packages,
         exts: target_args
         .extensions
@@ -586,22 +999,45 @@ fn strip_prefix_with_fallback(path: &Path, prefix: &Path) -> Option<PathBuf> {
 fn load_snapshot_containers<'a>(
     loc: &'a LocationInfo,
 ) -> Result<(Vec<(SnapshotContainer, &'a Package)>, HashSet<PathBuf>), Box<dyn Error>> {
-    let mut roots = HashSet::new();
+    let (pending_files, roots) = find_pending_files(loc);
     let mut snapshot_containers = vec![];
+    for (pending_path, target_path, kind, package) in pending_files {
+        snapshot_containers.push((
+            SnapshotContainer::load(
+                pending_path,
+                target_path,
+                kind,
+                loc.tool_config.indent_width(),
+            )?,
+            package,
+        ));
+    }
+    snapshot_containers.sort_by(|a, b| a.0.snapshot_sort_key().cmp(&b.0.snapshot_sort_key()));
+    Ok((snapshot_containers, roots))
+}
+
+/// A pending snapshot file, the file it's accepted into, the kind of its
+/// snapshots and the package it belongs to.
+type PendingFile<'a> = (PathBuf, PathBuf, TextSnapshotKind, &'a Package);
+
+/// Finds the pending snapshot files of the packages without loading them, and
+/// returns them with the roots that were searched.
+fn find_pending_files<'a>(loc: &'a LocationInfo) -> (Vec<PendingFile<'a>>, HashSet<PathBuf>) {
+    let mut roots = HashSet::new();
+    let mut pending_files = vec![];
 
     debug_assert!(!loc.packages.is_empty());
 
     // Pending snapshots are stored with the same relative directory structure as the
-    // workspace. When INSTA_PENDING_DIR is set (e.g., for Bazel's hermetic builds),
-    // pending files go to that directory. Otherwise, they live next to their targets.
+    // workspace. When INSTA_PENDING_DIR is set (e.g., for Bazel's hermetic builds)
+    // or `pending_in_target` is enabled, pending files go to that directory.
+    // Otherwise, they live next to their targets.
     //
     // The path mapping is: pending_root/relative/path → target_root/relative/path
     // When pending_root == target_root, this is a no-op (just strip ".new").
-    let pending_dir = get_pending_dir();
-
     for package in &loc.packages {
         for root in find_snapshot_roots(package) {
-            let (search_root, target_root) = if let Some(ref pending) = pending_dir {
+            let (search_root, target_root) = if let Some(ref pending) = loc.pending_dir {
                 // Hermetic mode: map package root to pending_dir.
                 // Try direct strip first (for Bazel), fall back to normalized (for Windows).
                 match strip_prefix_with_fallback(&root, &loc.workspace_root) {
@@ -616,16 +1052,15 @@ fn load_snapshot_containers<'a>(
             };
 
             roots.insert(search_root.clone());
-            for snapshot_container in
-                find_pending_snapshots(&search_root, &target_root, &loc.exts, loc.find_flags)
+            for (pending_path, target_path, kind) in
+                find_pending_snapshot_files(&search_root, &target_root, &loc.exts, loc.find_flags)
             {
-                snapshot_containers.push((snapshot_container?, package));
+                pending_files.push((pending_path, target_path, kind, package));
             }
         }
     }
 
-    snapshot_containers.sort_by(|a, b| a.0.snapshot_sort_key().cmp(&b.0.snapshot_sort_key()));
-    Ok((snapshot_containers, roots))
+    (pending_files, roots)
 }
 
 /// Formats a snapshot key for use in filters and display.
@@ -649,9 +1084,22 @@ fn review_snapshots(
     snapshot_filter: Option<&[String]>,
     loc: &LocationInfo<'_>,
     op: Option<Operation>,
+    and_fmt: bool,
 ) -> Result<(), Box<dyn Error>> {
     let term = Term::stdout();
 
+    // accepting or rejecting all snapshots doesn't depend on their order, so
+    // their files are processed by a pool of threads
+    if let (Some(op @ (Operation::Accept | Operation::Reject)), None) = (op, snapshot_filter) {
+        if let Some(summary) = apply_to_all_in_parallel(loc, op)? {
+            if and_fmt {
+                format_sources(&summary.patched_sources, quiet);
+            }
+            summary.print(quiet);
+            return Ok(());
+        }
+    }
+
     let (mut snapshot_containers, roots) = load_snapshot_containers(loc)?;
 
     let snapshot_count = snapshot_containers.iter().map(|x| x.0.len()).sum();
@@ -674,13 +1122,12 @@ fn review_snapshots(
         return Ok(());
     }
 
-    let mut accepted = vec![];
-    let mut rejected = vec![];
-    let mut skipped = vec![];
+    let mut summary = ReviewSummary::default();
     let mut num = 0;
     let mut show_info = true;
     let mut show_diff = true;
     let mut apply_to_all: Option<Operation> = None;
+    let mut confirmations = BTreeMap::new();
 
     // Non-interactive mode: if we have a filter and no TTY, just show diffs.
     // Accept doesn't need display (it just accepts), but review and reject should show what they're affecting.
@@ -696,7 +1143,7 @@ fn review_snapshots(
             if let Some(filter) = snapshot_filter {
                 let key = format_snapshot_key(&loc.workspace_root, &target_file, snapshot_ref.line);
                 if !filter.contains(&key) {
-                    skipped.push(snapshot_ref.summary());
+                    summary.skipped.push(snapshot_ref.summary());
                     continue;
                 }
             }
@@ -723,6 +1170,7 @@ fn review_snapshots(
                 printer.set_show_info(true);
                 printer.set_show_diff(true);
                 printer.print();
+                print_volatile_hints(snapshot_ref.old.as_ref(), &snapshot_ref.new);
 
                 println!();
 
@@ -734,13 +1182,14 @@ fn review_snapshots(
                     println!("To reject: cargo insta reject --snapshot '{}'", key);
                     println!();
 
-                    skipped.push(snapshot_ref.summary());
+                    summary.skipped.push(snapshot_ref.summary());
                     continue;
                 }
                 // Otherwise fall through to apply the operation (reject)
                 // Note: Only reject mode reaches here because review mode returns early above
             }
 
+            let interactive = op.is_none() && apply_to_all.is_none();
             let op = match (op, apply_to_all) {
                 (Some(op), _) => op, // Use provided op if any (from CLI)
                 (_, Some(op)) => op, // Use apply_to_all if set from previous choice
@@ -749,7 +1198,7 @@ fn review_snapshots(
                     let choice = query_snapshot(
                         &loc.workspace_root,
                         &term,
-                        &snapshot_ref.new,
+                        &mut snapshot_ref.new,
                         snapshot_ref.old.as_ref(),
                         package,
                         snapshot_ref.line,
@@ -780,49 +1229,320 @@ fn review_snapshots(
             };
 
             match op {
+                // a conflict is only overwritten if it was shown in the review
+                Operation::Accept | Operation::AcceptAll
+                    if !interactive && snapshot_ref.is_conflicted() =>
+                {
+                    summary.conflicts.push(snapshot_ref.summary());
+                }
                 Operation::Accept | Operation::AcceptAll => {
-                    snapshot_ref.op = Operation::Accept;
-                    accepted.push(snapshot_ref.summary());
+                    let path = snapshot_file.as_deref().unwrap_or(&target_file);
+                    if confirm_accept(loc, &term, path, &mut confirmations)? {
+                        snapshot_ref.op = Operation::Accept;
+                        summary.accepted.push(snapshot_ref.summary());
+                    } else {
+                        summary.skipped.push(snapshot_ref.summary());
+                    }
                 }
                 Operation::Reject | Operation::RejectAll => {
                     snapshot_ref.op = Operation::Reject;
-                    rejected.push(snapshot_ref.summary());
+                    summary.rejected.push(snapshot_ref.summary());
                 }
                 Operation::Skip | Operation::SkipAll => {
-                    skipped.push(snapshot_ref.summary());
+                    summary.skipped.push(snapshot_ref.summary());
                 }
             }
         }
-        snapshot_container.commit()?;
+        if snapshot_file.is_none()
+            && snapshot_container
+                .iter_snapshots()
+                .any(|x| matches!(x.op, Operation::Accept))
+        {
+            summary
+                .patched_sources
+                .push((target_file, package.edition.to_string()));
+        }
+        snapshot_container.commit(loc.tool_config.review_wrap_long_inline_snapshots())?;
+    }
+
+    if and_fmt {
+        format_sources(&summary.patched_sources, quiet);
     }
 
     if op.is_none() && apply_to_all.is_none() {
         term.clear_screen()?;
     }
 
-    if !quiet {
-        println!("{}", style("insta review finished").bold());
-        if !accepted.is_empty() {
-            println!("{}:", style("accepted").green());
-            for item in accepted {
-                println!("  {item}");
+    summary.print(quiet);
+    Ok(())
+}
+
+/// The snapshots of a review, by what happened to them.
+#[derive(Debug, Default)]
+struct ReviewSummary {
+    accepted: Vec<String>,
+    rejected: Vec<String>,
+    skipped: Vec<String>,
+    conflicts: Vec<String>,
+    /// The source files that accepted inline snapshots were written to, and
+    /// the editions of their packages.
+    patched_sources: Vec<(PathBuf, String)>,
+}
+
+impl ReviewSummary {
+    fn extend(&mut self, other: ReviewSummary) {
+        self.accepted.extend(other.accepted);
+        self.rejected.extend(other.rejected);
+        self.skipped.extend(other.skipped);
+        self.conflicts.extend(other.conflicts);
+        self.patched_sources.extend(other.patched_sources);
+    }
+
+    /// Prints the snapshots unless `quiet`, and the conflicts in any case.
+    fn print(&self, quiet: bool) {
+        if !quiet {
+            println!("{}", style("insta review finished").bold());
+            if !self.accepted.is_empty() {
+                println!("{}:", style("accepted").green());
+                for item in &self.accepted {
+                    println!("  {item}");
+                }
+            }
+            if !self.rejected.is_empty() {
+                println!("{}:", style("rejected").red());
+                for item in &self.rejected {
+                    println!("  {item}");
+                }
+            }
+            if !self.skipped.is_empty() {
+                println!("{}:", style("skipped").yellow());
+                for item in &self.skipped {
+                    println!("  {item}");
+                }
             }
         }
-        if !rejected.is_empty() {
-            println!("{}:", style("rejected").red());
-            for item in rejected {
-                println!("  {item}");
+        if !self.conflicts.is_empty() {
+            eprintln!(
+                "{}: the reference snapshots changed after the tests ran:",
+                style("conflicts").red().bold()
+            );
+            for item in &self.conflicts {
+                eprintln!("  {item}");
+            }
+            eprintln!(
+                "{}: re-run the tests with `cargo insta test` to compare against the new \
+                 references, or accept the snapshots one by one with `cargo insta review`",
+                style("hint").bold()
+            );
+        }
+    }
+}
+
+/// Accepts or rejects all pending snapshots with a pool of threads, each of
+/// which loads, updates and writes whole pending files.
+///
+/// Returns `None` if the snapshots have to be processed one at a time, which
+/// is the case if there are none, so that undiscovered ones are hinted at,
+/// or if accepting them has to be confirmed.
+fn apply_to_all_in_parallel(
+    loc: &LocationInfo,
+    op: Operation,
+) -> Result<Option<ReviewSummary>, Box<dyn Error>> {
+    let (mut pending_files, _) = find_pending_files(loc);
+    let needs_confirmation = matches!(op, Operation::Accept)
+        && pending_files
+            .iter()
+            .map(|(_, target_path, _, _)| {
+                target_path
+                    .strip_prefix(&loc.workspace_root)
+                    .unwrap_or(target_path)
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .any(|path| {
+                loc.tool_config
+                    .review_confirm_prompts(&path)
+                    .next()
+                    .is_some()
+            });
+    if pending_files.is_empty() || needs_confirmation {
+        return Ok(None);
+    }
+    pending_files.sort_by(|a, b| snapshot_sort_key(&a.0).cmp(&snapshot_sort_key(&b.0)));
+
+    // the pending files of a target file are processed by the same thread,
+    // one after the other, so that the edits of a file never interleave
+    let mut groups: Vec<Vec<&PendingFile>> = vec![];
+    let mut group_of_target = HashMap::new();
+    for file in &pending_files {
+        let idx = *group_of_target.entry(file.1.as_path()).or_insert_with(|| {
+            groups.push(vec![]);
+            groups.len() - 1
+        });
+        groups[idx].push(file);
+    }
+
+    let wrap_long_lines = loc.tool_config.review_wrap_long_inline_snapshots();
+    let indent_width = loc.tool_config.indent_width();
+    let next_group = AtomicUsize::new(0);
+    let threads = thread::available_parallelism()
+        .map_or(1, |x| x.get())
+        .min(groups.len());
+    let mut results = thread::scope(|scope| {
+        let workers = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut rv = vec![];
+                    loop {
+                        let idx = next_group.fetch_add(1, AtomicOrdering::Relaxed);
+                        let group = match groups.get(idx) {
+                            Some(group) => group,
+                            None => break rv,
+                        };
+                        for file in group {
+                            // errors cannot be sent between threads
+                            let result =
+                                apply_to_pending_file(file, op, wrap_long_lines, indent_width)
+                                    .map_err(|err| err.to_string());
+                            rv.push((idx, result));
+                        }
+                    }
+                })
+            })
+            .collect_vec();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect_vec()
+    });
+    // the sort is stable, so the files of a group keep their order
+    results.sort_by_key(|(idx, _)| *idx);
+
+    let mut summary = ReviewSummary::default();
+    for (_, result) in results {
+        summary.extend(result.map_err(err_msg)?);
+    }
+    Ok(Some(summary))
+}
+
+/// Accepts or rejects the snapshots of a pending file, like a review that
+/// applies `op` to all of them.
+fn apply_to_pending_file(
+    file: &PendingFile,
+    op: Operation,
+    wrap_long_lines: bool,
+    indent_width: usize,
+) -> Result<ReviewSummary, Box<dyn Error>> {
+    let (pending_path, target_path, kind, package) = file;
+    let mut container = SnapshotContainer::load(
+        pending_path.clone(),
+        target_path.clone(),
+        *kind,
+        indent_width,
+    )?;
+    let mut summary = ReviewSummary::default();
+    for snapshot_ref in container.iter_snapshots() {
+        match op {
+            // a conflict is only overwritten if it was shown in the review
+            Operation::Accept if snapshot_ref.is_conflicted() => {
+                summary.conflicts.push(snapshot_ref.summary());
+            }
+            Operation::Accept => {
+                snapshot_ref.op = Operation::Accept;
+                summary.accepted.push(snapshot_ref.summary());
+            }
+            _ => {
+                snapshot_ref.op = Operation::Reject;
+                summary.rejected.push(snapshot_ref.summary());
             }
         }
-        if !skipped.is_empty() {
-            println!("{}:", style("skipped").yellow());
-            for item in skipped {
-                println!("  {item}");
+    }
+    if container.snapshot_file().is_none() && !summary.accepted.is_empty() {
+        summary
+            .patched_sources
+            .push((target_path.clone(), package.edition.to_string()));
+    }
+    container.commit(wrap_long_lines)?;
+    Ok(summary)
+}
+
+/// Asks the questions of the `review.confirm` config that match a snapshot
+/// before it's accepted, and returns whether all of them were confirmed.
+///
+/// Every question is asked once, and its answer applies to all snapshots it
+/// matches.  Answers are read from the standard input, so without one the
+/// snapshots are not accepted.
+fn confirm_accept(
+    loc: &LocationInfo,
+    term: &Term,
+    path: &Path,
+    confirmations: &mut BTreeMap<String, bool>,
+) -> Result<bool, Box<dyn Error>> {
+    let path = path
+        .strip_prefix(&loc.workspace_root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/");
+    for prompt in loc.tool_config.review_confirm_prompts(&path) {
+        let confirmed = match confirmations.get(prompt) {
+            Some(&confirmed) => confirmed,
+            None => {
+                term.write_str(&format!(
+                    "{} {} [y/N] ",
+                    style("confirm:").bold().yellow(),
+                    prompt
+                ))?;
+                let mut answer = String::new();
+                io::stdin().read_line(&mut answer)?;
+                if !term.is_term() {
+                    term.write_line("")?;
+                }
+                let confirmed = matches!(answer.trim(), "y" | "Y" | "yes");
+                confirmations.insert(prompt.to_string(), confirmed);
+                confirmed
             }
+        };
+        if !confirmed {
+            return Ok(false);
         }
     }
+    Ok(true)
+}
 
-    Ok(())
+/// Runs rustfmt on the source files that accepted inline snapshots were
+/// written to.
+///
+/// A failure to format is reported but does not fail the command; the
+/// snapshots are already accepted at this point.
+fn format_sources(sources: &[(PathBuf, String)], quiet: bool) {
+    let rustfmt = env::var_os("RUSTFMT").unwrap_or_else(|| "rustfmt".into());
+    for (edition, files) in &sources.iter().group_by(|(_, edition)| edition) {
+        let files = files.map(|(file, _)| file).collect_vec();
+        let result = process::Command::new(&rustfmt)
+            .arg("--edition")
+            .arg(edition)
+            .args(&files)
+            .status();
+        match result {
+            Ok(status) if status.success() => {
+                if !quiet {
+                    for file in files {
+                        println!("{} {}", style("formatted").green(), file.display());
+                    }
+                }
+            }
+            Ok(status) => eprintln!(
+                "{}: rustfmt exited with {}, accepted snapshots were left unformatted",
+                style("warning").bold().yellow(),
+                status
+            ),
+            Err(err) => eprintln!(
+                "{}: failed to run rustfmt, accepted snapshots were left unformatted: {}",
+                style("warning").bold().yellow(),
+                err
+            ),
+        }
+    }
 }
 
 /// Check if any of the packages have doctests
@@ -848,7 +1568,7 @@ fn has_doctests(packages: &[Package]) -> bool {
 
 /// Run the tests
 fn test_run(mut cmd: TestCommand, color: ColorWhen) -> Result<(), Box<dyn Error>> {
-    let loc = handle_target_args(&cmd.target_args, &cmd.test_runner_options.package)?;
+    let mut loc = handle_target_args(&cmd.target_args, &cmd.test_runner_options.package)?;
 
     if cmd.accept_unseen {
         eprintln!(
@@ -882,7 +1602,16 @@ fn test_run(mut cmd: TestCommand, color: ColorWhen) -> Result<(), Box<dyn Error>
         SnapshotUpdate::Force => {
             cmd.force_update_snapshots = true;
         }
+        SnapshotUpdate::Forbid => {
+            if cmd.accept || cmd.accept_unseen || cmd.review || cmd.force_update_snapshots {
+                return Err(err_msg(
+                    "INSTA_UPDATE=forbid does not allow accepting, reviewing or updating snapshots",
+                ));
+            }
+            cmd.check = true;
+        }
     }
+    let forbid = loc.tool_config.snapshot_update() == SnapshotUpdate::Forbid;
     // `--force-update-snapshots` implies `--accept`
     if cmd.force_update_snapshots {
         cmd.accept = true;
@@ -903,10 +1632,10 @@ fn test_run(mut cmd: TestCommand, color: ColorWhen) -> Result<(), Box<dyn Error>
 
     // the tool config can also indicate that --accept-unseen should be picked
     // automatically unless instructed otherwise.
-    if loc.tool_config.auto_accept_unseen() && !cmd.accept && !cmd.review {
+    if loc.tool_config.auto_accept_unseen() && !cmd.accept && !cmd.review && !forbid {
         cmd.accept_unseen = true;
     }
-    if loc.tool_config.auto_review() && !cmd.review && !cmd.accept {
+    if loc.tool_config.auto_review() && !cmd.review && !cmd.accept && !forbid {
         cmd.review = true;
     }
 
@@ -920,6 +1649,36 @@ fn test_run(mut cmd: TestCommand, color: ColorWhen) -> Result<(), Box<dyn Error>
     cmd.unreferenced = cmd
         .unreferenced
         .or_else(|| Some(loc.tool_config.test_unreferenced()));
+    if forbid {
+        match cmd.unreferenced {
+            Some(UnreferencedSnapshots::Delete) => {
+                return Err(err_msg(
+                    "INSTA_UPDATE=forbid does not allow deleting unreferenced snapshots",
+                ))
+            }
+            Some(UnreferencedSnapshots::Auto) => {
+                cmd.unreferenced = Some(UnreferencedSnapshots::Reject);
+            }
+            _ => {}
+        }
+    }
+
+    // with `--ephemeral` the pending snapshots are written to and accepted into
+    // an overlay directory, and nothing in the source tree may be deleted
+    let ephemeral = cmd.ephemeral.then(|| ephemeral_dir(&loc));
+    if let Some(ref overlay) = ephemeral {
+        match fs::remove_dir_all(overlay) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+        loc.pending_dir = Some(overlay.clone());
+        if matches!(
+            cmd.unreferenced,
+            Some(UnreferencedSnapshots::Auto | UnreferencedSnapshots::Delete)
+        ) {
+            cmd.unreferenced = Some(UnreferencedSnapshots::Warn);
+        }
+    }
 
     // Prioritize the command line over the tool config
     let test_runner = match cmd.test_runner {
@@ -932,21 +1691,93 @@ fn test_run(mut cmd: TestCommand, color: ColorWhen) -> Result<(), Box<dyn Error>
             .unwrap_or(loc.tool_config.test_runner_fallback()),
     );
 
-    let (mut proc, snapshot_ref_file, prevents_doc_run) =
-        prepare_test_runner(&cmd, test_runner, color, &[], None, &loc)?;
+    // the durations of the tests are taken from the output of the test
+    // runner, which only names the tests without the quiet flag of libtest
+    let runner = match test_runner {
+        TestRunner::Nextest => Runner::Nextest,
+        _ => Runner::CargoTest,
+    };
+    let timed = runner == Runner::Nextest
+        || cmd.quick.is_some()
+        || cmd.no_quiet
+        || cmd.cargo_options.iter().any(|x| x == "--report-time");
+    let timings_path = timings_path(&loc);
+
+    let mut runner_args = vec![];
+    if let Some(percent) = cmd.quick {
+        match Timings::load(&timings_path, runner) {
+            Some((timings, recorded)) => {
+                let roots = loc
+                    .packages
+                    .iter()
+                    .filter_map(|package| package.manifest_path.parent())
+                    .map(|root| root.as_std_path())
+                    .collect_vec();
+                let changed_sources = timings.changed_sources(
+                    &loc.workspace_root,
+                    &roots,
+                    &loc.exts,
+                    loc.find_flags,
+                    recorded,
+                );
+                let tests = timings.select(percent, &changed_sources);
+                eprintln!(
+                    "{}: running {} of {} timed tests",
+                    style("info").bold(),
+                    tests.len(),
+                    timings.len()
+                );
+                let args = timings.filter_args(&tests);
+                match runner {
+                    Runner::Nextest => runner_args = args,
+                    Runner::CargoTest => cmd.cargo_options.extend(args),
+                }
+                // the snapshots of the tests that don't run are still in use
+                cmd.unreferenced = Some(UnreferencedSnapshots::Ignore);
+            }
+            None => eprintln!(
+                "{}: no timings of previous runs with this test runner, running all tests",
+                style("warning").bold().yellow()
+            ),
+        }
+    }
+
+    // nextest's status lines are captured, so its colors are chosen here
+    let runner_color = match (runner, color) {
+        (Runner::Nextest, ColorWhen::Auto) if console::colors_enabled_stderr() => ColorWhen::Always,
+        (Runner::Nextest, ColorWhen::Auto) => ColorWhen::Never,
+        _ => color,
+    };
+    let (mut proc, snapshot_ref_file, prevents_doc_run) = prepare_test_runner(
+        &cmd,
+        test_runner,
+        runner_color,
+        &runner_args.iter().map(String::as_str).collect_vec(),
+        None,
+        &loc,
+    )?;
 
     // Set up warnings file for collecting warnings from test processes.
     // This is necessary because test runners like nextest suppress stdout/stderr
     // from passing tests by default.
     let warnings_file = env::temp_dir().join(format!("insta-warnings-{}", Uuid::new_v4()));
     proc.env("INSTA_WARNINGS_FILE", &warnings_file);
+    let assertions_file = env::temp_dir().join(format!("insta-assertions-{}", Uuid::new_v4()));
+    proc.env("INSTA_ASSERTIONS_FILE", &assertions_file);
+    let failures_file = env::temp_dir().join(format!("insta-failures-{}", Uuid::new_v4()));
+    proc.env("INSTA_FAILURES_FILE", &failures_file);
 
     if let Some(workspace_root) = &cmd.target_args.workspace_root {
         proc.current_dir(workspace_root);
     }
 
     // Run the tests
-    let status = proc.status()?;
+    debug!("cli", "running {proc:?}");
+    let mut durations = timed.then(|| Durations::new(runner));
+    let status = match durations {
+        Some(ref mut durations) => durations.collect(&mut proc)?,
+        None => proc.status()?,
+    };
     let mut success = status.success();
 
     // nextest currently cannot run doctests, run them with regular tests. We'd
@@ -978,8 +1809,11 @@ fn test_run(mut cmd: TestCommand, color: ColorWhen) -> Result<(), Box<dyn Error>
             snapshot_ref_file.as_deref(),
             &loc,
         )?;
-        // Use the same warnings file for doctests
+        // Use the same warnings and assertions files for doctests
         proc.env("INSTA_WARNINGS_FILE", &warnings_file);
+        proc.env("INSTA_ASSERTIONS_FILE", &assertions_file);
+        proc.env("INSTA_FAILURES_FILE", &failures_file);
+        debug!("cli", "running {proc:?}");
         success = success && proc.status()?.success();
     }
 
@@ -996,6 +1830,30 @@ fn test_run(mut cmd: TestCommand, color: ColorWhen) -> Result<(), Box<dyn Error>
         fs::remove_file(&warnings_file).ok();
     }
 
+    let assertions = fs::read_to_string(&assertions_file).ok();
+    if let Some(ref contents) = assertions {
+        print_assertion_counts(contents);
+        fs::remove_file(&assertions_file).ok();
+    }
+    if let Some(ref durations) = durations {
+        let assertions = assertions.as_deref().unwrap_or_default();
+        if let Err(err) = Timings::record(&timings_path, durations, assertions) {
+            eprintln!(
+                "{}: failed to store the timings of the tests: {err}",
+                style("warning").bold().yellow()
+            );
+        }
+    }
+
+    // assertions of tests that are expected to panic fail too, so they are
+    // only listed if the run failed
+    if let Ok(contents) = fs::read_to_string(&failures_file) {
+        if !success {
+            print_failure_summary(&contents);
+        }
+        fs::remove_file(&failures_file).ok();
+    }
+
     if !success && cmd.review {
         eprintln!(
             "{} non snapshot tests failed, skipping review",
@@ -1017,7 +1875,9 @@ fn test_run(mut cmd: TestCommand, color: ColorWhen) -> Result<(), Box<dyn Error>
         }
     }
 
-    if cmd.review || cmd.accept {
+    if let Some(ref overlay) = ephemeral {
+        accept_ephemeral(&loc, overlay)?;
+    } else if cmd.review || cmd.accept {
         review_snapshots(
             false,
             None,
@@ -1027,6 +1887,7 @@ fn test_run(mut cmd: TestCommand, color: ColorWhen) -> Result<(), Box<dyn Error>
             } else {
                 None
             },
+            cmd.and_fmt,
         )?
     } else {
         let (snapshot_containers, roots) = load_snapshot_containers(&loc)?;
@@ -1056,17 +1917,182 @@ fn test_run(mut cmd: TestCommand, color: ColorWhen) -> Result<(), Box<dyn Error>
     }
 }
 
-/// Quick check if a file is likely an insta snapshot by checking for the
-/// `---\nsource:` prefix. This distinguishes insta snapshots from other
-/// snapshot formats (e.g., vitest, jest) that may use the same `.snap` extension.
+/// Prints a table of the failed snapshot assertions with their locations,
+/// given the failures file with one line per failed assertion.
 ///
-/// Only reads 16 bytes for efficiency (longest pattern is 13 bytes for CRLF).
-fn is_likely_insta_snapshot(path: &Path) -> bool {
-    use io::Read;
-    let mut buf = [0u8; 16];
-    let Ok(mut file) = fs::File::open(path) else {
-        return false;
-    };
+/// Nothing is printed for a single failure, whose output is easy to find.
+fn print_failure_summary(contents: &str) {
+    let failures = contents
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .collect_vec();
+    if failures.len() < 2 {
+        return;
+    }
+    let name_width = failures
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    eprintln!();
+    eprintln!(
+        "{} {}",
+        style(failures.len()).bold().red(),
+        style("snapshot assertions failed:").bold()
+    );
+    for (name, location) in failures {
+        eprintln!(
+            "  {}  {}",
+            style(format!("{name:<name_width$}")).yellow(),
+            style(location).cyan()
+        );
+    }
+}
+
+/// Prints how many snapshot assertions the tests made, given the assertions
+/// file with one line per assertion that starts with its test.
+fn print_assertion_counts(contents: &str) {
+    let mut counts = BTreeMap::<&str, usize>::new();
+    for test in contents
+        .lines()
+        .filter_map(|line| line.split('\t').next())
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+    {
+        *counts.entry(test).or_default() += 1;
+    }
+    let total = counts.values().sum::<usize>();
+    if let Some((test, count)) = counts.iter().max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0))) {
+        println!(
+            "{}: {} snapshot assertion{} in {} test{}, most in {} ({})",
+            style("info").bold(),
+            total,
+            if total != 1 { "s" } else { "" },
+            counts.len(),
+            if counts.len() != 1 { "s" } else { "" },
+            style(test).cyan(),
+            count
+        );
+    }
+}
+
+/// Returns the overlay directory that `--ephemeral` accepts snapshots into.
+///
+/// Like the pending directory of `pending_in_target` it's named after the
+/// workspace since target directories can be shared between workspaces.
+fn ephemeral_dir(loc: &LocationInfo) -> PathBuf {
+    let mut rv = loc.target_dir.join("insta-ephemeral");
+    if let Some(name) = loc.workspace_root.file_name() {
+        rv.push(name);
+    }
+    rv
+}
+
+/// Returns the file the test timings of `cargo insta test --quick` are stored
+/// in.
+fn timings_path(loc: &LocationInfo) -> PathBuf {
+    loc.workspace_root.join(".insta").join("timings.json")
+}
+
+/// Accepts the pending snapshots of an `--ephemeral` run into the overlay
+/// directory and prints a summary of the changes.
+///
+/// The pending snapshots are already in the overlay directory.  File
+/// snapshots are accepted next to them, and inline snapshots into copies of
+/// their source files, so the source tree is left untouched.
+fn accept_ephemeral(loc: &LocationInfo, overlay: &Path) -> Result<(), Box<dyn Error>> {
+    let (snapshot_containers, _) = load_snapshot_containers(loc)?;
+    let mut rows = vec![];
+    let (mut changed, mut added, mut insertions, mut deletions) = (0, 0, 0, 0);
+
+    for (mut snapshot_container, _package) in snapshot_containers {
+        let target_file = snapshot_container.target_file().to_path_buf();
+        let overlay_file = match strip_prefix_with_fallback(&target_file, &loc.workspace_root) {
+            Some(relative) => overlay.join(relative),
+            None => continue,
+        };
+        fs::create_dir_all(overlay_file.parent().unwrap())?;
+        if target_file.is_file() {
+            fs::copy(&target_file, &overlay_file)?;
+        }
+
+        for snapshot_ref in snapshot_container.iter_snapshots() {
+            let key = format_snapshot_key(&loc.workspace_root, &target_file, snapshot_ref.line);
+            let old = match snapshot_ref.old {
+                Some(ref old) => old,
+                None => {
+                    added += 1;
+                    rows.push((key, "new".to_string()));
+                    snapshot_ref.op = Operation::Accept;
+                    continue;
+                }
+            };
+            changed += 1;
+            if let Some(binary_file) = old.build_binary_path(&target_file) {
+                if binary_file.is_file() {
+                    fs::copy(&binary_file, old.build_binary_path(&overlay_file).unwrap())?;
+                }
+            }
+            match (old.contents(), snapshot_ref.new.contents()) {
+                (SnapshotContents::Text(old), SnapshotContents::Text(new)) => {
+                    let (old, new) = (old.to_string(), new.to_string());
+                    let diff = similar::TextDiff::from_lines(&old, &new);
+                    let (mut plus, mut minus) = (0, 0);
+                    for change in diff.iter_all_changes() {
+                        match change.tag() {
+                            similar::ChangeTag::Insert => plus += 1,
+                            similar::ChangeTag::Delete => minus += 1,
+                            similar::ChangeTag::Equal => {}
+                        }
+                    }
+                    insertions += plus;
+                    deletions += minus;
+                    rows.push((key, format!("+{plus} -{minus}")));
+                }
+                _ => rows.push((key, "binary".to_string())),
+            }
+            snapshot_ref.op = Operation::Accept;
+        }
+
+        snapshot_container.set_target_file(overlay_file)?;
+        snapshot_container.commit(false)?;
+    }
+
+    if rows.is_empty() {
+        println!("{}: no snapshots changed", style("info").bold());
+        return Ok(());
+    }
+    println!(
+        "{}: accepted into {}, the source tree is unchanged:",
+        style("info").bold(),
+        overlay.display()
+    );
+    let width = rows.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+    for (key, summary) in &rows {
+        println!("  {key:width$}  {summary}");
+    }
+    println!(
+        "{}: {} changed, {} new, {} insertions(+), {} deletions(-)",
+        style("info").bold(),
+        style(changed).yellow(),
+        style(added).green(),
+        insertions,
+        deletions
+    );
+    Ok(())
+}
+
+/// Quick check if a file is likely an insta snapshot by checking for the
+/// `---\nsource:` prefix. This distinguishes insta snapshots from other
+/// snapshot formats (e.g., vitest, jest) that may use the same `.snap` extension.
+///
+/// Only reads 16 bytes for efficiency (longest pattern is 13 bytes for CRLF).
+fn is_likely_insta_snapshot(path: &Path) -> bool {
+    use io::Read;
+    let mut buf = [0u8; 16];
+    let Ok(mut file) = fs::File::open(path) else {
+        return false;
+    };
     let Ok(n) = file.read(&mut buf) else {
         return false;
     };
@@ -1300,8 +2326,8 @@ fn prepare_test_runner<'snapshot_ref>(
         proc.env("INSTA_FORCE_PASS", "1");
     }
 
-    // Pass INSTA_PENDING_DIR to the test subprocess if set
-    if let Some(pending_dir) = get_pending_dir() {
+    // Pass the pending directory to the test subprocess if set
+    if let Some(ref pending_dir) = loc.pending_dir {
         proc.env("INSTA_PENDING_DIR", pending_dir);
     }
 
@@ -1311,6 +2337,12 @@ fn prepare_test_runner<'snapshot_ref>(
         // older versions
         if loc.insta_version >= Version::new(1,41,0) {
             match (cmd.check, cmd.accept_unseen, cmd.force_update_snapshots) {
+                (true, false, false)
+                    if loc.tool_config.snapshot_update() == SnapshotUpdate::Forbid
+                        && loc.insta_version >= Version::new(1, 46, 3) =>
+                {
+                    "forbid"
+                }
                 (true, false, false) => "no",
                 (false, true, false) => "unseen",
                 (false, false, false) => "new",
@@ -1380,7 +2412,13 @@ fn prepare_test_runner<'snapshot_ref>(
     proc.args(extra_args);
     // Items after this are passed to the test runner
     proc.arg("--");
-    if !cmd.no_quiet && matches!(test_runner, TestRunner::CargoTest) {
+    // the quiet output of libtest leaves out the names of the tests, which
+    // `--quick` and `--report-time` need to time them
+    if !cmd.no_quiet
+        && matches!(test_runner, TestRunner::CargoTest)
+        && cmd.quick.is_none()
+        && !cmd.cargo_options.iter().any(|x| x == "--report-time")
+    {
         proc.arg("-q");
     }
     proc.args(&cmd.cargo_options);
@@ -1421,6 +2459,849 @@ fn show_cmd(cmd: ShowCommand) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+fn migrate_cmd(cmd: MigrateCommand) -> Result<(), Box<dyn Error>> {
+    let roots = if cmd.paths.is_empty() {
+        let loc = handle_target_args(&cmd.target_args, &cmd.package)?;
+        loc.packages
+            .iter()
+            .filter_map(|package| package.manifest_path.parent())
+            .map(|root| root.as_std_path().to_path_buf())
+            .collect()
+    } else {
+        cmd.paths.clone()
+    };
+
+    let mut migrated = 0;
+    let mut skipped = 0;
+    for root in roots {
+        let mut walker = ignore::WalkBuilder::new(&root);
+        walker.filter_entry(|entry| entry.file_name() != "target");
+        for entry in walker.build() {
+            let entry = entry?;
+            let path = entry.path();
+            if !entry.file_type().map_or(false, |x| x.is_file())
+                || path.extension().map_or(true, |x| x != "rs")
+            {
+                continue;
+            }
+            let source = fs::read_to_string(path)?;
+            if !source.contains("expect") {
+                continue;
+            }
+            let migration = match cmd.from {
+                MigrateFrom::ExpectTest => migrate::migrate_expect_test(&source),
+            }
+            .map_err(|err| format!("failed to parse {}: {}", path.display(), err))?;
+
+            for line in &migration.skipped {
+                eprintln!(
+                    "{}: cannot migrate assertion automatically at {}:{}",
+                    style("warning").bold().yellow(),
+                    path.display(),
+                    line
+                );
+            }
+            skipped += migration.skipped.len();
+            if let Some(new_source) = migration.source {
+                if !cmd.dry_run {
+                    fs::write(path, new_source)?;
+                }
+                println!(
+                    "{} {} assertion{} in {}",
+                    if cmd.dry_run {
+                        "would migrate"
+                    } else {
+                        "migrated"
+                    },
+                    style(migration.migrated).bold(),
+                    if migration.migrated == 1 { "" } else { "s" },
+                    path.display()
+                );
+                migrated += migration.migrated;
+            }
+        }
+    }
+
+    if migrated == 0 && skipped == 0 {
+        println!("{}: no assertions to migrate", style("done").bold());
+    } else if !cmd.dry_run {
+        println!(
+            "{}: migrated {} assertion{}, {} left for manual migration",
+            style("done").bold(),
+            migrated,
+            if migrated == 1 { "" } else { "s" },
+            skipped
+        );
+        if migrated > 0 {
+            println!(
+                "{}",
+                style("Add insta to [dev-dependencies] and run `cargo insta test` to verify.")
+                    .dim()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn watch_cmd(cmd: WatchCommand, color: ColorWhen) -> Result<(), Box<dyn Error>> {
+    let loc = handle_target_args(&cmd.test.target_args, &cmd.test.test_runner_options.package)?;
+    let mut roots = loc
+        .packages
+        .iter()
+        .filter_map(|package| package.manifest_path.parent())
+        .map(|root| root.as_std_path().to_path_buf())
+        .collect_vec();
+    roots.extend(cmd.watch.iter().cloned());
+    let mut watcher = watch::Watcher::new(roots);
+    let interval = std::time::Duration::from_millis(cmd.interval);
+
+    let mut test_cmd = cmd.test.clone();
+    loop {
+        match test_run(test_cmd, color) {
+            Ok(()) => {}
+            Err(err) if err.downcast_ref::<QuietExit>().is_some() => {}
+            Err(err) => eprintln!("{} {}", style("error:").red().bold(), err),
+        }
+
+        // the test runner captures the output of passing tests, so show the
+        // diffs of the pending snapshots here
+        let (mut snapshot_containers, _) = load_snapshot_containers(&loc)?;
+        for (snapshot_container, _) in snapshot_containers.iter_mut() {
+            let snapshot_file = snapshot_container.snapshot_file().map(|x| x.to_path_buf());
+            for snapshot_ref in snapshot_container.iter_snapshots() {
+                let mut printer = SnapshotPrinter::new(
+                    &loc.workspace_root,
+                    snapshot_ref.old.as_ref(),
+                    &snapshot_ref.new,
+                );
+                printer.set_snapshot_file(snapshot_file.as_deref());
+                printer.set_line(snapshot_ref.line);
+                printer.set_show_info(true);
+                printer.set_show_diff(true);
+                printer.print();
+            }
+        }
+        eprintln!(
+            "{}: watching for changes, press ctrl-c to stop",
+            style("info").bold()
+        );
+
+        let changed = watcher.wait(interval);
+        test_cmd = cmd.test.clone();
+        if let Some(packages) = watch::affected_packages(&loc.packages, &changed) {
+            test_cmd.test_runner_options.package = packages;
+        }
+        eprintln!(
+            "{}: {} changed, re-running {}",
+            style("info").bold(),
+            match &changed[..] {
+                [path] => path.display().to_string(),
+                _ => format!("{} files", changed.len()),
+            },
+            if test_cmd.test_runner_options.package.is_empty() {
+                "all tests".to_string()
+            } else {
+                test_cmd.test_runner_options.package.join(", ")
+            }
+        );
+    }
+}
+
+fn expand_cmd(cmd: ExpandCommand) -> Result<(), Box<dyn Error>> {
+    let packages = cmd.package.iter().cloned().collect_vec();
+    let loc = handle_target_args(&cmd.target_args, &packages)?;
+    let package = match &loc.packages[..] {
+        [package] => package,
+        [] => return Err(err_msg("no package found to expand")),
+        _ => {
+            return Err(err_msg(
+                "multiple packages selected; pick one with `--package`",
+            ))
+        }
+    };
+    let manifest_path = package.manifest_path.as_std_path();
+    let package_root = manifest_path.parent().unwrap();
+
+    let (kind, name) = if let Some(ref bin) = cmd.bin {
+        ("bin", Some(bin.as_str()))
+    } else if let Some(ref test) = cmd.test {
+        ("test", Some(test.as_str()))
+    } else if let Some(ref example) = cmd.example {
+        ("example", Some(example.as_str()))
+    } else {
+        ("lib", None)
+    };
+    let mut target_args = vec![format!("--{kind}")];
+    target_args.extend(name.map(str::to_string));
+    if let Some(ref features) = cmd.features {
+        target_args.push("--features".into());
+        target_args.push(features.clone());
+    }
+    let source = package
+        .targets
+        .iter()
+        .find(|t| {
+            t.kind.iter().any(|k| match kind {
+                "lib" => k.ends_with("lib") || k == "proc-macro",
+                kind => k == kind,
+            }) && name.map_or(true, |name| t.name == name)
+        })
+        .and_then(|t| strip_prefix_with_fallback(t.src_path.as_std_path(), &loc.workspace_root))
+        .map(|x| x.to_string_lossy().replace('\\', "/"));
+
+    let use_rustc = match cmd.backend {
+        ExpandBackend::Rustc => true,
+        ExpandBackend::CargoExpand => false,
+        ExpandBackend::Auto => !process::Command::new(get_cargo())
+            .args(["expand", "--version"])
+            .stdout(process::Stdio::null())
+            .stderr(process::Stdio::null())
+            .status()
+            .map_or(false, |status| status.success()),
+    };
+    if use_rustc && !cmd.items.is_empty() {
+        return Err(err_msg(
+            "expanding individual items requires cargo-expand (`cargo install cargo-expand`)",
+        ));
+    }
+
+    let items = if cmd.items.is_empty() {
+        vec![None]
+    } else {
+        cmd.items.iter().map(|x| Some(x.as_str())).collect()
+    };
+    let target_name = match name {
+        Some(name) => format!("{kind}-{name}"),
+        None => kind.to_string(),
+    };
+    let mut changed = vec![];
+
+    for item in items {
+        let mut expression = if use_rustc {
+            format!(
+                "cargo rustc {} -- -Zunpretty=expanded",
+                target_args.join(" ")
+            )
+        } else {
+            format!("cargo expand {}", target_args.join(" "))
+        };
+        let snapshot_name = match item {
+            Some(item) => {
+                expression.push(' ');
+                expression.push_str(item);
+                format!("{target_name}__{}", item.replace("::", "__"))
+            }
+            None => target_name.clone(),
+        };
+        let contents = run_expand(manifest_path, &target_args, item, use_rustc)?;
+        let snapshot_file = package_root.join(&cmd.snapshot_path).join(format!(
+            "expand__{}.snap",
+            sanitize_snapshot_name(&snapshot_name, loc.tool_config.sanitize_names())
+        ));
+        let pending_file = snapshot_file.with_extension("snap.new");
+        let new = Snapshot::from_text(
+            "expand".into(),
+            snapshot_name,
+            source.clone(),
+            Some(expression),
+            contents,
+        );
+        let old = if snapshot_file.is_file() {
+            Some(Snapshot::from_file(&snapshot_file)?)
+        } else {
+            None
+        };
+
+        if old.map_or(false, |old| old.contents() == new.contents()) {
+            fs::remove_file(&pending_file).ok();
+            continue;
+        }
+        if cmd.accept {
+            new.save(&snapshot_file)?;
+            fs::remove_file(&pending_file).ok();
+            eprintln!(
+                "{}: updated {}",
+                style("info").bold(),
+                snapshot_file.display()
+            );
+        } else {
+            if !cmd.check {
+                new.save(&pending_file)?;
+            }
+            changed.push(snapshot_file);
+        }
+    }
+
+    if changed.is_empty() {
+        println!("{}: expansions are up to date", style("info").bold());
+        return Ok(());
+    }
+    eprintln!(
+        "{}: {} expansion{} changed:",
+        style("info").bold(),
+        style(changed.len()).yellow(),
+        if changed.len() != 1 { "s" } else { "" }
+    );
+    for path in &changed {
+        eprintln!("  {}", path.display());
+    }
+    if !cmd.check {
+        eprintln!("use `cargo insta review` to review snapshots");
+    }
+    Err(QuietExit(1).into())
+}
+
+/// Runs the macro expansion and returns the expanded source.
+fn run_expand(
+    manifest_path: &Path,
+    target_args: &[String],
+    item: Option<&str>,
+    use_rustc: bool,
+) -> Result<String, Box<dyn Error>> {
+    let mut proc = process::Command::new(get_cargo());
+    proc.arg(if use_rustc { "rustc" } else { "expand" });
+    proc.arg("--manifest-path");
+    proc.arg(manifest_path);
+    proc.args(target_args);
+
+    // rustc writes to a fresh file on every run: a changed `-o` argument makes
+    // cargo rebuild the crate rather than considering it fresh and printing
+    // nothing.
+    let out_file = tempfile::Builder::new()
+        .prefix("insta-expand-")
+        .suffix(".rs")
+        .tempfile()?;
+    if use_rustc {
+        proc.args(["--profile=check", "--", "-Zunpretty=expanded", "-o"]);
+        proc.arg(out_file.path());
+        // `-Z` flags are only accepted on nightly otherwise
+        proc.env("RUSTC_BOOTSTRAP", "1");
+    } else {
+        proc.args(["--color", "never"]);
+        proc.args(item);
+    }
+
+    let output = proc.stderr(process::Stdio::inherit()).output()?;
+    if !output.status.success() {
+        return Err(err_msg(format!(
+            "failed to expand macros ({})",
+            output.status
+        )));
+    }
+    Ok(if use_rustc {
+        fs::read_to_string(out_file.path())?
+    } else {
+        String::from_utf8(output.stdout)?
+    })
+}
+
+fn bootstrap_cmd(cmd: BootstrapCommand, color: ColorWhen) -> Result<(), Box<dyn Error>> {
+    let loc = handle_target_args(&cmd.test.target_args, &cmd.test.test_runner_options.package)?;
+
+    // run the suite without failing so that every assertion writes its
+    // pending snapshot
+    let mut test_cmd = cmd.test.clone();
+    test_cmd.accept = false;
+    test_cmd.review = false;
+    test_cmd.check = false;
+    match test_run(test_cmd, color) {
+        Ok(()) => {}
+        Err(err) if err.downcast_ref::<QuietExit>().is_some() => {}
+        Err(err) => return Err(err),
+    }
+
+    // snapshots without a previous value are accepted, changed ones are left
+    // for review
+    let (mut snapshot_containers, _) = load_snapshot_containers(&loc)?;
+    let mut created = BTreeMap::<String, Vec<String>>::new();
+    let mut changed = BTreeMap::<String, Vec<String>>::new();
+    let mut contents = vec![];
+    for (snapshot_container, _) in snapshot_containers.iter_mut() {
+        let target_file = snapshot_container.target_file().to_path_buf();
+        for snapshot_ref in snapshot_container.iter_snapshots() {
+            let key = format_snapshot_key(&loc.workspace_root, &target_file, snapshot_ref.line);
+            let module = snapshot_ref
+                .new
+                .metadata()
+                .source()
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| key.split(':').next().unwrap_or_default().to_string());
+            let is_new = snapshot_ref
+                .old
+                .as_ref()
+                .map_or(true, |old| match old.contents() {
+                    SnapshotContents::Text(text) => text.to_string().is_empty(),
+                    SnapshotContents::Binary(_) => false,
+                });
+            if is_new {
+                created.entry(module).or_default().push(key);
+            } else {
+                changed.entry(module).or_default().push(key);
+            }
+            if let SnapshotContents::Text(text) = snapshot_ref.new.contents() {
+                contents.push(text.to_string());
+            }
+        }
+    }
+
+    let created_keys = created.values().flatten().cloned().collect_vec();
+    if !created_keys.is_empty() {
+        review_snapshots(
+            true,
+            Some(&created_keys),
+            &loc,
+            Some(Operation::Accept),
+            false,
+        )?;
+    }
+    for (title, groups) in [("created", &created), ("changed", &changed)] {
+        if groups.is_empty() {
+            continue;
+        }
+        println!(
+            "{} {} snapshots:",
+            style(groups.values().map(Vec::len).sum::<usize>()).bold(),
+            title
+        );
+        for (module, keys) in groups {
+            println!("  {}: {}", style(module).cyan(), keys.len());
+        }
+    }
+    if created.is_empty() && changed.is_empty() {
+        println!("{}: no new snapshots", style("done").bold());
+    }
+
+    if !cmd.no_config {
+        write_bootstrap_config(&loc, contents.iter().map(String::as_str))?;
+    }
+
+    if !changed.is_empty() {
+        if !is_ci() && Term::stdout().is_term() {
+            let changed_keys = changed.values().flatten().cloned().collect_vec();
+            review_snapshots(false, Some(&changed_keys), &loc, None, false)?;
+        } else {
+            println!(
+                "{}: review the changed snapshots with `cargo insta review`",
+                style("info").bold()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Writes the initial insta.yaml of a workspace unless it already has one.
+fn write_bootstrap_config<'a>(
+    loc: &LocationInfo,
+    snapshots: impl IntoIterator<Item = &'a str> + Clone,
+) -> Result<(), Box<dyn Error>> {
+    let existing = [".config/insta.yaml", "insta.yaml", ".insta.yaml"]
+        .iter()
+        .map(|name| loc.workspace_root.join(name))
+        .find(|path| path.exists());
+    if let Some(path) = existing {
+        println!(
+            "{}: keeping the existing {}",
+            style("info").bold(),
+            path.display()
+        );
+        return Ok(());
+    }
+
+    let recommendations = bootstrap::recommend_filters(snapshots);
+    let has_nextest = process::Command::new(get_cargo())
+        .arg("nextest")
+        .arg("--version")
+        .output()
+        .map_or(false, |output| output.status.success());
+    let path = loc.workspace_root.join("insta.yaml");
+    fs::write(
+        &path,
+        bootstrap::config_template(&recommendations, has_nextest),
+    )?;
+    println!("{} {}", style("wrote").green(), path.display());
+    for recommendation in &recommendations {
+        println!(
+            "  {} found in {} snapshots, consider filtering them (see insta.yaml)",
+            recommendation.value.name, recommendation.snapshots
+        );
+    }
+    Ok(())
+}
+
+fn doctor_cmd(cmd: DoctorCommand) -> Result<(), Box<dyn Error>> {
+    let loc = handle_target_args(&cmd.target_args, &[])?;
+
+    // pending snapshots are written next to the snapshot with an extra
+    // `.new` extension, so that is the longest path insta needs
+    let mut long_paths = vec![];
+    let mut by_lowercase_path = BTreeMap::<String, Vec<PathBuf>>::new();
+    for package in &loc.packages {
+        let files = make_snapshot_walker(
+            package.manifest_path.parent().unwrap().as_std_path(),
+            &loc.exts,
+            loc.find_flags,
+        )
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().map(|ft| ft.is_file()).unwrap_or(false))
+        .filter(|e| is_likely_insta_snapshot(e.path()));
+        for entry in files {
+            let path = entry.path();
+            let len = path.to_string_lossy().chars().count() + ".new".len();
+            if len > cmd.max_path_len {
+                long_paths.push((path.to_path_buf(), len));
+            }
+            by_lowercase_path
+                .entry(path.to_string_lossy().to_lowercase())
+                .or_default()
+                .push(path.to_path_buf());
+        }
+    }
+    let case_clashes = by_lowercase_path
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .collect::<Vec<_>>();
+
+    if long_paths.is_empty() && case_clashes.is_empty() {
+        println!("{}: no problems found", style("done").bold());
+        return Ok(());
+    }
+
+    let relative = |path: &Path| {
+        path.strip_prefix(&loc.workspace_root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    if !long_paths.is_empty() {
+        long_paths.sort();
+        eprintln!(
+            "{}: snapshot paths longer than {} characters, which Windows rejects by default:",
+            style("warning").bold(),
+            cmd.max_path_len
+        );
+        for (path, len) in &long_paths {
+            eprintln!("  {} ({len})", relative(path));
+        }
+        eprintln!(
+            "{}: set `snapshot_layout: hashed` under `behavior` in insta.yaml to store \
+             snapshots in short directories, or enable long paths on Windows",
+            style("hint").bold()
+        );
+    }
+    if !case_clashes.is_empty() {
+        eprintln!(
+            "{}: snapshot files that only differ in case, which overwrite each other on \
+             case-insensitive file systems such as on macOS and Windows:",
+            style("warning").bold(),
+        );
+        for paths in &case_clashes {
+            eprintln!("  {}", paths.iter().map(|x| relative(x)).join(", "));
+        }
+        eprintln!(
+            "{}: rename the snapshots so that their names differ in more than case",
+            style("hint").bold()
+        );
+    }
+    Err(QuietExit(1).into())
+}
+
+fn gc_cmd(cmd: GcCommand) -> Result<(), Box<dyn Error>> {
+    let loc = handle_target_args(&cmd.target_args, &[])?;
+    let removed = gc::find_removed_tests(&loc.workspace_root, &cmd.since)?;
+
+    let mut orphaned = vec![];
+    for package in &loc.packages {
+        let files = make_snapshot_walker(
+            package.manifest_path.parent().unwrap().as_std_path(),
+            &loc.exts,
+            loc.find_flags,
+        )
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().map(|ft| ft.is_file()).unwrap_or(false))
+        .filter(|e| is_likely_insta_snapshot(e.path()));
+        for entry in files {
+            let path = entry.path();
+            if !removed.may_contain(path) {
+                continue;
+            }
+            let snapshot = match Snapshot::from_file(path) {
+                Ok(snapshot) => snapshot,
+                Err(err) => {
+                    eprintln!("Error loading snapshot at {path:?}: {err}");
+                    continue;
+                }
+            };
+            if removed.contains(path, &snapshot) {
+                orphaned.push((path.to_path_buf(), snapshot));
+            }
+        }
+    }
+
+    if orphaned.is_empty() {
+        eprintln!(
+            "{}: no snapshots of tests removed since {} found",
+            style("info").bold(),
+            cmd.since
+        );
+        return Ok(());
+    }
+
+    orphaned.sort_by(|a, b| a.0.cmp(&b.0));
+    eprintln!(
+        "{}: {} of tests removed since {}:",
+        style("info").bold(),
+        if cmd.delete {
+            "deleted snapshots"
+        } else {
+            "snapshots"
+        },
+        cmd.since
+    );
+    for (path, snapshot) in &orphaned {
+        eprintln!(
+            "  {}",
+            path.strip_prefix(&loc.workspace_root)
+                .unwrap_or(path)
+                .display()
+        );
+        if cmd.delete {
+            if let Some(binary_path) = snapshot.build_binary_path(path) {
+                fs::remove_file(&binary_path).ok();
+            }
+            fs::remove_file(path)?;
+        }
+    }
+    if !cmd.delete {
+        eprintln!(
+            "{}: run with `--delete` to delete them",
+            style("hint").bold()
+        );
+    }
+    Ok(())
+}
+
+fn meta_cmd(cmd: MetaCommand) -> Result<(), Box<dyn Error>> {
+    let loc = handle_target_args(&cmd.target_args, &[])?;
+    let (field, value) = match cmd.action {
+        MetaAction::Set { field, ref value } => (field, Some(value.as_str())),
+        MetaAction::Strip { field } => (field, None),
+    };
+    let field = field
+        .to_possible_value()
+        .expect("no skipped fields")
+        .get_name()
+        .to_string();
+
+    let mut changed = 0;
+    for package in &loc.packages {
+        let root = package.manifest_path.parent().unwrap().as_std_path();
+        let files = make_snapshot_walker(root, &loc.exts, loc.find_flags)
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().map(|ft| ft.is_file()).unwrap_or(false));
+        for entry in files {
+            let path = entry.path();
+            let relative = path
+                .strip_prefix(&loc.workspace_root)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let matched = cmd.glob.is_empty()
+                || cmd.glob.iter().any(|pattern| {
+                    matches_pattern(pattern, &relative)
+                        || relative
+                            .match_indices('/')
+                            .any(|(idx, _)| matches_pattern(pattern, &relative[idx + 1..]))
+                });
+            if !matched || relative.ends_with(".new") || !is_likely_insta_snapshot(path) {
+                continue;
+            }
+
+            let mut snapshot = Snapshot::from_file(path)?;
+            if !snapshot
+                .set_metadata_field(&field, value)
+                .map_err(err_msg)?
+            {
+                continue;
+            }
+            changed += 1;
+            if cmd.dry_run {
+                println!("{} {relative}", style("would update").yellow());
+            } else {
+                snapshot.save(path)?;
+                println!("{} {relative}", style("updated").green());
+            }
+        }
+    }
+
+    if cmd.dry_run {
+        println!("{changed} snapshot files would be updated");
+    } else {
+        println!("{changed} snapshot files updated");
+    }
+    Ok(())
+}
+
+fn locate_cmd(cmd: LocateCommand) -> Result<(), Box<dyn Error>> {
+    let loc = handle_target_args(&cmd.target_args, &[])?;
+    let segments: Vec<_> = cmd.test.split("::").collect();
+    let (test_name, module_path) = match segments.split_last() {
+        Some((test_name, module_path)) if !module_path.is_empty() => (*test_name, module_path),
+        _ => {
+            return Err(err_msg(format!(
+                "expected the path of a test function, such as `my_crate::test_foo`, got `{}`",
+                cmd.test
+            )))
+        }
+    };
+    let target = loc
+        .packages
+        .iter()
+        .flat_map(|package| &package.targets)
+        .filter(|target| !target.kind.iter().any(|kind| kind == "custom-build"))
+        .find(|target| target.name.replace('-', "_") == module_path[0])
+        .ok_or_else(|| err_msg(format!("no target named `{}` was found", module_path[0])))?;
+
+    // follow the modules through their files for as long as they exist, the
+    // remaining modules are declared inline in the last file
+    let root_file = target.src_path.as_std_path();
+    let mut source_file = root_file.to_path_buf();
+    for module in &module_path[1..] {
+        let dir = if source_file == root_file
+            || source_file
+                .file_name()
+                .map_or(false, |name| name == "mod.rs")
+        {
+            source_file.parent().unwrap().to_path_buf()
+        } else {
+            source_file.with_extension("")
+        };
+        match [
+            dir.join(format!("{module}.rs")),
+            dir.join(module).join("mod.rs"),
+        ]
+        .into_iter()
+        .find(|path| path.is_file())
+        {
+            Some(path) => source_file = path,
+            None => break,
+        }
+    }
+
+    let path = predict_snapshot_file(
+        &module_path.join("::"),
+        &source_file.to_string_lossy(),
+        test_name,
+        cmd.name.as_deref(),
+        &loc.workspace_root,
+        &loc.tool_config,
+    );
+    println!("{}", path.display());
+    Ok(())
+}
+
+fn stats_cmd(cmd: StatsCommand) -> Result<(), Box<dyn Error>> {
+    let loc = handle_target_args(&cmd.target_args, &[])?;
+    let relative = |path: &Path| {
+        path.strip_prefix(&loc.workspace_root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+
+    let mut snapshot_count = 0;
+    let mut pending_count = 0;
+    let mut quarantined = BTreeMap::<&str, Vec<PathBuf>>::new();
+    for pattern in loc.tool_config.quarantine() {
+        quarantined.insert(pattern, vec![]);
+    }
+    let mut flaky = vec![];
+    let flaky_re = regex::Regex::new(r"\bflaky\s*=>\s*true\b|\bset_flaky\(\s*true\s*\)").unwrap();
+    for package in &loc.packages {
+        let root = package.manifest_path.parent().unwrap().as_std_path();
+        let files = make_snapshot_walker(root, &loc.exts, loc.find_flags)
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().map(|ft| ft.is_file()).unwrap_or(false));
+        for entry in files {
+            let path = entry.path();
+            let file_name = path.file_name().unwrap().to_string_lossy();
+            if file_name.ends_with(".new") || file_name.ends_with(".pending-snap") {
+                pending_count += 1;
+                continue;
+            }
+            if !is_likely_insta_snapshot(path) {
+                continue;
+            }
+            snapshot_count += 1;
+            let name = loc
+                .exts
+                .iter()
+                .find_map(|ext| file_name.strip_suffix(&format!(".{ext}")));
+            if let Some(pattern) = name.and_then(|name| loc.tool_config.quarantined_by(name)) {
+                quarantined
+                    .entry(pattern)
+                    .or_default()
+                    .push(path.to_path_buf());
+            }
+        }
+
+        // `flaky` is set in the settings of the tests themselves
+        let sources = ignore::WalkBuilder::new(root)
+            .filter_entry(|e| e.file_name() != "target")
+            .build()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().map_or(false, |x| x == "rs"));
+        for entry in sources {
+            let source = fs::read_to_string(entry.path()).unwrap_or_default();
+            for (idx, line) in source.lines().enumerate() {
+                if flaky_re.is_match(line) {
+                    flaky.push(format!("{}:{}", relative(entry.path()), idx + 1));
+                }
+            }
+        }
+    }
+
+    println!("snapshot files: {snapshot_count}");
+    println!("pending snapshots: {pending_count}");
+    println!(
+        "quarantined snapshot files: {}",
+        quarantined.values().map(Vec::len).sum::<usize>()
+    );
+    println!("uses of `flaky`: {}", flaky.len());
+    if !cmd.quarantined {
+        return Ok(());
+    }
+
+    if !quarantined.is_empty() {
+        println!();
+        println!("quarantined in the insta config:");
+        for (pattern, paths) in &quarantined {
+            match paths.len() {
+                0 => println!("  {pattern} (no snapshot files)"),
+                1 => println!("  {pattern} (1 snapshot file)"),
+                n => println!("  {pattern} ({n} snapshot files)"),
+            }
+            for path in paths {
+                println!("    {}", relative(path));
+            }
+        }
+    }
+    if !flaky.is_empty() {
+        println!();
+        println!("quarantined with `flaky`:");
+        for location in &flaky {
+            println!("  {location}");
+        }
+    }
+    if quarantined.values().any(Vec::is_empty) {
+        eprintln!(
+            "{}: patterns without snapshot files only match inline snapshots or \
+             can be removed",
+            style("hint").bold()
+        );
+    }
+    Ok(())
+}
+
 fn pending_snapshots_cmd(cmd: PendingSnapshotsCommand) -> Result<(), Box<dyn Error>> {
     #[derive(Serialize, Debug)]
     #[serde(rename_all = "snake_case", tag = "type")]
@@ -1590,6 +3471,8 @@ pub(crate) fn run() -> Result<(), Box<dyn Error>> {
     let opts = Opts::parse_from(args);
 
     handle_color(opts.color);
+    trace::init(opts.verbose, opts.log_file.as_deref())?;
+    debug!("cli", "running cargo-insta {}", cargo_insta_version());
     match opts.command {
         Command::Review(ref cmd) => {
             if let Some(ref diff_tool) = cmd.diff_tool {
@@ -1600,6 +3483,7 @@ pub(crate) fn run() -> Result<(), Box<dyn Error>> {
                 cmd.process.snapshot_filter.as_deref(),
                 &handle_target_args(&cmd.process.target_args, &[])?,
                 None,
+                cmd.process.and_fmt,
             )
         }
         Command::Accept(ref cmd) | Command::Reject(ref cmd) => review_snapshots(
@@ -1611,10 +3495,20 @@ pub(crate) fn run() -> Result<(), Box<dyn Error>> {
                 Command::Reject(_) => Some(Operation::Reject),
                 _ => unreachable!(),
             },
+            cmd.and_fmt,
         ),
         Command::Test(cmd) => test_run(cmd, opts.color.unwrap_or(ColorWhen::Auto)),
         Command::Show(cmd) => show_cmd(cmd),
+        Command::Expand(cmd) => expand_cmd(cmd),
+        Command::Migrate(cmd) => migrate_cmd(cmd),
+        Command::Watch(cmd) => watch_cmd(cmd, opts.color.unwrap_or(ColorWhen::Auto)),
+        Command::Bootstrap(cmd) => bootstrap_cmd(cmd, opts.color.unwrap_or(ColorWhen::Auto)),
         Command::PendingSnapshots(cmd) => pending_snapshots_cmd(cmd),
+        Command::Doctor(cmd) => doctor_cmd(cmd),
+        Command::Gc(cmd) => gc_cmd(cmd),
+        Command::Stats(cmd) => stats_cmd(cmd),
+        Command::Meta(cmd) => meta_cmd(cmd),
+        Command::Locate(cmd) => locate_cmd(cmd),
     }
 }
 
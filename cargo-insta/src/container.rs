@@ -2,11 +2,13 @@ use std::error::Error;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-pub(crate) use insta::TextSnapshotKind;
 use insta::_cargo_insta_support::{ContentError, PendingInlineSnapshot};
+pub(crate) use insta::TextSnapshotKind;
 use insta::{internals::SnapshotContents, Snapshot};
 
-use crate::inline::FilePatcher;
+use crate::inline::{rustfmt_max_width, FilePatcher};
+use crate::utils::{err_msg, git};
+use crate::{debug, trace};
 
 #[derive(Clone, Copy, Debug)]
 pub(crate) enum Operation {
@@ -43,6 +45,51 @@ impl PendingSnapshot {
         }
         rv
     }
+
+    /// Returns whether the reference snapshot changed after the test that
+    /// wrote this pending snapshot ran, for instance because of a `git pull`.
+    pub(crate) fn is_conflicted(&self) -> bool {
+        is_conflicted(self.old.as_ref(), &self.new)
+    }
+}
+
+/// Returns whether `old` is not the reference that `new` was compared
+/// against when it was written.
+///
+/// Pending snapshots written by older versions of insta record no reference
+/// and never conflict, and neither do references hashed with an algorithm
+/// that is unknown to this version.
+pub(crate) fn is_conflicted(old: Option<&Snapshot>, new: &Snapshot) -> bool {
+    match new.metadata().reference_hash() {
+        Some(hash) => match old {
+            Some(old) => old.matches_contents_hash(hash) == Some(false),
+            None => hash != "none",
+        },
+        None => false,
+    }
+}
+
+/// Finds the reference text snapshot that `new` was compared against when it
+/// was written, the base of three-way merges, in the git history of
+/// `snapshot_file`.
+///
+/// Pending snapshots only record the hash of their reference, so this
+/// returns `None` outside of git or if that version was never committed.
+pub(crate) fn find_base(snapshot_file: &Path, new: &Snapshot) -> Option<String> {
+    let hash = new.metadata().reference_hash()?;
+    let dir = snapshot_file.parent()?;
+    let name = snapshot_file.file_name()?.to_str()?;
+    let revs = git(dir, &["log", "--format=%H", "--", name]).ok()?;
+    revs.lines().find_map(|rev| {
+        let file = git(dir, &["show", &format!("{rev}:./{name}")]).ok()?;
+        // the contents follow the metadata between the `---` lines
+        let (_, contents) = file.strip_prefix("---\n")?.split_once("\n---\n")?;
+        let base = new.with_text(contents.to_string());
+        match base.matches_contents_hash(hash) {
+            Some(true) => base.as_text().map(|x| x.to_string()),
+            _ => None,
+        }
+    })
 }
 
 /// A snapshot and its immediate context, which loads & saves the snapshot. It
@@ -60,20 +107,36 @@ pub(crate) struct SnapshotContainer {
 }
 
 impl SnapshotContainer {
+    /// Loads the pending snapshots of `pending_path`.
+    ///
+    /// Tabs in inline snapshots count as `indent_width` columns when their
+    /// lines are lined up.
     pub(crate) fn load(
         pending_path: PathBuf,
         target_path: PathBuf,
         kind: TextSnapshotKind,
+        indent_width: usize,
     ) -> Result<SnapshotContainer, Box<dyn Error>> {
         let mut snapshots = Vec::new();
         let patcher = match kind {
             TextSnapshotKind::File => {
                 let old = if fs::metadata(&target_path).is_err() {
+                    debug!(
+                        "container",
+                        "no existing snapshot at {}",
+                        target_path.display()
+                    );
                     None
                 } else {
                     Some(Snapshot::from_file(&target_path)?)
                 };
                 let new = Snapshot::from_file(&pending_path)?;
+                trace!(
+                    "container",
+                    "read pending snapshot {} with metadata {:?}",
+                    pending_path.display(),
+                    new.metadata()
+                );
                 snapshots.push(PendingSnapshot {
                     id: 0,
                     old,
@@ -86,6 +149,12 @@ impl SnapshotContainer {
             TextSnapshotKind::Inline => {
                 let mut pending_vec = PendingInlineSnapshot::load_batch(&pending_path)?;
                 let mut have_new = false;
+                debug!(
+                    "container",
+                    "read {} pending inline snapshots from {}",
+                    pending_vec.len(),
+                    pending_path.display()
+                );
 
                 let rv = if fs::metadata(&target_path).is_ok() {
                     let mut patcher = FilePatcher::open(&target_path)?;
@@ -95,19 +164,32 @@ impl SnapshotContainer {
                             if patcher.add_snapshot_macro(pending.line as usize) {
                                 snapshots.push(PendingSnapshot {
                                     id,
-                                    old: pending.old,
-                                    new,
+                                    old: pending.old.map(|old| old.with_indent_width(indent_width)),
+                                    new: new.with_indent_width(indent_width),
                                     op: Operation::Skip,
                                     line: Some(pending.line),
                                 });
                                 have_new = true;
                             } else {
                                 // this is an outdated snapshot and the file changed.
+                                debug!(
+                                    "container",
+                                    "skipping pending inline snapshot at {}:{}: there is no \
+                                     snapshot assertion that starts on that line anymore, or \
+                                     another pending snapshot belongs to the same assertion",
+                                    target_path.display(),
+                                    pending.line
+                                );
                             }
                         }
                     }
                     Some(patcher)
                 } else {
+                    debug!(
+                        "container",
+                        "skipping pending inline snapshots of {}: the source file does not exist",
+                        target_path.display()
+                    );
                     None
                 };
 
@@ -116,6 +198,11 @@ impl SnapshotContainer {
                 // The runtime code will issue something like this:
                 //   PendingInlineSnapshot::new(None, None, line).save(pending_snapshots)?;
                 if !have_new {
+                    debug!(
+                        "container",
+                        "removing {} without pending inline snapshots",
+                        pending_path.display()
+                    );
                     fs::remove_file(&pending_path)
                         .map_err(|e| ContentError::FileIo(e, pending_path.to_path_buf()))?;
                 }
@@ -137,6 +224,21 @@ impl SnapshotContainer {
         &self.target_path
     }
 
+    /// Makes the snapshots get accepted into `target_path` instead of their
+    /// target file.  For inline snapshots this must be a copy of the source
+    /// file.
+    pub(crate) fn set_target_file(&mut self, target_path: PathBuf) -> Result<(), Box<dyn Error>> {
+        if self.patcher.is_some() {
+            let mut patcher = FilePatcher::open(&target_path)?;
+            for snapshot in &self.snapshots {
+                patcher.add_snapshot_macro(snapshot.line.unwrap_or_default() as usize);
+            }
+            self.patcher = Some(patcher);
+        }
+        self.target_path = target_path;
+        Ok(())
+    }
+
     pub(crate) fn snapshot_file(&self) -> Option<&Path> {
         match self.kind {
             TextSnapshotKind::File => Some(&self.target_path),
@@ -145,17 +247,7 @@ impl SnapshotContainer {
     }
 
     pub(crate) fn snapshot_sort_key(&self) -> impl Ord + '_ {
-        let path = self
-            .pending_path
-            .file_name()
-            .and_then(|x| x.to_str())
-            .unwrap_or_default();
-        let mut pieces = path.rsplitn(2, '-');
-        if let Some(num_suffix) = pieces.next().and_then(|x| x.parse::<i64>().ok()) {
-            (pieces.next().unwrap_or(""), num_suffix)
-        } else {
-            (path, 0)
-        }
+        snapshot_sort_key(&self.pending_path)
     }
 
     pub(crate) fn len(&self) -> usize {
@@ -166,7 +258,11 @@ impl SnapshotContainer {
         self.snapshots.iter_mut()
     }
 
-    pub(crate) fn commit(&mut self) -> Result<(), Box<dyn Error>> {
+    /// Writes the accepted snapshots and the pending ones that were skipped.
+    ///
+    /// Inline snapshots which make the line of their macro call too long for
+    /// rustfmt get their call split across lines if `wrap_long_lines` is set.
+    pub(crate) fn commit(&mut self, wrap_long_lines: bool) -> Result<(), Box<dyn Error>> {
         // Try removing the snapshot file. If it fails, it's
         // likely because it another process removed it; which
         // is fine — print a message and continue.
@@ -179,6 +275,11 @@ impl SnapshotContainer {
         };
 
         if let Some(ref mut patcher) = self.patcher {
+            let max_width = if wrap_long_lines {
+                Some(rustfmt_max_width(&self.target_path))
+            } else {
+                None
+            };
             let mut new_pending = vec![];
             let mut did_accept = false;
             let mut did_skip = false;
@@ -186,12 +287,19 @@ impl SnapshotContainer {
             for (idx, snapshot) in self.snapshots.iter().enumerate() {
                 match snapshot.op {
                     Operation::Accept | Operation::AcceptAll => {
+                        debug!(
+                            "inline",
+                            "rewriting inline snapshot at {}:{}",
+                            self.target_path.display(),
+                            patcher.get_new_line(idx)
+                        );
                         patcher.set_new_content(
                             idx,
                             match snapshot.new.contents() {
                                 SnapshotContents::Text(c) => c,
                                 _ => unreachable!(),
                             },
+                            max_width,
                         );
                         did_accept = true;
                     }
@@ -221,6 +329,18 @@ impl SnapshotContainer {
             for snapshot in self.snapshots.iter() {
                 match snapshot.op {
                     Operation::Accept | Operation::AcceptAll => {
+                        if let Some(other) = find_case_variant(&self.target_path) {
+                            return Err(err_msg(format!(
+                                "cannot accept {}: it only differs in case from {}, which is \
+                                 the same file on case-insensitive file systems such as on \
+                                 macOS and Windows. Rename one of the snapshots, or delete \
+                                 {} if it is no longer used.",
+                                self.target_path.display(),
+                                other.display(),
+                                other.display()
+                            )));
+                        }
+
                         try_removing_snapshot(&self.pending_path);
 
                         if let Some(ref old) = snapshot.old {
@@ -235,6 +355,7 @@ impl SnapshotContainer {
 
                         // We save at the end because we might write a binary file into the same
                         // path again.
+                        debug!("container", "writing {}", self.target_path.display());
                         snapshot.new.save(&self.target_path)?;
                     }
                     Operation::Reject | Operation::RejectAll => {
@@ -251,3 +372,75 @@ impl SnapshotContainer {
         Ok(())
     }
 }
+
+/// Returns the key that orders the pending snapshot files for review, in
+/// which `foo-2` comes before `foo-10`.
+pub(crate) fn snapshot_sort_key(pending_path: &Path) -> impl Ord + '_ {
+    let path = pending_path
+        .file_name()
+        .and_then(|x| x.to_str())
+        .unwrap_or_default();
+    let mut pieces = path.rsplitn(2, '-');
+    if let Some(num_suffix) = pieces.next().and_then(|x| x.parse::<i64>().ok()) {
+        (pieces.next().unwrap_or(""), num_suffix)
+    } else {
+        (path, 0)
+    }
+}
+
+/// Returns another file next to `path` whose name only differs in case.
+pub(crate) fn find_case_variant(path: &Path) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_str()?;
+    let lowercase = file_name.to_lowercase();
+    fs::read_dir(path.parent()?)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .find(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name != file_name && name.to_lowercase() == lowercase
+        })
+        .map(|entry| entry.path())
+}
+
+#[test]
+fn test_find_base() {
+    let dir = tempfile::tempdir().unwrap();
+    let git = |args: &[&str]| {
+        let mut cmd = std::process::Command::new("git");
+        cmd.current_dir(dir.path()).args([
+            "-c",
+            "user.name=insta",
+            "-c",
+            "user.email=insta@example.com",
+        ]);
+        assert!(cmd.args(args).output().unwrap().status.success());
+    };
+    let path = dir.path().join("test__value.snap");
+    let pending = dir.path().join("test__value.snap.new");
+    git(&["init", "-q"]);
+
+    fs::write(&path, "---\nsource: src/lib.rs\n---\nline 1\nline 2\n").unwrap();
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "base"]);
+    let hash = Snapshot::from_file(&path).unwrap().contents_hash();
+    fs::write(
+        &pending,
+        format!("---\nsource: src/lib.rs\nreference_hash: {hash}\n---\nline 1\nours\n"),
+    )
+    .unwrap();
+    let new = Snapshot::from_file(&pending).unwrap();
+
+    fs::write(&path, "---\nsource: src/lib.rs\n---\ntheirs\nline 2\n").unwrap();
+    git(&["commit", "-q", "-am", "theirs"]);
+    assert_eq!(find_base(&path, &new).as_deref(), Some("line 1\nline 2"));
+
+    // the reference of the pending snapshot was never committed
+    fs::write(
+        &pending,
+        "---\nsource: src/lib.rs\nreference_hash: 0000000000000000\n---\nours\n",
+    )
+    .unwrap();
+    let new = Snapshot::from_file(&pending).unwrap();
+    assert_eq!(find_base(&path, &new), None);
+}
@@ -0,0 +1,192 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use insta::Snapshot;
+use regex::Regex;
+
+use crate::utils::git;
+
+/// The functions that were removed from the Rust files of a git repository.
+pub(crate) struct RemovedTests {
+    /// The canonical path of the workspace root, which the `source` of
+    /// snapshots is relative to.
+    workspace_root: PathBuf,
+    /// The snapshot names of the removed functions per file, by canonical
+    /// path.
+    names: BTreeMap<PathBuf, BTreeSet<String>>,
+}
+
+impl RemovedTests {
+    /// Returns whether the snapshot file at `path` carries the name derived
+    /// from a removed function, which is cheaper to check than
+    /// [`RemovedTests::contains`] as it doesn't load the snapshot.
+    pub(crate) fn may_contain(&self, path: &Path) -> bool {
+        base_snapshot_name(path).map_or(false, |name| {
+            self.names.values().any(|names| names.contains(name))
+        })
+    }
+
+    /// Returns whether `snapshot`, loaded from `path`, belongs to a removed
+    /// test: it was asserted in the file the test was removed from and
+    /// carries the name derived from the test.
+    pub(crate) fn contains(&self, path: &Path, snapshot: &Snapshot) -> bool {
+        let (name, source) = match (base_snapshot_name(path), snapshot.metadata().source()) {
+            (Some(name), Some(source)) => (name, source),
+            _ => return false,
+        };
+        self.names
+            .get(&self.workspace_root.join(source))
+            .map_or(false, |names| names.contains(name))
+    }
+}
+
+/// Finds the functions that were removed between `rev` and the working tree
+/// of the git repository that contains `workspace_root`.
+///
+/// Functions are reported by the name that insta derives snapshot names
+/// from, without a `test_` prefix.  A name is left out if a function with
+/// that name still exists in the file, or was added to any file, so that
+/// tests that were only changed or moved keep their snapshots.
+pub(crate) fn find_removed_tests(
+    workspace_root: &Path,
+    rev: &str,
+) -> Result<RemovedTests, Box<dyn Error>> {
+    // git reports the root with symlinks resolved, and the workspace root
+    // may be reached through one
+    let workspace_root = fs::canonicalize(workspace_root)?;
+    let repo_root =
+        fs::canonicalize(git(&workspace_root, &["rev-parse", "--show-toplevel"])?.trim_end())?;
+    let diff = git(
+        &repo_root,
+        &[
+            "-c",
+            "core.quotePath=false",
+            "diff",
+            "--no-color",
+            "--no-ext-diff",
+            "--no-renames",
+            "--unified=0",
+            "--src-prefix=a/",
+            "--dst-prefix=b/",
+            rev,
+            "--",
+            "*.rs",
+        ],
+    )?;
+
+    let (removed, added) = parse_diff(&diff);
+    let names = removed
+        .into_iter()
+        .filter_map(|(path, names)| {
+            let path = repo_root.join(path);
+            let current = fs::read_to_string(&path)
+                .map(|source| function_names(&source))
+                .unwrap_or_default();
+            let names = names
+                .into_iter()
+                .filter(|name| !current.contains(name) && !added.contains(name))
+                .collect::<BTreeSet<_>>();
+            (!names.is_empty()).then_some((path, names))
+        })
+        .collect();
+
+    Ok(RemovedTests {
+        workspace_root,
+        names,
+    })
+}
+
+/// Returns the snapshot name that insta derives from a snapshot file name,
+/// without the counter of repeated assertions and the snapshot suffix.
+fn base_snapshot_name(path: &Path) -> Option<&str> {
+    let stem = path.file_name()?.to_str()?.split('.').next()?;
+    let name = stem.rsplit("__").next()?;
+    let name = name.split('@').next()?;
+    match name.rsplit_once('-') {
+        Some((base, counter)) if counter.parse::<u32>().is_ok() => Some(base),
+        _ => Some(name),
+    }
+}
+
+/// Collects the names of the functions on removed lines per file, and the
+/// names of the functions on added lines of all files.
+fn parse_diff(diff: &str) -> (BTreeMap<PathBuf, BTreeSet<String>>, BTreeSet<String>) {
+    let mut removed = BTreeMap::<PathBuf, BTreeSet<String>>::new();
+    let mut added = BTreeSet::new();
+    let mut path = None;
+    let mut in_header = false;
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") {
+            path = None;
+            in_header = true;
+        } else if in_header {
+            if let Some(old) = line.strip_prefix("--- a/") {
+                path = Some(PathBuf::from(old));
+            } else if line.starts_with("@@") {
+                in_header = false;
+            }
+        } else if let Some(line) = line.strip_prefix('-') {
+            if let Some(path) = &path {
+                removed
+                    .entry(path.clone())
+                    .or_default()
+                    .extend(function_names(line));
+            }
+        } else if let Some(line) = line.strip_prefix('+') {
+            added.extend(function_names(line));
+        }
+    }
+    (removed, added)
+}
+
+fn function_names(source: &str) -> BTreeSet<String> {
+    Regex::new(r"\bfn\s+(?:r#)?([A-Za-z_][A-Za-z0-9_]*)")
+        .unwrap()
+        .captures_iter(source)
+        .map(|caps| {
+            let name = &caps[1];
+            name.strip_prefix("test_").unwrap_or(name).to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn test_parse_diff() {
+    let diff = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -3,3 +2,0 @@
+-#[test]
+-fn test_removed() {
+--- a/src/lib.rs
+-    insta::assert_snapshot!(\"a\");
+@@ -10 +6,0 @@
+-fn test_moved() {
+diff --git a/tests/new.rs b/tests/new.rs
+new file mode 100644
+--- /dev/null
++++ b/tests/new.rs
+@@ -0,0 +1 @@
++fn moved() {
+";
+    let (removed, added) = parse_diff(diff);
+    assert_eq!(
+        removed[Path::new("src/lib.rs")].iter().collect::<Vec<_>>(),
+        ["moved", "removed"]
+    );
+    assert_eq!(added.iter().collect::<Vec<_>>(), ["moved"]);
+
+    for (path, name) in [
+        ("src/snapshots/crate__removed.snap", "removed"),
+        ("src/snapshots/crate__tests__removed-2.snap", "removed"),
+        ("src/snapshots/crate__removed@case-1.snap", "removed"),
+        ("src/snapshots/crate__removed.snap.new", "removed"),
+        ("src/snapshots/tests-1234abcd/v1-beta.snap", "v1-beta"),
+    ] {
+        assert_eq!(base_snapshot_name(Path::new(path)), Some(name));
+    }
+}
@@ -10,11 +10,26 @@ use proc_macro2::{LineColumn, TokenTree};
 use syn::__private::ToTokens;
 use syn::spanned::Spanned;
 
+use crate::{debug, trace};
+
 #[derive(Debug, Clone)]
 struct InlineSnapshot {
     start: (usize, usize),
     end: (usize, usize),
     indentation: String,
+    /// The layout of the macro call if it is on a single line.
+    call: Option<CallLayout>,
+}
+
+/// Columns of the delimiters of a macro call that fits on one line.
+#[derive(Debug, Clone)]
+struct CallLayout {
+    /// The column after the opening parenthesis.
+    open: usize,
+    /// The column of the closing parenthesis.
+    close: usize,
+    /// The columns of the commas between the arguments.
+    commas: Vec<usize>,
 }
 
 #[derive(Clone)]
@@ -49,6 +64,7 @@ impl FilePatcher {
     }
 
     pub(crate) fn save(&self) -> Result<(), Box<dyn Error>> {
+        debug!("inline", "writing {}", self.filename.display());
         // We use a temp file and then atomically rename to prevent a
         // file watcher restarting the process midway through the write.
         let mut temp_file = tempfile::Builder::new()
@@ -92,7 +108,16 @@ impl FilePatcher {
         self.inline_snapshots[id].start.0 + 1
     }
 
-    pub(crate) fn set_new_content(&mut self, id: usize, snapshot: &TextSnapshotContents) {
+    /// Replaces the literal of an inline snapshot.
+    ///
+    /// With a `max_width`, a macro call that no longer fits on its line is
+    /// split into one argument per line the way rustfmt lays out calls.
+    pub(crate) fn set_new_content(
+        &mut self,
+        id: usize,
+        snapshot: &TextSnapshotContents,
+        max_width: Option<usize>,
+    ) {
         let inline = &mut self.inline_snapshots[id];
 
         // find prefix and suffix on the first and last lines
@@ -106,8 +131,25 @@ impl FilePatcher {
             .collect();
 
         // replace lines
-        let snapshot_line_contents =
-            [prefix, snapshot.to_inline(&inline.indentation), suffix].join("");
+        let literal = snapshot.to_inline(&inline.indentation);
+        let mut snapshot_line_contents = format!("{prefix}{literal}{suffix}");
+        if let (Some(max_width), Some(call)) = (max_width, &inline.call) {
+            if !snapshot_line_contents.contains('\n')
+                && line_width(&snapshot_line_contents) > max_width
+            {
+                trace!(
+                    "inline",
+                    "splitting the snapshot assertion at {}:{} across lines, it is wider than {max_width} columns",
+                    self.filename.display(),
+                    inline.start.0 + 1
+                );
+                // the closing parenthesis moves with the end of the literal
+                let close =
+                    prefix.chars().count() + literal.chars().count() + call.close - inline.end.1;
+                snapshot_line_contents =
+                    wrap_call(&snapshot_line_contents, call, close, &inline.indentation);
+            }
+        }
 
         self.lines.splice(
             inline.start.0..=inline.end.0,
@@ -238,6 +280,7 @@ impl FilePatcher {
                         start,
                         end,
                         indentation,
+                        call: None,
                     },
                 ));
                 true
@@ -254,6 +297,17 @@ impl FilePatcher {
                 }
 
                 let tokens: Vec<_> = i.meta.to_token_stream().into_iter().collect();
+
+                // inline snapshots in attribute arguments, such as
+                // `#[case(input = "...", expected = @"...")]`
+                if let Some(TokenTree::Group(group)) = tokens.last() {
+                    let args: Vec<_> = group.stream().into_iter().collect();
+                    let indentation = indentation(i.span().start(), self.2);
+                    if self.try_extract_snapshot(&args, indentation, start, end) {
+                        return;
+                    }
+                }
+
                 if !tokens.is_empty() {
                     self.scan_nested_macros(&tokens);
                 }
@@ -291,7 +345,26 @@ impl FilePatcher {
                 }
 
                 let indentation = indentation(span_start, self.2);
-                if !self.try_extract_snapshot(&tokens, indentation, start, end) {
+                if self.try_extract_snapshot(&tokens, indentation, start, end) {
+                    if let syn::MacroDelimiter::Paren(ref paren) = i.delimiter {
+                        let (open, close) = (paren.span.open().end(), paren.span.close().start());
+                        if span_start.line == close.line {
+                            self.1.last_mut().unwrap().2.call = Some(CallLayout {
+                                open: open.column,
+                                close: close.column,
+                                commas: tokens
+                                    .iter()
+                                    .filter_map(|token| match token {
+                                        TokenTree::Punct(punct) if punct.as_char() == ',' => {
+                                            Some(punct.span().start().column)
+                                        }
+                                        _ => None,
+                                    })
+                                    .collect(),
+                            });
+                        }
+                    }
+                } else {
                     // if we can't extract a snapshot here we want to scan for nested
                     // macros.  These are just represented as unparsed tokens in a
                     // token stream.
@@ -312,6 +385,52 @@ impl FilePatcher {
     }
 }
 
+/// Returns the width of a line the way rustfmt counts it.
+fn line_width(line: &str) -> usize {
+    line.chars().map(|c| if c == '\t' { 4 } else { 1 }).sum()
+}
+
+/// Splits a macro call on a single line into one argument per line.
+///
+/// `close` is the column of the closing parenthesis in `line`; all other
+/// columns of `call` are before the snapshot literal and thus unchanged.
+fn wrap_call(line: &str, call: &CallLayout, close: usize, indentation: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let text = |start: usize, end: usize| chars[start..end].iter().collect::<String>();
+    let mut rv = text(0, call.open);
+    let mut start = call.open;
+    for end in call.commas.iter().copied().chain(Some(close)) {
+        let arg = text(start, end);
+        let arg = arg.trim();
+        if !arg.is_empty() {
+            if start != call.open {
+                rv.push(',');
+            }
+            rv.push_str(&format!("\n{indentation}    {arg}"));
+        }
+        start = end + 1;
+    }
+    rv.push_str(&format!("\n{indentation}{}", text(close, chars.len())));
+    rv
+}
+
+/// Returns the `max_width` from the rustfmt config that applies to `path`.
+pub(crate) fn rustfmt_max_width(path: &Path) -> usize {
+    for dir in path.ancestors().skip(1) {
+        for name in ["rustfmt.toml", ".rustfmt.toml"] {
+            if let Ok(config) = fs::read_to_string(dir.join(name)) {
+                return config
+                    .lines()
+                    .filter_map(|line| line.split_once('='))
+                    .find(|(key, _)| key.trim() == "max_width")
+                    .and_then(|(_, value)| value.trim().parse().ok())
+                    .unwrap_or(100);
+            }
+        }
+    }
+    100
+}
+
 #[cfg(test)]
 mod tests {
     use insta::assert_debug_snapshot;
@@ -724,4 +843,117 @@ fn test_function() {
         assert_eq!(snapshot6.start.0, 5); // line 6 -> index 5
         assert_eq!(snapshot7.start.0, 6); // line 7 -> index 6
     }
+
+    #[test]
+    fn test_find_snapshot_in_case_attribute() {
+        let content = r######"
+#[insta::cases]
+#[case(input = "a", expected = @"A")]
+#[case(
+    input = "b",
+    expected = @"B",
+)]
+fn test_uppercase(input: &str, expected: insta::InlineCase) {
+    insta::assert_snapshot!(input.to_uppercase(), @expected);
+}
+"######;
+
+        let file_patcher = FilePatcher {
+            filename: PathBuf::new(),
+            lines: content.lines().map(String::from).collect(),
+            source: syn::parse_file(content).unwrap(),
+            inline_snapshots: vec![],
+        };
+
+        let first = file_patcher.find_snapshot_macro(3).unwrap();
+        assert_eq!((first.start, first.end), ((2, 32), (2, 35)));
+        let second = file_patcher.find_snapshot_macro(6).unwrap();
+        assert_eq!((second.start, second.end), ((5, 16), (5, 19)));
+        assert_debug_snapshot!(second.indentation, @r#""""#);
+    }
+
+    #[test]
+    fn test_wrap_long_inline_snapshot() {
+        let content = r#"
+fn test_long() {
+    insta::assert_snapshot!(render(&input, Mode::Full), @"");
+    insta::assert_snapshot!(render(&input, Mode::Short), @"");
+}
+"#;
+
+        let mut file_patcher = FilePatcher {
+            filename: PathBuf::new(),
+            lines: content.lines().map(String::from).collect(),
+            source: syn::parse_file(content).unwrap(),
+            inline_snapshots: vec![],
+        };
+        assert!(file_patcher.add_snapshot_macro(3));
+        assert!(file_patcher.add_snapshot_macro(4));
+        let snapshot = TextSnapshotContents::new(
+            "a rather long rendering of the input".to_string(),
+            insta::TextSnapshotKind::Inline,
+        );
+        file_patcher.set_new_content(0, &snapshot, Some(80));
+        let snapshot =
+            TextSnapshotContents::new("short".to_string(), insta::TextSnapshotKind::Inline);
+        file_patcher.set_new_content(1, &snapshot, Some(80));
+
+        insta::assert_snapshot!(file_patcher.lines[1..].join("\n"), @r#"
+        fn test_long() {
+            insta::assert_snapshot!(
+                render(&input, Mode::Full),
+                @"a rather long rendering of the input"
+            );
+            insta::assert_snapshot!(render(&input, Mode::Short), @"short");
+        }
+        "#);
+    }
+
+    #[test]
+    fn test_keep_metadata_comment() {
+        let content = r#"
+fn test_event() {
+    insta::assert_snapshot!(event(), @"" /* insta: filters=uuid */);
+    insta::assert_snapshot!(
+        event(),
+        @"old" /* insta: filters=timestamp */,
+    );
+}
+"#;
+
+        let mut file_patcher = FilePatcher {
+            filename: PathBuf::new(),
+            lines: content.lines().map(String::from).collect(),
+            source: syn::parse_file(content).unwrap(),
+            inline_snapshots: vec![],
+        };
+        assert!(file_patcher.add_snapshot_macro(3));
+        assert!(file_patcher.add_snapshot_macro(4));
+        let snapshot = TextSnapshotContents::new(
+            "a rather long rendering of the event with [uuid]".to_string(),
+            insta::TextSnapshotKind::Inline,
+        );
+        file_patcher.set_new_content(0, &snapshot, Some(80));
+        let snapshot = TextSnapshotContents::new(
+            "first line\nsecond line".to_string(),
+            insta::TextSnapshotKind::Inline,
+        );
+        file_patcher.set_new_content(1, &snapshot, Some(80));
+
+        insta::assert_snapshot!(file_patcher.lines[1..].join("\n"), @r#"
+        fn test_event() {
+            insta::assert_snapshot!(
+                event(),
+                @"a rather long rendering of the event with [uuid]" /* insta: filters=uuid */
+            );
+            insta::assert_snapshot!(
+                event(),
+                @"
+            first line
+            second line
+            " /* insta: filters=timestamp */,
+            );
+        }
+        "#);
+    }
 }
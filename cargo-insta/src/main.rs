@@ -15,12 +15,20 @@
 //! ```
 //!
 //! For more information see [the insta crate documentation](https://docs.rs/insta).
+mod bootstrap;
 mod cargo;
 mod cli;
 mod container;
+mod gc;
 mod inline;
+mod merge;
+mod migrate;
+mod quick;
+mod trace;
 mod utils;
+mod volatile;
 mod walk;
+mod watch;
 
 use console::style;
 
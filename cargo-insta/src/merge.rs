@@ -0,0 +1,153 @@
+use std::ops::Range;
+
+use similar::{capture_diff_slices, Algorithm, DiffTag};
+
+/// The lines of `base` that one side replaced, and the lines of that side
+/// that replaced them.
+struct Hunk {
+    base: Range<usize>,
+    side: Range<usize>,
+}
+
+fn hunks(base: &[&str], side: &[&str]) -> Vec<Hunk> {
+    let mut rv: Vec<Hunk> = vec![];
+    let mut last_equal = true;
+    for op in capture_diff_slices(Algorithm::Myers, base, side) {
+        if op.tag() == DiffTag::Equal {
+            last_equal = true;
+            continue;
+        }
+        match rv.last_mut() {
+            Some(hunk) if !last_equal => {
+                hunk.base.end = op.old_range().end;
+                hunk.side.end = op.new_range().end;
+            }
+            _ => rv.push(Hunk {
+                base: op.old_range(),
+                side: op.new_range(),
+            }),
+        }
+        last_equal = false;
+    }
+    rv
+}
+
+/// Returns the lines of a side for a region of `base` that contains all the
+/// hunks of that side which overlap it.
+fn side_lines<'a>(
+    base: &[&'a str],
+    side: &[&'a str],
+    hunks: &[Hunk],
+    region: &Range<usize>,
+) -> Vec<&'a str> {
+    match (hunks.first(), hunks.last()) {
+        (Some(first), Some(last)) => {
+            // outside of hunks the lines of the side line up with the base
+            let start = first.side.start - (first.base.start - region.start);
+            let end = last.side.end + (region.end - last.base.end);
+            side[start..end].to_vec()
+        }
+        _ => base[region.clone()].to_vec(),
+    }
+}
+
+/// Merges the changes from `base` to `ours` and from `base` to `theirs`.
+///
+/// Changes of only one side are taken over.  Where both sides changed the
+/// same lines differently, the lines of both sides and of the base are
+/// written between conflict markers like `git merge` does with the `diff3`
+/// conflict style.  Returns the merged text and whether it has conflicts.
+pub(crate) fn merge3(base: &str, ours: &str, theirs: &str) -> (String, bool) {
+    let base = base.lines().collect::<Vec<_>>();
+    let ours = ours.lines().collect::<Vec<_>>();
+    let theirs = theirs.lines().collect::<Vec<_>>();
+    let our_hunks = hunks(&base, &ours);
+    let their_hunks = hunks(&base, &theirs);
+
+    let mut out = vec![];
+    let mut conflicts = false;
+    let (mut i, mut j, mut pos) = (0, 0, 0);
+    while i < our_hunks.len() || j < their_hunks.len() {
+        let start = match (our_hunks.get(i), their_hunks.get(j)) {
+            (Some(a), Some(b)) => a.base.start.min(b.base.start),
+            (Some(a), None) => a.base.start,
+            (None, Some(b)) => b.base.start,
+            (None, None) => unreachable!(),
+        };
+        let mut region = start..start;
+        let (first_ours, first_theirs) = (i, j);
+        // grow the region until no hunk of either side touches its end
+        loop {
+            if let Some(hunk) = our_hunks.get(i).filter(|x| x.base.start <= region.end) {
+                region.end = region.end.max(hunk.base.end);
+                i += 1;
+            } else if let Some(hunk) = their_hunks.get(j).filter(|x| x.base.start <= region.end) {
+                region.end = region.end.max(hunk.base.end);
+                j += 1;
+            } else {
+                break;
+            }
+        }
+
+        out.extend_from_slice(&base[pos..region.start]);
+        let our_lines = side_lines(&base, &ours, &our_hunks[first_ours..i], &region);
+        let their_lines = side_lines(&base, &theirs, &their_hunks[first_theirs..j], &region);
+        if first_theirs == j || our_lines == their_lines {
+            out.extend(our_lines);
+        } else if first_ours == i {
+            out.extend(their_lines);
+        } else {
+            conflicts = true;
+            out.push("<<<<<<< new snapshot");
+            out.extend(our_lines);
+            out.push("||||||| reference when the test ran");
+            out.extend_from_slice(&base[region.clone()]);
+            out.push("=======");
+            out.extend(their_lines);
+            out.push(">>>>>>> current reference");
+        }
+        pos = region.end;
+    }
+    out.extend_from_slice(&base[pos..]);
+
+    let mut rv = out.join("\n");
+    if !rv.is_empty() {
+        rv.push('\n');
+    }
+    (rv, conflicts)
+}
+
+/// Returns whether `text` still has conflict markers written by [`merge3`].
+pub(crate) fn has_conflict_markers(text: &str) -> bool {
+    text.lines()
+        .any(|line| line.starts_with("<<<<<<< ") || line.starts_with(">>>>>>> "))
+}
+
+#[test]
+fn test_merge3() {
+    let base = "a\nb\nc\nd\ne\n";
+
+    // changes to different lines are merged
+    let (merged, conflicts) = merge3(base, "a\nB\nc\nd\ne\n", "a\nb\nc\nd\nE\nf\n");
+    assert_eq!(merged, "a\nB\nc\nd\nE\nf\n");
+    assert!(!conflicts);
+
+    // the same change on both sides is not a conflict
+    let (merged, conflicts) = merge3(base, "a\nb\nX\nd\ne\n", "a\nb\nX\nd\ne\n");
+    assert_eq!(merged, "a\nb\nX\nd\ne\n");
+    assert!(!conflicts);
+
+    let (merged, conflicts) = merge3(base, "a\nb\nours\nd\ne\n", "a\nb\ntheirs\nd\ne\n");
+    assert_eq!(
+        merged,
+        "a\nb\n\
+         <<<<<<< new snapshot\nours\n\
+         ||||||| reference when the test ran\nc\n\
+         =======\ntheirs\n\
+         >>>>>>> current reference\n\
+         d\ne\n"
+    );
+    assert!(conflicts);
+    assert!(has_conflict_markers(&merged));
+    assert!(!has_conflict_markers(base));
+}
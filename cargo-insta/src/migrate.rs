@@ -0,0 +1,441 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use proc_macro2::{Delimiter, LineColumn, Span, TokenStream, TokenTree};
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+/// The result of migrating a single source file.
+#[derive(Debug, Default)]
+pub(crate) struct Migration {
+    /// The rewritten source, if anything changed.
+    pub(crate) source: Option<String>,
+    /// The number of assertions that were rewritten.
+    pub(crate) migrated: usize,
+    /// Lines (1-based) of `expect!` invocations that could not be rewritten.
+    pub(crate) skipped: Vec<usize>,
+}
+
+/// Rewrites `expect-test` assertions into inline insta snapshots.
+///
+/// Two forms are supported: `expect![[...]].assert_eq(actual)` and a
+/// `let expected = expect![[...]];` binding that is used by exactly one
+/// `expected.assert_eq(actual)` later in the same block.  `assert_debug_eq`
+/// becomes `assert_debug_snapshot!`.  Everything else, such as `Expect` values
+/// passed to helper functions or `expect_file!`, is reported as skipped.
+pub(crate) fn migrate_expect_test(source: &str) -> Result<Migration, syn::Error> {
+    let file = syn::parse_file(source)?;
+    let mut visitor = ExpectVisitor {
+        source: SourceMap::new(source),
+        edits: Vec::new(),
+        expect_macros: Vec::new(),
+        migrated_macros: Vec::new(),
+        expect_imports: Vec::new(),
+    };
+    visitor.visit_file(&file);
+
+    let skipped = visitor
+        .expect_macros
+        .iter()
+        .filter(|start| !visitor.migrated_macros.contains(start))
+        .map(|start| start.line)
+        .collect::<Vec<_>>();
+    let migrated = visitor.edits.iter().filter(|edit| edit.2).count();
+
+    if skipped.is_empty() && migrated > 0 {
+        // the `use expect_test::expect;` imports are now unused
+        for span in visitor.expect_imports {
+            let range = visitor.source.line_range(span);
+            visitor.edits.push((range, String::new(), false));
+        }
+    }
+
+    let mut edits = visitor.edits;
+    if edits.is_empty() {
+        return Ok(Migration {
+            source: None,
+            migrated,
+            skipped,
+        });
+    }
+    edits.sort_by_key(|(range, _, _)| range.start);
+    let mut rv = String::with_capacity(source.len());
+    let mut pos = 0;
+    for (range, replacement, _) in edits {
+        if range.start < pos {
+            continue;
+        }
+        rv.push_str(&source[pos..range.start]);
+        rv.push_str(&replacement);
+        pos = range.end;
+    }
+    rv.push_str(&source[pos..]);
+
+    Ok(Migration {
+        source: Some(rv),
+        migrated,
+        skipped,
+    })
+}
+
+/// Maps span locations to byte offsets in the source.
+struct SourceMap<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> SourceMap<'a> {
+    fn new(source: &'a str) -> SourceMap<'a> {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        SourceMap {
+            source,
+            line_starts,
+        }
+    }
+
+    fn offset(&self, location: LineColumn) -> usize {
+        let line_start = self.line_starts[location.line - 1];
+        self.source[line_start..]
+            .char_indices()
+            .nth(location.column)
+            .map_or(self.source.len(), |(idx, _)| line_start + idx)
+    }
+
+    fn range(&self, span: Span) -> Range<usize> {
+        self.offset(span.start())..self.offset(span.end())
+    }
+
+    fn text(&self, span: Span) -> &'a str {
+        &self.source[self.range(span)]
+    }
+
+    /// Returns the range of `span`, extended to cover its whole lines if
+    /// nothing else is on them.
+    fn line_range(&self, span: Span) -> Range<usize> {
+        let range = self.range(span);
+        let line_start = self.source[..range.start].rfind('\n').map_or(0, |x| x + 1);
+        let line_end = self.source[range.end..]
+            .find('\n')
+            .map_or(self.source.len(), |x| range.end + x + 1);
+        if self.source[line_start..range.start].trim().is_empty()
+            && self.source[range.end..line_end].trim().is_empty()
+        {
+            line_start..line_end
+        } else {
+            range
+        }
+    }
+}
+
+struct ExpectVisitor<'a> {
+    source: SourceMap<'a>,
+    /// Replacements, flagged if they rewrite an assertion.
+    edits: Vec<(Range<usize>, String, bool)>,
+    /// Start locations of all `expect!` invocations.
+    expect_macros: Vec<LineColumn>,
+    /// Start locations of the `expect!` invocations that were rewritten.
+    migrated_macros: Vec<LineColumn>,
+    /// Spans of `use expect_test::expect;` items.
+    expect_imports: Vec<Span>,
+}
+
+impl ExpectVisitor<'_> {
+    /// Builds the replacement for `expect.assert_eq(actual)`.
+    fn assertion(&self, expect: &syn::Macro, call: &syn::ExprMethodCall) -> Option<String> {
+        let macro_name = match call.method.to_string().as_str() {
+            "assert_eq" => "assert_snapshot",
+            "assert_debug_eq" => "assert_debug_snapshot",
+            _ => return None,
+        };
+        if call.args.len() != 1 {
+            return None;
+        }
+        let literal = match expect_literal(expect)? {
+            Some(literal) => self.source.text(literal),
+            None => "\"\"",
+        };
+        Some(format!(
+            "insta::{}!({}, @{})",
+            macro_name,
+            self.source.text(call.args[0].span()),
+            literal
+        ))
+    }
+
+    fn visit_stmts(&mut self, stmts: &[syn::Stmt]) {
+        let mut bindings = BTreeMap::new();
+        for (idx, stmt) in stmts.iter().enumerate() {
+            if let syn::Stmt::Local(local) = stmt {
+                if let (syn::Pat::Ident(pat), Some(init)) = (&local.pat, &local.init) {
+                    if let syn::Expr::Macro(mac) = &*init.expr {
+                        if is_expect_macro(&mac.mac) && init.diverge.is_none() {
+                            bindings.insert(pat.ident.to_string(), (idx, &mac.mac));
+                        }
+                    }
+                }
+            }
+        }
+
+        for (name, (binding_idx, expect)) in bindings {
+            let mut uses = Vec::new();
+            for stmt in &stmts[binding_idx + 1..] {
+                let mut finder = IdentFinder {
+                    name: &name,
+                    found: Vec::new(),
+                };
+                finder.visit_stmt(stmt);
+                uses.extend(finder.found);
+            }
+            let call = match &uses[..] {
+                [Some(call)] => call,
+                _ => continue,
+            };
+            if let Some(replacement) = self.assertion(expect, call) {
+                let range = self.source.range(call.span());
+                self.edits.push((range, replacement, true));
+                let range = self.source.line_range(stmts[binding_idx].span());
+                self.edits.push((range, String::new(), false));
+                self.migrated_macros.push(expect.span().start());
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for ExpectVisitor<'_> {
+    fn visit_item_use(&mut self, item: &'ast syn::ItemUse) {
+        if is_expect_import(&item.tree) {
+            self.expect_imports.push(item.span());
+        }
+    }
+
+    fn visit_block(&mut self, block: &'ast syn::Block) {
+        self.visit_stmts(&block.stmts);
+        syn::visit::visit_block(self, block);
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        if let syn::Expr::Macro(mac) = &*call.receiver {
+            if is_expect_macro(&mac.mac) {
+                if let Some(replacement) = self.assertion(&mac.mac, call) {
+                    let range = self.source.range(call.span());
+                    self.edits.push((range, replacement, true));
+                    self.migrated_macros.push(mac.mac.span().start());
+                }
+            }
+        }
+        syn::visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        if let Some(name) = mac.path.segments.last() {
+            if name.ident == "expect" || name.ident == "expect_file" {
+                self.expect_macros.push(mac.span().start());
+            }
+        }
+        // `expect!` calls in other macros such as `vec![...]` are not parsed
+        // by syn, so look for them in the tokens.
+        self.visit_macro_tokens(mac.tokens.clone());
+    }
+}
+
+impl ExpectVisitor<'_> {
+    fn visit_macro_tokens(&mut self, tokens: TokenStream) {
+        let tokens = tokens.into_iter().collect::<Vec<_>>();
+        for (idx, token) in tokens.iter().enumerate() {
+            match token {
+                TokenTree::Ident(ident) if ident == "expect" || ident == "expect_file" => {
+                    if matches!(tokens.get(idx + 1), Some(TokenTree::Punct(p)) if p.as_char() == '!')
+                    {
+                        self.expect_macros.push(ident.span().start());
+                    }
+                }
+                TokenTree::Group(group) => self.visit_macro_tokens(group.stream()),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Finds uses of a local binding, recording the method call if the binding
+/// is the receiver of an assertion.
+struct IdentFinder<'a, 'ast> {
+    name: &'a str,
+    found: Vec<Option<&'ast syn::ExprMethodCall>>,
+}
+
+impl<'ast> Visit<'ast> for IdentFinder<'_, 'ast> {
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        if let syn::Expr::Path(path) = &*call.receiver {
+            if path.path.is_ident(self.name) {
+                self.found.push(Some(call));
+                for arg in &call.args {
+                    self.visit_expr(arg);
+                }
+                return;
+            }
+        }
+        syn::visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_ident(&mut self, ident: &'ast proc_macro2::Ident) {
+        if ident == self.name {
+            self.found.push(None);
+        }
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        // assume the binding is used if it shows up in a macro
+        if mac
+            .tokens
+            .clone()
+            .into_iter()
+            .any(|token| contains_ident(&token, self.name))
+        {
+            self.found.push(None);
+        }
+    }
+}
+
+fn contains_ident(token: &TokenTree, name: &str) -> bool {
+    match token {
+        TokenTree::Ident(ident) => ident == name,
+        TokenTree::Group(group) => group.stream().into_iter().any(|x| contains_ident(&x, name)),
+        _ => false,
+    }
+}
+
+fn is_expect_macro(mac: &syn::Macro) -> bool {
+    mac.path
+        .segments
+        .last()
+        .map_or(false, |segment| segment.ident == "expect")
+}
+
+fn is_expect_import(tree: &syn::UseTree) -> bool {
+    match tree {
+        syn::UseTree::Path(path) if path.ident == "expect_test" => {
+            matches!(&*path.tree, syn::UseTree::Name(name) if name.ident == "expect")
+        }
+        _ => false,
+    }
+}
+
+/// Returns the span of the string literal in `expect![[...]]` or
+/// `expect![...]`, `Some(None)` if it is empty and `None` if it is not a
+/// literal.
+fn expect_literal(mac: &syn::Macro) -> Option<Option<Span>> {
+    let mut tokens = mac.tokens.clone().into_iter().collect::<Vec<_>>();
+    if let [TokenTree::Group(group)] = &tokens[..] {
+        if group.delimiter() == Delimiter::Bracket {
+            tokens = group.stream().into_iter().collect();
+        }
+    }
+    match &tokens[..] {
+        [] => Some(None),
+        [TokenTree::Literal(literal)] => {
+            let repr = literal.to_string();
+            if repr.starts_with('"') || repr.starts_with("r\"") || repr.starts_with("r#") {
+                Some(Some(literal.span()))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migrate(source: &str) -> String {
+        migrate_expect_test(source).unwrap().source.unwrap()
+    }
+
+    #[test]
+    fn test_migrate_chained() {
+        let source = r####"
+use expect_test::expect;
+
+#[test]
+fn test_parse() {
+    let actual = parse("1 + 2");
+    expect![[r#"
+        Add(1, 2)
+    "#]].assert_eq(&actual);
+    expect!["3"].assert_debug_eq(&eval(&actual));
+}
+"####;
+        insta::assert_snapshot!(migrate(source), @r####"
+        #[test]
+        fn test_parse() {
+            let actual = parse("1 + 2");
+            insta::assert_snapshot!(&actual, @r#"
+                Add(1, 2)
+            "#);
+            insta::assert_debug_snapshot!(&eval(&actual), @"3");
+        }
+        "####);
+    }
+
+    #[test]
+    fn test_migrate_binding() {
+        let source = r#"
+use expect_test::{expect, Expect};
+
+fn check(input: &str, expect: Expect) {
+    expect.assert_eq(input);
+}
+
+#[test]
+fn test_binding() {
+    let expected = expect![[]];
+    let actual = render();
+    expected.assert_eq(&actual);
+}
+
+#[test]
+fn test_helper() {
+    check("1", expect![["1"]]);
+}
+"#;
+        let migration = migrate_expect_test(source).unwrap();
+        assert_eq!(migration.migrated, 1);
+        assert_eq!(migration.skipped, vec![17]);
+        insta::assert_snapshot!(migration.source.unwrap(), @r#"
+        use expect_test::{expect, Expect};
+
+        fn check(input: &str, expect: Expect) {
+            expect.assert_eq(input);
+        }
+
+        #[test]
+        fn test_binding() {
+            let actual = render();
+            insta::assert_snapshot!(&actual, @"");
+        }
+
+        #[test]
+        fn test_helper() {
+            check("1", expect![["1"]]);
+        }
+        "#);
+    }
+
+    #[test]
+    fn test_migrate_binding_used_twice() {
+        let source = r#"
+fn test_twice() {
+    let expected = expect!["a"];
+    expected.assert_eq("a");
+    expected.assert_eq("a");
+}
+"#;
+        let migration = migrate_expect_test(source).unwrap();
+        assert_eq!(migration.migrated, 0);
+        assert!(migration.source.is_none());
+        assert_eq!(migration.skipped, vec![3]);
+    }
+}
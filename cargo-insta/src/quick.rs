@@ -0,0 +1,420 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::{Instant, SystemTime};
+
+use insta::Snapshot;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::walk::{make_snapshot_walker, FindFlags};
+
+/// The test runner that reported how long the tests took.
+///
+/// The runners name tests differently, so timings are only used with the
+/// runner they were recorded with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Runner {
+    /// libtest, which reports the time of every test only on nightly with
+    /// `-Zunstable-options --report-time`, so tests are otherwise timed from
+    /// the start of their test binary until their result is printed.  A test
+    /// is named by its path without the crate, as in `tests::test_foo`,
+    /// which selects the tests of that name in all test binaries.
+    CargoTest,
+    /// nextest, which reports the time of every test.  A test is named by its
+    /// binary id and its path, as in `my-crate::integration tests::test_foo`.
+    Nextest,
+}
+
+/// How long the tests of a workspace took in previous runs, which `cargo
+/// insta test --quick` selects tests by.
+///
+/// The timings are stored in `.insta/timings.json` in the workspace and
+/// updated after every run of `cargo insta test` whose output names the
+/// tests.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Timings {
+    runner: Runner,
+    tests: BTreeMap<String, TestTiming>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TestTiming {
+    /// How long the test took, in milliseconds.
+    millis: u64,
+    /// The files of the snapshot assertions of the test, relative to the
+    /// workspace root.
+    sources: BTreeSet<String>,
+}
+
+impl Timings {
+    /// Returns the number of timed tests.
+    pub(crate) fn len(&self) -> usize {
+        self.tests.len()
+    }
+
+    /// Loads the timings recorded with `runner` and returns them with the
+    /// time they were recorded, or `None` if they were never recorded.
+    pub(crate) fn load(path: &Path, runner: Runner) -> Option<(Timings, SystemTime)> {
+        let recorded = fs::metadata(path).and_then(|x| x.modified()).ok()?;
+        let timings: Timings = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+        if timings.runner != runner || timings.tests.is_empty() {
+            return None;
+        }
+        Some((timings, recorded))
+    }
+
+    /// Updates the timings with the durations the test runner reported, and
+    /// keeps the timings of the tests that did not run.
+    ///
+    /// The sources of the tests are taken from the assertions file, whose
+    /// lines start with the test including its crate, as in
+    /// `my_crate::tests::test_foo`, and end with the file of the assertion.
+    pub(crate) fn record(
+        path: &Path,
+        durations: &Durations,
+        assertions: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        if durations.tests.is_empty() {
+            return Ok(());
+        }
+
+        let mut sources = BTreeMap::<&str, BTreeSet<String>>::new();
+        for line in assertions.lines() {
+            if let Some((test, source)) = line.trim_end().split_once('\t') {
+                if let Some((_, path)) = test.split_once("::") {
+                    sources
+                        .entry(path)
+                        .or_default()
+                        .insert(source.replace('\\', "/"));
+                }
+            }
+        }
+
+        let mut timings = Timings::load(path, durations.runner)
+            .map(|x| x.0)
+            .unwrap_or(Timings {
+                runner: durations.runner,
+                tests: BTreeMap::new(),
+            });
+        for (test, millis) in &durations.tests {
+            let test_path = match durations.runner {
+                Runner::CargoTest => test.as_str(),
+                Runner::Nextest => test.split_once(' ').map_or(test.as_str(), |x| x.1),
+            };
+            timings.tests.insert(
+                test.clone(),
+                TestTiming {
+                    millis: *millis,
+                    sources: sources.get(test_path).cloned().unwrap_or_default(),
+                },
+            );
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+            // the timings are specific to the machine
+            let gitignore = dir.join(".gitignore");
+            if !gitignore.exists() {
+                fs::write(gitignore, "*\n")?;
+            }
+        }
+        fs::write(path, serde_json::to_string_pretty(&timings)? + "\n")?;
+        Ok(())
+    }
+
+    /// Selects the fastest `percent` of the tests, and the tests with a
+    /// source in `changed_sources`.
+    pub(crate) fn select(&self, percent: u8, changed_sources: &BTreeSet<String>) -> Vec<&str> {
+        let mut by_time = self.tests.iter().collect::<Vec<_>>();
+        by_time.sort_by_key(|(_, timing)| timing.millis);
+        let fastest = (by_time.len() * usize::from(percent) + 99) / 100;
+        let mut rv = by_time
+            .iter()
+            .enumerate()
+            .filter(|(idx, (_, timing))| {
+                *idx < fastest || !timing.sources.is_disjoint(changed_sources)
+            })
+            .map(|(_, (test, _))| test.as_str())
+            .collect::<Vec<_>>();
+        rv.sort_unstable();
+        rv
+    }
+
+    /// Returns the arguments that make the test runner run only `tests`.
+    ///
+    /// The arguments for nextest go before `--`, the ones for libtest after.
+    pub(crate) fn filter_args(&self, tests: &[&str]) -> Vec<String> {
+        match self.runner {
+            Runner::CargoTest => tests
+                .iter()
+                .map(|test| test.to_string())
+                .chain(Some("--exact".to_string()))
+                .collect(),
+            Runner::Nextest => {
+                let expr = tests
+                    .iter()
+                    .filter_map(|test| test.split_once(' '))
+                    .map(|(binary_id, test)| format!("(binary_id(={binary_id}) & test(={test}))"))
+                    .collect::<Vec<_>>()
+                    .join(" | ");
+                vec!["-E".to_string(), expr]
+            }
+        }
+    }
+
+    /// Returns the sources of the tests that changed after `since`, and the
+    /// sources of the snapshot files below `roots` that changed after it.
+    pub(crate) fn changed_sources(
+        &self,
+        workspace_root: &Path,
+        roots: &[&Path],
+        extensions: &[&str],
+        find_flags: FindFlags,
+        since: SystemTime,
+    ) -> BTreeSet<String> {
+        let changed = |path: &Path| {
+            fs::metadata(path)
+                .and_then(|x| x.modified())
+                .map_or(false, |modified| modified > since)
+        };
+        let mut rv = self
+            .tests
+            .values()
+            .flat_map(|timing| &timing.sources)
+            .filter(|source| changed(&workspace_root.join(source)))
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        for root in roots {
+            for entry in make_snapshot_walker(root, extensions, find_flags).filter_map(|e| e.ok()) {
+                let path = entry.path();
+                let is_snapshot = extensions
+                    .iter()
+                    .any(|ext| path.extension().map_or(false, |x| x == *ext));
+                if !is_snapshot || !changed(path) {
+                    continue;
+                }
+                if let Ok(snapshot) = Snapshot::from_file(path) {
+                    if let Some(source) = snapshot.metadata().source() {
+                        rv.insert(source.replace('\\', "/"));
+                    }
+                }
+            }
+        }
+        rv
+    }
+}
+
+/// The durations of the tests, collected from the output of a test runner.
+#[derive(Debug)]
+pub(crate) struct Durations {
+    runner: Runner,
+    regex: Regex,
+    /// When the current libtest binary printed `running N tests`.
+    binary_started: Option<Instant>,
+    tests: BTreeMap<String, u64>,
+}
+
+impl Durations {
+    pub(crate) fn new(runner: Runner) -> Durations {
+        let regex = match runner {
+            // test tests::test_foo ... ok <0.305s>, where the time is only
+            // printed with `--report-time`
+            Runner::CargoTest => r"^test (\S+) \.\.\. (?:ok|FAILED)(?: <(\d+(?:\.\d+)?)s>)?\s*$",
+            //     PASS [   0.305s] (1/2) my-crate::integration tests::test_foo
+            Runner::Nextest => {
+                r"^\s*(?:TRY \d+ )?(?:PASS|FAIL|LEAK|LEAK-FAIL) \[\s*(\d+(?:\.\d+)?)s\]\s+(?:\(\s*\d+/\s*\d+\)\s+)?(\S+) (\S+)\s*$"
+            }
+        };
+        Durations {
+            runner,
+            regex: Regex::new(regex).unwrap(),
+            binary_started: None,
+            tests: BTreeMap::new(),
+        }
+    }
+
+    /// Collects the duration of a test from a line of the test runner's
+    /// output that was printed at `now`, if the line reports one.
+    ///
+    /// libtest runs tests in parallel and only prints a test when it
+    /// finished, so without `--report-time` the time since the start of the
+    /// test binary overestimates how long a test took that started late.
+    pub(crate) fn feed(&mut self, line: &str, now: Instant) {
+        let line = console::strip_ansi_codes(line);
+        let starts_binary = line
+            .trim_end()
+            .strip_prefix("running ")
+            .and_then(|x| x.strip_suffix(" tests").or_else(|| x.strip_suffix(" test")))
+            .map_or(false, |x| x.parse::<u32>().is_ok());
+        if self.runner == Runner::CargoTest && starts_binary {
+            self.binary_started = Some(now);
+            return;
+        }
+        let caps = match self.regex.captures(&line) {
+            Some(caps) => caps,
+            None => return,
+        };
+        let (test, millis) = match self.runner {
+            Runner::CargoTest => {
+                let millis = match (caps.get(2), self.binary_started) {
+                    (Some(secs), _) => secs_to_millis(secs.as_str()),
+                    (None, Some(started)) => now.duration_since(started).as_millis() as u64,
+                    (None, None) => return,
+                };
+                (caps[1].to_string(), millis)
+            }
+            Runner::Nextest => (
+                format!("{} {}", &caps[2], &caps[3]),
+                secs_to_millis(&caps[1]),
+            ),
+        };
+        match self.runner {
+            // every test binary runs the tests of the same name
+            Runner::CargoTest => *self.tests.entry(test).or_default() += millis,
+            // nextest repeats failed tests in its summary
+            Runner::Nextest => {
+                self.tests.insert(test, millis);
+            }
+        }
+    }
+
+    /// Runs `proc` and collects the durations from the output the runner
+    /// reports them on, which is passed through line by line.
+    pub(crate) fn collect(&mut self, proc: &mut Command) -> io::Result<ExitStatus> {
+        let mut child;
+        let reader: Box<dyn io::Read> = match self.runner {
+            Runner::CargoTest => {
+                child = proc.stdout(Stdio::piped()).spawn()?;
+                Box::new(child.stdout.take().unwrap())
+            }
+            Runner::Nextest => {
+                child = proc.stderr(Stdio::piped()).spawn()?;
+                Box::new(child.stderr.take().unwrap())
+            }
+        };
+        let mut reader = BufReader::new(reader);
+        let mut line = Vec::new();
+        while reader.read_until(b'\n', &mut line)? > 0 {
+            // the output goes where it would have gone without collecting
+            match self.runner {
+                Runner::CargoTest => io::stdout().write_all(&line)?,
+                Runner::Nextest => io::stderr().write_all(&line)?,
+            }
+            self.feed(&String::from_utf8_lossy(&line), Instant::now());
+            line.clear();
+        }
+        child.wait()
+    }
+}
+
+fn secs_to_millis(secs: &str) -> u64 {
+    (secs.parse::<f64>().unwrap_or(0.0) * 1000.0).round() as u64
+}
+
+#[test]
+fn test_durations() {
+    let start = Instant::now();
+    let mut durations = Durations::new(Runner::CargoTest);
+    for line in [
+        "running 2 tests",
+        "test tests::test_fast ... ok <0.004s>",
+        "test tests::test_slow ... FAILED <1.250s>",
+        "test tests::test_ignored ... ignored",
+        "test tests::test_fast ... ok <0.002s>",
+    ] {
+        durations.feed(line, start);
+    }
+    assert_eq!(
+        durations.tests,
+        BTreeMap::from([
+            ("tests::test_fast".to_string(), 6),
+            ("tests::test_slow".to_string(), 1250),
+        ])
+    );
+
+    // without `--report-time`, tests are timed from the start of their binary
+    let mut durations = Durations::new(Runner::CargoTest);
+    for (line, millis) in [
+        ("running 2 tests", 0),
+        ("test tests::test_fast ... ok", 4),
+        ("test tests::test_slow ... FAILED", 300),
+        ("running 1 test", 1000),
+        ("test tests::test_fast ... ok", 1002),
+    ] {
+        durations.feed(line, start + std::time::Duration::from_millis(millis));
+    }
+    assert_eq!(
+        durations.tests,
+        BTreeMap::from([
+            ("tests::test_fast".to_string(), 6),
+            ("tests::test_slow".to_string(), 300),
+        ])
+    );
+
+    let mut durations = Durations::new(Runner::Nextest);
+    for line in [
+        "    Starting 3 tests across 2 binaries",
+        "        PASS [   0.004s] my-crate tests::test_fast",
+        "        \x1b[32mPASS\x1b[0m [   0.305s] (2/3) my-crate::integration test_fast",
+        "        FAIL [   1.250s] my-crate tests::test_slow",
+        "     Summary [   1.260s] 3 tests run: 2 passed, 1 failed, 0 skipped",
+        "        FAIL [   1.250s] my-crate tests::test_slow",
+    ] {
+        durations.feed(line, start);
+    }
+    assert_eq!(
+        durations.tests,
+        BTreeMap::from([
+            ("my-crate tests::test_fast".to_string(), 4),
+            ("my-crate tests::test_slow".to_string(), 1250),
+            ("my-crate::integration test_fast".to_string(), 305),
+        ])
+    );
+}
+
+#[test]
+fn test_select() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("timings.json");
+    let mut durations = Durations::new(Runner::Nextest);
+    for line in [
+        "PASS [   2.000s] krate tests::slow",
+        "PASS [   0.001s] krate tests::fast",
+        "PASS [   0.500s] krate::other medium",
+    ] {
+        durations.feed(line, Instant::now());
+    }
+    Timings::record(
+        &path,
+        &durations,
+        "krate::tests::slow\tsrc/lib.rs\n\
+         krate::tests::fast\tsrc/lib.rs\n\
+         other::medium\ttests/other.rs\n",
+    )
+    .unwrap();
+    assert!(Timings::load(&path, Runner::CargoTest).is_none());
+    let (timings, _) = Timings::load(&path, Runner::Nextest).unwrap();
+    assert_eq!(timings.tests["krate tests::slow"].millis, 2000);
+
+    assert_eq!(
+        timings.select(34, &BTreeSet::new()),
+        ["krate tests::fast", "krate::other medium"]
+    );
+    assert_eq!(timings.select(1, &BTreeSet::new()), ["krate tests::fast"]);
+    assert_eq!(
+        timings.select(1, &BTreeSet::from(["tests/other.rs".to_string()])),
+        ["krate tests::fast", "krate::other medium"]
+    );
+    assert_eq!(timings.select(100, &BTreeSet::new()).len(), 3);
+    assert_eq!(
+        timings.filter_args(&timings.select(34, &BTreeSet::new())),
+        [
+            "-E",
+            "(binary_id(=krate) & test(=tests::fast)) | (binary_id(=krate::other) & test(=medium))"
+        ]
+    );
+}
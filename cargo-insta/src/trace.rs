@@ -0,0 +1,98 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use console::style;
+
+/// How many `-v` flags were passed.
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+/// Whether messages are written to a log file.
+static HAS_LOG_FILE: AtomicBool = AtomicBool::new(false);
+static LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
+
+/// The level of a message about what cargo-insta does internally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Level {
+    /// Shown with `-v`: decisions about snapshots, such as which pending
+    /// snapshots were found and which inline snapshots were rewritten.
+    Debug = 1,
+    /// Shown with `-vv`: the details behind these decisions, such as every
+    /// file or directory that snapshot discovery skipped.
+    Trace = 2,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+}
+
+/// Sets up where messages go: to stderr up to the level of `verbosity`, and
+/// all of them to `log_file` as JSON lines if given.
+pub(crate) fn init(verbosity: u8, log_file: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    VERBOSITY.store(verbosity, Ordering::Relaxed);
+    if let Some(path) = log_file {
+        let file = File::create(path)
+            .map_err(|err| format!("failed to create log file {}: {err}", path.display()))?;
+        *LOG_FILE.lock().unwrap() = Some(file);
+        HAS_LOG_FILE.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+/// Returns whether messages of `level` go anywhere, so that callers can skip
+/// formatting them.
+pub(crate) fn enabled(level: Level) -> bool {
+    VERBOSITY.load(Ordering::Relaxed) >= level as u8 || HAS_LOG_FILE.load(Ordering::Relaxed)
+}
+
+/// Writes a message about the `target` part of cargo-insta, such as `walk`.
+///
+/// Use the [`debug!`](macro@crate::debug) and [`trace!`](macro@crate::trace) macros
+/// instead, which only format the message if it is enabled.
+pub(crate) fn emit(level: Level, target: &str, message: fmt::Arguments<'_>) {
+    if VERBOSITY.load(Ordering::Relaxed) >= level as u8 {
+        eprintln!("{}: [{target}] {message}", style(level.name()).dim().bold());
+    }
+    if let Some(file) = LOG_FILE.lock().unwrap().as_mut() {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |x| x.as_millis());
+        let line = serde_json::json!({
+            "time_ms": time as u64,
+            "level": level.name(),
+            "target": target,
+            "message": message.to_string(),
+        });
+        // a log that cannot be written must not fail the command
+        writeln!(file, "{line}").ok();
+    }
+}
+
+/// Writes a message shown with `-v` and in the log file.
+#[macro_export]
+macro_rules! debug {
+    ($target:expr, $($arg:tt)+) => {
+        if $crate::trace::enabled($crate::trace::Level::Debug) {
+            $crate::trace::emit($crate::trace::Level::Debug, $target, format_args!($($arg)+));
+        }
+    };
+}
+
+/// Writes a message shown with `-vv` and in the log file.
+#[macro_export]
+macro_rules! trace {
+    ($target:expr, $($arg:tt)+) => {
+        if $crate::trace::enabled($crate::trace::Level::Trace) {
+            $crate::trace::emit($crate::trace::Level::Trace, $target, format_args!($($arg)+));
+        }
+    };
+}
@@ -1,4 +1,6 @@
 use std::fmt;
+use std::path::Path;
+use std::process;
 use std::{env, error::Error};
 
 /// Close without message but exit code.
@@ -28,6 +30,23 @@ pub(crate) fn err_msg<S: Into<String>>(s: S) -> Box<dyn Error> {
     Box::new(ErrMsg(s.into()))
 }
 
+/// Runs git with `args` in `dir` and returns its output.
+pub(crate) fn git(dir: &Path, args: &[&str]) -> Result<String, Box<dyn Error>> {
+    let output = process::Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .map_err(|err| err_msg(format!("failed to run git: {err}")))?;
+    if !output.status.success() {
+        return Err(err_msg(format!(
+            "git {} failed: {}",
+            args.iter().find(|x| !x.starts_with('-')).unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
 /// `cargo-insta` version (i.e. the binary that's currently running).
 // We could put this in a lazy_static
 pub(crate) fn cargo_insta_version() -> String {
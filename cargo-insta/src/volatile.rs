@@ -0,0 +1,121 @@
+use regex::Regex;
+
+/// A kind of value that usually changes between test runs.
+pub(crate) struct VolatileValue {
+    pub(crate) name: &'static str,
+    pub(crate) pattern: &'static str,
+    pub(crate) replacement: &'static str,
+}
+
+pub(crate) const VOLATILE_VALUES: &[VolatileValue] = &[
+    VolatileValue {
+        name: "timestamps",
+        pattern: r"\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(\.\d+)?(Z|[+-]\d{2}:?\d{2})?",
+        replacement: "[timestamp]",
+    },
+    VolatileValue {
+        name: "UUIDs",
+        pattern: "[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}",
+        replacement: "[uuid]",
+    },
+    VolatileValue {
+        name: "pointers",
+        pattern: r"\b0x[0-9a-fA-F]{6,16}\b",
+        replacement: "[pointer]",
+    },
+    VolatileValue {
+        name: "temporary paths",
+        pattern: r"(/tmp|/var/folders)/[^\s\x22']+",
+        replacement: "[temp_path]",
+    },
+];
+
+impl VolatileValue {
+    pub(crate) fn regex(&self) -> Regex {
+        Regex::new(self.pattern).unwrap()
+    }
+
+    /// Returns the code that adds a filter for this value to insta settings.
+    pub(crate) fn filter_code(&self) -> String {
+        format!(
+            "settings.add_filter(r\"{}\", \"{}\");",
+            self.pattern, self.replacement
+        )
+    }
+}
+
+/// Lines of a snapshot that only changed in a volatile value.
+pub(crate) struct VolatileChange {
+    pub(crate) value: &'static VolatileValue,
+    /// One-based line numbers in the new snapshot.
+    pub(crate) lines: Vec<usize>,
+}
+
+/// Finds the changed lines between `old` and `new` that only differ in
+/// volatile values.
+///
+/// Lines are compared pairwise, so this only looks at snapshots where the
+/// number of lines stayed the same.  A line counts towards every value that
+/// changed in it, as long as the line is the same once all values are
+/// replaced.
+pub(crate) fn find_volatile_changes(old: &str, new: &str) -> Vec<VolatileChange> {
+    let old_lines = old.lines().collect::<Vec<_>>();
+    let new_lines = new.lines().collect::<Vec<_>>();
+    if old_lines.len() != new_lines.len() {
+        return vec![];
+    }
+
+    let regexes = VOLATILE_VALUES
+        .iter()
+        .map(VolatileValue::regex)
+        .collect::<Vec<_>>();
+    let normalize = |line: &str| {
+        regexes
+            .iter()
+            .zip(VOLATILE_VALUES)
+            .fold(line.to_string(), |line, (regex, value)| {
+                regex.replace_all(&line, value.replacement).into_owned()
+            })
+    };
+
+    let mut rv = VOLATILE_VALUES
+        .iter()
+        .map(|value| VolatileChange {
+            value,
+            lines: vec![],
+        })
+        .collect::<Vec<_>>();
+    for (idx, (old_line, new_line)) in old_lines.iter().zip(&new_lines).enumerate() {
+        if old_line == new_line || normalize(old_line) != normalize(new_line) {
+            continue;
+        }
+        for (regex, change) in regexes.iter().zip(rv.iter_mut()) {
+            let old_matches = regex.find_iter(old_line).map(|x| x.as_str());
+            if !old_matches.eq(regex.find_iter(new_line).map(|x| x.as_str())) {
+                change.lines.push(idx + 1);
+            }
+        }
+    }
+    rv.retain(|change| !change.lines.is_empty());
+    rv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_volatile_changes() {
+        let old = "id: 67e55044-10b1-426f-9247-bb680e5fe0c8\nname: a\nat: 2024-01-02T03:04:05Z\nptr: 0x7ffd5e8c1a20";
+        let new = "id: 0b7f1b6e-7d5f-4c1d-9c53-2f0b3f6a9e11\nname: b\nat: 2024-05-06T07:08:09Z\nptr: 0x7ffd5e8c1a20";
+        let changes = find_volatile_changes(old, new);
+        assert_eq!(
+            changes
+                .iter()
+                .map(|x| (x.value.name, x.lines.clone()))
+                .collect::<Vec<_>>(),
+            [("timestamps", vec![3]), ("UUIDs", vec![1])]
+        );
+        assert!(find_volatile_changes(old, "id: 1").is_empty());
+    }
+}
@@ -1,11 +1,11 @@
-use std::error::Error;
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 
 use ignore::overrides::OverrideBuilder;
 use ignore::{DirEntry, Walk, WalkBuilder};
 
-use crate::container::{SnapshotContainer, TextSnapshotKind};
+use crate::container::TextSnapshotKind;
+use crate::{debug, trace};
 
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct FindFlags {
@@ -23,6 +23,9 @@ fn is_hidden(entry: &DirEntry) -> bool {
 
 /// Finds all pending snapshots by searching `pending_root` and mapping paths to `target_root`.
 ///
+/// Returns the pending file, the file it's accepted into, and the kind of
+/// snapshot.
+///
 /// # Path Structure
 ///
 /// Pending snapshots maintain the same directory structure relative to their root.
@@ -47,16 +50,27 @@ fn is_hidden(entry: &DirEntry) -> bool {
 /// When `INSTA_PENDING_DIR` is set, pending snapshots are written to a separate directory
 /// (e.g., Bazel's output directory) while the source tree remains read-only. The structure
 /// is preserved, so we can map back: `pending_root/relative/path` → `target_root/relative/path`.
-pub(crate) fn find_pending_snapshots<'a>(
+pub(crate) fn find_pending_snapshot_files<'a>(
     pending_root: &'a Path,
     target_root: &'a Path,
     extensions: &'a [&'a str],
     flags: FindFlags,
-) -> impl Iterator<Item = Result<SnapshotContainer, Box<dyn Error>>> + 'a {
+) -> impl Iterator<Item = (PathBuf, PathBuf, TextSnapshotKind)> + 'a {
     let pending_root_owned = pending_root.to_path_buf();
     let target_root_owned = target_root.to_path_buf();
+    debug!(
+        "walk",
+        "searching pending snapshots in {}",
+        pending_root.display()
+    );
     make_snapshot_walker(pending_root, extensions, flags)
-        .filter_map(Result::ok)
+        .filter_map(|entry| match entry {
+            Ok(entry) => Some(entry),
+            Err(err) => {
+                debug!("walk", "failed to read directory entry: {err}");
+                None
+            }
+        })
         .filter_map(move |entry| {
             let fname = entry.file_name().to_string_lossy();
             let pending_path = entry.clone().into_path();
@@ -68,24 +82,21 @@ pub(crate) fn find_pending_snapshots<'a>(
                 Some(target_root_owned.join(relative).with_file_name(new_fname))
             };
 
-            #[allow(clippy::manual_map)]
             if let Some(new_fname) = fname.strip_suffix(".new") {
                 let target_path = compute_target(new_fname)?;
-                Some(SnapshotContainer::load(
-                    pending_path,
-                    target_path,
-                    TextSnapshotKind::File,
-                ))
+                debug!("walk", "found pending snapshot {}", pending_path.display());
+                Some((pending_path, target_path, TextSnapshotKind::File))
             } else if let Some(new_fname) = fname
                 .strip_prefix('.')
                 .and_then(|f| f.strip_suffix(".pending-snap"))
             {
                 let target_path = compute_target(new_fname)?;
-                Some(SnapshotContainer::load(
-                    pending_path,
-                    target_path,
-                    TextSnapshotKind::Inline,
-                ))
+                debug!(
+                    "walk",
+                    "found pending inline snapshots {}",
+                    pending_path.display()
+                );
+                Some((pending_path, target_path, TextSnapshotKind::Inline))
             } else {
                 None
             }
@@ -94,7 +105,8 @@ pub(crate) fn find_pending_snapshots<'a>(
 
 /// Creates a walker for snapshots & pending snapshots within a directory. The
 /// walker returns snapshots ending in any of the supplied extensions, any of
-/// the supplied extensions with a `.new` suffix, and `.pending-snap` files.
+/// the supplied extensions with a `.new` suffix, pending snapshots with an
+/// extension of their own such as `.snap.sql.new`, and `.pending-snap` files.
 pub(crate) fn make_snapshot_walker(root: &Path, extensions: &[&str], flags: FindFlags) -> Walk {
     let mut builder = WalkBuilder::new(root);
     builder.standard_filters(!flags.include_ignored);
@@ -106,7 +118,13 @@ pub(crate) fn make_snapshot_walker(root: &Path, extensions: &[&str], flags: Find
 
     extensions
         .iter()
-        .flat_map(|ext| [format!("*.{ext}"), format!("*.{ext}.new")])
+        .flat_map(|ext| {
+            [
+                format!("*.{ext}"),
+                format!("*.{ext}.new"),
+                format!("*.{ext}.*.new"),
+            ]
+        })
         .chain(std::iter::once("*.pending-snap".to_string()))
         .for_each(|pattern| {
             override_builder.add(&pattern).unwrap();
@@ -119,6 +137,11 @@ pub(crate) fn make_snapshot_walker(root: &Path, extensions: &[&str], flags: Find
     builder.filter_entry(move |entry| {
         // Always skip `target` directories
         if entry.path().file_name() == Some(OsStr::new("target")) {
+            trace!(
+                "walk",
+                "skipping target directory {}",
+                entry.path().display()
+            );
             return false;
         }
         // Skip nested crates (directories with Cargo.toml that aren't the search root).
@@ -128,11 +151,17 @@ pub(crate) fn make_snapshot_walker(root: &Path, extensions: &[&str], flags: Find
             && entry.path().join("Cargo.toml").exists()
             && entry.path() != root_path
         {
+            trace!("walk", "skipping nested crate {}", entry.path().display());
             return false;
         }
         // Skip hidden directories (unless include_hidden), but always allow files
         if !include_hidden && !entry.file_type().map_or(false, |x| x.is_file()) && is_hidden(entry)
         {
+            trace!(
+                "walk",
+                "skipping hidden directory {} (pass --include-hidden to search it)",
+                entry.path().display()
+            );
             return false;
         }
 
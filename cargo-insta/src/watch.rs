@@ -0,0 +1,189 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::cargo::Package;
+
+/// Watches a set of directories for changes by polling modification times.
+///
+/// Polling keeps cargo-insta free of platform specific file notification
+/// APIs and is fast enough for the size of a typical crate.
+pub(crate) struct Watcher {
+    roots: Vec<PathBuf>,
+    files: BTreeMap<PathBuf, SystemTime>,
+}
+
+impl Watcher {
+    pub(crate) fn new(roots: Vec<PathBuf>) -> Watcher {
+        let files = scan(&roots);
+        Watcher { roots, files }
+    }
+
+    /// Blocks until files change and returns the changed, added and removed
+    /// files.
+    ///
+    /// Once a change is seen, the watcher waits for another `interval` so that
+    /// editors saving several files at once cause a single run.
+    pub(crate) fn wait(&mut self, interval: Duration) -> Vec<PathBuf> {
+        loop {
+            std::thread::sleep(interval);
+            if !self.changes().is_empty() {
+                std::thread::sleep(interval);
+                let changes = self.changes();
+                self.files = scan(&self.roots);
+                if !changes.is_empty() {
+                    return changes;
+                }
+            }
+        }
+    }
+
+    fn changes(&self) -> Vec<PathBuf> {
+        let files = scan(&self.roots);
+        files
+            .iter()
+            .filter(|(path, mtime)| self.files.get(*path) != Some(mtime))
+            .map(|(path, _)| path)
+            .chain(self.files.keys().filter(|path| !files.contains_key(*path)))
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Returns the modification times of all files below `roots`.
+///
+/// Build output and snapshots are skipped: snapshot files are written by the
+/// test run itself and would otherwise trigger the next run.
+fn scan(roots: &[PathBuf]) -> BTreeMap<PathBuf, SystemTime> {
+    let mut rv = BTreeMap::new();
+    for root in roots {
+        let mut walker = ignore::WalkBuilder::new(root);
+        walker.filter_entry(|entry| entry.file_name() != "target");
+        for entry in walker.build().flatten() {
+            if !entry.file_type().map_or(false, |x| x.is_file()) || is_snapshot_file(entry.path()) {
+                continue;
+            }
+            if let Some(mtime) = entry.metadata().ok().and_then(|x| x.modified().ok()) {
+                rv.insert(entry.path().to_path_buf(), mtime);
+            }
+        }
+    }
+    rv
+}
+
+fn is_snapshot_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|x| x.to_str())
+        .map_or(false, |name| {
+            name.ends_with(".snap") || name.contains(".snap.") || name.ends_with(".pending-snap")
+        })
+}
+
+/// Returns the names of the packages whose tests can be affected by changes
+/// to `changed`, or `None` if all packages need to run.
+///
+/// These are the packages containing the changed files and the packages that
+/// depend on them.
+pub(crate) fn affected_packages(packages: &[Package], changed: &[PathBuf]) -> Option<Vec<String>> {
+    let mut affected = BTreeSet::new();
+    for path in changed {
+        let package = packages
+            .iter()
+            .filter_map(|package| Some((package, package.manifest_path.parent()?)))
+            .filter(|(_, root)| path.starts_with(root.as_std_path()))
+            .max_by_key(|(_, root)| root.as_str().len())
+            .map(|(package, _)| package)?;
+        affected.insert(package.name.clone());
+    }
+
+    loop {
+        let dependents = packages
+            .iter()
+            .filter(|package| !affected.contains(&package.name))
+            .filter(|package| {
+                package
+                    .dependencies
+                    .iter()
+                    .any(|dep| affected.contains(&dep.name))
+            })
+            .map(|package| package.name.clone())
+            .collect::<Vec<_>>();
+        if dependents.is_empty() {
+            break;
+        }
+        affected.extend(dependents);
+    }
+    Some(affected.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, root: &str, dependencies: &[&str]) -> Package {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "version": "0.1.0",
+            "id": name,
+            "dependencies": dependencies.iter().map(|dep| serde_json::json!({
+                "name": dep,
+                "req": "*",
+                "kind": null,
+                "optional": false,
+                "uses_default_features": true,
+                "features": [],
+                "target": null,
+                "rename": null,
+                "registry": null,
+                "source": null,
+            })).collect::<Vec<_>>(),
+            "targets": [],
+            "features": {},
+            "manifest_path": format!("{root}/Cargo.toml"),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_affected_packages() {
+        let packages = vec![
+            package("core", "/ws/core", &[]),
+            package("core-macros", "/ws/core/macros", &[]),
+            package("app", "/ws/app", &["core"]),
+            package("cli", "/ws/cli", &["app"]),
+        ];
+        let affected = |paths: &[&str]| {
+            affected_packages(
+                &packages,
+                &paths.iter().map(PathBuf::from).collect::<Vec<_>>(),
+            )
+        };
+
+        assert_eq!(
+            affected(&["/ws/core/src/lib.rs"]).unwrap(),
+            ["app", "cli", "core"]
+        );
+        assert_eq!(
+            affected(&["/ws/core/macros/src/lib.rs"]).unwrap(),
+            ["core-macros"]
+        );
+        assert_eq!(
+            affected(&["/ws/cli/tests/fixtures/input.txt"]).unwrap(),
+            ["cli"]
+        );
+        assert_eq!(affected(&["/ws/README.md"]), None);
+    }
+
+    #[test]
+    fn test_is_snapshot_file() {
+        assert!(is_snapshot_file(Path::new("src/snapshots/a__b.snap")));
+        assert!(is_snapshot_file(Path::new("src/snapshots/a__b.snap.new")));
+        assert!(is_snapshot_file(Path::new(
+            "src/snapshots/a__b.snap.new.png"
+        )));
+        assert!(is_snapshot_file(Path::new("src/.lib.rs.pending-snap")));
+        assert!(!is_snapshot_file(Path::new("src/lib.rs")));
+    }
+}
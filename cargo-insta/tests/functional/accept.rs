@@ -0,0 +1,73 @@
+use std::fs;
+use std::process::Stdio;
+
+use crate::TestFiles;
+
+/// `cargo insta accept` accepts the pending files of many file and inline
+/// snapshots at once and lists them in the order of a review.
+#[test]
+fn test_accept_many_files() {
+    let mut test_files = TestFiles::new()
+        .add_cargo_toml("test_accept_many_files")
+        .add_file("src/lib.rs", "mod a;\nmod b;\nmod c;\n".to_string());
+    for module in ["a", "b", "c"] {
+        test_files = test_files.add_file(
+            format!("src/{module}.rs"),
+            r#"
+#[test]
+fn test_items() {
+    for idx in 0..12 {
+        insta::assert_snapshot!(format!("item_{idx}"), idx.to_string());
+    }
+    insta::assert_snapshot!("first", @"");
+    insta::assert_snapshot!("second", @"");
+}
+"#
+            .to_string(),
+        );
+    }
+    let test_project = test_files.create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .arg("test")
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let output = test_project
+        .insta_cmd()
+        .arg("accept")
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let accepted = stdout
+        .lines()
+        .filter(|line| line.starts_with("  "))
+        .collect::<Vec<_>>();
+    assert_eq!(accepted.len(), 3 * 14, "{stdout}");
+    let position = |item: &str| accepted.iter().position(|x| x.contains(item)).unwrap();
+    assert!(position("a.rs (item_0)") < position("b.rs (item_0)"));
+    assert!(position("b.rs (item_0)") < position("c.rs (item_0)"));
+
+    let snapshots = test_project.workspace_dir.join("src/snapshots");
+    for module in ["a", "b", "c"] {
+        for idx in 0..12 {
+            let snapshot = fs::read_to_string(
+                snapshots.join(format!("test_accept_many_files__{module}__item_{idx}.snap")),
+            )
+            .unwrap();
+            assert!(snapshot.ends_with(&format!("---\n{idx}\n")), "{snapshot}");
+        }
+        let source =
+            fs::read_to_string(test_project.workspace_dir.join(format!("src/{module}.rs")))
+                .unwrap();
+        assert!(source.contains(r#"insta::assert_snapshot!("first", @"first");"#));
+        assert!(source.contains(r#"insta::assert_snapshot!("second", @"second");"#));
+    }
+    assert!(!test_project.file_tree_diff().contains(".new"));
+    assert!(!test_project.file_tree_diff().contains(".pending-snap"));
+}
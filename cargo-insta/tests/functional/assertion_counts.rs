@@ -0,0 +1,43 @@
+use std::process::Stdio;
+
+use crate::TestFiles;
+
+/// `cargo insta test` reports how many snapshot assertions the tests made.
+#[test]
+fn test_assertion_counts() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_assertion_counts")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_one() {
+    insta::assert_snapshot!("one", @"one");
+}
+
+#[test]
+fn test_three() {
+    insta::assert_snapshot!("one", @"one");
+    insta::assert_snapshot!("two", @"two");
+    insta::assert_snapshot!("three", @"three");
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .arg("test")
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(
+            "info: 4 snapshot assertions in 2 tests, most in test_assertion_counts::test_three (3)"
+        ),
+        "{stdout}"
+    );
+}
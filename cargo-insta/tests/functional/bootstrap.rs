@@ -0,0 +1,89 @@
+//! Tests for `cargo insta bootstrap`.
+
+use std::fs;
+use std::process::Stdio;
+
+use crate::TestFiles;
+
+#[test]
+fn test_bootstrap() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_bootstrap")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_created() {
+    insta::assert_snapshot!("created", "created_at: 2024-01-02T03:04:05Z");
+}
+
+#[test]
+fn test_updated() {
+    insta::assert_snapshot!("updated", "updated_at: 2025-06-07T08:09:10Z");
+}
+
+#[test]
+fn test_inline() {
+    insta::assert_snapshot!("Hello, World!", @"");
+}
+
+#[test]
+fn test_changed() {
+    insta::assert_snapshot!("changed", "new");
+}
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/snapshots/test_bootstrap__changed.snap",
+            "---\nsource: src/lib.rs\nexpression: \"\\\"new\\\"\"\n---\nold\n".to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["bootstrap"])
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(&output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("3 created snapshots:\n  src/lib.rs: 3\n"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("1 changed snapshots:\n  src/lib.rs: 1\n"),
+        "{stdout}"
+    );
+
+    // new snapshots are accepted, the changed one is left for review
+    let snapshots = test_project.workspace_dir.join("src/snapshots");
+    assert!(snapshots.join("test_bootstrap__created.snap").exists());
+    assert!(snapshots.join("test_bootstrap__updated.snap").exists());
+    assert!(snapshots.join("test_bootstrap__changed.snap.new").exists());
+    assert!(
+        fs::read_to_string(test_project.workspace_dir.join("src/lib.rs"))
+            .unwrap()
+            .contains(r#"@"Hello, World!""#)
+    );
+
+    let config = fs::read_to_string(test_project.workspace_dir.join("insta.yaml")).unwrap();
+    assert!(config.contains("  unreferenced: warn\n"), "{config}");
+    assert!(
+        config.contains("#   // timestamps in 2 snapshots\n#   settings.add_filter("),
+        "{config}"
+    );
+
+    // the config is valid and kept on the next run
+    let output = test_project
+        .insta_cmd()
+        .args(["bootstrap"])
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(&output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("keeping the existing"), "{stdout}");
+}
@@ -0,0 +1,70 @@
+//! Tests for limiting diff output on CI.
+
+use std::fs;
+use std::process::Stdio;
+
+use crate::{target_dir, TestFiles};
+
+#[test]
+fn test_ci_diff_budget() {
+    let old: String = (0..100).map(|i| format!("old line {i}\n")).collect();
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_ci_diff_budget")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_long() {
+    let value: String = (0..100).map(|i| format!("new line {i}\n")).collect();
+    insta::assert_snapshot!("ci_diff_budget_long", value);
+}
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/snapshots/test_ci_diff_budget__ci_diff_budget_long.snap",
+            format!("---\nsource: src/lib.rs\nexpression: value\n---\n{old}"),
+        )
+        .create_project();
+
+    let report_file = target_dir()
+        .join("insta-reports")
+        .join("test_ci_diff_budget__ci_diff_budget_long.diff");
+    let _ = fs::remove_file(&report_file);
+    let preview_file = report_file.with_extension("html");
+    let _ = fs::remove_file(&preview_file);
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--check"])
+        .env("CI", "1")
+        .env("INSTA_CI_DIFF_BUDGET", "100")
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("diff truncated after 100 bytes, full diff:"),
+        "{stdout}"
+    );
+    assert!(!stdout.contains("new line 50"), "{stdout}");
+
+    let report = fs::read_to_string(&report_file).unwrap();
+    assert!(report.contains("-old line 99"), "{report}");
+    assert!(report.contains("+new line 99"), "{report}");
+
+    let preview = fs::read_to_string(&preview_file).unwrap();
+    assert!(
+        preview.contains("diff truncated here after 100 bytes"),
+        "{preview}"
+    );
+    assert!(
+        preview.contains(
+            "<tr class=\"insert\"><td class=\"line\"></td><td class=\"line\">100</td>\
+             <td>+</td><td><pre><mark>new</mark> line 99</pre></td></tr>"
+        ),
+        "{preview}"
+    );
+}
@@ -0,0 +1,81 @@
+use std::io::Write;
+use std::process::Stdio;
+
+use crate::TestFiles;
+
+/// Snapshots matching a `review.confirm` pattern are only accepted once the
+/// question is answered with "y".
+#[test]
+fn test_confirm_before_accept() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_confirm_before_accept")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_api() {
+    insta::assert_snapshot!("api", "GET /users");
+}
+
+#[test]
+fn test_other() {
+    insta::assert_snapshot!("other", "hello");
+}
+"#
+            .to_string(),
+        )
+        .add_file(
+            "insta.yaml",
+            r#"
+review:
+  confirm:
+    - path: "src/snapshots/*__api.snap"
+      prompt: "Does this API change have a changelog entry?"
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let accept = |answer: &str| {
+        let mut child = test_project
+            .insta_cmd()
+            .args(["test", "--accept"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(answer.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    let snapshots = test_project.workspace_dir.join("src/snapshots");
+
+    let stdout = accept("n\n");
+    assert!(
+        stdout.contains("Does this API change have a changelog entry? [y/N]"),
+        "{stdout}"
+    );
+    assert!(snapshots
+        .join("test_confirm_before_accept__other.snap")
+        .exists());
+    assert!(!snapshots
+        .join("test_confirm_before_accept__api.snap")
+        .exists());
+    assert!(snapshots
+        .join("test_confirm_before_accept__api.snap.new")
+        .exists());
+
+    accept("y\n");
+    assert!(snapshots
+        .join("test_confirm_before_accept__api.snap")
+        .exists());
+    assert!(!snapshots
+        .join("test_confirm_before_accept__api.snap.new")
+        .exists());
+}
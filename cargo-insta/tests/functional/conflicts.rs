@@ -0,0 +1,79 @@
+use std::fs;
+use std::process::Stdio;
+
+use crate::TestFiles;
+
+/// Pending snapshots are not accepted over a reference that changed after
+/// the test ran, until the test is run again.
+#[test]
+fn test_accept_skips_changed_reference() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_accept_skips_changed_reference")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_value() {
+    insta::assert_snapshot!("value", "new value");
+}
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/snapshots/test_accept_skips_changed_reference__value.snap",
+            "---\nsource: src/lib.rs\nexpression: \"\\\"new value\\\"\"\n---\nold value\n"
+                .to_string(),
+        )
+        .create_project();
+    let snapshot = test_project
+        .workspace_dir
+        .join("src/snapshots/test_accept_skips_changed_reference__value.snap");
+    let pending = snapshot.with_extension("snap.new");
+
+    let output = test_project
+        .insta_cmd()
+        .arg("test")
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    // only the hash of the reference is kept, the base of a three-way merge
+    // is read from git
+    let pending_contents = fs::read_to_string(&pending).unwrap();
+    assert!(pending_contents.contains("reference_hash: "));
+    assert!(!pending_contents.contains("old value"));
+
+    // the reference is updated, e.g. by a `git pull`
+    let pulled = fs::read_to_string(&snapshot)
+        .unwrap()
+        .replace("old value", "pulled value");
+    fs::write(&snapshot, &pulled).unwrap();
+
+    let output = test_project
+        .insta_cmd()
+        .arg("accept")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("conflicts: the reference snapshots changed after the tests ran:"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("  src/lib.rs (value"), "{stderr}");
+    assert_eq!(fs::read_to_string(&snapshot).unwrap(), pulled);
+    assert!(pending.exists());
+
+    // once the test ran against the new reference, it can be accepted
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept"])
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(fs::read_to_string(&snapshot).unwrap().contains("new value"));
+    assert!(!pending.exists());
+}
@@ -0,0 +1,117 @@
+use std::fs;
+
+use insta::assert_snapshot;
+
+use crate::TestFiles;
+
+/// `cargo insta expand` stores macro expansions as snapshots and only
+/// reports them again once the expansion changes.
+#[test]
+fn test_expand_rustc_backend() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_expand_rustc_backend")
+        .add_file(
+            "src/lib.rs",
+            r#"
+macro_rules! answer {
+    ($name:ident) => {
+        pub fn $name() -> u32 {
+            42
+        }
+    };
+}
+
+answer!(answer);
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    // a new expansion is left for review
+    assert!(!&test_project
+        .insta_cmd()
+        .args(["expand", "--backend", "rustc"])
+        .output()
+        .unwrap()
+        .status
+        .success());
+
+    assert_snapshot!(test_project.file_tree_diff(), @"
+    --- Original file tree
+    +++ Updated file tree
+    @@ -1,3 +1,6 @@
+    +  Cargo.lock
+       Cargo.toml
+    +  snapshots
+    +    snapshots/expand__lib.snap.new
+       src
+         src/lib.rs
+    ");
+
+    assert!(&test_project
+        .insta_cmd()
+        .args(["accept"])
+        .output()
+        .unwrap()
+        .status
+        .success());
+
+    let snapshot = fs::read_to_string(
+        test_project
+            .workspace_dir
+            .join("snapshots/expand__lib.snap"),
+    )
+    .unwrap();
+    assert!(snapshot.starts_with(
+        "---\nsource: src/lib.rs\nexpression: cargo rustc --lib -- -Zunpretty=expanded\n---\n"
+    ));
+    assert!(
+        snapshot.contains("pub fn answer() -> u32 { 42 }"),
+        "{snapshot}"
+    );
+
+    // an unchanged expansion passes
+    assert!(&test_project
+        .insta_cmd()
+        .args(["expand", "--backend", "rustc", "--check"])
+        .output()
+        .unwrap()
+        .status
+        .success());
+
+    // a changed expansion fails with `--check` without writing anything
+    test_project.update_file("src/lib.rs", "pub fn answer() -> u32 { 43 }\n".to_string());
+    assert!(!&test_project
+        .insta_cmd()
+        .args(["expand", "--backend", "rustc", "--check"])
+        .output()
+        .unwrap()
+        .status
+        .success());
+    assert!(!test_project
+        .workspace_dir
+        .join("snapshots/expand__lib.snap.new")
+        .exists());
+}
+
+/// Selecting items needs `cargo expand`, which the rustc backend can't do.
+#[test]
+fn test_expand_items_require_cargo_expand() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_expand_items_require_cargo_expand")
+        .add_file("src/lib.rs", "pub mod inner {}\n".to_string())
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["expand", "--backend", "rustc", "inner"])
+        .stdout(std::process::Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("requires cargo-expand"),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
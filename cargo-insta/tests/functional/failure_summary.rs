@@ -0,0 +1,112 @@
+//! Tests for the summary of failed assertions printed when `cargo insta test`
+//! fails.
+
+use std::process::Stdio;
+
+use crate::TestFiles;
+
+#[test]
+fn test_failure_summary_lists_all_failures() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_failure_summary")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_first() {
+    insta::assert_snapshot!("first", "new value");
+}
+
+#[test]
+fn test_second() {
+    insta::assert_snapshot!("new value", @"old value");
+}
+
+#[test]
+fn test_passing() {
+    insta::assert_snapshot!("value", @"value");
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--check", "--", "--test-threads=1"])
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let summary = &stderr[stderr
+        .find("2 snapshot assertions failed:")
+        .unwrap_or_else(|| panic!("no failure summary in stderr: {stderr}"))..];
+    assert!(summary.contains("first   src/lib.rs:4"), "{summary}");
+    assert!(summary.contains("second  src/lib.rs:9"), "{summary}");
+    assert!(!summary.contains("src/lib.rs:14"), "{summary}");
+}
+
+#[test]
+fn test_no_failure_summary_for_single_failure() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_no_failure_summary")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_only() {
+    insta::assert_snapshot!("new value", @"old value");
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--check"])
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("snapshot assertions failed:"), "{stderr}");
+}
+
+#[test]
+fn test_no_failure_summary_for_expected_failures() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_no_failure_summary_expected")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+#[should_panic]
+fn test_a() {
+    insta::assert_snapshot!("new value", @"old value");
+}
+
+#[test]
+#[should_panic]
+fn test_b() {
+    insta::assert_snapshot!("new value", @"old value");
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--check"])
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("snapshot assertions failed:"), "{stderr}");
+}
@@ -0,0 +1,143 @@
+//! Tests for `INSTA_UPDATE=forbid`.
+
+use std::process::{Command, Stdio};
+
+use crate::{target_dir, TestFiles, TestProject};
+
+#[test]
+fn test_forbid_missing_snapshot() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_forbid_missing")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_existing() {
+    insta::assert_snapshot!("existing", "Hello, World!");
+}
+
+#[test]
+fn test_missing() {
+    insta::assert_snapshot!("missing", "Hello, World!");
+}
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/snapshots/test_forbid_missing__existing.snap",
+            "---\nsource: src/lib.rs\nexpression: \"\\\"Hello, World!\\\"\"\n---\nHello, World!\n"
+                .to_string(),
+        )
+        .add_file(
+            "src/snapshots/test_forbid_missing__existing.snap.new",
+            "---\nsource: src/lib.rs\nexpression: \"\\\"Hello, World!\\\"\"\n---\nstale\n"
+                .to_string(),
+        )
+        .create_project();
+
+    let mut cargo_cmd = Command::new("cargo");
+    TestProject::clean_env(&mut cargo_cmd);
+    let output = cargo_cmd
+        .args(["test", "--", "--test-threads=1"])
+        .current_dir(&test_project.workspace_dir)
+        .env("CARGO_TARGET_DIR", target_dir())
+        .env("INSTA_UPDATE", "forbid")
+        .env("INSTA_FORCE_PASS", "1")
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(
+            "snapshot assertion for 'missing' at src/lib.rs:9 has no stored snapshot and \
+             INSTA_UPDATE=forbid does not allow creating one"
+        ),
+        "{stdout}"
+    );
+
+    // nothing was written and the pending snapshot of the passing assertion
+    // was left alone
+    let snapshots = test_project.workspace_dir.join("src/snapshots");
+    assert!(!snapshots.join("test_forbid_missing__missing.snap").exists());
+    assert!(!snapshots
+        .join("test_forbid_missing__missing.snap.new")
+        .exists());
+    assert!(snapshots
+        .join("test_forbid_missing__existing.snap.new")
+        .exists());
+}
+
+/// `cargo insta test` passes `INSTA_UPDATE=forbid` on to the tests.
+#[test]
+fn test_forbid_missing_snapshot_insta_test() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_forbid_missing_insta_test")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_missing() {
+    insta::assert_snapshot!("missing", "Hello, World!");
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test"])
+        .env("INSTA_UPDATE", "forbid")
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(
+            "snapshot assertion for 'missing' at src/lib.rs:4 has no stored snapshot and \
+             INSTA_UPDATE=forbid does not allow creating one"
+        ),
+        "{stdout}"
+    );
+    let snapshots = test_project.workspace_dir.join("src/snapshots");
+    assert!(!snapshots
+        .join("test_forbid_missing_insta_test__missing.snap.new")
+        .exists());
+}
+
+#[test]
+fn test_forbid_rejects_accept() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_forbid_accept")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_snapshot() {
+    insta::assert_snapshot!("Hello, World!", @"");
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept"])
+        .env("INSTA_UPDATE", "forbid")
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("INSTA_UPDATE=forbid does not allow accepting"),
+        "{stdout}"
+    );
+    assert_eq!(test_project.diff("src/lib.rs"), "");
+}
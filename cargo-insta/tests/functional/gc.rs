@@ -0,0 +1,159 @@
+use std::process::{Command, Stdio};
+
+use insta::assert_snapshot;
+
+use crate::TestFiles;
+
+fn git(test_project: &crate::TestProject, args: &[&str]) {
+    let output = Command::new("git")
+        .current_dir(&test_project.workspace_dir)
+        .args([
+            "-c",
+            "user.name=insta",
+            "-c",
+            "user.email=insta@example.com",
+        ])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+}
+
+/// `gc` finds the snapshots of tests that were removed since a revision.
+#[test]
+fn test_gc_removed_tests() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_gc_removed_tests")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_kept() {
+    insta::assert_snapshot!("kept");
+}
+
+#[test]
+fn test_removed() {
+    insta::assert_snapshot!("removed");
+    insta::assert_snapshot!("removed again");
+}
+"#
+            .to_string(),
+        )
+        // a test of the same name in another file keeps its snapshot
+        .add_file(
+            "tests/other.rs",
+            r#"
+#[test]
+fn test_removed() {
+    insta::assert_snapshot!("other");
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept", "--", "--nocapture"])
+        .output()
+        .unwrap();
+    assert!(&output.status.success());
+
+    git(&test_project, &["init"]);
+    git(&test_project, &["add", "."]);
+    git(&test_project, &["commit", "-m", "initial"]);
+
+    test_project.update_file(
+        "src/lib.rs",
+        r#"
+#[test]
+fn test_kept() {
+    insta::assert_snapshot!("kept");
+}
+"#
+        .to_string(),
+    );
+
+    let output = test_project
+        .insta_cmd()
+        .args(["gc", "--since", "HEAD"])
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(&output.status.success());
+    assert_snapshot!(String::from_utf8_lossy(&output.stderr), @r"
+    info: snapshots of tests removed since HEAD:
+      src/snapshots/test_gc_removed_tests__removed-2.snap
+      src/snapshots/test_gc_removed_tests__removed.snap
+    hint: run with `--delete` to delete them
+    ");
+
+    let output = test_project
+        .insta_cmd()
+        .args(["gc", "--since", "HEAD", "--delete"])
+        .output()
+        .unwrap();
+    assert!(&output.status.success());
+    let snapshots = test_project.workspace_dir.join("src/snapshots");
+    assert!(snapshots.join("test_gc_removed_tests__kept.snap").exists());
+    assert!(!snapshots
+        .join("test_gc_removed_tests__removed.snap")
+        .exists());
+    assert!(!snapshots
+        .join("test_gc_removed_tests__removed-2.snap")
+        .exists());
+    assert!(test_project
+        .workspace_dir
+        .join("tests/snapshots/other__removed.snap")
+        .exists());
+}
+
+/// Snapshots are matched to the files of the git diff when the workspace is
+/// reached through a symlink.
+#[cfg(unix)]
+#[test]
+fn test_gc_symlinked_workspace() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_gc_symlinked_workspace")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_removed() {
+    insta::assert_snapshot!("removed");
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept"])
+        .output()
+        .unwrap();
+    assert!(&output.status.success());
+
+    git(&test_project, &["init"]);
+    git(&test_project, &["add", "."]);
+    git(&test_project, &["commit", "-m", "initial"]);
+    test_project.update_file("src/lib.rs", "".to_string());
+
+    let link = tempfile::tempdir().unwrap();
+    let workspace = link.path().join("workspace");
+    std::os::unix::fs::symlink(&test_project.workspace_dir, &workspace).unwrap();
+    let output = test_project
+        .insta_cmd()
+        .args(["gc", "--since", "HEAD", "--manifest-path"])
+        .arg(workspace.join("Cargo.toml"))
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(&output.status.success());
+    assert_snapshot!(String::from_utf8_lossy(&output.stderr), @r"
+    info: snapshots of tests removed since HEAD:
+      src/snapshots/test_gc_symlinked_workspace__removed.snap
+    hint: run with `--delete` to delete them
+    ");
+}
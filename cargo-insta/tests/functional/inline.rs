@@ -604,3 +604,199 @@ fn test_cr_preserved() {
         combined
     );
 }
+
+#[test]
+fn test_inline_case_attribute() {
+    let test_project = TestFiles::new()
+        .add_file(
+            "Cargo.toml",
+            r#"
+[package]
+name = "test_inline_case_attribute"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+insta = { path = '$PROJECT_PATH', features=["macros"] }
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[insta::cases]
+#[case(input = "hello", expected = @"HELLO")]
+#[case(input = "world", expected = @"")]
+#[case(input = "multiple\nlines", expected = @"old")]
+fn test_uppercase(input: &str, expected: insta::InlineCase) {
+    insta::assert_snapshot!(input.to_uppercase(), @expected);
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept", "--", "--nocapture"])
+        .output()
+        .unwrap();
+
+    assert!(&output.status.success());
+
+    assert_snapshot!(test_project.diff("src/lib.rs"), @r#"
+    --- Original: src/lib.rs
+    +++ Updated: src/lib.rs
+    @@ -1,8 +1,11 @@
+     
+     #[insta::cases]
+     #[case(input = "hello", expected = @"HELLO")]
+    -#[case(input = "world", expected = @"")]
+    -#[case(input = "multiple\nlines", expected = @"old")]
+    +#[case(input = "world", expected = @"WORLD")]
+    +#[case(input = "multiple\nlines", expected = @"
+    +MULTIPLE
+    +LINES
+    +")]
+     fn test_uppercase(input: &str, expected: insta::InlineCase) {
+         insta::assert_snapshot!(input.to_uppercase(), @expected);
+     }
+    "#);
+}
+
+#[test]
+fn test_inline_mixed_tab_indentation() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_inline_mixed_tab_indentation")
+        .add_file("insta.yaml", "behavior:\n  indent_width: 8\n".to_string())
+        .add_file(
+            "src/lib.rs",
+            "
+#[test]
+fn test_mixed() {
+\tinsta::assert_snapshot!(\"a\\n  b\", @\"
+\ta
+          b
+\t\");
+}
+
+#[test]
+fn test_new() {
+\tinsta::assert_snapshot!(\"a\\n  b\", @\"\");
+}
+"
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept", "--", "--nocapture"])
+        .output()
+        .unwrap();
+
+    assert!(&output.status.success());
+
+    // the existing snapshot with lines indented by a tab and by spaces
+    // matches, the new one is indented with tabs like the file
+    assert_snapshot!(test_project.diff("src/lib.rs"), @r#"
+    --- Original: src/lib.rs
+    +++ Updated: src/lib.rs
+    @@ -9,5 +9,8 @@
+     
+     #[test]
+     fn test_new() {
+    -	insta::assert_snapshot!("a\n  b", @"");
+    +	insta::assert_snapshot!("a\n  b", @"
+    +	a
+    +	  b
+    +	");
+     }
+    "#);
+}
+
+#[test]
+fn test_wrap_long_inline_snapshot() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_wrap_long_inline_snapshot")
+        .add_file(
+            "insta.yaml",
+            "review:\n  wrap_long_inline_snapshots: true\n".to_string(),
+        )
+        .add_file("rustfmt.toml", "max_width = 60\n".to_string())
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_long() {
+    insta::assert_snapshot!("a".repeat(30), @"");
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept", "--", "--nocapture"])
+        .output()
+        .unwrap();
+
+    assert!(&output.status.success());
+
+    assert_snapshot!(test_project.diff("src/lib.rs"), @r#"
+    --- Original: src/lib.rs
+    +++ Updated: src/lib.rs
+    @@ -1,5 +1,8 @@
+     
+     #[test]
+     fn test_long() {
+    -    insta::assert_snapshot!("a".repeat(30), @"");
+    +    insta::assert_snapshot!(
+    +        "a".repeat(30),
+    +        @"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"
+    +    );
+     }
+    "#);
+}
+
+#[test]
+fn test_accept_and_fmt() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_accept_and_fmt")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_fmt()  {
+    let value =   "Hello";
+    insta::assert_snapshot!(value, @"");
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept", "--and-fmt", "--", "--nocapture"])
+        .output()
+        .unwrap();
+
+    assert!(&output.status.success());
+
+    assert_snapshot!(test_project.diff("src/lib.rs"), @r#"
+    --- Original: src/lib.rs
+    +++ Updated: src/lib.rs
+    @@ -1,6 +1,5 @@
+    -
+     #[test]
+    -fn test_fmt()  {
+    -    let value =   "Hello";
+    -    insta::assert_snapshot!(value, @"");
+    +fn test_fmt() {
+    +    let value = "Hello";
+    +    insta::assert_snapshot!(value, @"Hello");
+     }
+    "#);
+}
@@ -0,0 +1,98 @@
+use std::path::PathBuf;
+use std::process::Stdio;
+
+use crate::TestFiles;
+
+/// `cargo insta locate` predicts the snapshot files of tests before they have
+/// been run, following modules through files and into inline modules.
+#[test]
+fn test_locate_predicts_snapshot_files() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_locate")
+        .add_file("src/lib.rs", "mod parser;\n".to_string())
+        .add_file(
+            "src/parser.rs",
+            r#"
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_parse() {
+        insta::assert_snapshot!("parsed");
+        insta::assert_snapshot!("tokens", "a b");
+    }
+}
+"#
+            .to_string(),
+        )
+        .add_file(
+            "tests/integration.rs",
+            r#"
+#[test]
+fn roundtrip() {
+    insta::assert_snapshot!("roundtrip");
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let locate = |args: &[&str]| {
+        let output = test_project
+            .insta_cmd()
+            .arg("locate")
+            .args(args)
+            .stdout(Stdio::piped())
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        PathBuf::from(String::from_utf8_lossy(&output.stdout).trim())
+    };
+    let predicted = [
+        locate(&["--test", "test_locate::parser::tests::test_parse"]),
+        locate(&[
+            "--test",
+            "test_locate::parser::tests::test_parse",
+            "--name",
+            "tokens",
+        ]),
+        locate(&["--test", "integration::roundtrip"]),
+    ];
+    for path in &predicted {
+        assert!(!path.exists(), "{}", path.display());
+    }
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    for path in &predicted {
+        assert!(path.is_file(), "{}", path.display());
+    }
+    assert!(predicted[0].ends_with("src/snapshots/test_locate__parser__tests__parse.snap"));
+    assert!(predicted[2].ends_with("tests/snapshots/integration__roundtrip.snap"));
+}
+
+/// Tests in targets that are not part of the workspace are reported.
+#[test]
+fn test_locate_unknown_target() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_locate_unknown_target")
+        .add_file("src/lib.rs", String::new())
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["locate", "--test", "other::test_foo"])
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("no target named `other` was found"),
+        "{stdout}"
+    );
+}
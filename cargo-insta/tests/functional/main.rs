@@ -68,20 +68,41 @@ use itertools::Itertools;
 use similar::udiff::unified_diff;
 use tempfile::TempDir;
 
+mod accept;
+mod assertion_counts;
 mod back_compat;
 mod binary;
+mod bootstrap;
+mod ci_diff_budget;
 mod comparator;
+mod confirm;
+mod conflicts;
 mod delete_pending;
+mod expand;
+mod failure_summary;
+mod forbid;
+mod gc;
 mod glob_filter;
 mod inline;
 mod inline_snapshot_trimming;
+mod locate;
+mod meta;
+mod migrate;
 mod nextest_doctest;
 mod pending_dir;
+mod quarantine;
+mod quick;
 mod raw_strings;
+mod sanitize_names;
+mod sections;
+mod snapshot_extension;
+mod snapshot_layout;
 mod test_runner_fallback;
 mod test_workspace_source_path;
 mod unreferenced;
+mod verbose;
 mod workspace;
+mod xfail;
 
 /// Wraps a formatting function to be used as a `Stdio`
 struct OutputFormatter<F>(F)
@@ -1207,3 +1228,101 @@ old content
         "Expected unified diff output (with -u flag), got:\nstdout: {stdout}\nstderr: {stderr}"
     );
 }
+
+#[test]
+fn test_review_volatile_hints() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_review_volatile_hints")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_event() {
+    insta::assert_snapshot!("event", "name: deploy\nat: 2025-06-07T08:09:10Z");
+}
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/snapshots/test_review_volatile_hints__event.snap",
+            "---\nsource: src/lib.rs\nexpression: \"\\\"name: deploy\\\\nat: 2025-06-07T08:09:10Z\\\"\"\n---\nname: deploy\nat: 2024-01-02T03:04:05Z\n"
+                .to_string(),
+        )
+        .create_project();
+
+    let output = test_project.insta_cmd().args(["test"]).output().unwrap();
+    assert!(!output.status.success());
+
+    let output = test_project
+        .insta_cmd()
+        .args([
+            "review",
+            "--snapshot",
+            "src/snapshots/test_review_volatile_hints__event.snap",
+        ])
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(
+            "hint: only timestamps changed in line 2, they likely differ between runs. \
+             Filter them with:\n  settings.add_filter("
+        ),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn test_redaction_hints() {
+    let test_project = TestFiles::new()
+        .add_file(
+            "Cargo.toml",
+            r#"
+[package]
+name = "test_redaction_hints"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+insta = { path = '$PROJECT_PATH', features = ["json", "redactions"] }
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_event() {
+    let event = std::collections::BTreeMap::from([
+        ("at", "2025-06-07T08:09:10Z"),
+        ("name", "deploy"),
+    ]);
+    insta::assert_json_snapshot!("event", event);
+}
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/snapshots/test_redaction_hints__event.snap",
+            "---\nsource: src/lib.rs\nexpression: event\n---\n{\n  \"at\": \"2024-01-02T03:04:05Z\",\n  \"name\": \"deploy\"\n}\n"
+                .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--check"])
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(
+            "hint: only values that likely differ between runs changed. Redact them with:\n  \
+             settings.add_redaction(\".at\", \"[timestamp]\");\n"
+        ),
+        "{stdout}"
+    );
+}
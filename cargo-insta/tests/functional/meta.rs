@@ -0,0 +1,96 @@
+use std::fs;
+use std::process::Stdio;
+
+use crate::TestFiles;
+
+/// `cargo insta meta` edits the metadata of the snapshot files matching a
+/// pattern.
+#[test]
+fn test_meta_set_and_strip() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_meta_set_and_strip")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_api() {
+    insta::assert_snapshot!("api__users", "[]");
+    insta::assert_snapshot!("api__orders", "[]");
+    insta::assert_snapshot!("other", "hello");
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let snapshots = test_project.workspace_dir.join("src/snapshots");
+    let read = |name: &str| {
+        fs::read_to_string(snapshots.join(format!("test_meta_set_and_strip__{name}.snap"))).unwrap()
+    };
+    let meta = |args: &[&str]| {
+        test_project
+            .insta_cmd()
+            .arg("meta")
+            .args(args)
+            .stdout(Stdio::piped())
+            .output()
+            .unwrap()
+    };
+
+    let output = meta(&[
+        "set",
+        "--glob",
+        "*__api__*",
+        "description",
+        "v2 API responses",
+        "--dry-run",
+    ]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("2 snapshot files would be updated"),
+        "{stdout}"
+    );
+    assert!(!read("api__users").contains("description"));
+
+    let output = meta(&[
+        "set",
+        "--glob",
+        "*__api__*",
+        "description",
+        "v2 API responses",
+    ]);
+    assert!(output.status.success());
+    assert!(read("api__users").contains("description: v2 API responses\n"));
+    assert!(read("api__orders").contains("description: v2 API responses\n"));
+    assert!(!read("other").contains("description"));
+
+    let output = meta(&[
+        "strip",
+        "--field",
+        "expression",
+        "--glob",
+        "src/snapshots/*",
+    ]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("3 snapshot files updated"), "{stdout}");
+    assert!(!read("other").contains("expression:"));
+    assert!(read("other").ends_with("---\nhello\n"));
+
+    // info is structured and cannot be set from the command line
+    let output = meta(&["set", "info", "x"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("the metadata field `info` cannot be set"),
+        "{stdout}"
+    );
+}
@@ -0,0 +1,74 @@
+use insta::assert_snapshot;
+
+use crate::TestFiles;
+
+/// `cargo insta migrate --from expect-test` rewrites `expect!` assertions
+/// to inline snapshots.
+#[test]
+fn test_migrate_expect_test() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_migrate_expect_test")
+        .add_file(
+            "src/lib.rs",
+            r####"
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    #[test]
+    fn test_lines() {
+        let actual = "line 1\nline 2";
+        expect![[r#"
+            line 1
+            line 2"#]]
+        .assert_eq(actual);
+    }
+
+    #[test]
+    fn test_debug() {
+        let expected = expect![["[1, 2]"]];
+        expected.assert_debug_eq(&vec![1, 2]);
+    }
+}
+"####
+                .to_string(),
+        )
+        .create_project();
+
+    assert!(&test_project
+        .insta_cmd()
+        .args(["migrate", "--from", "expect-test"])
+        .output()
+        .unwrap()
+        .status
+        .success());
+
+    assert_snapshot!(test_project.diff("src/lib.rs"), @r####"
+    --- Original: src/lib.rs
+    +++ Updated: src/lib.rs
+    @@ -1,20 +1,17 @@
+     
+     #[cfg(test)]
+     mod tests {
+    -    use expect_test::expect;
+     
+         #[test]
+         fn test_lines() {
+             let actual = "line 1\nline 2";
+    -        expect![[r#"
+    +        insta::assert_snapshot!(actual, @r#"
+                 line 1
+    -            line 2"#]]
+    -        .assert_eq(actual);
+    +            line 2"#);
+         }
+     
+         #[test]
+         fn test_debug() {
+    -        let expected = expect![["[1, 2]"]];
+    -        expected.assert_debug_eq(&vec![1, 2]);
+    +        insta::assert_debug_snapshot!(&vec![1, 2], @"[1, 2]");
+         }
+     }
+    "####);
+}
@@ -731,3 +731,228 @@ fn test_snapshot() {
         "Pending snapshot should NOT be in execroot"
     );
 }
+
+/// Test that a read-only snapshot directory fails with a hint to use
+/// INSTA_PENDING_DIR, and that INSTA_PENDING_DIR works around it
+#[test]
+#[cfg(unix)]
+#[ignore = "needs a non-root user, as root ignores file permissions"]
+fn test_pending_dir_read_only_source_tree() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_pending_dir_read_only")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_snapshot() {
+    insta::assert_snapshot!("read_only", "Hello, World!");
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let snapshot_dir = test_project.workspace_dir.join("src/snapshots");
+    fs::create_dir_all(&snapshot_dir).unwrap();
+    fs::set_permissions(&snapshot_dir, fs::Permissions::from_mode(0o555)).unwrap();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--", "--nocapture"])
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("insta could not write the snapshot to")
+            && stderr.contains("Set INSTA_PENDING_DIR to a writable directory"),
+        "Expected a read-only diagnostic, got: {stderr}"
+    );
+
+    let pending_dir = test_project.workspace_dir.join("pending_output");
+    test_project
+        .insta_cmd()
+        .args(["test"])
+        .env("INSTA_PENDING_DIR", &pending_dir)
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+
+    assert!(pending_dir
+        .join("src/snapshots/test_pending_dir_read_only__read_only.snap.new")
+        .exists());
+}
+
+/// Test that `pending_in_target` writes pending snapshots below the target
+/// directory and that accepting them moves them into the source tree
+#[test]
+fn test_pending_in_target() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_pending_in_target")
+        .add_file(
+            "insta.yaml",
+            "behavior:\n  pending_in_target: true\n".to_string(),
+        )
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_file() {
+    insta::assert_snapshot!("file", "Hello, World!");
+}
+
+#[test]
+fn test_inline() {
+    insta::assert_snapshot!("Hello, World!", @"");
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test"])
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let pending_dir = crate::target_dir()
+        .join("insta-pending")
+        .join(test_project.workspace_dir.file_name().unwrap());
+    assert!(pending_dir
+        .join("src/snapshots/test_pending_in_target__file.snap.new")
+        .exists());
+    assert!(pending_dir.join("src/.lib.rs.pending-snap").exists());
+    assert!(!test_project
+        .workspace_dir
+        .join("src/snapshots/test_pending_in_target__file.snap.new")
+        .exists());
+    assert!(!test_project
+        .workspace_dir
+        .join("src/.lib.rs.pending-snap")
+        .exists());
+
+    let output = test_project
+        .insta_cmd()
+        .args(["accept"])
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "cargo insta accept failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    assert!(test_project
+        .workspace_dir
+        .join("src/snapshots/test_pending_in_target__file.snap")
+        .exists());
+    assert!(!pending_dir.join("src/.lib.rs.pending-snap").exists());
+    assert!(
+        fs::read_to_string(test_project.workspace_dir.join("src/lib.rs"))
+            .unwrap()
+            .contains(r#"@"Hello, World!""#)
+    );
+}
+
+/// Test that `--accept --ephemeral` accepts snapshots into an overlay
+/// directory below the target directory and leaves the source tree alone
+#[test]
+fn test_accept_ephemeral() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_accept_ephemeral")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_file() {
+    insta::assert_snapshot!("file", "Hello, World!");
+}
+
+#[test]
+fn test_inline() {
+    insta::assert_snapshot!("Hello, World!", @"");
+}
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/snapshots/test_accept_ephemeral__file.snap",
+            r#"---
+source: src/lib.rs
+expression: "\"Hello, World!\""
+---
+Hello
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept", "--ephemeral"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "cargo insta test failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.lines().any(|line| line
+        .contains("src/snapshots/test_accept_ephemeral__file.snap")
+        && line.ends_with("+1 -1")));
+    assert!(stdout.contains("2 changed, 0 new, 2 insertions(+), 1 deletions(-)"));
+
+    // the source tree is unchanged
+    assert_eq!(
+        fs::read_to_string(
+            test_project
+                .workspace_dir
+                .join("src/snapshots/test_accept_ephemeral__file.snap")
+        )
+        .unwrap()
+        .lines()
+        .last(),
+        Some("Hello")
+    );
+    assert!(
+        fs::read_to_string(test_project.workspace_dir.join("src/lib.rs"))
+            .unwrap()
+            .contains(r#"@"")"#)
+    );
+    assert!(!test_project
+        .workspace_dir
+        .join("src/snapshots/test_accept_ephemeral__file.snap.new")
+        .exists());
+    assert!(!test_project
+        .workspace_dir
+        .join("src/.lib.rs.pending-snap")
+        .exists());
+
+    // and the overlay has the accepted snapshots
+    let overlay = crate::target_dir()
+        .join("insta-ephemeral")
+        .join(test_project.workspace_dir.file_name().unwrap());
+    assert!(
+        fs::read_to_string(overlay.join("src/snapshots/test_accept_ephemeral__file.snap"))
+            .unwrap()
+            .ends_with("Hello, World!\n")
+    );
+    assert!(!overlay
+        .join("src/snapshots/test_accept_ephemeral__file.snap.new")
+        .exists());
+    assert!(fs::read_to_string(overlay.join("src/lib.rs"))
+        .unwrap()
+        .contains(r#"@"Hello, World!""#));
+    assert!(!overlay.join("src/.lib.rs.pending-snap").exists());
+}
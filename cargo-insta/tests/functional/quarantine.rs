@@ -0,0 +1,101 @@
+use std::process::Stdio;
+
+use insta::assert_snapshot;
+
+use crate::TestFiles;
+
+/// Quarantined snapshots don't fail the tests and are listed by `stats`.
+#[test]
+fn test_quarantine() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_quarantine")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_timing() {
+    insta::assert_snapshot!("fast");
+}
+
+#[test]
+fn test_order() {
+    insta::with_settings!({flaky => true}, {
+        insta::assert_snapshot!("b, a", @"a, b");
+    });
+}
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/snapshots/test_quarantine__timing.snap",
+            r#"---
+source: src/lib.rs
+expression: "\"fast\""
+---
+slow
+"#
+            .to_string(),
+        )
+        .add_file(
+            "insta.yaml",
+            r#"
+behavior:
+  quarantine:
+    - "test_quarantine__timing"
+    - "test_quarantine__removed_*"
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--check", "--", "--nocapture"])
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(&output.status.success(), "{stderr}");
+    assert!(
+        stderr
+            .contains("'timing' in line 4 failed but is quarantined by `test_quarantine__timing`"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("'order' in line 10 failed but is quarantined by the `flaky` setting"),
+        "{stderr}"
+    );
+
+    // no pending snapshots are written
+    assert_snapshot!(test_project.file_tree_diff(), @r"
+    --- Original file tree
+    +++ Updated file tree
+    @@ -1,3 +1,4 @@
+    +  Cargo.lock
+       Cargo.toml
+       insta.yaml
+       src
+    ");
+
+    let output = test_project
+        .insta_cmd()
+        .args(["stats", "--quarantined"])
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(&output.status.success());
+    assert_snapshot!(String::from_utf8_lossy(&output.stdout), @r"
+    snapshot files: 1
+    pending snapshots: 0
+    quarantined snapshot files: 1
+    uses of `flaky`: 1
+
+    quarantined in the insta config:
+      test_quarantine__removed_* (no snapshot files)
+      test_quarantine__timing (1 snapshot file)
+        src/snapshots/test_quarantine__timing.snap
+
+    quarantined with `flaky`:
+      src/lib.rs:9
+    ");
+}
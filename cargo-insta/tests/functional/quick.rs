@@ -0,0 +1,139 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Stdio;
+
+use crate::{TestFiles, TestProject};
+
+fn nextest_available() -> bool {
+    std::process::Command::new("cargo")
+        .args(["nextest", "--version"])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// A project with a fast and a slow test, and an integration test whose test
+/// has the same path as the fast one.
+fn create_project(name: &str) -> TestProject {
+    let test = r#"
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_fast() {
+        insta::assert_snapshot!("fast", @"fast");
+    }
+
+    #[test]
+    fn test_slow() {
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        insta::assert_snapshot!("slow", @"slow");
+    }
+}
+"#;
+    let other = r#"
+mod tests {
+    #[test]
+    fn test_fast() {
+        insta::assert_snapshot!("other", @"other");
+    }
+}
+"#;
+    TestFiles::new()
+        .add_cargo_toml(name)
+        .add_file("src/lib.rs", test.to_string())
+        .add_file("tests/other.rs", other.to_string())
+        .create_project()
+}
+
+fn timings_path(test_project: &TestProject) -> PathBuf {
+    test_project.workspace_dir.join(".insta/timings.json")
+}
+
+/// libtest names tests without their binary, so `--quick` runs the tests of
+/// the selected name in every binary, as they were timed together.
+#[test]
+fn test_quick_cargo_test() {
+    let test_project = create_project("test_quick_cargo_test");
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--quick"])
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("no timings of previous runs with this test runner, running all tests"),
+        "{stderr}"
+    );
+    // the tests are timed from the output of libtest on stable
+    let timings = fs::read_to_string(timings_path(&test_project)).unwrap();
+    assert!(timings.contains("\"runner\": \"cargo-test\""), "{timings}");
+    assert!(timings.contains("\"tests::test_fast\""), "{timings}");
+    assert!(timings.contains("\"tests::test_slow\""), "{timings}");
+    assert_eq!(
+        fs::read_to_string(test_project.workspace_dir.join(".insta/.gitignore")).unwrap(),
+        "*\n"
+    );
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--quick", "1"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("running 1 of 2 timed tests"), "{stderr}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("info: 2 snapshot assertions in 2 tests,"),
+        "{stdout}"
+    );
+}
+
+/// nextest reports how long every test took, and `--quick` selects tests by
+/// their binary.
+#[test]
+fn test_quick_nextest() {
+    if !nextest_available() {
+        eprintln!("Skipping test: cargo-nextest not installed");
+        return;
+    }
+    let test_project = create_project("test_quick_nextest");
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--test-runner", "nextest", "--dnd"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let timings = fs::read_to_string(timings_path(&test_project)).unwrap();
+    assert!(timings.contains("\"runner\": \"nextest\""), "{timings}");
+    assert!(
+        timings.contains("test_quick_nextest tests::test_fast"),
+        "{timings}"
+    );
+    assert!(
+        timings.contains("test_quick_nextest::other tests::test_fast"),
+        "{timings}"
+    );
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--test-runner", "nextest", "--dnd", "--quick", "1"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("running 1 of 3 timed tests"), "{stderr}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("info: 1 snapshot assertion in 1 test,"),
+        "{stdout}"
+    );
+}
@@ -0,0 +1,173 @@
+use std::process::Stdio;
+
+use insta::assert_snapshot;
+
+use crate::TestFiles;
+
+/// Portable names replace the characters that some file systems reject.
+#[test]
+fn test_portable_snapshot_names() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_portable_snapshot_names")
+        .add_file(
+            "insta.yaml",
+            "behavior:\n  sanitize_names: portable\n".to_string(),
+        )
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_names() {
+    insta::assert_snapshot!("fixtures/héllo world: 1?", "Hello");
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept", "--", "--nocapture"])
+        .output()
+        .unwrap();
+
+    assert!(&output.status.success());
+
+    assert_snapshot!(test_project.file_tree_diff(), @"
+    --- Original file tree
+    +++ Updated file tree
+    @@ -1,4 +1,7 @@
+    +  Cargo.lock
+       Cargo.toml
+       insta.yaml
+       src
+         src/lib.rs
+    +    src/snapshots
+    +      src/snapshots/test_portable_snapshot_names__fixtures__h_llo_world__1_.snap
+    ");
+}
+
+/// Names that end up in the same file are reported instead of overwriting
+/// each other.
+#[test]
+fn test_snapshot_file_name_clash() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_snapshot_file_name_clash")
+        .add_file(
+            "insta.yaml",
+            "behavior:\n  sanitize_names: portable\n".to_string(),
+        )
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_names() {
+    insta::assert_snapshot!("a b", "first");
+    insta::assert_snapshot!("a:b", "second");
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept", "--", "--nocapture"])
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Insta snapshot names 'a b' and 'a:b' are both stored in"));
+}
+
+/// Names that only differ in case are the same file on macOS and Windows.
+#[test]
+fn test_snapshot_name_case_clash() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_snapshot_name_case_clash")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_names() {
+    insta::assert_snapshot!("Output", "first");
+    insta::assert_snapshot!("output", "second");
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept", "--", "--nocapture"])
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("only differ in case"), "{stderr}");
+    assert!(stderr.contains("Rename 'Output' or 'output'."), "{stderr}");
+}
+
+/// A snapshot is not accepted next to a file whose name only differs in
+/// case, and `doctor` reports such files.
+#[test]
+fn test_accept_case_variant() {
+    let snapshot = "---\nsource: src/lib.rs\nexpression: \"\\\"old\\\"\"\n---\nold\n";
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_accept_case_variant")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_name() {
+    insta::assert_snapshot!("output", "new");
+}
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/snapshots/test_accept_case_variant__Output.snap",
+            snapshot.to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept", "--", "--nocapture"])
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("only differs in case"), "{stdout}");
+    assert!(!test_project
+        .workspace_dir
+        .join("src/snapshots/test_accept_case_variant__output.snap")
+        .exists());
+
+    std::fs::write(
+        test_project
+            .workspace_dir
+            .join("src/snapshots/test_accept_case_variant__output.snap"),
+        snapshot,
+    )
+    .unwrap();
+    let output = test_project
+        .insta_cmd()
+        .args(["doctor"])
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("test_accept_case_variant__Output.snap, "),
+        "{stderr}"
+    );
+}
@@ -0,0 +1,71 @@
+//! Tests for snapshot files with several sections.
+
+use std::fs;
+
+use crate::TestFiles;
+
+fn sections_source(summary: &str, details: &str) -> String {
+    format!(
+        r#"
+use insta::{{assert_snapshot, with_settings}};
+
+#[test]
+fn test_report() {{
+    with_settings!({{snapshot_section => "summary"}}, {{
+        assert_snapshot!("report", "{summary}");
+    }});
+    with_settings!({{snapshot_section => "details"}}, {{
+        assert_snapshot!("report", "{details}");
+    }});
+}}
+"#
+    )
+}
+
+#[test]
+fn test_sections_are_stored_in_one_file() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_sections")
+        .add_file("src/lib.rs", sections_source("two items", "a, b"))
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let snapshot_path = test_project
+        .workspace_dir
+        .join("src/snapshots/test_sections__report.snap");
+    insta::assert_snapshot!(fs::read_to_string(&snapshot_path).unwrap(), @r"
+    ---
+    source: src/lib.rs
+    ---
+    --- summary ---
+    two items
+
+    --- details ---
+    a, b
+    ");
+
+    // both sections change, the pending snapshot holds both of them
+    test_project.update_file("src/lib.rs", sections_source("three items", "a, b, c"));
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--", "--nocapture"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let pending = fs::read_to_string(snapshot_path.with_extension("snap.new")).unwrap();
+    let (_, contents) = pending.split_once("\n---\n").unwrap();
+    insta::assert_snapshot!(contents, @r"
+    --- summary ---
+    three items
+
+    --- details ---
+    a, b, c
+    ");
+}
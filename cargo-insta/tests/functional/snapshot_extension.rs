@@ -0,0 +1,91 @@
+use std::fs;
+use std::process::Stdio;
+
+use crate::TestFiles;
+
+/// Snapshots with an extension are stored as `name.snap.ext` and reviewed
+/// like other snapshots.
+#[test]
+fn test_snapshot_extension_accept() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_snapshot_extension_accept")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_query() {
+    insta::assert_snapshot!(extension = "sql", "query", "SELECT 1");
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let snapshots = test_project.workspace_dir.join("src/snapshots");
+    let output = test_project.insta_cmd().arg("test").output().unwrap();
+    assert!(!output.status.success());
+    assert!(snapshots
+        .join("test_snapshot_extension_accept__query.snap.sql.new")
+        .exists());
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let snapshot =
+        fs::read_to_string(snapshots.join("test_snapshot_extension_accept__query.snap.sql"))
+            .unwrap();
+    assert!(snapshot.ends_with("---\nSELECT 1\n"), "{snapshot}");
+    assert!(!snapshots
+        .join("test_snapshot_extension_accept__query.snap.sql.new")
+        .exists());
+}
+
+/// External diff tools get files with the extension of the snapshot, so that
+/// they can highlight the diff.
+#[test]
+#[cfg(unix)]
+fn test_snapshot_extension_diff_tool() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_snapshot_extension_diff_tool")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_query() {
+    insta::assert_snapshot!(extension = "sql", "query", "SELECT 2");
+}
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/snapshots/test_snapshot_extension_diff_tool__query.snap.sql",
+            r#"---
+source: src/lib.rs
+expression:
+---
+SELECT 1
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--check"])
+        .env("INSTA_DIFF_TOOL", "diff -u")
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("--- test_snapshot_extension_diff_tool__query.old.sql"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("+++ test_snapshot_extension_diff_tool__query.new.sql"),
+        "{stdout}"
+    );
+}
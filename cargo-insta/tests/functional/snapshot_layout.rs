@@ -0,0 +1,88 @@
+use std::process::Stdio;
+
+use insta::assert_snapshot;
+
+use crate::TestFiles;
+
+const DEEP_MODULES: &str = r#"
+mod parser {
+    mod expressions {
+        #[test]
+        fn test_binary() {
+            insta::assert_snapshot!("binary", "a + b");
+        }
+    }
+}
+"#;
+
+/// The hashed layout stores the snapshots of a module in a short directory.
+#[test]
+fn test_hashed_snapshot_layout() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_hashed_snapshot_layout")
+        .add_file(
+            "insta.yaml",
+            "behavior:\n  snapshot_layout: hashed\n".to_string(),
+        )
+        .add_file("src/lib.rs", DEEP_MODULES.to_string())
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept", "--", "--nocapture"])
+        .output()
+        .unwrap();
+
+    assert!(&output.status.success());
+
+    let tree = test_project.file_tree_diff();
+    assert!(tree.contains("+      src/snapshots/expressions-"), "{tree}");
+    assert!(tree.ends_with("/binary.snap\n"), "{tree}");
+
+    // the snapshot is found again in the next run
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--check", "--", "--nocapture"])
+        .output()
+        .unwrap();
+    assert!(&output.status.success());
+}
+
+/// `doctor` reports snapshot paths that are too long.
+#[test]
+fn test_doctor_long_paths() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_doctor_long_paths")
+        .add_file("src/lib.rs", DEEP_MODULES.to_string())
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept", "--", "--nocapture"])
+        .output()
+        .unwrap();
+    assert!(&output.status.success());
+
+    let output = test_project
+        .insta_cmd()
+        .args(["doctor"])
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(&output.status.success());
+    assert_snapshot!(String::from_utf8_lossy(&output.stdout), @"done: no problems found");
+
+    let output = test_project
+        .insta_cmd()
+        .args(["doctor", "--max-path-len", "20"])
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("test_doctor_long_paths__parser__expressions__binary.snap ("),
+        "{stderr}"
+    );
+    assert!(stderr.contains("snapshot_layout: hashed"), "{stderr}");
+}
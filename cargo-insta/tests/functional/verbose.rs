@@ -0,0 +1,58 @@
+use std::fs;
+use std::process::Stdio;
+
+use crate::TestFiles;
+
+/// `-v` explains which snapshots were found and written, and `--log-file`
+/// records the same messages as JSON lines.
+#[test]
+fn test_verbose_log() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_verbose_log")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_inline() {
+    insta::assert_snapshot!("new", @"old");
+}
+"#
+            .to_string(),
+        )
+        // a directory the search skips
+        .add_file(".hidden/notes.txt", "notes".to_string())
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept", "-v", "--log-file", "insta.log"])
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("debug: [walk] found pending inline snapshots"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("debug: [inline] rewriting inline snapshot at"),
+        "{stderr}"
+    );
+    // directories skipped by the search are only shown with `-vv`
+    assert!(!stderr.contains("[walk] skipping"), "{stderr}");
+
+    let log = fs::read_to_string(test_project.workspace_dir.join("insta.log")).unwrap();
+    let messages = log
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert!(messages
+        .iter()
+        .any(|x| x["level"] == "trace" && x["target"] == "walk"));
+    assert!(messages.iter().any(|x| x["target"] == "inline"
+        && x["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("rewriting inline snapshot at")));
+}
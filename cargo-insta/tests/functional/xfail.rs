@@ -0,0 +1,75 @@
+use std::process::Stdio;
+
+use insta::assert_snapshot;
+
+use crate::TestFiles;
+
+fn xfail_lib(value: &str) -> String {
+    format!(
+        r#"
+#[test]
+fn test_sum() {{
+    insta::with_settings!({{xfail => true}}, {{
+        insta::assert_snapshot!("{value}");
+    }});
+}}
+"#
+    )
+}
+
+/// An expected failure is stored as a known divergence, and fails once the
+/// expected value matches again.
+#[test]
+fn test_xfail() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_xfail")
+        .add_file("src/lib.rs", xfail_lib("1 + 1 = 3"))
+        .add_file(
+            "src/snapshots/test_xfail__sum.snap",
+            "---\nsource: src/lib.rs\nexpression: \"\\\"1 + 1 = 2\\\"\"\n---\n1 + 1 = 2\n"
+                .to_string(),
+        )
+        .create_project();
+
+    // the known divergence is accepted like any new snapshot
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept", "--", "--nocapture"])
+        .output()
+        .unwrap();
+    assert!(&output.status.success());
+    assert_snapshot!(test_project.file_tree_diff(), @r"
+    --- Original file tree
+    +++ Updated file tree
+    @@ -1,5 +1,7 @@
+    +  Cargo.lock
+       Cargo.toml
+       src
+         src/lib.rs
+         src/snapshots
+           src/snapshots/test_xfail__sum.snap
+    +      src/snapshots/test_xfail__sum.xfail.snap
+    ");
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--check", "--unreferenced=reject"])
+        .output()
+        .unwrap();
+    assert!(&output.status.success());
+
+    // once the bug is fixed the assertion fails
+    test_project.update_file("src/lib.rs", xfail_lib("1 + 1 = 2"));
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--check", "--", "--nocapture"])
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(!&output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("snapshot assertion for 'sum' in line 5 expects to fail but matches now"),
+        "{stderr}"
+    );
+}
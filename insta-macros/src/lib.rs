@@ -0,0 +1,146 @@
+//! Procedural macros for insta.
+//!
+//! This crate is not meant to be used directly; enable the `macros` feature
+//! of [insta](https://docs.rs/insta) and use the re-exports from there.
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote, quote_spanned};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Attribute, Error, Expr, FnArg, Ident, ItemFn, LitStr, Pat, Token};
+
+/// Generates one test per `#[case(...)]` attribute of a function.
+///
+/// See `insta::cases` for the documentation.
+#[proc_macro_attribute]
+pub fn cases(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr = TokenStream2::from(attr);
+    if !attr.is_empty() {
+        return Error::new(attr.span(), "`cases` does not take arguments")
+            .into_compile_error()
+            .into();
+    }
+    let func = syn::parse_macro_input!(item as ItemFn);
+    expand_cases(func)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// A single `name = value` or `name = @"..."` argument of a case.
+struct CaseArg {
+    name: Ident,
+    value: CaseValue,
+}
+
+enum CaseValue {
+    Expr(Expr),
+    Inline(LitStr),
+}
+
+impl Parse for CaseArg {
+    fn parse(input: ParseStream) -> syn::Result<CaseArg> {
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let value = if input.peek(Token![@]) {
+            input.parse::<Token![@]>()?;
+            CaseValue::Inline(input.parse()?)
+        } else {
+            CaseValue::Expr(input.parse()?)
+        };
+        Ok(CaseArg { name, value })
+    }
+}
+
+fn expand_cases(mut func: ItemFn) -> syn::Result<TokenStream2> {
+    if !func.sig.generics.params.is_empty() || func.sig.asyncness.is_some() {
+        return Err(Error::new(
+            func.sig.span(),
+            "`cases` only supports non-generic, synchronous functions",
+        ));
+    }
+
+    let (cases, attrs): (Vec<Attribute>, Vec<Attribute>) = func
+        .attrs
+        .drain(..)
+        .partition(|attr| attr.path().is_ident("case"));
+    func.attrs = attrs;
+    if cases.is_empty() {
+        return Err(Error::new(
+            func.sig.ident.span(),
+            "expected at least one `#[case(...)]` attribute",
+        ));
+    }
+
+    let params = func
+        .sig
+        .inputs
+        .iter()
+        .map(|input| match input {
+            FnArg::Typed(arg) => match &*arg.pat {
+                Pat::Ident(pat) => Ok(pat.ident.clone()),
+                pat => Err(Error::new(pat.span(), "expected a named parameter")),
+            },
+            FnArg::Receiver(receiver) => Err(Error::new(
+                receiver.span(),
+                "`cases` does not support `self`",
+            )),
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let name = &func.sig.ident;
+    let output = &func.sig.output;
+    let mut tests = Vec::new();
+    for (idx, case) in cases.iter().enumerate() {
+        let case_args = case.parse_args_with(Punctuated::<CaseArg, Token![,]>::parse_terminated)?;
+        for arg in &case_args {
+            if !params.contains(&arg.name) {
+                return Err(Error::new(
+                    arg.name.span(),
+                    format!("`{}` has no parameter `{}`", name, arg.name),
+                ));
+            }
+        }
+        let args = params
+            .iter()
+            .map(|param| {
+                let arg = case_args
+                    .iter()
+                    .find(|arg| &arg.name == param)
+                    .ok_or_else(|| {
+                        Error::new(case.span(), format!("missing value for `{param}`"))
+                    })?;
+                Ok(match &arg.value {
+                    CaseValue::Expr(expr) => quote!(#expr),
+                    CaseValue::Inline(lit) => {
+                        // `line!()` with the span of the literal resolves to
+                        // the line of the literal, which is where cargo-insta
+                        // looks for the snapshot to update.
+                        let line = quote_spanned!(lit.span()=> ::core::line!());
+                        quote!(::insta::InlineCase::new(#lit, #line))
+                    }
+                })
+            })
+            .collect::<syn::Result<Vec<_>>>()?;
+        let test_name = format_ident!("case_{}", idx + 1, span = Span::call_site());
+        tests.push(quote! {
+            #[test]
+            fn #test_name() #output {
+                super::#name(#(#args),*)
+            }
+        });
+    }
+
+    Ok(quote! {
+        #[allow(dead_code)]
+        #func
+
+        #[cfg(test)]
+        #[allow(non_snake_case)]
+        mod #name {
+            use super::*;
+
+            #(#tests)*
+        }
+    })
+}
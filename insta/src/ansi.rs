@@ -0,0 +1,228 @@
+use std::fmt::Display;
+
+use crate::settings::Settings;
+
+/// Controls how [`assert_ansi_snapshot!`](crate::assert_ansi_snapshot!)
+/// stores ANSI escape codes.
+///
+/// The mode is set with [`Settings::set_ansi_mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnsiMode {
+    /// Removes all escape codes and keeps only the text.
+    #[default]
+    Strip,
+    /// Writes colors and text styles as tags such as `<red>…</red>` or
+    /// `<bold>…</bold>` and removes all other escape codes.
+    Markup,
+}
+
+#[cfg(feature = "toml")]
+impl AnsiMode {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            AnsiMode::Strip => "strip",
+            AnsiMode::Markup => "markup",
+        }
+    }
+
+    pub(crate) fn parse(value: &str) -> Option<AnsiMode> {
+        match value {
+            "strip" => Some(AnsiMode::Strip),
+            "markup" => Some(AnsiMode::Markup),
+            _ => None,
+        }
+    }
+}
+
+const COLORS: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// The text styles that are active at a point of the output.
+#[derive(Default)]
+struct Style {
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    blink: bool,
+    reverse: bool,
+    hidden: bool,
+    strikethrough: bool,
+    fg: Option<String>,
+    bg: Option<String>,
+}
+
+impl Style {
+    /// Returns the tags for the style, always in the same order.
+    fn tags(&self) -> Vec<String> {
+        let flags = [
+            (self.bold, "bold"),
+            (self.dim, "dim"),
+            (self.italic, "italic"),
+            (self.underline, "underline"),
+            (self.blink, "blink"),
+            (self.reverse, "reverse"),
+            (self.hidden, "hidden"),
+            (self.strikethrough, "strikethrough"),
+        ];
+        let mut rv = flags
+            .iter()
+            .filter(|(on, _)| *on)
+            .map(|(_, tag)| tag.to_string())
+            .collect::<Vec<_>>();
+        rv.extend(self.fg.clone());
+        rv.extend(self.bg.as_ref().map(|color| format!("bg-{color}")));
+        rv
+    }
+
+    /// Applies the parameters of a select graphic rendition sequence.
+    fn apply(&mut self, params: &str) {
+        let mut codes = params
+            .split([';', ':'])
+            .map(|x| x.parse::<u32>().unwrap_or(0));
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 | 21 => self.underline = true,
+                5 | 6 => self.blink = true,
+                7 => self.reverse = true,
+                8 => self.hidden = true,
+                9 => self.strikethrough = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                25 => self.blink = false,
+                27 => self.reverse = false,
+                28 => self.hidden = false,
+                29 => self.strikethrough = false,
+                30..=37 => self.fg = Some(COLORS[code as usize - 30].to_string()),
+                38 => self.fg = extended_color(&mut codes),
+                39 => self.fg = None,
+                40..=47 => self.bg = Some(COLORS[code as usize - 40].to_string()),
+                48 => self.bg = extended_color(&mut codes),
+                49 => self.bg = None,
+                90..=97 => self.fg = Some(format!("bright-{}", COLORS[code as usize - 90])),
+                100..=107 => self.bg = Some(format!("bright-{}", COLORS[code as usize - 100])),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Reads the color of a `38` or `48` code, which is either `5;n` for one of
+/// 256 colors or `2;r;g;b` for an RGB color.
+fn extended_color(codes: &mut impl Iterator<Item = u32>) -> Option<String> {
+    match codes.next() {
+        Some(5) => codes.next().map(|n| format!("color-{n}")),
+        Some(2) => {
+            let rgb = [codes.next()?, codes.next()?, codes.next()?];
+            Some(format!(
+                "#{:02x}{:02x}{:02x}",
+                rgb[0].min(255),
+                rgb[1].min(255),
+                rgb[2].min(255)
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Removes or converts the ANSI escape codes in `input` according to the
+/// current [`AnsiMode`].
+pub fn render_ansi<V: Display + ?Sized>(input: &V) -> String {
+    convert_ansi(&input.to_string(), Settings::with(|s| s.ansi_mode()))
+}
+
+fn convert_ansi(input: &str, mode: AnsiMode) -> String {
+    let mut rv = String::with_capacity(input.len());
+    let mut style = Style::default();
+    let mut open: Vec<String> = vec![];
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            if mode == AnsiMode::Markup {
+                update_tags(&mut rv, &mut open, style.tags());
+            }
+            rv.push(c);
+            continue;
+        }
+        match chars.next() {
+            // control sequences end with a character from `@` to `~`
+            Some('[') => {
+                let mut params = String::new();
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        if c == 'm' {
+                            style.apply(&params);
+                        }
+                        break;
+                    }
+                    params.push(c);
+                }
+            }
+            // operating system commands (such as hyperlinks) end with a bell
+            // or with `ESC \`
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    update_tags(&mut rv, &mut open, vec![]);
+    rv
+}
+
+/// Closes and opens tags so that the open tags become `tags`.
+///
+/// Tags that both have in common at the start stay open, so that changing
+/// the color within bold text does not close the bold tag.
+fn update_tags(out: &mut String, open: &mut Vec<String>, tags: Vec<String>) {
+    let common = open
+        .iter()
+        .zip(tags.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    for tag in open.drain(common..).rev() {
+        out.push_str(&format!("</{tag}>"));
+    }
+    for tag in &tags[common..] {
+        out.push_str(&format!("<{tag}>"));
+    }
+    *open = tags;
+}
+
+#[test]
+fn test_convert_ansi() {
+    let text = "\x1b[1merror\x1b[0m: \x1b[31mfailed\x1b[39m to \x1b[1;38;5;208mbuild\x1b[0m\n\
+                \x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\ \x1b[2Kdone";
+    assert_eq!(
+        convert_ansi(text, AnsiMode::Strip),
+        "error: failed to build\nlink done"
+    );
+    assert_eq!(
+        convert_ansi(text, AnsiMode::Markup),
+        "<bold>error</bold>: <red>failed</red> to <bold><color-208>build</color-208></bold>\nlink done"
+    );
+}
+
+#[test]
+fn test_convert_ansi_nesting() {
+    assert_eq!(
+        convert_ansi(
+            "\x1b[1;31ma\x1b[32mb\x1b[22mc\x1b[48;2;255;128;0md\x1b[m",
+            AnsiMode::Markup
+        ),
+        "<bold><red>a</red><green>b</green></bold><green>c<bg-#ff8000>d</bg-#ff8000></green>"
+    );
+}
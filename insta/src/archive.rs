@@ -0,0 +1,214 @@
+use std::fmt::Write;
+use std::io::{Cursor, Read};
+
+use sha2::{Digest, Sha256};
+
+/// The largest file whose contents are written to the snapshot instead of a
+/// hash.
+const MAX_TEXT_LEN: usize = 4096;
+
+/// An entry of an archive.
+struct Entry {
+    path: String,
+    mode: Option<u32>,
+    kind: EntryKind,
+}
+
+enum EntryKind {
+    File(Vec<u8>),
+    Dir,
+    Symlink(String),
+    Other(String),
+}
+
+/// Reads a zip, tar or gzip compressed tar archive and formats its entries.
+///
+/// The format is detected from the first bytes.  Entries are sorted by path
+/// and written one per line with their permissions.  Files of up to 4 KiB
+/// that are text are followed by their contents, indented by two spaces,
+/// and other files show their size and SHA-256 hash.  Modification times
+/// and the user and group of entries are left out, since they depend on
+/// when and by whom the archive was built.
+///
+/// Panics if `input` is not a valid archive.
+pub fn render_archive<B: AsRef<[u8]> + ?Sized>(input: &B) -> String {
+    let bytes = input.as_ref();
+    let mut entries = if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+        read_zip(bytes)
+    } else if bytes.starts_with(b"\x1f\x8b") {
+        read_tar(flate2::read::GzDecoder::new(bytes))
+    } else {
+        read_tar(bytes)
+    };
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut rv = String::new();
+    for entry in &entries {
+        write_entry(entry, &mut rv);
+    }
+    rv.truncate(rv.trim_end().len());
+    rv
+}
+
+fn read_tar<R: Read>(reader: R) -> Vec<Entry> {
+    let mut archive = tar::Archive::new(reader);
+    let mut rv = vec![];
+    let entries = archive
+        .entries()
+        .unwrap_or_else(|err| panic!("failed to read snapshot value as tar: {err}"));
+    for entry in entries {
+        let mut entry =
+            entry.unwrap_or_else(|err| panic!("failed to read snapshot value as tar: {err}"));
+        let mode = entry.header().mode().ok().map(|mode| mode & 0o7777);
+        let kind = match entry.header().entry_type() {
+            tar::EntryType::Regular | tar::EntryType::Continuous => {
+                let mut contents = vec![];
+                entry
+                    .read_to_end(&mut contents)
+                    .unwrap_or_else(|err| panic!("failed to read tar entry: {err}"));
+                EntryKind::File(contents)
+            }
+            tar::EntryType::Directory => EntryKind::Dir,
+            tar::EntryType::Symlink => EntryKind::Symlink(link_name(&entry)),
+            tar::EntryType::Link => EntryKind::Other(format!("hard link to {}", link_name(&entry))),
+            tar::EntryType::Char => EntryKind::Other("character device".into()),
+            tar::EntryType::Block => EntryKind::Other("block device".into()),
+            tar::EntryType::Fifo => EntryKind::Other("fifo".into()),
+            // extended headers are applied to the entries by the tar crate
+            _ => continue,
+        };
+        rv.push(Entry {
+            path: entry
+                .path()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            mode,
+            kind,
+        });
+    }
+    rv
+}
+
+fn link_name<R: Read>(entry: &tar::Entry<'_, R>) -> String {
+    entry
+        .link_name()
+        .ok()
+        .flatten()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn read_zip(bytes: &[u8]) -> Vec<Entry> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+        .unwrap_or_else(|err| panic!("failed to read snapshot value as zip: {err}"));
+    let mut rv = vec![];
+    for idx in 0..archive.len() {
+        let mut file = archive
+            .by_index(idx)
+            .unwrap_or_else(|err| panic!("failed to read zip entry: {err}"));
+        let unix_mode = file.unix_mode();
+        let mut contents = vec![];
+        if !file.is_dir() {
+            file.read_to_end(&mut contents)
+                .unwrap_or_else(|err| panic!("failed to read zip entry: {err}"));
+        }
+        let kind = if file.is_dir() {
+            EntryKind::Dir
+        } else if unix_mode.map_or(false, |mode| mode & 0o170000 == 0o120000) {
+            EntryKind::Symlink(String::from_utf8_lossy(&contents).into_owned())
+        } else {
+            EntryKind::File(contents)
+        };
+        rv.push(Entry {
+            path: file.name().to_string(),
+            mode: unix_mode.map(|mode| mode & 0o7777),
+            kind,
+        });
+    }
+    rv
+}
+
+fn write_entry(entry: &Entry, out: &mut String) {
+    let path = entry.path.trim_end_matches('/');
+    let mode = entry.mode.map(|mode| format!("mode {mode:03o}"));
+    match &entry.kind {
+        EntryKind::Dir => {
+            write!(out, "{path}/").unwrap();
+            if let Some(mode) = mode {
+                write!(out, " ({mode})").unwrap();
+            }
+            out.push('\n');
+        }
+        EntryKind::Symlink(target) => writeln!(out, "{path} -> {target}").unwrap(),
+        EntryKind::Other(kind) => writeln!(out, "{path} ({kind})").unwrap(),
+        EntryKind::File(contents) => {
+            let text = std::str::from_utf8(contents)
+                .ok()
+                .filter(|text| text.len() <= MAX_TEXT_LEN && !is_binary(text));
+            let mut details = vec![format!("{} bytes", contents.len())];
+            details.extend(mode);
+            if text.is_none() {
+                details.push(format!("sha256 {:x}", Sha256::digest(contents)));
+            }
+            writeln!(out, "{path} ({})", details.join(", ")).unwrap();
+            for line in text.iter().flat_map(|text| text.lines()) {
+                writeln!(out, "  {line}").unwrap();
+            }
+        }
+    }
+}
+
+fn is_binary(text: &str) -> bool {
+    text.chars()
+        .any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+}
+
+#[test]
+fn test_render_archive() {
+    use std::io::Write;
+
+    let mut builder = tar::Builder::new(vec![]);
+    for (path, mode, contents) in [
+        ("pkg/bin/tool", 0o755, &b"#!/bin/sh\necho hi\n"[..]),
+        ("pkg/data.bin", 0o644, &b"\x00\x01\x02"[..]),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(mode);
+        header.set_mtime(1_700_000_000);
+        header.set_uid(1000);
+        header.set_cksum();
+        builder.append_data(&mut header, path, contents).unwrap();
+    }
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Symlink);
+    header.set_size(0);
+    header.set_mode(0o777);
+    builder
+        .append_link(&mut header, "pkg/current", "bin/tool")
+        .unwrap();
+    let tar = builder.into_inner().unwrap();
+
+    let expected = "pkg/bin/tool (18 bytes, mode 755)\n  #!/bin/sh\n  echo hi\n\
+                    pkg/current -> bin/tool\n\
+                    pkg/data.bin (3 bytes, mode 644, sha256 \
+                    ae4b3280e56e2faf83f414a6e3dabe9d5fbe18976544c05fed121accb85b53fc)";
+    assert_eq!(render_archive(&tar), expected);
+
+    let mut gz = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+    gz.write_all(&tar).unwrap();
+    assert_eq!(render_archive(&gz.finish().unwrap()), expected);
+
+    let mut zip = zip::ZipWriter::new(Cursor::new(vec![]));
+    let options = zip::write::FileOptions::default().unix_permissions(0o644);
+    zip.start_file("b.txt", options).unwrap();
+    zip.write_all(b"b\n").unwrap();
+    zip.add_directory("a/", options.unix_permissions(0o755))
+        .unwrap();
+    let zip = zip.finish().unwrap().into_inner();
+    assert_eq!(
+        render_archive(&zip),
+        "a/ (mode 755)\nb.txt (2 bytes, mode 644)\n  b"
+    );
+}
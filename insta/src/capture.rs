@@ -0,0 +1,129 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::io::AsRawFd;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+
+/// The standard streams are shared by the whole process, so only one
+/// capture can be active at a time.
+static CAPTURE_LOCK: Mutex<()> = Mutex::new(());
+
+/// The output captured by [`capture_output`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CapturedOutput {
+    /// Everything written to the standard output.
+    pub stdout: String,
+    /// Everything written to the standard error.
+    pub stderr: String,
+}
+
+impl fmt::Display for CapturedOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "----- stdout -----")?;
+        writeln!(f, "{}", self.stdout.trim_end())?;
+        writeln!(f, "----- stderr -----")?;
+        write!(f, "{}", self.stderr.trim_end())
+    }
+}
+
+/// Runs `f` and captures what it writes to the standard output and error.
+///
+/// The file descriptors of both streams are redirected while `f` runs, so
+/// output written with [`std::io::stdout`] and [`std::io::stderr`], by C
+/// libraries and by child processes that inherit the streams is captured.
+///
+/// Note that the test harness captures [`print!`] and [`eprint!`] of tests
+/// itself, before the output reaches the streams, unless tests are run with
+/// `--nocapture`.  Since the streams are shared by all threads, output of
+/// other threads that is written while `f` runs is captured too.
+///
+/// ```no_run
+/// use std::io::Write;
+///
+/// let output = insta::capture_output(|| {
+///     writeln!(std::io::stdout(), "hello").unwrap();
+/// });
+/// assert_eq!(output.stdout, "hello\n");
+/// ```
+///
+/// **Platform:** Unix only.
+pub fn capture_output<F: FnOnce()>(f: F) -> CapturedOutput {
+    let _guard = CAPTURE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut stdout = Redirect::new(libc::STDOUT_FILENO).expect("failed to capture stdout");
+    let mut stderr = Redirect::new(libc::STDERR_FILENO).expect("failed to capture stderr");
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    let output = CapturedOutput {
+        stdout: stdout.finish().expect("failed to read captured stdout"),
+        stderr: stderr.finish().expect("failed to read captured stderr"),
+    };
+    if let Err(panic) = result {
+        panic::resume_unwind(panic);
+    }
+    output
+}
+
+/// Renders the output of `f` for snapshots.
+pub fn render_output<F: Fn()>(f: &F) -> String {
+    capture_output(f).to_string()
+}
+
+/// Points a file descriptor at a temporary file until it is finished or
+/// dropped.
+struct Redirect {
+    fd: i32,
+    saved: Option<i32>,
+    file: File,
+}
+
+impl Redirect {
+    fn new(fd: i32) -> io::Result<Redirect> {
+        flush_std();
+        let file = tempfile::tempfile()?;
+        // SAFETY: `fd` is a standard stream and `file` stays open for as
+        // long as the descriptor points to it.
+        let saved = unsafe { libc::dup(fd) };
+        if saved < 0 || unsafe { libc::dup2(file.as_raw_fd(), fd) } < 0 {
+            let err = io::Error::last_os_error();
+            if saved >= 0 {
+                unsafe { libc::close(saved) };
+            }
+            return Err(err);
+        }
+        Ok(Redirect {
+            fd,
+            saved: Some(saved),
+            file,
+        })
+    }
+
+    fn restore(&mut self) {
+        if let Some(saved) = self.saved.take() {
+            flush_std();
+            // SAFETY: `saved` is the duplicate of the original descriptor.
+            unsafe {
+                libc::dup2(saved, self.fd);
+                libc::close(saved);
+            }
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<String> {
+        self.restore();
+        let mut bytes = vec![];
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.read_to_end(&mut bytes)?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+impl Drop for Redirect {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}
+
+fn flush_std() {
+    io::stdout().flush().ok();
+    io::stderr().flush().ok();
+}
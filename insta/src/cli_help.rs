@@ -0,0 +1,40 @@
+use clap::Command;
+
+/// The terminal width help is rendered with, so that it is wrapped the same
+/// way on every machine.
+const HELP_WIDTH: usize = 100;
+
+/// Renders the `--help` output of `cmd` and all of its subcommands.
+///
+/// Returns the path of each command joined with `-` (`app-remote-add`), its
+/// invocation (`app remote add --help`) and its help.  Hidden subcommands and
+/// the generated `help` subcommand are skipped.  Trailing whitespace is
+/// removed from the lines of the help.
+pub fn render_clap_help(cmd: Command) -> Vec<(String, String, String)> {
+    let mut cmd = cmd.term_width(HELP_WIDTH);
+    cmd.build();
+    let mut rv = vec![];
+    collect_help(&mut cmd, &mut vec![], &mut rv);
+    rv
+}
+
+fn collect_help(cmd: &mut Command, path: &mut Vec<String>, rv: &mut Vec<(String, String, String)>) {
+    path.push(cmd.get_name().to_string());
+    rv.push((
+        path.join("-"),
+        format!("{} --help", path.join(" ")),
+        // editors strip the trailing whitespace clap leaves after empty help
+        cmd.render_long_help()
+            .to_string()
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n"),
+    ));
+    for sub in cmd.get_subcommands_mut() {
+        if !sub.is_hide_set() && sub.get_name() != "help" {
+            collect_help(sub, path, rv);
+        }
+    }
+    path.pop();
+}
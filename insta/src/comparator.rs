@@ -1,6 +1,10 @@
 //! Provides the [`Comparator`] trait, which provides a mechanism for specifying
 //! how [`Snapshot`] data should be compared.
 
+use std::cmp::Ordering;
+use std::path::Path;
+
+use crate::content::{yaml, Content};
 use crate::snapshot::{Snapshot, SnapshotContents, TextSnapshotKind};
 
 /// Allows specific behavior to be invoked when [`Snapshot`]s are compared.
@@ -81,9 +85,239 @@ impl Comparator for DefaultComparator {
     }
 }
 
+/// Compares YAML (and JSON) snapshots structurally, ignoring the order of
+/// map keys.
+///
+/// Refactors that reorder struct fields or switch map types tend to reorder
+/// the keys in serialized snapshots without changing any data.  This
+/// comparator parses both sides and only reports a mismatch if the values
+/// differ, so the diff shown on failure is caused by an actual value change.
+/// The order of sequences still matters.
+///
+/// Only snapshots that parse to a map or a sequence and whose lines are a
+/// reordering of each other are compared structurally, so that plain text
+/// which YAML happens to accept, such as lines that YAML would drop as
+/// comments or fold into one scalar, isn't considered equal.  Other
+/// snapshots are compared like [`DefaultComparator`] would.
+///
+/// It can be enabled for a single assertion:
+///
+/// ```rust
+/// # use insta::{with_settings, UnorderedMapComparator};
+/// with_settings!({comparator => Box::new(UnorderedMapComparator)}, {
+///     insta::assert_snapshot!("b: 2\na: 1", @"
+///     a: 1
+///     b: 2
+///     ");
+/// });
+/// ```
+#[derive(Clone)]
+pub struct UnorderedMapComparator;
+
+impl UnorderedMapComparator {
+    fn parse(text: &str) -> Option<Content> {
+        let mut content = yaml::parse_str(text, Path::new("<snapshot>")).ok()?;
+        if !matches!(content, Content::Map(_) | Content::Seq(_)) {
+            return None;
+        }
+        content.walk(&mut |content| {
+            if let Content::Map(ref mut items) = content {
+                items.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+            }
+            true
+        });
+        Some(content)
+    }
+
+    /// Returns the lines of `text` in sorted order, without the trailing
+    /// commas that move with the last entry of a JSON object.
+    fn sorted_lines(text: &str) -> Vec<&str> {
+        let mut lines = text
+            .lines()
+            .map(|line| line.strip_suffix(',').unwrap_or(line))
+            .collect::<Vec<_>>();
+        lines.sort_unstable();
+        lines
+    }
+}
+
+impl Comparator for UnorderedMapComparator {
+    fn matches(&self, reference: &Snapshot, test: &Snapshot) -> bool {
+        let (reference_text, test_text) = match (reference.as_text(), test.as_text()) {
+            (Some(reference), Some(test)) => (reference.to_string(), test.to_string()),
+            _ => return DefaultComparator.matches(reference, test),
+        };
+        if Self::sorted_lines(&reference_text) == Self::sorted_lines(&test_text) {
+            if let (Some(reference_content), Some(test_content)) =
+                (Self::parse(&reference_text), Self::parse(&test_text))
+            {
+                return reference_content == test_content;
+            }
+        }
+        DefaultComparator.matches(reference, test)
+    }
+
+    fn matches_fully(&self, reference: &Snapshot, test: &Snapshot) -> bool {
+        self.matches(reference, test)
+            && match reference.as_text().map(|x| &x.kind) {
+                Some(TextSnapshotKind::File) => {
+                    reference.metadata().trim_for_persistence()
+                        == test.metadata().trim_for_persistence()
+                }
+                _ => true,
+            }
+    }
+
+    fn dyn_clone(&self) -> Box<dyn Comparator> {
+        Box::new(self.clone())
+    }
+}
+
+/// Compares text snapshots with a tolerance for numbers.
+///
+/// Numbers that appear at the same place in both snapshots match if they
+/// differ by at most `tolerance`, or by at most `tolerance` times the
+/// larger of the two for numbers greater than one.  Everything else has to
+/// match exactly.  This keeps snapshots of floating point results from
+/// failing on differences in the last digits between platforms, while the
+/// reference value stays in the snapshot until it changes by more than the
+/// tolerance.  Binary snapshots are compared like [`DefaultComparator`]
+/// would.
+///
+/// It is usually enabled with [`Settings::set_float_tolerance`](crate::Settings::set_float_tolerance)
+/// or [`assert_float_snapshot!`](crate::assert_float_snapshot!):
+///
+/// ```rust
+/// # use insta::with_settings;
+/// with_settings!({float_tolerance => 1e-9}, {
+///     insta::assert_debug_snapshot!((0.1 + 0.2, 2.0), @"
+///     (
+///         0.3,
+///         2.0,
+///     )
+///     ");
+/// });
+/// ```
+#[derive(Clone)]
+pub struct FloatToleranceComparator {
+    tolerance: f64,
+}
+
+impl FloatToleranceComparator {
+    /// Creates a comparator that accepts numbers within `tolerance`.
+    pub fn new(tolerance: f64) -> FloatToleranceComparator {
+        FloatToleranceComparator { tolerance }
+    }
+
+    fn numbers_match(&self, a: f64, b: f64) -> bool {
+        a == b || (a - b).abs() <= self.tolerance * a.abs().max(b.abs()).max(1.0)
+    }
+}
+
+/// A part of a snapshot that is either a number or the text between numbers.
+#[derive(Debug, PartialEq)]
+enum NumberToken<'a> {
+    Number(f64),
+    Text(&'a str),
+}
+
+/// Splits text into numbers and the text between them.
+///
+/// Digits that follow a letter or `_`, such as in `f64` or `x_1`, belong to
+/// the text.
+fn split_numbers(text: &str) -> Vec<NumberToken<'_>> {
+    let bytes = text.as_bytes();
+    let mut rv = vec![];
+    let mut text_start = 0;
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let starts_number = bytes[idx].is_ascii_digit()
+            || (bytes[idx] == b'-' && bytes.get(idx + 1).map_or(false, u8::is_ascii_digit));
+        let after_word =
+            idx > 0 && (bytes[idx - 1].is_ascii_alphanumeric() || bytes[idx - 1] == b'_');
+        if !starts_number || after_word {
+            idx += 1;
+            continue;
+        }
+        let start = idx;
+        idx += 1;
+        let skip_digits = |idx: &mut usize| {
+            while *idx < bytes.len() && bytes[*idx].is_ascii_digit() {
+                *idx += 1;
+            }
+        };
+        skip_digits(&mut idx);
+        if bytes.get(idx) == Some(&b'.') && bytes.get(idx + 1).map_or(false, u8::is_ascii_digit) {
+            idx += 1;
+            skip_digits(&mut idx);
+        }
+        if matches!(bytes.get(idx), Some(b'e' | b'E')) {
+            let digits = match bytes.get(idx + 1) {
+                Some(b'+' | b'-') => idx + 2,
+                _ => idx + 1,
+            };
+            if bytes.get(digits).map_or(false, u8::is_ascii_digit) {
+                idx = digits;
+                skip_digits(&mut idx);
+            }
+        }
+        match text[start..idx].parse() {
+            Ok(number) => {
+                if text_start < start {
+                    rv.push(NumberToken::Text(&text[text_start..start]));
+                }
+                rv.push(NumberToken::Number(number));
+                text_start = idx;
+            }
+            Err(_) => continue,
+        }
+    }
+    if text_start < text.len() {
+        rv.push(NumberToken::Text(&text[text_start..]));
+    }
+    rv
+}
+
+impl Comparator for FloatToleranceComparator {
+    fn matches(&self, reference: &Snapshot, test: &Snapshot) -> bool {
+        let (reference_text, test_text) = match (reference.as_text(), test.as_text()) {
+            (Some(reference), Some(test)) => (reference.to_string(), test.to_string()),
+            _ => return DefaultComparator.matches(reference, test),
+        };
+        let reference_tokens = split_numbers(&reference_text);
+        let test_tokens = split_numbers(&test_text);
+        reference_tokens.len() == test_tokens.len()
+            && reference_tokens
+                .iter()
+                .zip(test_tokens.iter())
+                .all(|pair| match pair {
+                    (NumberToken::Number(a), NumberToken::Number(b)) => self.numbers_match(*a, *b),
+                    (a, b) => a == b,
+                })
+    }
+
+    fn matches_fully(&self, reference: &Snapshot, test: &Snapshot) -> bool {
+        self.matches(reference, test)
+            && match reference.as_text().map(|x| &x.kind) {
+                Some(TextSnapshotKind::File) => {
+                    reference.metadata().trim_for_persistence()
+                        == test.metadata().trim_for_persistence()
+                }
+                _ => true,
+            }
+    }
+
+    fn dyn_clone(&self) -> Box<dyn Comparator> {
+        Box::new(self.clone())
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::DefaultComparator;
+    use super::{
+        split_numbers, DefaultComparator, FloatToleranceComparator, NumberToken,
+        UnorderedMapComparator,
+    };
 
     use crate::comparator::Comparator;
     use crate::snapshot::{
@@ -141,4 +375,126 @@ mod test {
         // Comparing snapshots with differing metadata fails.
         assert!(!comparator.matches_fully(&a, &b));
     }
+
+    #[test]
+    fn unordered_map_comparator_ignores_key_order() {
+        let snapshot = |text: &str| {
+            Snapshot::from_components(
+                String::from("test"),
+                None,
+                MetaData::default(),
+                SnapshotContents::Text(TextSnapshotContents::new(
+                    String::from(text),
+                    TextSnapshotKind::File,
+                )),
+            )
+        };
+        let comparator = UnorderedMapComparator;
+        let a = snapshot("a: 1\nb:\n  c: [1, 2]\n  d: x\n");
+        let b = snapshot("b:\n  d: x\n  c: [1, 2]\na: 1\n");
+        assert!(comparator.matches(&a, &b));
+        assert!(comparator.matches_fully(&a, &b));
+        // sequence order still matters
+        assert!(!comparator.matches(&a, &snapshot("a: 1\nb:\n  c: [2, 1]\n  d: x\n")));
+        assert!(!comparator.matches(&a, &snapshot("a: 2\nb:\n  c: [1, 2]\n  d: x\n")));
+        // falls back to a text comparison
+        assert!(comparator.matches(&snapshot("a: [1"), &snapshot("a: [1")));
+        assert!(!comparator.matches(&snapshot("a: [1"), &snapshot("a: [2")));
+        // JSON objects reorder their trailing commas
+        assert!(comparator.matches(
+            &snapshot("{\n  \"a\": 1,\n  \"b\": 2\n}"),
+            &snapshot("{\n  \"b\": 2,\n  \"a\": 1\n}")
+        ));
+    }
+
+    #[test]
+    fn unordered_map_comparator_compares_plain_text() {
+        let snapshot = |text: &str| {
+            Snapshot::from_components(
+                String::from("test"),
+                None,
+                MetaData::default(),
+                SnapshotContents::Text(TextSnapshotContents::new(
+                    String::from(text),
+                    TextSnapshotKind::File,
+                )),
+            )
+        };
+        let comparator = UnorderedMapComparator;
+        // YAML drops comments and folds plain scalars over several lines
+        assert!(!comparator.matches(
+            &snapshot("status: ok\n# retried once"),
+            &snapshot("status: ok")
+        ));
+        assert!(!comparator.matches(
+            &snapshot("message: hello\n  world"),
+            &snapshot("message: hello world")
+        ));
+        assert!(!comparator.matches(&snapshot("hello\nworld"), &snapshot("hello world")));
+        // the same data laid out as JSON and YAML
+        assert!(!comparator.matches(&snapshot("{\"a\": 1}"), &snapshot("a: 1")));
+        // lines of plain text are not reordered
+        assert!(!comparator.matches(&snapshot("b\na"), &snapshot("a\nb")));
+    }
+
+    #[test]
+    fn float_tolerance_comparator_accepts_close_numbers() {
+        let snapshot = |text: &str| {
+            Snapshot::from_components(
+                String::from("test"),
+                None,
+                MetaData::default(),
+                SnapshotContents::Text(TextSnapshotContents::new(
+                    String::from(text),
+                    TextSnapshotKind::File,
+                )),
+            )
+        };
+        assert_eq!(
+            split_numbers("x: -1.5e3, f64_2 [3]"),
+            vec![
+                NumberToken::Text("x: "),
+                NumberToken::Number(-1500.0),
+                NumberToken::Text(", f64_2 ["),
+                NumberToken::Number(3.0),
+                NumberToken::Text("]"),
+            ]
+        );
+
+        let comparator = FloatToleranceComparator::new(1e-9);
+        let a = snapshot(
+            "x: 0.30000000000000004
+y: 1e12",
+        );
+        assert!(comparator.matches(
+            &a,
+            &snapshot(
+                "x: 0.3
+y: 1000000000000.0001"
+            )
+        ));
+        assert!(comparator.matches_fully(&a, &a));
+        assert!(!comparator.matches(
+            &a,
+            &snapshot(
+                "x: 0.3001
+y: 1e12"
+            )
+        ));
+        assert!(!comparator.matches(
+            &a,
+            &snapshot(
+                "x: 0.3
+y: 1e12
+z: 1"
+            )
+        ));
+        assert!(!comparator.matches(
+            &a,
+            &snapshot(
+                "z: 0.3
+y: 1e12"
+            )
+        ));
+    }
 }
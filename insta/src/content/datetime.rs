@@ -0,0 +1,172 @@
+use crate::content::Content;
+
+impl Content {
+    /// Rewrites all RFC 3339 timestamps in string values to UTC with
+    /// `precision` fractional second digits.
+    pub(crate) fn normalize_datetimes(&mut self, precision: u8) {
+        self.walk(&mut |content| {
+            if let Content::String(ref mut value) = content {
+                if let Some(normalized) = normalize_rfc3339(value, precision) {
+                    *value = normalized;
+                }
+            }
+            true
+        })
+    }
+}
+
+/// Converts an RFC 3339 timestamp with an offset to UTC.
+///
+/// The fractional seconds are truncated or zero-padded to `precision`
+/// digits.  Returns `None` if `value` is not a timestamp with an offset;
+/// naive date times are left alone since they carry no time zone.
+pub(crate) fn normalize_rfc3339(value: &str, precision: u8) -> Option<String> {
+    let b = value.as_bytes();
+    let num = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = b.get(range)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        Some(
+            digits
+                .iter()
+                .fold(0, |acc, d| acc * 10 + i64::from(d - b'0')),
+        )
+    };
+    let expect = |idx: usize, options: &[u8]| b.get(idx).map_or(false, |c| options.contains(c));
+
+    if !(expect(4, b"-")
+        && expect(7, b"-")
+        && expect(10, b"Tt ")
+        && expect(13, b":")
+        && expect(16, b":"))
+    {
+        return None;
+    }
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hour, minute, second) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    if !(1..=12).contains(&month)
+        || day < 1
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let mut pos = 19;
+    let mut fraction = "";
+    if expect(pos, b".") {
+        let len = b[pos + 1..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        if len == 0 {
+            return None;
+        }
+        fraction = &value[pos + 1..pos + 1 + len];
+        pos += 1 + len;
+    }
+
+    let offset = match b.get(pos)? {
+        b'Z' | b'z' if pos + 1 == b.len() => 0,
+        sign @ (b'+' | b'-') if pos + 6 == b.len() && expect(pos + 3, b":") => {
+            let (hours, minutes) = (num(pos + 1..pos + 3)?, num(pos + 4..pos + 6)?);
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let offset = hours * 3600 + minutes * 60;
+            if *sign == b'-' {
+                -offset
+            } else {
+                offset
+            }
+        }
+        _ => return None,
+    };
+
+    let timestamp =
+        days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second - offset;
+    let (year, month, day) = civil_from_days(timestamp.div_euclid(86400));
+    if !(0..=9999).contains(&year) {
+        return None;
+    }
+    let secs = timestamp.rem_euclid(86400);
+    let mut rv = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    );
+    if precision > 0 {
+        let precision = usize::from(precision);
+        rv.push('.');
+        rv.push_str(&fraction[..fraction.len().min(precision)]);
+        rv.extend(std::iter::repeat('0').take(precision.saturating_sub(fraction.len())));
+    }
+    rv.push('Z');
+    Some(rv)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Date conversions follow Howard Hinnant's `days_from_civil` and
+// `civil_from_days` algorithms.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[test]
+fn test_normalize_rfc3339() {
+    let n = |s| normalize_rfc3339(s, 3);
+    assert_eq!(
+        n("2024-03-01T01:30:00+02:00").as_deref(),
+        Some("2024-02-29T23:30:00.000Z")
+    );
+    assert_eq!(
+        n("2023-12-31T20:00:00.123456-05:30").as_deref(),
+        Some("2024-01-01T01:30:00.123Z")
+    );
+    assert_eq!(
+        n("2024-01-01 00:00:00.5z").as_deref(),
+        Some("2024-01-01T00:00:00.500Z")
+    );
+    assert_eq!(
+        normalize_rfc3339("2024-01-01T00:00:00.999Z", 0).as_deref(),
+        Some("2024-01-01T00:00:00Z")
+    );
+    assert_eq!(n("2024-01-01T00:00:00"), None);
+    assert_eq!(n("2023-02-29T00:00:00Z"), None);
+    assert_eq!(n("2024-01-01T00:00:00+0100"), None);
+    assert_eq!(n("2024-01-01T00:00:00Z and more"), None);
+    assert_eq!(n("not a date"), None);
+}
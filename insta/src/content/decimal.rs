@@ -0,0 +1,117 @@
+use crate::content::Content;
+
+/// Exponents beyond this are kept in scientific notation instead of being
+/// written out with all their zeros.
+const MAX_PLAIN_EXPONENT: i64 = 64;
+
+impl Content {
+    /// Rewrites decimal numbers in string values into their canonical form.
+    ///
+    /// This is only applied to the values that [`crate::decimal_redaction`]
+    /// selects, as strings like version numbers look like decimals too.
+    pub(crate) fn normalize_decimals(&mut self) {
+        self.walk(&mut |content| {
+            if let Content::String(ref mut value) = content {
+                if let Some(normalized) = normalize_decimal(value) {
+                    *value = normalized;
+                }
+            }
+            true
+        })
+    }
+}
+
+/// Canonicalizes the textual form of a decimal number.
+///
+/// Trailing zeros of the fraction, leading zeros of the integer part and
+/// exponents are removed so that numbers that are equal but differ in scale
+/// (`1.50`, `1.5`, `15E-1`) are written the same way.  Only strings with a
+/// fractional part or an exponent are considered; plain integers such as
+/// `"007"` are left alone since they are often identifiers.
+pub(crate) fn normalize_decimal(value: &str) -> Option<String> {
+    let (negative, rest) = match value.as_bytes().first()? {
+        b'-' => (true, &value[1..]),
+        b'+' => (false, &value[1..]),
+        _ => (false, value),
+    };
+    let (mantissa, exponent) = match rest.find(['e', 'E']) {
+        Some(idx) => {
+            let exp = &rest[idx + 1..];
+            let digits = exp.strip_prefix(['+', '-']).unwrap_or(exp);
+            if digits.is_empty() || digits.len() > 18 || !digits.bytes().all(|c| c.is_ascii_digit())
+            {
+                return None;
+            }
+            (&rest[..idx], exp.parse::<i64>().ok()?)
+        }
+        None => (rest, 0),
+    };
+    let (int_part, frac_part) = match mantissa.split_once('.') {
+        Some((int_part, frac_part)) => (int_part, frac_part),
+        None if exponent != 0 || rest.len() != mantissa.len() => (mantissa, ""),
+        None => return None,
+    };
+    if (int_part.is_empty() && frac_part.is_empty())
+        || !int_part
+            .bytes()
+            .chain(frac_part.bytes())
+            .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    // all significant digits with the decimal point after `point` digits
+    let digits = format!("{int_part}{frac_part}");
+    let leading = digits.len() - digits.trim_start_matches('0').len();
+    let digits = digits.trim_matches('0');
+    if digits.is_empty() {
+        return Some("0".into());
+    }
+    let point = int_part.len() as i64 + exponent - leading as i64;
+
+    let mut rv = String::new();
+    if negative {
+        rv.push('-');
+    }
+    let len = digits.len() as i64;
+    if point > len + MAX_PLAIN_EXPONENT || point < -MAX_PLAIN_EXPONENT {
+        rv.push_str(&digits[..1]);
+        if digits.len() > 1 {
+            rv.push('.');
+            rv.push_str(&digits[1..]);
+        }
+        rv.push_str(&format!("e{}", point - 1));
+    } else if point <= 0 {
+        rv.push_str("0.");
+        rv.extend(std::iter::repeat('0').take((-point) as usize));
+        rv.push_str(digits);
+    } else if point >= len {
+        rv.push_str(digits);
+        rv.extend(std::iter::repeat('0').take((point - len) as usize));
+    } else {
+        rv.push_str(&digits[..point as usize]);
+        rv.push('.');
+        rv.push_str(&digits[point as usize..]);
+    }
+    Some(rv)
+}
+
+#[test]
+fn test_normalize_decimal() {
+    let n = |s| normalize_decimal(s);
+    assert_eq!(n("1.500").as_deref(), Some("1.5"));
+    assert_eq!(n("001.50").as_deref(), Some("1.5"));
+    assert_eq!(n("-0.00").as_deref(), Some("0"));
+    assert_eq!(n("15E-1").as_deref(), Some("1.5"));
+    assert_eq!(n("1.5e+3").as_deref(), Some("1500"));
+    assert_eq!(n("1e-3").as_deref(), Some("0.001"));
+    assert_eq!(n(".25").as_deref(), Some("0.25"));
+    assert_eq!(n("-12.").as_deref(), Some("-12"));
+    assert_eq!(n("2.50e100").as_deref(), Some("2.5e100"));
+    assert_eq!(n("7e-80").as_deref(), Some("7e-80"));
+    assert_eq!(n("007"), None);
+    assert_eq!(n("1.2.3"), None);
+    assert_eq!(n("e5"), None);
+    assert_eq!(n("1.5 EUR"), None);
+    assert_eq!(n(""), None);
+}
@@ -7,6 +7,12 @@
 //! YAML is a superset of JSON insta instead currently parses JSON via the
 //! YAML implementation.
 
+#[cfg(feature = "serde")]
+mod datetime;
+#[cfg(feature = "redactions")]
+mod decimal;
+#[cfg(feature = "serde")]
+mod deserialization;
 pub mod json;
 #[cfg(feature = "serde")]
 mod serialization;
@@ -344,6 +350,23 @@ impl Content {
         }
     }
 
+    /// Returns the serde name of the struct or enum the content was
+    /// serialized from.
+    #[cfg(feature = "serde")]
+    pub(crate) fn type_name(&self) -> Option<&'static str> {
+        match *self {
+            Content::UnitStruct(name)
+            | Content::UnitVariant(name, ..)
+            | Content::NewtypeStruct(name, _)
+            | Content::NewtypeVariant(name, ..)
+            | Content::TupleStruct(name, _)
+            | Content::TupleVariant(name, ..)
+            | Content::Struct(name, _)
+            | Content::StructVariant(name, ..) => Some(name),
+            _ => None,
+        }
+    }
+
     /// Recursively walks the content structure mutably.
     ///
     /// The callback is invoked for every content in the tree.
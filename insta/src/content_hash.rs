@@ -0,0 +1,113 @@
+use crate::naming::fnv1a;
+
+/// Contents from this size on are hashed with the preferred algorithm even
+/// if they are text, smaller text keeps the cheaper FNV-1a hash.
+pub(crate) const LARGE_CONTENTS: usize = 64 * 1024;
+
+/// An algorithm that hashes the contents of snapshots.
+///
+/// Hashes other than FNV-1a are prefixed with the name of their algorithm,
+/// as in `blake3:…`, so that hashes stored by one version of insta can be
+/// checked by another which was built with different features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HashAlgorithm {
+    Fnv1a,
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// Returns the best algorithm that is enabled.  BLAKE3 is preferred over
+    /// SHA-256, and FNV-1a is used if neither is enabled.
+    fn preferred() -> HashAlgorithm {
+        if cfg!(feature = "blake3") {
+            HashAlgorithm::Blake3
+        } else if cfg!(feature = "sha256") {
+            HashAlgorithm::Sha256
+        } else {
+            HashAlgorithm::Fnv1a
+        }
+    }
+
+    /// Returns the algorithm that made a stored hash.
+    fn of_hash(hash: &str) -> Option<HashAlgorithm> {
+        match hash.split_once(':').map(|(name, _)| name) {
+            None => Some(HashAlgorithm::Fnv1a),
+            Some("sha256") => Some(HashAlgorithm::Sha256),
+            Some("blake3") => Some(HashAlgorithm::Blake3),
+            Some(_) => None,
+        }
+    }
+
+    /// Returns the hash of `bytes`, or `None` if the algorithm is not
+    /// enabled.
+    fn hash(self, bytes: &[u8]) -> Option<String> {
+        match self {
+            HashAlgorithm::Fnv1a => Some(format!("{:016x}", fnv1a(bytes))),
+            #[cfg(feature = "sha256")]
+            HashAlgorithm::Sha256 => {
+                use sha2::{Digest, Sha256};
+                Some(format!("sha256:{:x}", Sha256::digest(bytes)))
+            }
+            #[cfg(feature = "blake3")]
+            HashAlgorithm::Blake3 => Some(format!("blake3:{}", blake3::hash(bytes).to_hex())),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+}
+
+/// Hashes `bytes` with the preferred algorithm, or with FNV-1a if they are
+/// small text.
+pub(crate) fn hash_contents(bytes: &[u8], is_text: bool) -> String {
+    let algorithm = if is_text && bytes.len() < LARGE_CONTENTS {
+        HashAlgorithm::Fnv1a
+    } else {
+        HashAlgorithm::preferred()
+    };
+    algorithm
+        .hash(bytes)
+        .expect("preferred algorithm is enabled")
+}
+
+/// Returns whether `hash` is the hash of `bytes`, or `None` if `hash` was
+/// made with an algorithm that is unknown or not enabled.
+pub(crate) fn verify_hash(hash: &str, bytes: &[u8]) -> Option<bool> {
+    let algorithm = HashAlgorithm::of_hash(hash)?;
+    Some(algorithm.hash(bytes)? == hash)
+}
+
+#[test]
+fn test_hash_contents() {
+    assert_eq!(hash_contents(b"", true), "cbf29ce484222325");
+    #[cfg(feature = "blake3")]
+    assert_eq!(
+        hash_contents(b"", false),
+        "blake3:af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+    );
+    #[cfg(feature = "blake3")]
+    assert!(hash_contents(&vec![b'a'; LARGE_CONTENTS], true).starts_with("blake3:"));
+}
+
+#[test]
+fn test_verify_hash() {
+    assert_eq!(verify_hash("cbf29ce484222325", b""), Some(true));
+    assert_eq!(verify_hash("cbf29ce484222325", b"a"), Some(false));
+    assert_eq!(
+        verify_hash("md5:d41d8cd98f00b204e9800998ecf8427e", b""),
+        None
+    );
+    #[cfg(feature = "sha256")]
+    assert_eq!(
+        verify_hash(
+            "sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            b""
+        ),
+        Some(true)
+    );
+    #[cfg(not(feature = "sha256"))]
+    assert_eq!(verify_hash("sha256:e3b0c442", b""), None);
+    let hash = hash_contents(b"\x00\x01", false);
+    assert_eq!(verify_hash(&hash, b"\x00\x01"), Some(true));
+    assert_eq!(verify_hash(&hash, b"\x00\x02"), Some(false));
+}
@@ -0,0 +1,61 @@
+use std::fmt::Display;
+
+use crate::table::{escape_cell, render_table};
+
+/// Parses CSV and formats it as a table with aligned columns.
+///
+/// The first record is the header.  For every `(column, replacement)` in
+/// `redactions` the cells of the column with that header are replaced,
+/// except for the header itself.  Line breaks and `|` in cells are escaped
+/// as `\n` and `\|`.
+///
+/// Panics if `input` is not valid CSV or a redacted column does not exist.
+pub fn normalize_csv<V: Display + ?Sized>(input: &V, redactions: &[(&str, String)]) -> String {
+    let input = input.to_string();
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(input.as_bytes());
+    let mut rows = reader
+        .records()
+        .map(|record| match record {
+            Ok(record) => record.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            Err(err) => panic!("failed to parse snapshot value as CSV: {err}\n\n{input}"),
+        })
+        .collect::<Vec<_>>();
+
+    for (column, replacement) in redactions {
+        let idx = rows
+            .first()
+            .and_then(|header| header.iter().position(|x| x == column))
+            .unwrap_or_else(|| panic!("CSV has no column named {column:?}"));
+        for row in rows.iter_mut().skip(1) {
+            if let Some(cell) = row.get_mut(idx) {
+                *cell = replacement.clone();
+            }
+        }
+    }
+
+    let rows = rows
+        .iter()
+        .map(|row| row.iter().map(|cell| escape_cell(cell)).collect())
+        .collect::<Vec<_>>();
+    render_table(&rows)
+}
+
+#[test]
+fn test_normalize_csv() {
+    let input = "id,name,email\n1,Ada,ada@example.com\n22,\"Lovelace, A\",a|l@example.com\n";
+    assert_eq!(
+        normalize_csv(input, &[]),
+        "id | name        | email\n\
+         1  | Ada         | ada@example.com\n\
+         22 | Lovelace, A | a\\|l@example.com"
+    );
+    assert_eq!(
+        normalize_csv(input, &[("email", "[email]".into())]),
+        "id | name        | email\n\
+         1  | Ada         | [email]\n\
+         22 | Lovelace, A | [email]"
+    );
+}
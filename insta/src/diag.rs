@@ -0,0 +1,253 @@
+use std::fmt::Write;
+
+use serde::Serialize;
+
+/// A decoded value, in the terms of the CBOR diagnostic notation.
+enum Item {
+    Int(String),
+    Float(String),
+    Bytes(Vec<u8>),
+    Text(String),
+    Bool(bool),
+    Null,
+    #[cfg(feature = "cbor")]
+    Tag(u64, Box<Item>),
+    #[cfg(feature = "msgpack")]
+    Ext(i8, Vec<u8>),
+    Array(Vec<Item>),
+    Map(Vec<(Item, Item)>),
+}
+
+/// Decodes CBOR and formats it in diagnostic notation.
+///
+/// Values are written like in [RFC 8949](https://www.rfc-editor.org/rfc/rfc8949#section-8),
+/// with byte strings as `h'…'` and tags as `tag(value)`, and arrays and maps
+/// are spread over one line per element.  Input that holds several values
+/// one after another, such as a CBOR sequence, gives one value per line.
+///
+/// Panics if `input` is not valid CBOR.
+#[cfg(feature = "cbor")]
+pub fn render_cbor<B: AsRef<[u8]> + ?Sized>(input: &B) -> String {
+    let mut bytes = input.as_ref();
+    let mut items = vec![];
+    while !bytes.is_empty() {
+        match ciborium::de::from_reader::<ciborium::Value, _>(&mut bytes) {
+            Ok(value) => items.push(from_cbor(value)),
+            Err(err) => panic!("failed to decode snapshot value as CBOR: {err}"),
+        }
+    }
+    render_items(&items)
+}
+
+/// Encodes a [`serde::Serialize`] value as CBOR and formats it like
+/// [`render_cbor`].
+#[cfg(feature = "cbor")]
+pub fn render_cbor_value<T: Serialize + ?Sized>(value: &T) -> String {
+    let mut bytes = vec![];
+    ciborium::ser::into_writer(value, &mut bytes)
+        .unwrap_or_else(|err| panic!("failed to encode snapshot value as CBOR: {err}"));
+    render_cbor(&bytes)
+}
+
+/// Decodes MessagePack and formats it in the notation of [`render_cbor`].
+///
+/// Binary data is written as `h'…'` and extension types as
+/// `ext(type, h'…')`.
+///
+/// Panics if `input` is not valid MessagePack.
+#[cfg(feature = "msgpack")]
+pub fn render_msgpack<B: AsRef<[u8]> + ?Sized>(input: &B) -> String {
+    let mut bytes = input.as_ref();
+    let mut items = vec![];
+    while !bytes.is_empty() {
+        match rmpv::decode::read_value(&mut bytes) {
+            Ok(value) => items.push(from_msgpack(value)),
+            Err(err) => panic!("failed to decode snapshot value as MessagePack: {err}"),
+        }
+    }
+    render_items(&items)
+}
+
+/// Encodes a [`serde::Serialize`] value as MessagePack, with structs as maps
+/// of their field names, and formats it like [`render_msgpack`].
+#[cfg(feature = "msgpack")]
+pub fn render_msgpack_value<T: Serialize + ?Sized>(value: &T) -> String {
+    let bytes = rmp_serde::to_vec_named(value)
+        .unwrap_or_else(|err| panic!("failed to encode snapshot value as MessagePack: {err}"));
+    render_msgpack(&bytes)
+}
+
+#[cfg(feature = "cbor")]
+fn from_cbor(value: ciborium::Value) -> Item {
+    use ciborium::Value;
+
+    match value {
+        Value::Integer(x) => Item::Int(i128::from(x).to_string()),
+        Value::Float(x) => Item::Float(format_float(x, format!("{x:?}"))),
+        Value::Bytes(x) => Item::Bytes(x),
+        Value::Text(x) => Item::Text(x),
+        Value::Bool(x) => Item::Bool(x),
+        Value::Null => Item::Null,
+        Value::Tag(tag, x) => Item::Tag(tag, Box::new(from_cbor(*x))),
+        Value::Array(x) => Item::Array(x.into_iter().map(from_cbor).collect()),
+        Value::Map(x) => Item::Map(
+            x.into_iter()
+                .map(|(k, v)| (from_cbor(k), from_cbor(v)))
+                .collect(),
+        ),
+        other => panic!("unsupported CBOR value in snapshot: {other:?}"),
+    }
+}
+
+#[cfg(feature = "msgpack")]
+fn from_msgpack(value: rmpv::Value) -> Item {
+    use rmpv::Value;
+
+    match value {
+        Value::Nil => Item::Null,
+        Value::Boolean(x) => Item::Bool(x),
+        Value::Integer(x) => Item::Int(x.to_string()),
+        Value::F32(x) => Item::Float(format_float(f64::from(x), format!("{x:?}"))),
+        Value::F64(x) => Item::Float(format_float(x, format!("{x:?}"))),
+        Value::String(x) => match x.into_str() {
+            Some(x) => Item::Text(x),
+            None => panic!("invalid UTF-8 in MessagePack string in snapshot"),
+        },
+        Value::Binary(x) => Item::Bytes(x),
+        Value::Ext(ty, x) => Item::Ext(ty, x),
+        Value::Array(x) => Item::Array(x.into_iter().map(from_msgpack).collect()),
+        Value::Map(x) => Item::Map(
+            x.into_iter()
+                .map(|(k, v)| (from_msgpack(k), from_msgpack(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// Formats a float with `repr`, the shortest representation that reads
+/// back as the same value, or as `NaN`, `Infinity` or `-Infinity`.
+fn format_float(value: f64, repr: String) -> String {
+    if value.is_nan() {
+        "NaN".into()
+    } else if value == f64::INFINITY {
+        "Infinity".into()
+    } else if value == f64::NEG_INFINITY {
+        "-Infinity".into()
+    } else {
+        repr
+    }
+}
+
+fn render_items(items: &[Item]) -> String {
+    let mut rv = String::new();
+    for item in items {
+        write_item(item, 0, &mut rv);
+        rv.push('\n');
+    }
+    rv.truncate(rv.trim_end().len());
+    rv
+}
+
+fn write_item(item: &Item, indent: usize, out: &mut String) {
+    match item {
+        Item::Int(x) | Item::Float(x) => out.push_str(x),
+        Item::Bytes(x) => write_bytes(x, out),
+        Item::Text(x) => write_text(x, out),
+        Item::Bool(x) => write!(out, "{x}").unwrap(),
+        Item::Null => out.push_str("null"),
+        #[cfg(feature = "cbor")]
+        Item::Tag(tag, x) => {
+            write!(out, "{tag}(").unwrap();
+            write_item(x, indent, out);
+            out.push(')');
+        }
+        #[cfg(feature = "msgpack")]
+        Item::Ext(ty, x) => {
+            write!(out, "ext({ty}, ").unwrap();
+            write_bytes(x, out);
+            out.push(')');
+        }
+        Item::Array(items) if items.is_empty() => out.push_str("[]"),
+        Item::Array(items) => {
+            out.push_str("[\n");
+            for (idx, item) in items.iter().enumerate() {
+                write!(out, "{:indent$}", "", indent = (indent + 1) * 2).unwrap();
+                write_item(item, indent + 1, out);
+                out.push_str(if idx + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            write!(out, "{:indent$}]", "", indent = indent * 2).unwrap();
+        }
+        Item::Map(entries) if entries.is_empty() => out.push_str("{}"),
+        Item::Map(entries) => {
+            out.push_str("{\n");
+            for (idx, (key, value)) in entries.iter().enumerate() {
+                write!(out, "{:indent$}", "", indent = (indent + 1) * 2).unwrap();
+                write_item(key, indent + 1, out);
+                out.push_str(": ");
+                write_item(value, indent + 1, out);
+                out.push_str(if idx + 1 < entries.len() { ",\n" } else { "\n" });
+            }
+            write!(out, "{:indent$}}}", "", indent = indent * 2).unwrap();
+        }
+    }
+}
+
+fn write_bytes(bytes: &[u8], out: &mut String) {
+    out.push_str("h'");
+    for byte in bytes {
+        write!(out, "{byte:02x}").unwrap();
+    }
+    out.push('\'');
+}
+
+/// Writes a string with the escapes of JSON.
+fn write_text(text: &str, out: &mut String) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn test_render_cbor() {
+    // {"a": [1, -2, 1.5], "b": h'0102', "c": 1(1363896240), "d": null}
+    // followed by "x" as a CBOR sequence
+    let bytes = b"\xa4\x61a\x83\x01\x21\xf9\x3e\x00\x61b\x42\x01\x02\
+                  \x61c\xc1\x1a\x51\x4b\x67\xb0\x61d\xf6\x61x";
+    assert_eq!(
+        render_cbor(bytes),
+        "{\n  \"a\": [\n    1,\n    -2,\n    1.5\n  ],\n  \"b\": h'0102',\n  \
+         \"c\": 1(1363896240),\n  \"d\": null\n}\n\"x\""
+    );
+    assert_eq!(render_cbor(b"\xf9\x7e\x00"), "NaN");
+    assert_eq!(render_cbor(b"\xf9\xfc\x00"), "-Infinity");
+    assert_eq!(
+        render_cbor_value(&("a\"\n", [(); 0])),
+        "[\n  \"a\\\"\\n\",\n  []\n]"
+    );
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn test_render_msgpack() {
+    // {"a": 1, "b": [0.1f32, true], "c": ext(-1, h'00')}
+    let bytes = b"\x83\xa1a\x01\xa1b\x92\xca\x3d\xcc\xcc\xcd\xc3\xa1c\xd4\xff\x00";
+    assert_eq!(
+        render_msgpack(bytes),
+        "{\n  \"a\": 1,\n  \"b\": [\n    0.1,\n    true\n  ],\n  \"c\": ext(-1, h'00')\n}"
+    );
+    assert_eq!(
+        render_msgpack_value(&(-1, vec![1u8, 2])),
+        "[\n  -1,\n  [\n    1,\n    2\n  ]\n]"
+    );
+}
@@ -85,6 +85,25 @@ pub enum UnreferencedSnapshots {
     Ignore,
 }
 
+/// How snapshot names are turned into file names.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NameSanitization {
+    /// Only path separators are replaced
+    Legacy,
+    /// Everything but ASCII letters, digits and `-_.@+` is replaced
+    Portable,
+}
+
+/// How the snapshot files of a module are laid out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotLayout {
+    /// Files are named after the full module path
+    Flat,
+    /// Files are stored in a short directory named after a hash of the
+    /// module path
+    Hashed,
+}
+
 /// Snapshot update flag
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SnapshotUpdate {
@@ -94,6 +113,7 @@ pub enum SnapshotUpdate {
     New,
     No,
     Force,
+    Forbid,
 }
 
 #[derive(Debug)]
@@ -123,13 +143,25 @@ impl std::error::Error for Error {
     }
 }
 
+/// The number of bytes of a diff that are printed on CI by default.
+const DEFAULT_CI_DIFF_BUDGET: usize = 16 * 1024;
+
+/// The number of columns of a tab in inline snapshots by default.
+pub(crate) const DEFAULT_INDENT_WIDTH: usize = 4;
+
 /// Represents a tool configuration.
 #[derive(Debug, Clone)]
 pub struct ToolConfig {
     force_pass: bool,
     require_full_match: bool,
     output: OutputBehavior,
+    ci_diff_budget: usize,
+    indent_width: usize,
     snapshot_update: SnapshotUpdate,
+    pending_in_target: bool,
+    sanitize_names: NameSanitization,
+    snapshot_layout: SnapshotLayout,
+    quarantine: Vec<String>,
     #[cfg(feature = "glob")]
     glob_fail_fast: bool,
     #[cfg(feature = "_cargo_insta_internal")]
@@ -149,6 +181,10 @@ pub struct ToolConfig {
     #[cfg(feature = "_cargo_insta_internal")]
     review_warn_undiscovered: bool,
     #[cfg(feature = "_cargo_insta_internal")]
+    review_wrap_long_inline_snapshots: bool,
+    #[cfg(feature = "_cargo_insta_internal")]
+    review_confirm: Vec<(String, String)>,
+    #[cfg(feature = "_cargo_insta_internal")]
     disable_nextest_doctest: bool,
 }
 
@@ -242,6 +278,21 @@ impl ToolConfig {
                     _ => return Err(Error::Env("INSTA_OUTPUT")),
                 }
             },
+            ci_diff_budget: match env::var("INSTA_CI_DIFF_BUDGET").as_deref() {
+                Err(_) | Ok("") => resolve(&cfg, &["behavior", "ci_diff_budget"])
+                    .and_then(|x| x.as_u64())
+                    .map_or(DEFAULT_CI_DIFF_BUDGET, |x| x as usize),
+                Ok(val) => val
+                    .parse()
+                    .map_err(|_| Error::Env("INSTA_CI_DIFF_BUDGET"))?,
+            },
+            indent_width: match env::var("INSTA_INDENT_WIDTH").as_deref() {
+                Err(_) | Ok("") => resolve(&cfg, &["behavior", "indent_width"])
+                    .and_then(|x| x.as_u64())
+                    .map_or(DEFAULT_INDENT_WIDTH, |x| x as usize),
+                Ok(val) => val.parse().map_err(|_| Error::Env("INSTA_INDENT_WIDTH"))?,
+            }
+            .max(1),
             snapshot_update: {
                 let env_var = env::var("INSTA_UPDATE");
                 let val = match env_var.as_deref() {
@@ -265,9 +316,58 @@ impl ToolConfig {
                     "unseen" => SnapshotUpdate::Unseen,
                     "no" => SnapshotUpdate::No,
                     "force" => SnapshotUpdate::Force,
+                    "forbid" => SnapshotUpdate::Forbid,
                     _ => return Err(Error::Env("INSTA_UPDATE")),
                 }
             },
+            pending_in_target: match env::var("INSTA_PENDING_IN_TARGET").as_deref() {
+                Err(_) | Ok("") => resolve(&cfg, &["behavior", "pending_in_target"])
+                    .and_then(|x| x.as_bool())
+                    .unwrap_or(false),
+                Ok("1") => true,
+                Ok("0") => false,
+                _ => return Err(Error::Env("INSTA_PENDING_IN_TARGET")),
+            },
+            sanitize_names: {
+                let env_var = env::var("INSTA_SANITIZE_NAMES");
+                let val = match env_var.as_deref() {
+                    Err(_) | Ok("") => resolve(&cfg, &["behavior", "sanitize_names"])
+                        .and_then(|x| x.as_str())
+                        .unwrap_or("legacy"),
+                    Ok(val) => val,
+                };
+                match val {
+                    "legacy" => NameSanitization::Legacy,
+                    "portable" => NameSanitization::Portable,
+                    _ => return Err(Error::Env("INSTA_SANITIZE_NAMES")),
+                }
+            },
+            snapshot_layout: {
+                let env_var = env::var("INSTA_SNAPSHOT_LAYOUT");
+                let val = match env_var.as_deref() {
+                    Err(_) | Ok("") => resolve(&cfg, &["behavior", "snapshot_layout"])
+                        .and_then(|x| x.as_str())
+                        .unwrap_or("flat"),
+                    Ok(val) => val,
+                };
+                match val {
+                    "flat" => SnapshotLayout::Flat,
+                    "hashed" => SnapshotLayout::Hashed,
+                    _ => return Err(Error::Env("INSTA_SNAPSHOT_LAYOUT")),
+                }
+            },
+            quarantine: match resolve(&cfg, &["behavior", "quarantine"]) {
+                None => vec![],
+                Some(value) => value
+                    .as_slice()
+                    .and_then(|items| {
+                        items
+                            .iter()
+                            .map(|x| x.as_str().map(str::to_string))
+                            .collect()
+                    })
+                    .ok_or(Error::Config("quarantine"))?,
+            },
             #[cfg(feature = "glob")]
             glob_fail_fast: match env::var("INSTA_GLOB_FAIL_FAST").as_deref() {
                 Err(_) | Ok("") => resolve(&cfg, &["behavior", "glob_fail_fast"])
@@ -327,6 +427,30 @@ impl ToolConfig {
                 .and_then(|x| x.as_bool())
                 .unwrap_or(true),
             #[cfg(feature = "_cargo_insta_internal")]
+            review_wrap_long_inline_snapshots: resolve(
+                &cfg,
+                &["review", "wrap_long_inline_snapshots"],
+            )
+            .and_then(|x| x.as_bool())
+            .unwrap_or(false),
+            #[cfg(feature = "_cargo_insta_internal")]
+            review_confirm: match resolve(&cfg, &["review", "confirm"]) {
+                None => vec![],
+                Some(value) => value
+                    .as_slice()
+                    .and_then(|items| {
+                        items
+                            .iter()
+                            .map(|item| {
+                                let path = resolve(item, &["path"])?.as_str()?;
+                                let prompt = resolve(item, &["prompt"])?.as_str()?;
+                                Some((path.to_string(), prompt.to_string()))
+                            })
+                            .collect()
+                    })
+                    .ok_or(Error::Config("confirm"))?,
+            },
+            #[cfg(feature = "_cargo_insta_internal")]
             disable_nextest_doctest: resolve(&cfg, &["test", "disable_nextest_doctest"])
                 .and_then(|x| x.as_bool())
                 .unwrap_or(false),
@@ -350,11 +474,75 @@ impl ToolConfig {
         self.output
     }
 
+    /// Returns how many bytes of a diff are printed per failed assertion.
+    ///
+    /// This only applies on CI; the full diff is written to a report file
+    /// instead.  `None` means the output is not limited.
+    pub fn ci_diff_budget(&self) -> Option<usize> {
+        if self.ci_diff_budget == 0 || !is_ci() {
+            None
+        } else {
+            Some(self.ci_diff_budget)
+        }
+    }
+
+    /// Returns the number of columns a tab counts as when lining up the
+    /// lines of inline snapshots.
+    pub fn indent_width(&self) -> usize {
+        self.indent_width
+    }
+
     /// Returns the intended snapshot update behavior.
     pub fn snapshot_update(&self) -> SnapshotUpdate {
         self.snapshot_update
     }
 
+    /// Should pending snapshots be written below the target directory
+    /// instead of next to their destination?
+    pub fn pending_in_target(&self) -> bool {
+        self.pending_in_target
+    }
+
+    /// Returns how snapshot names are turned into file names.
+    pub fn sanitize_names(&self) -> NameSanitization {
+        self.sanitize_names
+    }
+
+    /// Returns how the snapshot files of a module are laid out.
+    pub fn snapshot_layout(&self) -> SnapshotLayout {
+        self.snapshot_layout
+    }
+
+    /// Returns the patterns of the quarantined snapshots.
+    #[cfg(feature = "_cargo_insta_internal")]
+    pub fn quarantine(&self) -> &[String] {
+        &self.quarantine
+    }
+
+    /// Returns the first quarantine pattern that matches a snapshot.
+    ///
+    /// Snapshots are named like their files, as the module path and the
+    /// snapshot name joined by `__`, and `*` in a pattern matches any text.
+    pub fn quarantined_by(&self, snapshot: &str) -> Option<&str> {
+        self.quarantine
+            .iter()
+            .map(String::as_str)
+            .find(|pattern| matches_pattern(pattern, snapshot))
+    }
+
+    /// Returns the prompts that have to be confirmed before a snapshot is
+    /// accepted.
+    ///
+    /// The path is the snapshot file, or the source file of an inline
+    /// snapshot, relative to the workspace root and with `/` as separator.
+    #[cfg(feature = "_cargo_insta_internal")]
+    pub fn review_confirm_prompts<'a>(&'a self, path: &'a str) -> impl Iterator<Item = &'a str> {
+        self.review_confirm
+            .iter()
+            .filter(move |(pattern, _)| matches_pattern(pattern, path))
+            .map(|(_, prompt)| prompt.as_str())
+    }
+
     /// Returns whether the glob should fail fast, as snapshot failures within the glob macro will appear only at the end of execution unless `glob_fail_fast` is set.
     #[cfg(feature = "glob")]
     pub fn glob_fail_fast(&self) -> bool {
@@ -400,6 +588,10 @@ impl ToolConfig {
         self.review_warn_undiscovered
     }
 
+    pub fn review_wrap_long_inline_snapshots(&self) -> bool {
+        self.review_wrap_long_inline_snapshots
+    }
+
     pub fn disable_nextest_doctest(&self) -> bool {
         self.disable_nextest_doctest
     }
@@ -435,7 +627,7 @@ pub fn snapshot_update_behavior(tool_config: &ToolConfig, unseen: bool) -> Snaps
             }
         }
         SnapshotUpdate::New => SnapshotUpdateBehavior::NewFile,
-        SnapshotUpdate::No => SnapshotUpdateBehavior::NoUpdate,
+        SnapshotUpdate::No | SnapshotUpdate::Forbid => SnapshotUpdateBehavior::NoUpdate,
         SnapshotUpdate::Force => SnapshotUpdateBehavior::InPlace,
     }
 }
@@ -584,6 +776,36 @@ pub fn memoize_warning(message: &str) {
     }
 }
 
+/// Records a failed snapshot assertion in the failures file, from which
+/// cargo-insta lists the failed assertions when a test run fails.
+///
+/// Test runners interleave the failure output of many tests, so a compact
+/// list at the end shows what to review.  Every line holds the name of the
+/// snapshot and its location, separated by a tab.
+/// Best-effort: does nothing if `INSTA_FAILURES_FILE` is not set or IO fails.
+pub fn memoize_failed_assertion(name: &str, location: &str) {
+    if let Ok(path) = env::var("INSTA_FAILURES_FILE") {
+        if let Ok(mut f) = fs::OpenOptions::new().append(true).create(true).open(path) {
+            let _ = writeln!(f, "{name}\t{location}");
+        }
+    }
+}
+
+/// Records a snapshot assertion of the test `function_name` in the
+/// assertions file, from which cargo-insta counts the assertions per test
+/// and finds the files of the tests.
+///
+/// Every line holds the test and the file of the assertion, separated by a
+/// tab.
+/// Best-effort: does nothing if `INSTA_ASSERTIONS_FILE` is not set or IO fails.
+pub fn memoize_assertion(function_name: &str, assertion_file: &str) {
+    if let Ok(path) = env::var("INSTA_ASSERTIONS_FILE") {
+        if let Ok(mut f) = fs::OpenOptions::new().append(true).create(true).open(path) {
+            let _ = writeln!(f, "{function_name}\t{assertion_file}");
+        }
+    }
+}
+
 /// Returns the pending directory if `INSTA_PENDING_DIR` is set and non-empty.
 pub fn get_pending_dir() -> Option<PathBuf> {
     env::var("INSTA_PENDING_DIR")
@@ -592,6 +814,40 @@ pub fn get_pending_dir() -> Option<PathBuf> {
         .map(PathBuf::from)
 }
 
+/// Returns the directory pending snapshots of `workspace` are written to when
+/// `pending_in_target` is enabled, given the cargo target directory.
+///
+/// The directory is named after the workspace since target directories can
+/// be shared between workspaces.
+pub fn target_pending_dir(target_dir: &Path, workspace: &Path) -> PathBuf {
+    let mut rv = target_dir.join("insta-pending");
+    if let Some(name) = workspace.file_name() {
+        rv.push(name);
+    }
+    rv
+}
+
+/// Returns the directory pending snapshots of `workspace` are written to, if
+/// not next to their destination.
+///
+/// This is `INSTA_PENDING_DIR` if set.  Otherwise, with `pending_in_target`
+/// enabled, it's a directory in `CARGO_TARGET_DIR` or the `target` directory of
+/// the workspace.  `cargo insta` passes the directory it uses to the tests in
+/// `INSTA_PENDING_DIR`, so that a target directory configured elsewhere is
+/// honored too.
+fn pending_dir(workspace: &Path) -> Option<PathBuf> {
+    get_pending_dir().or_else(|| {
+        if get_tool_config(workspace).pending_in_target() {
+            let target_dir = env::var_os("CARGO_TARGET_DIR")
+                .filter(|x| !x.is_empty())
+                .map_or_else(|| workspace.join("target"), |x| workspace.join(x));
+            Some(target_pending_dir(&target_dir, workspace))
+        } else {
+            None
+        }
+    })
+}
+
 /// Tries to strip a prefix from a path, with optional normalization fallback.
 ///
 /// First attempts direct `strip_prefix` (preserves symlinks, works for Bazel).
@@ -625,9 +881,9 @@ fn strip_prefix_with_fallback<'a>(
 
 /// Computes the path for a pending snapshot file.
 ///
-/// If `INSTA_PENDING_DIR` is set, returns a path within that directory
-/// preserving the relative structure from the workspace. Otherwise returns
-/// the original path unchanged.
+/// If `INSTA_PENDING_DIR` is set or `pending_in_target` is enabled, returns a
+/// path within the pending directory preserving the relative structure from
+/// the workspace. Otherwise returns the original path unchanged.
 ///
 /// # Panics
 ///
@@ -635,7 +891,7 @@ fn strip_prefix_with_fallback<'a>(
 /// workspace (e.g., external test paths like `../tests/lib.rs`). This is because
 /// such paths would escape the pending directory.
 pub fn pending_snapshot_path(workspace: &Path, original_path: &Path) -> PathBuf {
-    match get_pending_dir() {
+    match pending_dir(workspace) {
         Some(pending_dir) => {
             // Compute relative path from workspace to original_path.
             // Try direct strip first (for Bazel), fall back to normalized (for Windows).
@@ -684,3 +940,42 @@ fn resolve<'a>(value: &'a Content, path: &[&str]) -> Option<&'a Content> {
             _ => None,
         })
 }
+
+/// Matches `text` against a pattern in which `*` stands for any text.
+pub fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let mut rest = match text.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let mut parts = parts.collect::<Vec<_>>();
+    let last = match parts.pop() {
+        Some(last) => last,
+        None => return rest.is_empty(),
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[test]
+fn test_matches_pattern() {
+    assert!(matches_pattern(
+        "crate__tests__flaky",
+        "crate__tests__flaky"
+    ));
+    assert!(!matches_pattern(
+        "crate__tests__flaky",
+        "crate__tests__flaky-2"
+    ));
+    assert!(matches_pattern("crate__tests__*", "crate__tests__flaky"));
+    assert!(matches_pattern("*__flaky*", "crate__tests__flaky-2"));
+    assert!(matches_pattern("a*b*c", "abc"));
+    assert!(!matches_pattern("a*b*c", "acb"));
+    assert!(!matches_pattern("ab*ba", "aba"));
+}
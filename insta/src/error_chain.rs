@@ -0,0 +1,178 @@
+use std::error::Error;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Frames of the standard library, the test harness and error libraries,
+/// which are left out of backtraces.
+const SKIPPED_MODULES: &[&str] = &[
+    "std::",
+    "core::",
+    "alloc::",
+    "test::",
+    "backtrace::",
+    "anyhow::",
+    "eyre::",
+    "<std::",
+    "<core::",
+    "<alloc::",
+    "<anyhow::",
+    "<F as ",
+    "__rust",
+];
+
+/// Frames of the C runtime that start the process and its threads.
+const SKIPPED_FUNCTIONS: &[&str] = &[
+    "rust_begin_unwind",
+    "main",
+    "_start",
+    "__libc_start_main",
+    "__libc_start_call_main",
+    "start_thread",
+    "clone",
+    "clone3",
+];
+
+/// Formats an error and the errors returned by [`Error::source`].
+///
+/// The message of the error is followed by one `caused by:` line for every
+/// source.  If a `backtrace` is given, the frames are written below the
+/// chain without their line numbers.  Paths within `workspace` are made
+/// relative, other paths are replaced with `[path]`, and frames of the
+/// standard library and of error handling libraries are left out.
+pub fn render_error_chain<E: Error + ?Sized>(
+    err: &E,
+    backtrace: Option<String>,
+    workspace: &Path,
+) -> String {
+    let mut rv = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        write!(rv, "\ncaused by: {err}").unwrap();
+        source = err.source();
+    }
+    if let Some(backtrace) = backtrace {
+        rv.push_str("\n\n");
+        write_backtrace(&backtrace, workspace, &mut rv);
+    }
+    rv
+}
+
+/// Writes the frames of a backtrace in the format of
+/// [`std::backtrace::Backtrace`], or the text itself if it has no frames,
+/// such as for a disabled backtrace.
+fn write_backtrace(backtrace: &str, workspace: &Path, out: &mut String) {
+    let mut frames = Vec::<String>::new();
+    let mut has_frames = false;
+    let mut skip = false;
+    for line in backtrace.lines().map(str::trim) {
+        if let Some(location) = line.strip_prefix("at ") {
+            if !skip && !frames.is_empty() {
+                let location = redact_location(location, workspace);
+                write!(frames.last_mut().unwrap(), "\n    at {location}").unwrap();
+            }
+        } else if let Some((idx, function)) = line.split_once(": ") {
+            if !idx.chars().all(|c| c.is_ascii_digit()) {
+                continue;
+            }
+            has_frames = true;
+            let function = strip_hash(function);
+            skip = SKIPPED_MODULES.iter().any(|x| function.starts_with(x))
+                || SKIPPED_FUNCTIONS.contains(&function);
+            if !skip {
+                frames.push(format!("  {function}"));
+            }
+        }
+    }
+    if !has_frames {
+        write!(out, "stack backtrace: {}", backtrace.trim()).unwrap();
+    } else {
+        out.push_str("stack backtrace:");
+        for frame in frames {
+            write!(out, "\n{frame}").unwrap();
+        }
+    }
+}
+
+/// Removes the hash that legacy symbol mangling appends to function names.
+fn strip_hash(function: &str) -> &str {
+    match function.rsplit_once("::h") {
+        Some((name, hash)) if hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit()) => {
+            name
+        }
+        _ => function,
+    }
+}
+
+/// Makes a `path:line:column` location relative to the workspace and drops
+/// the line and column, which change with every edit of the file.
+fn redact_location(location: &str, workspace: &Path) -> String {
+    let mut path = location;
+    for _ in 0..2 {
+        if let Some((rest, number)) = path.rsplit_once(':') {
+            if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) {
+                path = rest;
+            }
+        }
+    }
+    let path = Path::new(path);
+    if path.is_relative() {
+        return path
+            .strip_prefix("./")
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/");
+    }
+    match path.strip_prefix(workspace) {
+        Ok(path) => path.to_string_lossy().replace('\\', "/"),
+        Err(_) => "[path]".into(),
+    }
+}
+
+#[test]
+fn test_render_error_chain() {
+    use std::fmt;
+
+    #[derive(Debug)]
+    struct Layer(&'static str, Option<Box<Layer>>);
+
+    impl fmt::Display for Layer {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.0)
+        }
+    }
+
+    impl Error for Layer {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            self.1.as_deref().map(|x| x as &dyn Error)
+        }
+    }
+
+    let err = Layer(
+        "failed to load config",
+        Some(Box::new(Layer("invalid port", None))),
+    );
+    let backtrace = "   0: std::backtrace::Backtrace::capture
+             at /rustc/abc/library/std/src/backtrace.rs:296:9
+   1: app::config::load::h0123456789abcdef
+             at /work/app/src/config.rs:12:5
+   2: serde_json::de::from_str
+             at /home/me/.cargo/registry/src/serde_json/src/de.rs:2676:5
+   3: app::main
+             at ./src/main.rs:3:13
+   4: core::ops::function::FnOnce::call_once
+             at /rustc/abc/library/core/src/ops/function.rs:250:5";
+    assert_eq!(
+        render_error_chain(&err, Some(backtrace.into()), Path::new("/work/app")),
+        "failed to load config\n\
+         caused by: invalid port\n\
+         \n\
+         stack backtrace:\n  \
+         app::config::load\n    at src/config.rs\n  \
+         serde_json::de::from_str\n    at [path]\n  \
+         app::main\n    at src/main.rs"
+    );
+    assert_eq!(
+        render_error_chain(&err, Some("disabled backtrace".into()), Path::new("/")),
+        "failed to load config\ncaused by: invalid port\n\nstack backtrace: disabled backtrace"
+    );
+}
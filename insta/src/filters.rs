@@ -1,14 +1,49 @@
 use std::borrow::Cow;
 use std::iter::FromIterator;
 use std::iter::IntoIterator;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
 
-use regex::Regex;
+use regex::{Captures, Regex};
+
+/// The built-in filters that the `filters = [...]` argument of the assertion
+/// macros and metadata comments of inline snapshots name, as a regex and its
+/// replacement.
+#[allow(non_upper_case_globals)]
+pub mod presets {
+    pub const uuid: (&str, &str) = (
+        r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b",
+        "[uuid]",
+    );
+    pub const timestamp: (&str, &str) = (
+        r"\b\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(\.\d+)?(Z|[+-]\d{2}:?\d{2})?",
+        "[timestamp]",
+    );
+
+    /// Returns the preset of the given name.
+    pub(crate) fn by_name(name: &str) -> Option<(&'static str, &'static str)> {
+        match name {
+            "uuid" => Some(uuid),
+            "timestamp" => Some(timestamp),
+            _ => None,
+        }
+    }
+}
 
 /// Represents stored filters.
 #[derive(Debug, Default, Clone)]
 #[cfg_attr(docsrs, doc(cfg(feature = "filters")))]
 pub struct Filters {
-    rules: Vec<(Regex, String)>,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    regex: Regex,
+    replacement: String,
+    /// Whether `\` is replaced by `/` in the replaced text, which makes the
+    /// paths matched by the standard filters look the same on all platforms.
+    forward_slashes: bool,
 }
 
 impl<'a, I> From<I> for Filters
@@ -33,10 +68,115 @@ impl<'a> FromIterator<(&'a str, &'a str)> for Filters {
 impl Filters {
     /// Adds a simple regex with a replacement.
     pub(crate) fn add<S: Into<String>>(&mut self, regex: &str, replacement: S) {
-        self.rules.push((
-            Regex::new(regex).expect("invalid regex for snapshot filter rule"),
-            replacement.into(),
-        ));
+        self.try_add(regex, replacement)
+            .unwrap_or_else(|err| panic!("invalid snapshot filter rule: {err}"));
+    }
+
+    /// Adds a simple regex with a replacement, failing if the regex is
+    /// invalid or the replacement refers to a group the regex does not have.
+    pub(crate) fn try_add<S: Into<String>>(
+        &mut self,
+        regex: &str,
+        replacement: S,
+    ) -> Result<(), String> {
+        let regex = Regex::new(regex).map_err(|err| err.to_string())?;
+        let replacement = replacement.into();
+        check_group_references(&regex, &replacement)?;
+        self.rules.push(Rule {
+            regex,
+            replacement,
+            forward_slashes: false,
+        });
+        Ok(())
+    }
+
+    /// Adds the filters of [`Settings::add_standard_filters`] for the values
+    /// of the current machine and user.
+    ///
+    /// [`Settings::add_standard_filters`]: crate::Settings::add_standard_filters
+    pub(crate) fn add_standard(&mut self) {
+        let home = env::var_os("HOME")
+            .or_else(|| env::var_os("USERPROFILE"))
+            .map(PathBuf::from);
+        let user = ["USER", "USERNAME", "LOGNAME"]
+            .iter()
+            .find_map(|key| env::var(key).ok());
+        let hostname = ["HOSTNAME", "COMPUTERNAME"]
+            .iter()
+            .find_map(|key| env::var(key).ok())
+            .or_else(|| fs::read_to_string("/etc/hostname").ok());
+        self.add_standard_for(
+            &env::temp_dir(),
+            home.as_deref(),
+            user.as_deref(),
+            hostname.as_deref(),
+        );
+    }
+
+    fn add_standard_for(
+        &mut self,
+        temp_dir: &Path,
+        home: Option<&Path>,
+        user: Option<&str>,
+        hostname: Option<&str>,
+    ) {
+        let mut dirs = vec![];
+        for (dir, placeholder) in [(Some(temp_dir), "[TEMP_DIR]"), (home, "[HOME]")] {
+            // a home of `/`, as in some containers, would match every path
+            let dir = match dir.filter(|dir| dir.parent().is_some()) {
+                Some(dir) => dir,
+                None => continue,
+            };
+            dirs.push((dir.to_path_buf(), placeholder));
+            // the temporary directory of macOS is behind a symlink
+            if let Ok(canonical) = dir.canonicalize() {
+                if canonical != dir {
+                    dirs.push((canonical, placeholder));
+                }
+            }
+        }
+        // the temporary directory can be in the home directory, and longer
+        // paths have to be replaced before the paths they start with
+        dirs.sort_by_key(|(dir, _)| std::cmp::Reverse(dir.as_os_str().len()));
+        for (dir, placeholder) in dirs {
+            let dir = dir.to_string_lossy();
+            let dir = dir.trim_end_matches(['/', '\\']);
+            let mut regex = dir
+                .split(['/', '\\'])
+                .map(regex::escape)
+                .collect::<Vec<_>>()
+                .join(r"[\\/]");
+            if cfg!(windows) {
+                regex.insert_str(0, "(?i)");
+            }
+            if dir.ends_with(|c: char| c.is_alphanumeric() || c == '_') {
+                regex.push_str(r"\b");
+            }
+            regex.push_str(r"((?:[\\/][\w.\-]+)*)");
+            self.rules.push(Rule {
+                regex: Regex::new(&regex).unwrap(),
+                replacement: format!("{placeholder}$1"),
+                forward_slashes: true,
+            });
+        }
+
+        let hostname = hostname.map(str::trim).filter(|x| *x != "localhost");
+        for (value, placeholder) in [(hostname, "[HOSTNAME]"), (user, "[USER]")] {
+            if let Some(value) = value.filter(|x| !x.is_empty()) {
+                self.add(&format!(r"\b{}\b", regex::escape(value)), placeholder);
+            }
+        }
+        // only labelled process ids are replaced, since bare numbers that
+        // happen to be the id of this process are not
+        self.add(r"(?i)\b(pids?\W{1,3})\d+\b", "${1}[PID]");
+    }
+
+    /// Iterates over the regexes and their replacements.
+    #[cfg(feature = "toml")]
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.rules
+            .iter()
+            .map(|rule| (rule.regex.as_str(), rule.replacement.as_str()))
     }
 
     /// Clears all filters.
@@ -48,8 +188,17 @@ impl Filters {
     pub(crate) fn apply_to<'s>(&self, s: &'s str) -> Cow<'s, str> {
         let mut rv = Cow::Borrowed(s);
 
-        for (regex, replacement) in &self.rules {
-            match regex.replace_all(&rv, replacement) {
+        for rule in &self.rules {
+            let replaced = if rule.forward_slashes {
+                rule.regex.replace_all(&rv, |caps: &Captures| {
+                    let mut rv = String::new();
+                    caps.expand(&rule.replacement, &mut rv);
+                    rv.replace('\\', "/")
+                })
+            } else {
+                rule.regex.replace_all(&rv, rule.replacement.as_str())
+            };
+            match replaced {
                 Cow::Borrowed(_) => continue,
                 Cow::Owned(value) => rv = Cow::Owned(value),
             };
@@ -59,6 +208,51 @@ impl Filters {
     }
 }
 
+/// Fails if `replacement` refers to a group that `regex` does not have, which
+/// [`Regex::replace_all`] would silently replace with nothing.  This catches
+/// the common mistake of `$1ms`, which refers to a group named `1ms`.
+fn check_group_references(regex: &Regex, replacement: &str) -> Result<(), String> {
+    let mut rest = replacement;
+    while let Some(idx) = rest.find('$') {
+        rest = &rest[idx + 1..];
+        let name = if let Some(tail) = rest.strip_prefix('$') {
+            rest = tail;
+            continue;
+        } else if let Some((name, tail)) = rest.strip_prefix('{').and_then(|x| x.split_once('}')) {
+            rest = tail;
+            name
+        } else {
+            let end = rest
+                .find(|c: char| c != '_' && !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            let (name, tail) = rest.split_at(end);
+            rest = tail;
+            name
+        };
+        // a `$` that is not followed by a name is kept as is
+        let exists = match name.parse::<usize>() {
+            Ok(index) => index < regex.captures_len(),
+            Err(_) => name.is_empty() || regex.capture_names().any(|x| x == Some(name)),
+        };
+        if !exists {
+            let mut msg = format!(
+                "replacement {replacement:?} refers to the group `{name}`, which the regex does not have"
+            );
+            let digits = name.len() - name.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            if digits > 0 {
+                msg.push_str(&format!(
+                    "; write `${{{}}}{}` to follow group {} by text",
+                    &name[..digits],
+                    &name[digits..],
+                    &name[..digits]
+                ));
+            }
+            return Err(msg);
+        }
+    }
+    Ok(())
+}
+
 #[test]
 fn test_filters() {
     let mut filters = Filters::default();
@@ -70,6 +264,54 @@ fn test_filters() {
     );
 }
 
+#[test]
+fn test_filters_group_references() {
+    let mut filters = Filters::default();
+    filters.add(r"took \d+(ms|s)\b", "took [DURATION]$1");
+    filters.add(r"(?P<key>\w+)=\d+", "${key}=[NUM]");
+    filters.add(r"\$(\d+)", "$$$1");
+    assert_eq!(
+        filters.apply_to("took 123ms, took 2s, retries=3, $5"),
+        "took [DURATION]ms, took [DURATION]s, retries=[NUM], $5"
+    );
+
+    let mut filters = Filters::default();
+    assert_eq!(
+        filters.try_add(r"took \d+(ms)", "$1x").unwrap_err(),
+        "replacement \"$1x\" refers to the group `1x`, which the regex does not have; \
+         write `${1}x` to follow group 1 by text"
+    );
+    assert_eq!(
+        filters.try_add("(?P<unit>ms)", "$units").unwrap_err(),
+        "replacement \"$units\" refers to the group `units`, which the regex does not have"
+    );
+    assert!(filters.try_add("(a)", "$2").is_err());
+    assert!(filters.try_add("(a)", "$ ${1}b $").is_ok());
+}
+
+#[test]
+fn test_standard_filters() {
+    let mut filters = Filters::default();
+    filters.add_standard_for(
+        Path::new("/home/alice/.cache/tmp/"),
+        Some(Path::new("/home/alice")),
+        Some("alice"),
+        Some("build-01\n"),
+    );
+    assert_eq!(
+        filters.apply_to(
+            "/home/alice/.cache/tmp/x.log /home/alice/src\\main.rs /home/alicex \
+             alice@build-01 pid=4242 PID: 7"
+        ),
+        "[TEMP_DIR]/x.log [HOME]/src/main.rs /home/alicex [USER]@[HOSTNAME] \
+         pid=[PID] PID: [PID]"
+    );
+
+    let mut filters = Filters::default();
+    filters.add_standard_for(Path::new("/tmp"), Some(Path::new("/")), Some(""), None);
+    assert_eq!(filters.apply_to("/tmp/a /usr/bin"), "[TEMP_DIR]/a /usr/bin");
+}
+
 #[test]
 fn test_static_str_array_conversion() {
     let arr: [(&'static str, &'static str); 2] = [("a1", "b1"), ("a2", "b2")];
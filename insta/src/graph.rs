@@ -0,0 +1,616 @@
+use std::fmt;
+
+type Edge = (String, String, Option<String>);
+
+/// A graph in canonical form, ready to be snapshotted.
+///
+/// Graph libraries typically iterate nodes and edges in insertion or hash
+/// order, so snapshotting them directly tends to produce flaky diffs.  A
+/// `GraphSnapshot` identifies every node by a stable string key and sorts
+/// nodes and edges by those keys, so the rendered output only depends on the
+/// structure of the graph.
+///
+/// ```rust
+/// # use insta::GraphSnapshot;
+/// let mut graph = GraphSnapshot::directed();
+/// graph.add_edge("parse", "lower");
+/// graph.add_edge("lower", "codegen");
+/// graph.add_labeled_edge("parse", "codegen", "spans");
+/// graph.add_node("unused");
+///
+/// assert_eq!(graph.to_adjacency_list(), "\
+/// codegen
+/// lower -> codegen
+/// parse -> codegen [spans], lower
+/// unused
+/// ");
+/// ```
+///
+/// Nodes referenced by an edge are added implicitly.  Parallel edges are
+/// kept, so multigraphs render every edge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphSnapshot {
+    directed: bool,
+    nodes: Vec<String>,
+    edges: Vec<Edge>,
+}
+
+impl GraphSnapshot {
+    /// Creates an empty directed graph.
+    pub fn directed() -> GraphSnapshot {
+        GraphSnapshot {
+            directed: true,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    /// Creates an empty undirected graph.
+    ///
+    /// The endpoints of undirected edges are ordered by key, so `a -- b` and
+    /// `b -- a` render the same way.
+    pub fn undirected() -> GraphSnapshot {
+        GraphSnapshot {
+            directed: false,
+            ..GraphSnapshot::directed()
+        }
+    }
+
+    /// Returns `true` if this is a directed graph.
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    /// Adds a node by its stable key.
+    pub fn add_node<K: Into<String>>(&mut self, key: K) -> &mut GraphSnapshot {
+        self.nodes.push(key.into());
+        self
+    }
+
+    /// Adds an unlabeled edge between two node keys.
+    pub fn add_edge<A: Into<String>, B: Into<String>>(
+        &mut self,
+        from: A,
+        to: B,
+    ) -> &mut GraphSnapshot {
+        self.push_edge(from.into(), to.into(), None);
+        self
+    }
+
+    /// Adds an edge with a label between two node keys.
+    pub fn add_labeled_edge<A: Into<String>, B: Into<String>, L: Into<String>>(
+        &mut self,
+        from: A,
+        to: B,
+        label: L,
+    ) -> &mut GraphSnapshot {
+        self.push_edge(from.into(), to.into(), Some(label.into()));
+        self
+    }
+
+    fn push_edge(&mut self, mut from: String, mut to: String, label: Option<String>) {
+        if !self.directed && to < from {
+            std::mem::swap(&mut from, &mut to);
+        }
+        self.edges.push((from, to, label));
+    }
+
+    /// Builds a snapshot from a [`petgraph`](https://docs.rs/petgraph) graph.
+    ///
+    /// `node_key` has to return a key that is unique and stable across runs
+    /// (for instance a name rather than a node index); nodes that produce the
+    /// same key are merged.  `edge_label` can return `None` for edges that
+    /// should not carry a label.
+    ///
+    /// ```rust
+    /// # use insta::GraphSnapshot;
+    /// let mut deps = petgraph::Graph::<&str, ()>::new();
+    /// let app = deps.add_node("app");
+    /// let log = deps.add_node("log");
+    /// deps.add_edge(app, log, ());
+    ///
+    /// let graph = GraphSnapshot::from_petgraph(&deps, |n| n.to_string(), |_| None);
+    /// assert_eq!(graph.to_adjacency_list(), "app -> log\nlog\n");
+    /// ```
+    #[cfg(feature = "petgraph")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "petgraph")))]
+    pub fn from_petgraph<G, K, L>(graph: G, mut node_key: K, mut edge_label: L) -> GraphSnapshot
+    where
+        G: petgraph::visit::IntoNodeReferences
+            + petgraph::visit::IntoEdgeReferences
+            + petgraph::visit::NodeIndexable
+            + petgraph::visit::GraphProp,
+        K: FnMut(&G::NodeWeight) -> String,
+        L: FnMut(&G::EdgeWeight) -> Option<String>,
+    {
+        use petgraph::visit::{EdgeRef, NodeRef};
+
+        let mut rv = if graph.is_directed() {
+            GraphSnapshot::directed()
+        } else {
+            GraphSnapshot::undirected()
+        };
+        let mut keys = vec![None; graph.node_bound()];
+        for node in graph.node_references() {
+            let key = node_key(node.weight());
+            keys[graph.to_index(node.id())] = Some(key.clone());
+            rv.add_node(key);
+        }
+        let key_of = |id| keys[graph.to_index(id)].clone().unwrap_or_default();
+        for edge in graph.edge_references() {
+            let label = edge_label(edge.weight());
+            rv.push_edge(key_of(edge.source()), key_of(edge.target()), label);
+        }
+        rv
+    }
+
+    fn sorted(&self) -> (Vec<&str>, Vec<&Edge>) {
+        let mut nodes: Vec<&str> = self
+            .nodes
+            .iter()
+            .chain(self.edges.iter().flat_map(|(a, b, _)| [a, b]))
+            .map(|x| x.as_str())
+            .collect();
+        nodes.sort_unstable();
+        nodes.dedup();
+        let mut edges: Vec<_> = self.edges.iter().collect();
+        edges.sort();
+        (nodes, edges)
+    }
+
+    /// Renders the graph as an adjacency list.
+    ///
+    /// Every node gets one line with its outgoing edges, sorted by key.
+    /// Labels are shown in brackets after the target.
+    pub fn to_adjacency_list(&self) -> String {
+        let (nodes, edges) = self.sorted();
+        let arrow = if self.directed { "->" } else { "--" };
+        let mut rv = String::new();
+        for node in nodes {
+            rv.push_str(node);
+            let mut targets = edges.iter().filter(|(from, _, _)| from == node).peekable();
+            if targets.peek().is_some() {
+                rv.push(' ');
+                rv.push_str(arrow);
+                for (idx, (_, to, label)) in targets.enumerate() {
+                    rv.push_str(if idx == 0 { " " } else { ", " });
+                    rv.push_str(to);
+                    if let Some(label) = label {
+                        rv.push_str(&format!(" [{label}]"));
+                    }
+                }
+            }
+            rv.push('\n');
+        }
+        rv
+    }
+
+    /// Renders the graph in Graphviz DOT format.
+    ///
+    /// Nodes are declared first, followed by the edges, both sorted by key.
+    /// All identifiers are quoted so keys can contain arbitrary characters.
+    pub fn to_dot(&self) -> String {
+        let (nodes, edges) = self.sorted();
+        let (kind, arrow) = if self.directed {
+            ("digraph", "->")
+        } else {
+            ("graph", "--")
+        };
+        let mut rv = format!("{kind} {{\n");
+        for node in nodes {
+            rv.push_str(&format!("    {};\n", dot_quote(node)));
+        }
+        for (from, to, label) in edges {
+            rv.push_str(&format!(
+                "    {} {arrow} {}",
+                dot_quote(from),
+                dot_quote(to)
+            ));
+            if let Some(label) = label {
+                rv.push_str(&format!(" [label={}]", dot_quote(label)));
+            }
+            rv.push_str(";\n");
+        }
+        rv.push_str("}\n");
+        rv
+    }
+}
+
+impl fmt::Display for GraphSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_adjacency_list())
+    }
+}
+
+/// Quotes a DOT identifier.
+fn dot_quote(s: &str) -> String {
+    let mut rv = String::with_capacity(s.len() + 2);
+    rv.push('"');
+    for c in s.chars() {
+        match c {
+            '\\' => rv.push_str("\\\\"),
+            '"' => rv.push_str("\\\""),
+            '\n' => rv.push_str("\\n"),
+            c => rv.push(c),
+        }
+    }
+    rv.push('"');
+    rv
+}
+
+/// Normalizes Graphviz DOT source for snapshotting.
+///
+/// Tools that emit DOT rarely agree on formatting, and many write attributes
+/// in hash order.  This reformats `source` into a canonical layout: one
+/// statement per line with four space indentation, every identifier and
+/// attribute value in double quotes and the attributes of each statement
+/// sorted by key.  Comments are dropped.  The order of statements is kept
+/// as-is since default attributes in DOT only apply to later statements.
+///
+/// ```rust
+/// # use insta::normalize_dot;
+/// let dot = "digraph G { b -> c [weight=2, color=red] a; rankdir=LR }";
+/// assert_eq!(normalize_dot(dot), r#"digraph "G" {
+///     "b" -> "c" [color="red", weight="2"];
+///     "a";
+///     rankdir="LR";
+/// }
+/// "#);
+/// ```
+pub fn normalize_dot(source: &str) -> String {
+    let tokens = tokenize_dot(source);
+    let mut rv = String::new();
+    let mut pos = 0;
+    while pos < tokens.len() {
+        format_dot_block(&tokens, &mut pos, 0, &mut rv);
+        // stray closing brace at the top level
+        if pos < tokens.len() {
+            rv.push_str("}\n");
+            pos += 1;
+        }
+    }
+    rv
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum DotToken {
+    /// An unquoted identifier or numeral.
+    Bare(String),
+    /// The raw contents of a double quoted string.
+    Quoted(String),
+    /// An HTML string including its angle brackets.
+    Html(String),
+    Punct(&'static str),
+}
+
+const DOT_KEYWORDS: &[&str] = &["strict", "graph", "digraph", "subgraph", "node", "edge"];
+
+impl DotToken {
+    fn is_id(&self) -> bool {
+        matches!(
+            self,
+            DotToken::Bare(_) | DotToken::Quoted(_) | DotToken::Html(_)
+        )
+    }
+
+    fn keyword(&self) -> Option<String> {
+        match self {
+            DotToken::Bare(s) => {
+                let lower = s.to_ascii_lowercase();
+                DOT_KEYWORDS.contains(&lower.as_str()).then_some(lower)
+            }
+            _ => None,
+        }
+    }
+
+    fn format_id(&self) -> String {
+        match self {
+            DotToken::Bare(s) => format!("\"{s}\""),
+            DotToken::Quoted(s) => format!("\"{s}\""),
+            DotToken::Html(s) => s.clone(),
+            DotToken::Punct(p) => p.to_string(),
+        }
+    }
+
+    fn format_key(&self) -> String {
+        let is_plain = |s: &str| {
+            matches!(s.chars().next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+                && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        };
+        match self {
+            DotToken::Bare(s) | DotToken::Quoted(s) if is_plain(s) && self.keyword().is_none() => {
+                s.clone()
+            }
+            _ => self.format_id(),
+        }
+    }
+}
+
+fn tokenize_dot(source: &str) -> Vec<DotToken> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    let mut line_start = true;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            line_start = true;
+            i += 1;
+            continue;
+        } else if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let at_line_start = std::mem::replace(&mut line_start, false);
+        let next = chars.get(i + 1).copied();
+        if (c == '#' && at_line_start) || (c == '/' && next == Some('/')) {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i += 2;
+        } else if c == '"' {
+            let mut value = String::new();
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                if chars[i] == '\\' && i + 1 < chars.len() {
+                    value.push(chars[i]);
+                    i += 1;
+                }
+                value.push(chars[i]);
+                i += 1;
+            }
+            i += 1;
+            // `"a" + "b"` concatenates the two strings
+            if let [.., DotToken::Quoted(prev), DotToken::Punct("+")] = &mut tokens[..] {
+                prev.push_str(&value);
+                tokens.pop();
+            } else {
+                tokens.push(DotToken::Quoted(value));
+            }
+        } else if c == '<' {
+            let start = i;
+            let mut depth = 0;
+            while i < chars.len() {
+                match chars[i] {
+                    '<' => depth += 1,
+                    '>' => depth -= 1,
+                    _ => {}
+                }
+                i += 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            tokens.push(DotToken::Html(chars[start..i].iter().collect()));
+        } else if c == '-' && (next == Some('>') || next == Some('-')) {
+            tokens.push(DotToken::Punct(if next == Some('>') { "->" } else { "--" }));
+            i += 2;
+        } else if let Some(p) = ["{", "}", "[", "]", ";", ",", "=", ":", "+"]
+            .iter()
+            .find(|p| p.starts_with(c))
+        {
+            tokens.push(DotToken::Punct(p));
+            i += 1;
+        } else {
+            let start = i;
+            let is_numeral = c.is_ascii_digit() || c == '-' || c == '.';
+            i += 1;
+            while i < chars.len()
+                && (if is_numeral {
+                    chars[i].is_ascii_digit() || chars[i] == '.'
+                } else {
+                    chars[i].is_alphanumeric() || chars[i] == '_'
+                })
+            {
+                i += 1;
+            }
+            tokens.push(DotToken::Bare(chars[start..i].iter().collect()));
+        }
+    }
+    tokens
+}
+
+/// Formats statements until the closing brace of the current block (which is
+/// left unconsumed) or the end of the input.
+fn format_dot_block(tokens: &[DotToken], pos: &mut usize, depth: usize, out: &mut String) {
+    let indent = "    ".repeat(depth);
+    let mut line = String::new();
+    // the previous token if it can end a statement without a semicolon
+    let mut prev_ends_stmt = false;
+    let mut prev_is_keyword = false;
+
+    let flush = |line: &mut String, out: &mut String| {
+        if !line.is_empty() {
+            out.push_str(&indent);
+            out.push_str(line);
+            if line != "}" {
+                out.push(';');
+            }
+            out.push('\n');
+            line.clear();
+        }
+    };
+
+    while let Some(token) = tokens.get(*pos) {
+        match token {
+            DotToken::Punct("}") => break,
+            DotToken::Punct(";") | DotToken::Punct(",") => {
+                flush(&mut line, out);
+                prev_ends_stmt = false;
+                *pos += 1;
+                continue;
+            }
+            DotToken::Punct("{") => {
+                out.push_str(&indent);
+                out.push_str(&line);
+                if !line.is_empty() {
+                    out.push(' ');
+                }
+                out.push_str("{\n");
+                *pos += 1;
+                format_dot_block(tokens, pos, depth + 1, out);
+                *pos += 1;
+                line = "}".into();
+                prev_ends_stmt = true;
+                prev_is_keyword = false;
+                continue;
+            }
+            DotToken::Punct("[") => {
+                *pos += 1;
+                let attrs = format_dot_attrs(tokens, pos);
+                if !attrs.is_empty() {
+                    line.push_str(" [");
+                    line.push_str(&attrs.join(", "));
+                    line.push(']');
+                }
+                prev_ends_stmt = true;
+                prev_is_keyword = false;
+                continue;
+            }
+            DotToken::Punct(p @ ("=" | ":")) => {
+                line.push_str(p);
+                prev_ends_stmt = false;
+                *pos += 1;
+                continue;
+            }
+            DotToken::Punct(p) => {
+                if line == "}" && !matches!(*p, "->" | "--") {
+                    flush(&mut line, out);
+                }
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.push_str(p);
+                prev_ends_stmt = false;
+                *pos += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        if prev_ends_stmt && !prev_is_keyword {
+            flush(&mut line, out);
+        }
+        let keyword = token.keyword();
+        let is_key = tokens.get(*pos + 1) == Some(&DotToken::Punct("="));
+        let after_op = line.ends_with('=') || line.ends_with(':');
+        if !line.is_empty() && !after_op {
+            line.push(' ');
+        }
+        line.push_str(&match keyword {
+            Some(ref keyword) if !after_op && !is_key => keyword.clone(),
+            _ if is_key => token.format_key(),
+            _ => token.format_id(),
+        });
+        prev_ends_stmt = token.is_id();
+        prev_is_keyword = keyword.is_some() && !after_op && !is_key;
+        *pos += 1;
+    }
+    flush(&mut line, out);
+}
+
+/// Formats an attribute list up to and including its closing bracket.
+fn format_dot_attrs(tokens: &[DotToken], pos: &mut usize) -> Vec<String> {
+    let mut attrs: Vec<(String, String)> = vec![];
+    while let Some(token) = tokens.get(*pos) {
+        *pos += 1;
+        match token {
+            DotToken::Punct("]") => break,
+            DotToken::Punct(_) => {}
+            token => {
+                let key = token.format_key();
+                let value = if tokens.get(*pos) == Some(&DotToken::Punct("=")) {
+                    *pos += 1;
+                    match tokens.get(*pos) {
+                        Some(value) if value.is_id() => {
+                            *pos += 1;
+                            value.format_id()
+                        }
+                        _ => "\"\"".into(),
+                    }
+                } else {
+                    "\"true\"".into()
+                };
+                attrs.push((key, value));
+            }
+        }
+    }
+    attrs.sort_by(|a, b| a.0.cmp(&b.0));
+    attrs
+        .into_iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect()
+}
+
+#[test]
+fn test_undirected_edges_are_canonical() {
+    let mut a = GraphSnapshot::undirected();
+    a.add_edge("b", "a").add_edge("c", "b");
+    let mut b = GraphSnapshot::undirected();
+    b.add_edge("b", "c").add_edge("a", "b");
+    assert_eq!(a.to_adjacency_list(), b.to_adjacency_list());
+    crate::assert_snapshot!(a.to_dot(), @r#"
+    graph {
+        "a";
+        "b";
+        "c";
+        "a" -- "b";
+        "b" -- "c";
+    }
+    "#);
+}
+
+#[test]
+fn test_dot_quoting() {
+    let mut graph = GraphSnapshot::directed();
+    graph.add_labeled_edge("say \"hi\"", "x", "line\nbreak");
+    graph.add_labeled_edge("x", "C:\\temp", "\\n is a newline");
+    crate::assert_snapshot!(graph.to_dot(), @r#"
+    digraph {
+        "C:\\temp";
+        "say \"hi\"";
+        "x";
+        "say \"hi\"" -> "x" [label="line\nbreak"];
+        "x" -> "C:\\temp" [label="\\n is a newline"];
+    }
+    "#);
+}
+
+#[test]
+fn test_normalize_dot() {
+    let dot = r#"
+        /* generated */
+        strict digraph {
+            node [shape=box fontname="Helvetica"];
+            a:out:s -> "b" -> c [ label = <<b>x</b>>, color=blue ];
+            subgraph cluster_0 { label = "inner" + " part"; x; y }
+            # preprocessor style comment
+            -1.5 -- z
+        }
+    "#;
+    crate::assert_snapshot!(normalize_dot(dot), @r#"
+    strict digraph {
+        node [fontname="Helvetica", shape="box"];
+        "a":"out":"s" -> "b" -> "c" [color="blue", label=<<b>x</b>>];
+        subgraph "cluster_0" {
+            label="inner part";
+            "x";
+            "y";
+        }
+        "-1.5" -- "z";
+    }
+    "#);
+}
+
+#[test]
+fn test_normalize_dot_roundtrips_graph_snapshot() {
+    let mut graph = GraphSnapshot::directed();
+    graph.add_labeled_edge("a", "b", "x").add_node("c");
+    graph.add_labeled_edge("c", "d\\", "\"\\\"");
+    assert_eq!(normalize_dot(&graph.to_dot()), graph.to_dot());
+}
@@ -0,0 +1,135 @@
+use std::fmt::Display;
+use std::path::Path;
+
+use graphql_parser::query::{
+    parse_query, Definition, Document, OperationDefinition, Selection, SelectionSet, TypeCondition,
+};
+
+use crate::content::{json, yaml, Content};
+
+/// Formats a GraphQL document, or the JSON response to a GraphQL request.
+///
+/// Documents are pretty-printed with the fields of selection sets sorted by
+/// their response name, followed by the fragment spreads and the inline
+/// fragments, and with the arguments of fields sorted by name.  Operations
+/// keep their order and fragment definitions are sorted by name after them.
+/// This keeps the snapshot stable when a code generator or gateway emits the
+/// same operation in another order.
+///
+/// Input that is not a GraphQL document but JSON, such as a response, is
+/// pretty-printed with the keys of objects sorted.  The order of lists is
+/// kept.
+///
+/// Panics if the input is neither a GraphQL document nor JSON.
+pub fn format_graphql<V: Display + ?Sized>(input: &V) -> String {
+    let input = input.to_string();
+    let err = match parse_query::<&str>(&input) {
+        Ok(mut document) => {
+            normalize_document(&mut document);
+            return document.to_string().trim_end().to_string();
+        }
+        Err(err) => err,
+    };
+    // a shorthand query starts with `{` too, so JSON is only tried after
+    // the input failed to parse as GraphQL
+    if input.trim_start().starts_with(['{', '[']) {
+        // JSON is a subset of YAML, so the YAML parser reads it
+        if let Ok(mut value) = yaml::parse_str(&input, Path::new("")) {
+            sort_keys(&mut value);
+            return json::to_string_pretty(&value);
+        }
+    }
+    panic!("failed to parse snapshot value as GraphQL: {err}");
+}
+
+fn normalize_document<'a>(document: &mut Document<'a, &'a str>) {
+    for definition in &mut document.definitions {
+        let selection_set = match definition {
+            Definition::Operation(OperationDefinition::SelectionSet(set)) => set,
+            Definition::Operation(OperationDefinition::Query(query)) => &mut query.selection_set,
+            Definition::Operation(OperationDefinition::Mutation(mutation)) => {
+                &mut mutation.selection_set
+            }
+            Definition::Operation(OperationDefinition::Subscription(subscription)) => {
+                &mut subscription.selection_set
+            }
+            Definition::Fragment(fragment) => &mut fragment.selection_set,
+        };
+        normalize_selection_set(selection_set);
+    }
+    // sorting is stable, so operations keep their order
+    document
+        .definitions
+        .sort_by_key(|definition| match definition {
+            Definition::Operation(_) => None,
+            Definition::Fragment(fragment) => Some(fragment.name),
+        });
+}
+
+fn normalize_selection_set<'a>(selection_set: &mut SelectionSet<'a, &'a str>) {
+    for selection in &mut selection_set.items {
+        match selection {
+            Selection::Field(field) => {
+                field.arguments.sort_by_key(|(name, _)| *name);
+                normalize_selection_set(&mut field.selection_set);
+            }
+            Selection::FragmentSpread(_) => {}
+            Selection::InlineFragment(fragment) => {
+                normalize_selection_set(&mut fragment.selection_set);
+            }
+        }
+    }
+    selection_set
+        .items
+        .sort_by_key(|selection| match selection {
+            Selection::Field(field) => (0, field.alias.unwrap_or(field.name)),
+            Selection::FragmentSpread(spread) => (1, spread.fragment_name),
+            Selection::InlineFragment(fragment) => match fragment.type_condition {
+                Some(TypeCondition::On(name)) => (2, name),
+                None => (2, ""),
+            },
+        });
+}
+
+/// Sorts the keys of all maps in `value`, but not the items of sequences.
+fn sort_keys(value: &mut Content) {
+    match value {
+        Content::Map(entries) => {
+            entries.sort_by(|a, b| a.0.as_str().cmp(&b.0.as_str()));
+            for (_, value) in entries {
+                sort_keys(value);
+            }
+        }
+        Content::Seq(items) => items.iter_mut().for_each(sort_keys),
+        _ => {}
+    }
+}
+
+#[test]
+fn test_format_graphql() {
+    assert_snapshot!(format_graphql(
+        "query User($id: ID!) { user(name: \"x\", id: $id) { ...Avatar name id \
+         ... on Admin { role } } } fragment Avatar on User { url(size: 2) }"
+    ), @r#"
+    query User($id: ID!) {
+      user(id: $id, name: "x") {
+        id
+        name
+        ...Avatar
+        ... on Admin {
+          role
+        }
+      }
+    }
+
+    fragment Avatar on User {
+      url(size: 2)
+    }
+    "#);
+    assert_snapshot!(format_graphql("{ b a }"), @"
+    {
+      a
+      b
+    }
+    ");
+}
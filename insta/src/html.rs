@@ -0,0 +1,213 @@
+use std::fmt::Display;
+
+use html5ever::tendril::TendrilSink;
+use html5ever::{local_name, namespace_url, ns, parse_document, parse_fragment, QualName};
+use markup5ever_rcdom::{Handle, NodeData, RcDom};
+
+/// Elements that never have children or a closing tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements whose text is kept exactly as it is.
+const PREFORMATTED_ELEMENTS: &[&str] = &["pre", "textarea", "script", "style"];
+
+/// Parses `input` as HTML and pretty prints it in a normalized form.
+///
+/// Attributes are sorted by name, whitespace in text is collapsed and text
+/// that is only whitespace is dropped, except within `<pre>`, `<textarea>`,
+/// `<script>` and `<style>`.  Every element goes on its own line, indented
+/// by its depth, unless its only child is text.
+///
+/// Inputs starting with a doctype or an `<html>` tag are parsed as a full
+/// document, anything else as a fragment of a `<body>`.
+pub fn normalize_html<V: Display + ?Sized>(input: &V) -> String {
+    let input = input.to_string();
+    let start = input
+        .trim_start()
+        .get(..5)
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    let mut rv = String::new();
+    if start == "<!doc" || start == "<html" {
+        let dom = parse_document(RcDom::default(), Default::default()).one(input);
+        write_children(&mut rv, &dom.document, 0);
+    } else {
+        let dom = parse_fragment(
+            RcDom::default(),
+            Default::default(),
+            QualName::new(None, ns!(html), local_name!("body")),
+            vec![],
+        )
+        .one(input);
+        // fragments are parsed into an `<html>` element below the document
+        let root = dom.document.children.borrow()[0].clone();
+        write_children(&mut rv, &root, 0);
+    }
+    rv.pop();
+    rv
+}
+
+fn write_children(out: &mut String, node: &Handle, depth: usize) {
+    for child in node.children.borrow().iter() {
+        write_node(out, child, depth);
+    }
+}
+
+fn write_node(out: &mut String, node: &Handle, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match node.data {
+        NodeData::Document => write_children(out, node, depth),
+        NodeData::Doctype { ref name, .. } => {
+            out.push_str(&format!("{indent}<!DOCTYPE {name}>\n"));
+        }
+        NodeData::Text { ref contents } => {
+            let text = collapse_whitespace(&contents.borrow());
+            if !text.is_empty() {
+                out.push_str(&format!("{indent}{}\n", escape(&text, false)));
+            }
+        }
+        NodeData::Comment { ref contents } => {
+            out.push_str(&format!("{indent}<!-- {} -->\n", contents.trim()));
+        }
+        NodeData::ProcessingInstruction {
+            ref target,
+            ref contents,
+        } => {
+            out.push_str(&format!("{indent}<?{target} {contents}>\n"));
+        }
+        NodeData::Element {
+            ref name,
+            ref attrs,
+            ref template_contents,
+            ..
+        } => {
+            let tag = &*name.local;
+            let mut attrs = attrs
+                .borrow()
+                .iter()
+                .map(|attr| {
+                    let name = match attr.name.prefix {
+                        Some(ref prefix) => format!("{prefix}:{}", attr.name.local),
+                        None => attr.name.local.to_string(),
+                    };
+                    (name, attr.value.to_string())
+                })
+                .collect::<Vec<_>>();
+            attrs.sort();
+            let mut open = format!("{indent}<{tag}");
+            for (name, value) in &attrs {
+                if value.is_empty() {
+                    open.push_str(&format!(" {name}"));
+                } else {
+                    open.push_str(&format!(" {name}=\"{}\"", escape(value, true)));
+                }
+            }
+            open.push('>');
+
+            if VOID_ELEMENTS.contains(&tag) {
+                out.push_str(&format!("{open}\n"));
+                return;
+            }
+
+            let template_contents = template_contents.borrow();
+            let node = template_contents.as_ref().unwrap_or(node);
+            let children = node.children.borrow();
+            if PREFORMATTED_ELEMENTS.contains(&tag) {
+                let text = children
+                    .iter()
+                    .filter_map(|child| match child.data {
+                        NodeData::Text { ref contents } => Some(contents.borrow().to_string()),
+                        _ => None,
+                    })
+                    .collect::<String>();
+                let text = match tag {
+                    "script" | "style" => text.trim().to_string(),
+                    _ => escape(&text, false),
+                };
+                out.push_str(&format!("{open}{text}</{tag}>\n"));
+                return;
+            }
+
+            let children = children
+                .iter()
+                .filter(|child| match child.data {
+                    NodeData::Text { ref contents } => {
+                        !collapse_whitespace(&contents.borrow()).is_empty()
+                    }
+                    _ => true,
+                })
+                .collect::<Vec<_>>();
+            match children[..] {
+                [] => out.push_str(&format!("{open}</{tag}>\n")),
+                [child] if matches!(child.data, NodeData::Text { .. }) => {
+                    let mut text = String::new();
+                    write_node(&mut text, child, 0);
+                    out.push_str(&format!("{open}{}</{tag}>\n", text.trim_end()));
+                }
+                _ => {
+                    out.push_str(&format!("{open}\n"));
+                    for child in children {
+                        write_node(out, child, depth + 1);
+                    }
+                    out.push_str(&format!("{indent}</{tag}>\n"));
+                }
+            }
+        }
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn escape(text: &str, attribute: bool) -> String {
+    let mut rv = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => rv.push_str("&amp;"),
+            '<' if !attribute => rv.push_str("&lt;"),
+            '>' if !attribute => rv.push_str("&gt;"),
+            '"' if attribute => rv.push_str("&quot;"),
+            c => rv.push(c),
+        }
+    }
+    rv
+}
+
+#[test]
+fn test_normalize_html() {
+    let html = r#"<div id="main"   class="card">
+        <p>Hello,   <b>world</b>!</p>
+        <input type="checkbox" disabled name="agree">
+        <pre>  keep
+   this </pre>
+        <!--note-->
+    </div>"#;
+    assert_eq!(
+        normalize_html(html),
+        r#"<div class="card" id="main">
+  <p>
+    Hello,
+    <b>world</b>
+    !
+  </p>
+  <input disabled name="agree" type="checkbox">
+  <pre>  keep
+   this </pre>
+  <!-- note -->
+</div>"#
+    );
+
+    // attribute order and whitespace do not matter
+    assert_eq!(
+        normalize_html(r#"<a href="/" title="home">Home</a>"#),
+        normalize_html("<a  title=\"home\"\n href=\"/\"> Home </a>")
+    );
+
+    assert_eq!(
+        normalize_html("<!DOCTYPE html><title>A &amp; B</title><p>x < y"),
+        "<!DOCTYPE html>\n<html>\n  <head>\n    <title>A &amp; B</title>\n  </head>\n  <body>\n    <p>x &lt; y</p>\n  </body>\n</html>"
+    );
+}
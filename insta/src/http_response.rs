@@ -0,0 +1,153 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use http::header::{HeaderName, CONTENT_TYPE, DATE, ETAG, SET_COOKIE};
+use http::Response;
+
+use crate::content::{json, yaml};
+
+/// The headers that change with every response and are redacted unless a
+/// redaction for them is passed.
+const VOLATILE_HEADERS: &[(HeaderName, &str)] = &[(DATE, "[date]"), (ETAG, "[etag]")];
+
+/// Formats an HTTP response as its status line, headers and body.
+///
+/// Headers are sorted by name, keeping the order of repeated headers.  For
+/// every `(header, replacement)` in `redactions` the values of the header
+/// are replaced.  The `date` and `etag` headers are redacted unless they
+/// are in `redactions`, and so is the value of every `set-cookie` header,
+/// which keeps the name and the attributes of the cookie apart from
+/// `Expires` and `Max-Age`.
+///
+/// JSON bodies are pretty printed, other text is written as it is and
+/// binary bodies are replaced with their size.
+pub fn render_http_response<B: AsRef<[u8]>>(
+    response: &Response<B>,
+    redactions: &[(&str, String)],
+) -> String {
+    let status = response.status();
+    let mut rv = format!("{:?} {}", response.version(), status.as_str());
+    if let Some(reason) = status.canonical_reason() {
+        write!(rv, " {reason}").unwrap();
+    }
+
+    let mut headers = response.headers().iter().collect::<Vec<_>>();
+    headers.sort_by_key(|(name, _)| name.as_str());
+    for (name, value) in headers {
+        let value = String::from_utf8_lossy(value.as_bytes());
+        let value = match redactions
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name.as_str()))
+        {
+            Some((_, replacement)) => replacement.clone(),
+            None => match VOLATILE_HEADERS.iter().find(|(header, _)| header == name) {
+                Some((_, replacement)) => replacement.to_string(),
+                None if name == SET_COOKIE => redact_cookie(&value),
+                None => value.into_owned(),
+            },
+        };
+        write!(rv, "\n{name}: {value}").unwrap();
+    }
+
+    let body = response.body().as_ref();
+    if !body.is_empty() {
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|x| x.to_str().ok())
+            .unwrap_or("");
+        rv.push_str("\n\n");
+        write_body(body, content_type, &mut rv);
+    }
+    rv
+}
+
+/// Replaces the value and the expiry of a `set-cookie` header.
+fn redact_cookie(value: &str) -> String {
+    let mut attributes = value.split(';').map(str::trim);
+    let mut rv = match attributes.next().and_then(|x| x.split_once('=')) {
+        Some((name, _)) => format!("{name}=[value]"),
+        None => "[value]".to_string(),
+    };
+    for attribute in attributes {
+        let name = attribute.split('=').next().unwrap_or(attribute);
+        if name.eq_ignore_ascii_case("expires") || name.eq_ignore_ascii_case("max-age") {
+            write!(rv, "; {name}=[date]").unwrap();
+        } else {
+            write!(rv, "; {attribute}").unwrap();
+        }
+    }
+    rv
+}
+
+fn write_body(body: &[u8], content_type: &str, out: &mut String) {
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    let text = match std::str::from_utf8(body) {
+        Ok(text) if !is_binary(text) => text,
+        _ => {
+            write!(out, "[{} bytes]", body.len()).unwrap();
+            return;
+        }
+    };
+    if media_type == "application/json" || media_type.ends_with("+json") {
+        // JSON is a subset of YAML, so the YAML parser reads it
+        if let Ok(value) = yaml::parse_str(text, Path::new("")) {
+            out.push_str(&json::to_string_pretty(&value));
+            return;
+        }
+    }
+    out.push_str(text.trim_end());
+}
+
+fn is_binary(text: &str) -> bool {
+    text.chars()
+        .any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+}
+
+#[test]
+fn test_render_http_response() {
+    let response = Response::builder()
+        .status(201)
+        .header("x-request-id", "4f1c")
+        .header("content-type", "application/json; charset=utf-8")
+        .header("date", "Tue, 15 Nov 1994 08:12:31 GMT")
+        .header(
+            "set-cookie",
+            "session=abc123; Path=/; Max-Age=3600; HttpOnly",
+        )
+        .header("etag", "\"33a64df5\"")
+        .body(r#"{"id":1,"tags":["a","b"]}"#)
+        .unwrap();
+    assert_eq!(
+        render_http_response(&response, &[("X-Request-Id", "[id]".into())]),
+        "HTTP/1.1 201 Created\n\
+         content-type: application/json; charset=utf-8\n\
+         date: [date]\n\
+         etag: [etag]\n\
+         set-cookie: session=[value]; Path=/; Max-Age=[date]; HttpOnly\n\
+         x-request-id: [id]\n\
+         \n\
+         {\n  \"id\": 1,\n  \"tags\": [\n    \"a\",\n    \"b\"\n  ]\n}"
+    );
+
+    let response = Response::builder()
+        .status(404)
+        .header("content-type", "application/octet-stream")
+        .body(vec![0u8, 159, 146, 150])
+        .unwrap();
+    assert_eq!(
+        render_http_response(&response, &[]),
+        "HTTP/1.1 404 Not Found\ncontent-type: application/octet-stream\n\n[4 bytes]"
+    );
+
+    let response = Response::builder().status(204).body("").unwrap();
+    assert_eq!(
+        render_http_response(&response, &[]),
+        "HTTP/1.1 204 No Content"
+    );
+}
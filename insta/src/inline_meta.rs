@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::filters::{presets, Filters};
+
+/// The source files that were read for metadata comments.
+static SOURCES: Lazy<Mutex<BTreeMap<PathBuf, Option<Arc<str>>>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// Returns the filters of the metadata comment that follows the inline
+/// snapshot of the assertion at `line` of `file`.
+///
+/// The comment is written right after the snapshot literal, as in
+/// `@"..." /* insta: filters=uuid,timestamp */`.  Returns `None` if there
+/// is no such comment or the source file cannot be read, for instance when
+/// the tests run on another machine than the one they were built on, which
+/// the `filters = [...]` argument of the assertion macros doesn't depend on.
+pub(crate) fn inline_filters(workspace: &Path, file: &str, line: u32) -> Option<Filters> {
+    let path = workspace.join(file);
+    let source = SOURCES
+        .lock()
+        .unwrap()
+        .entry(path)
+        .or_insert_with_key(|path| fs::read_to_string(path).ok().map(Arc::from))
+        .clone()?;
+    let start = source
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1) as usize)
+        .map(str::len)
+        .sum();
+    let comment = find_metadata_comment(&source[start..])?;
+    Some(
+        parse_metadata(comment)
+            .unwrap_or_else(|err| panic!("invalid insta metadata comment in {file}:{line}: {err}")),
+    )
+}
+
+/// Parses the `key=value` pairs of a metadata comment.
+fn parse_metadata(comment: &str) -> Result<Filters, String> {
+    let mut filters = Filters::default();
+    for pair in comment.split_whitespace() {
+        match pair.split_once('=') {
+            Some(("filters", names)) => {
+                for name in names.split(',').filter(|x| !x.is_empty()) {
+                    let (regex, replacement) =
+                        presets::by_name(name).ok_or_else(|| format!("unknown filter {name:?}"))?;
+                    filters.add(regex, replacement);
+                }
+            }
+            _ => return Err(format!("unknown option {pair:?}")),
+        }
+    }
+    Ok(filters)
+}
+
+/// Finds the metadata comment after the first inline snapshot literal in
+/// `source`, which starts at the line of the macro call.
+///
+/// String literals, character literals and comments are skipped, so code
+/// before the macro call on the same line is passed over.
+fn find_metadata_comment(source: &str) -> Option<&str> {
+    let mut rest = source;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '@' if is_literal_start(rest[1..].trim_start()) => {
+                let after = skip_literal(rest[1..].trim_start())?;
+                let comment = after.trim_start().strip_prefix("/*")?;
+                let comment = comment.trim_start().strip_prefix("insta:")?;
+                return comment.split_once("*/").map(|(comment, _)| comment.trim());
+            }
+            '"' => {
+                rest = skip_literal(rest)?;
+                continue;
+            }
+            'r' | 'b' if is_literal_start(rest) => {
+                rest = skip_literal(rest)?;
+                continue;
+            }
+            '\'' => {
+                // skip character literals, but not lifetimes
+                let mut chars = rest[1..].char_indices();
+                match (chars.next(), chars.next()) {
+                    (Some((_, '\\')), _) => {
+                        rest = &rest[1..];
+                        rest = &rest[rest[2..].find('\'')? + 3..];
+                        continue;
+                    }
+                    (Some(_), Some((idx, '\''))) => {
+                        rest = &rest[idx + 2..];
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            '/' if rest.starts_with("//") => {
+                rest = rest.split_once('\n').map_or("", |(_, rest)| rest);
+                continue;
+            }
+            '/' if rest.starts_with("/*") => {
+                rest = rest[2..].split_once("*/")?.1;
+                continue;
+            }
+            _ => {}
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    None
+}
+
+/// Checks if a raw or byte string literal starts at the beginning of `s`.
+fn is_literal_start(s: &str) -> bool {
+    let s = s.strip_prefix('b').unwrap_or(s);
+    let s = match s.strip_prefix('r') {
+        Some(s) => s.trim_start_matches('#'),
+        None => s,
+    };
+    s.starts_with('"')
+}
+
+/// Skips a string literal at the start of `s` and returns what follows.
+fn skip_literal(s: &str) -> Option<&str> {
+    let s = s.strip_prefix('b').unwrap_or(s);
+    if let Some(raw) = s.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let body = raw[hashes..].strip_prefix('"')?;
+        let end = format!("\"{}", "#".repeat(hashes));
+        let idx = body.find(&end)?;
+        return Some(&body[idx + end.len()..]);
+    }
+    let mut chars = s.strip_prefix('"')?.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => return Some(&s[idx + 2..]),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[test]
+fn test_find_metadata_comment() {
+    assert_eq!(
+        find_metadata_comment(
+            "assert_snapshot!(format!(\"@{}\", '\"'), @r#\"a \"@\" b\"# /* insta: filters=uuid */);"
+        ),
+        Some("filters=uuid")
+    );
+    assert_eq!(
+        find_metadata_comment("assert_snapshot!(x, @\"\\\"\" /*insta:filters=timestamp*/)"),
+        Some("filters=timestamp")
+    );
+    assert_eq!(
+        find_metadata_comment(
+            "assert_snapshot!(x, @\"a\");\nassert_snapshot!(y, @\"b\" /* insta: */);"
+        ),
+        None
+    );
+    // code before the macro call on the same line
+    assert_eq!(
+        find_metadata_comment(
+            "let x = f(\"@\"); assert_snapshot!(x, @\"a\" /* insta: filters=uuid */);"
+        ),
+        Some("filters=uuid")
+    );
+    // the binding operator of patterns is not a snapshot
+    assert_eq!(
+        find_metadata_comment("match x { y @ 1 => {} } assert_snapshot!(x, @\"a\");"),
+        None
+    );
+    assert_eq!(
+        find_metadata_comment("assert_snapshot!(x, @\"a\" /* other */);"),
+        None
+    );
+}
+
+#[test]
+fn test_parse_metadata() {
+    let filters = parse_metadata("filters=uuid,timestamp").unwrap();
+    assert_eq!(
+        filters.apply_to("id 67e55044-10b1-426f-9247-bb680e5fe0c8 at 2024-05-01T10:00:00.123Z"),
+        "id [uuid] at [timestamp]"
+    );
+    assert_eq!(
+        parse_metadata("filters=date").unwrap_err(),
+        "unknown filter \"date\""
+    );
+    assert_eq!(
+        parse_metadata("trim=true").unwrap_err(),
+        "unknown option \"trim=true\""
+    );
+}
@@ -0,0 +1,448 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::de::value::Error as ValueError;
+use serde::Serialize;
+
+use crate::content::{json, yaml, Content, ContentSerializer};
+use crate::naming::fnv1a;
+use crate::settings::Settings;
+
+/// A JSON schema that serialized values are validated against.
+#[derive(Debug)]
+pub struct JsonSchema {
+    schema: Content,
+    reference: String,
+}
+
+impl JsonSchema {
+    /// Loads a schema from a value or, if the value is a string, from the
+    /// JSON or YAML file at that path relative to `base_dir`.
+    pub fn load<S: Serialize + ?Sized>(schema: &S, base_dir: &Path) -> JsonSchema {
+        let (schema, name) =
+            match Serialize::serialize(schema, ContentSerializer::<ValueError>::new())
+                .expect("failed to serialize JSON schema")
+            {
+                Content::String(path) => {
+                    let full_path = base_dir.join(&path);
+                    let source = fs::read_to_string(&full_path).unwrap_or_else(|err| {
+                        panic!("failed to read JSON schema {}: {err}", full_path.display())
+                    });
+                    let schema = yaml::parse_str(&source, &full_path).unwrap_or_else(|err| {
+                        panic!("failed to parse JSON schema {}: {err}", full_path.display())
+                    });
+                    (schema, Some(path))
+                }
+                schema => (normalize(&schema), None),
+            };
+        let name = as_map(&schema)
+            .and_then(|map| get(map, "$id"))
+            .and_then(Content::as_str)
+            .map(str::to_string)
+            .or(name)
+            .unwrap_or_else(|| "inline".into());
+        let hash = fnv1a(json::to_string_compact(&schema).as_bytes());
+        JsonSchema {
+            reference: format!("{name}@{:08x}", hash as u32),
+            schema,
+        }
+    }
+
+    /// Returns the name of the schema and a hash of its contents, which is
+    /// stored in the metadata of snapshots.
+    pub(crate) fn reference(&self) -> &str {
+        &self.reference
+    }
+
+    /// Runs `f` with settings that validate serialized values against this
+    /// schema.
+    pub fn bind<R, F: FnOnce() -> R>(self, f: F) -> R {
+        let mut settings = Settings::clone_current();
+        settings._private_inner_mut().json_schema = Some(Arc::new(self));
+        settings.bind(f)
+    }
+
+    /// Validates a serialized value and panics with all violations.
+    pub(crate) fn validate(&self, value: &Content) {
+        let value = normalize(value);
+        let mut errors = vec![];
+        self.check(&self.schema, &value, &mut String::new(), &mut errors);
+        if !errors.is_empty() {
+            panic!(
+                "value does not match JSON schema {}:\n{}",
+                self.reference,
+                errors
+                    .iter()
+                    .map(|err| format!("  {err}"))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            );
+        }
+    }
+
+    fn check(
+        &self,
+        schema: &Content,
+        value: &Content,
+        path: &mut String,
+        errors: &mut Vec<String>,
+    ) {
+        let schema = match schema {
+            Content::Bool(true) => return,
+            Content::Bool(false) => return error(errors, path, "no value is allowed here".into()),
+            Content::Map(schema) => schema,
+            _ => panic!("invalid JSON schema at {}", display_path(path)),
+        };
+
+        if let Some(reference) = get(schema, "$ref").and_then(Content::as_str) {
+            let target = self
+                .resolve(reference)
+                .unwrap_or_else(|| panic!("unresolved JSON schema reference {reference:?}"));
+            self.check(target, value, path, errors);
+        }
+
+        if let Some(types) = get(schema, "type") {
+            let types = match types {
+                Content::Seq(types) => types.iter().filter_map(Content::as_str).collect(),
+                types => types.as_str().into_iter().collect::<Vec<_>>(),
+            };
+            if !types.iter().any(|ty| has_type(value, ty)) {
+                return error(
+                    errors,
+                    path,
+                    format!("expected {}, got {}", types.join(" or "), type_name(value)),
+                );
+            }
+        }
+        if let Some(expected) = get(schema, "const") {
+            if !json_eq(expected, value) {
+                error(
+                    errors,
+                    path,
+                    format!("expected {}", json::to_string_compact(expected)),
+                );
+            }
+        }
+        if let Some(Content::Seq(options)) = get(schema, "enum") {
+            if !options.iter().any(|option| json_eq(option, value)) {
+                let options = options
+                    .iter()
+                    .map(json::to_string_compact)
+                    .collect::<Vec<_>>();
+                error(
+                    errors,
+                    path,
+                    format!("expected one of {}", options.join(", ")),
+                );
+            }
+        }
+
+        match value {
+            Content::Map(map) => self.check_object(schema, map, path, errors),
+            Content::Seq(items) => self.check_array(schema, items, path, errors),
+            Content::String(s) => {
+                let len = s.chars().count() as f64;
+                if let Some(min) = get(schema, "minLength").and_then(as_number) {
+                    if len < min {
+                        error(errors, path, format!("expected at least {min} characters"));
+                    }
+                }
+                if let Some(max) = get(schema, "maxLength").and_then(as_number) {
+                    if len > max {
+                        error(errors, path, format!("expected at most {max} characters"));
+                    }
+                }
+            }
+            value => {
+                if let Some(number) = as_number(value) {
+                    check_number(schema, number, path, errors);
+                }
+            }
+        }
+
+        if let Some(Content::Seq(schemas)) = get(schema, "allOf") {
+            for schema in schemas {
+                self.check(schema, value, path, errors);
+            }
+        }
+        for keyword in ["anyOf", "oneOf"] {
+            if let Some(Content::Seq(schemas)) = get(schema, keyword) {
+                let matching = schemas
+                    .iter()
+                    .filter(|schema| {
+                        let mut sub_errors = vec![];
+                        self.check(schema, value, path, &mut sub_errors);
+                        sub_errors.is_empty()
+                    })
+                    .count();
+                if matching == 0 || (keyword == "oneOf" && matching > 1) {
+                    let msg = format!(
+                        "expected a value matching one schema of {keyword}, matched {matching}"
+                    );
+                    error(errors, path, msg);
+                }
+            }
+        }
+        if let Some(not) = get(schema, "not") {
+            let mut sub_errors = vec![];
+            self.check(not, value, path, &mut sub_errors);
+            if sub_errors.is_empty() {
+                error(
+                    errors,
+                    path,
+                    "expected a value not matching the schema of not".into(),
+                );
+            }
+        }
+    }
+
+    fn check_object(
+        &self,
+        schema: &[(Content, Content)],
+        map: &[(Content, Content)],
+        path: &mut String,
+        errors: &mut Vec<String>,
+    ) {
+        let properties = get(schema, "properties").and_then(as_map);
+        if let Some(Content::Seq(required)) = get(schema, "required") {
+            for key in required.iter().filter_map(Content::as_str) {
+                if get(map, key).is_none() {
+                    error(errors, path, format!("missing required property {key:?}"));
+                }
+            }
+        }
+        let additional = get(schema, "additionalProperties");
+        for (key, value) in map {
+            let key = match key.as_str() {
+                Some(key) => key,
+                None => continue,
+            };
+            let len = path.len();
+            write!(path, ".{key}").unwrap();
+            match (properties.and_then(|x| get(x, key)), additional) {
+                (Some(schema), _) | (None, Some(schema)) => self.check(schema, value, path, errors),
+                (None, None) => {}
+            }
+            path.truncate(len);
+        }
+    }
+
+    fn check_array(
+        &self,
+        schema: &[(Content, Content)],
+        items: &[Content],
+        path: &mut String,
+        errors: &mut Vec<String>,
+    ) {
+        if let Some(min) = get(schema, "minItems").and_then(as_number) {
+            if (items.len() as f64) < min {
+                error(errors, path, format!("expected at least {min} items"));
+            }
+        }
+        if let Some(max) = get(schema, "maxItems").and_then(as_number) {
+            if (items.len() as f64) > max {
+                error(errors, path, format!("expected at most {max} items"));
+            }
+        }
+        if get(schema, "uniqueItems").and_then(Content::as_bool) == Some(true) {
+            for (idx, item) in items.iter().enumerate() {
+                if items[..idx].iter().any(|other| json_eq(other, item)) {
+                    error(
+                        errors,
+                        path,
+                        format!("expected unique items, item {idx} is repeated"),
+                    );
+                }
+            }
+        }
+        if let Some(item_schema) = get(schema, "items") {
+            for (idx, item) in items.iter().enumerate() {
+                let len = path.len();
+                write!(path, "[{idx}]").unwrap();
+                self.check(item_schema, item, path, errors);
+                path.truncate(len);
+            }
+        }
+    }
+
+    /// Resolves a JSON pointer within the schema, such as `#/$defs/user`.
+    fn resolve(&self, reference: &str) -> Option<&Content> {
+        let pointer = reference.strip_prefix('#')?;
+        pointer
+            .split('/')
+            .skip(1)
+            .try_fold(&self.schema, |schema, token| {
+                let token = token.replace("~1", "/").replace("~0", "~");
+                get(as_map(schema)?, &token)
+            })
+    }
+}
+
+/// Validates a serialized value against the schema of the current settings,
+/// if there is one.
+pub(crate) fn validate_current(value: &Content) {
+    if let Some(schema) = Settings::with(|settings| settings.json_schema().cloned()) {
+        schema.validate(value);
+    }
+}
+
+fn check_number(schema: &[(Content, Content)], number: f64, path: &str, errors: &mut Vec<String>) {
+    let bounds = [
+        ("minimum", "at least"),
+        ("maximum", "at most"),
+        ("exclusiveMinimum", "more than"),
+        ("exclusiveMaximum", "less than"),
+    ];
+    for (keyword, description) in bounds {
+        if let Some(bound) = get(schema, keyword).and_then(as_number) {
+            let holds = match keyword {
+                "minimum" => number >= bound,
+                "maximum" => number <= bound,
+                "exclusiveMinimum" => number > bound,
+                _ => number < bound,
+            };
+            if !holds {
+                error(
+                    errors,
+                    path,
+                    format!("expected {description} {bound}, got {number}"),
+                );
+            }
+        }
+    }
+}
+
+/// Turns a serialized value into the value a JSON parser would read from its
+/// JSON form, which leaves only maps, sequences, strings, numbers, booleans
+/// and null.
+fn normalize(value: &Content) -> Content {
+    // JSON is a subset of YAML, so the YAML parser reads it
+    yaml::parse_str(&json::to_string_compact(value), Path::new(""))
+        .expect("serialized JSON can be parsed")
+}
+
+fn as_map(value: &Content) -> Option<&[(Content, Content)]> {
+    match value {
+        Content::Map(map) => Some(map),
+        _ => None,
+    }
+}
+
+fn as_number(value: &Content) -> Option<f64> {
+    value
+        .as_f64()
+        .or_else(|| value.as_i64().map(|x| x as f64))
+        .or_else(|| value.as_u64().map(|x| x as f64))
+}
+
+fn get<'a>(map: &'a [(Content, Content)], key: &str) -> Option<&'a Content> {
+    map.iter()
+        .find(|(k, _)| k.as_str() == Some(key))
+        .map(|(_, v)| v)
+}
+
+fn has_type(value: &Content, ty: &str) -> bool {
+    match ty {
+        "integer" => is_number(value) && as_number(value).map_or(false, |x| x.fract() == 0.0),
+        "number" => is_number(value),
+        ty => type_name(value) == ty,
+    }
+}
+
+fn is_number(value: &Content) -> bool {
+    matches!(type_name(value), "integer" | "number")
+}
+
+fn type_name(value: &Content) -> &'static str {
+    match value {
+        Content::None | Content::Unit => "null",
+        Content::Bool(_) => "boolean",
+        Content::String(_) => "string",
+        Content::Seq(_) => "array",
+        Content::Map(_) => "object",
+        Content::F32(_) | Content::F64(_) => "number",
+        _ => "integer",
+    }
+}
+
+/// Compares two normalized values, with numbers compared by their value.
+fn json_eq(a: &Content, b: &Content) -> bool {
+    match (a, b) {
+        (Content::Seq(a), Content::Seq(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| json_eq(a, b))
+        }
+        (Content::Map(a), Content::Map(b)) => {
+            a.len() == b.len()
+                && a.iter().all(|(key, a)| {
+                    key.as_str()
+                        .and_then(|key| get(b, key))
+                        .map_or(false, |b| json_eq(a, b))
+                })
+        }
+        (a, b) if is_number(a) && is_number(b) => as_number(a) == as_number(b),
+        (a, b) => a == b,
+    }
+}
+
+fn display_path(path: &str) -> &str {
+    if path.is_empty() {
+        "."
+    } else {
+        path
+    }
+}
+
+fn error(errors: &mut Vec<String>, path: &str, msg: String) {
+    errors.push(format!("{}: {msg}", display_path(path)));
+}
+
+#[test]
+fn test_validate() {
+    let schema = JsonSchema::load(&Content::Map(vec![]), Path::new(""));
+    assert_eq!(schema.reference(), "inline@b5901a25");
+
+    let schema = yaml::parse_str(
+        r##"{
+            "$id": "user",
+            "type": "object",
+            "required": ["id", "name"],
+            "properties": {
+                "id": {"type": "integer", "minimum": 1},
+                "name": {"type": "string", "minLength": 1},
+                "role": {"enum": ["admin", "user"]},
+                "tags": {"type": "array", "items": {"$ref": "#/$defs/tag"}, "uniqueItems": true}
+            },
+            "additionalProperties": false,
+            "$defs": {"tag": {"type": "string", "maxLength": 3}}
+        }"##,
+        Path::new(""),
+    )
+    .unwrap();
+    let schema = JsonSchema::load(&schema, Path::new(""));
+    assert!(schema.reference().starts_with("user@"));
+
+    let check = |value: &str| {
+        let value = yaml::parse_str(value, Path::new("")).unwrap();
+        let mut errors = vec![];
+        schema.check(&schema.schema, &value, &mut String::new(), &mut errors);
+        errors
+    };
+    assert_eq!(
+        check(r#"{"id": 1, "name": "Ada", "tags": ["a", "b"]}"#),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        check(r#"{"id": 0.5, "role": "root", "tags": ["a", "a", "long"], "x": 1}"#),
+        [
+            ".: missing required property \"name\"",
+            ".id: expected integer, got number",
+            ".role: expected one of \"admin\", \"user\"",
+            ".tags: expected unique items, item 1 is repeated",
+            ".tags[2]: expected at most 3 characters",
+            ".x: no value is allowed here",
+        ]
+    );
+    assert_eq!(check("[]"), [".: expected object, got array"]);
+}
@@ -90,7 +90,7 @@
 //!
 #![cfg_attr(
     feature = "csv",
-    doc = "- [`assert_csv_snapshot!`] for comparing CSV serialized output. (requires the `csv` feature)"
+    doc = "- [`assert_csv_snapshot!`] for comparing CSV serialized output or CSV text as a table. (requires the `csv` feature)"
 )]
 #![cfg_attr(
     feature = "toml",
@@ -112,6 +112,10 @@
     feature = "json",
     doc = "- [`assert_compact_json_snapshot!`] for comparing JSON serialized output while preferring single-line formatting. (requires the `json` feature)"
 )]
+#![cfg_attr(
+    feature = "serde",
+    doc = "- [`assert_table_snapshot!`] for comparing sequences of items as a text table with a column per field. (requires one of the serialization features)"
+)]
 //!
 //! For macros that work with [`serde`] this crate also permits redacting of
 //! partial values.  See [redactions in the
@@ -136,6 +140,9 @@
 //!   snapshots
 //! - `no`: does not write to snapshot files at all; just runs tests
 //! - `force`: forcibly updates snapshot files, even if assertions pass
+//! - `forbid`: like `no`, but an assertion without a stored snapshot is an
+//!   immediate error, `INSTA_FORCE_PASS` is ignored and no pending snapshots
+//!   are cleaned up.  Meant for CI runs that must not modify the tree.
 //!
 //! When `new`, `auto` or `unseen` is used, the
 //! [`cargo-insta`](https://crates.io/crates/cargo-insta) command can be used to
@@ -172,6 +179,30 @@
 //! review the proposed changes and update the source files on acceptance
 //! automatically.
 //!
+//! With the `filters` feature an assertion can start with a list of
+//! built-in filters that normalize the value of just this assertion:
+//! `uuid` replaces UUIDs with `[uuid]` and `timestamp` replaces ISO 8601
+//! date times with `[timestamp]`.
+//!
+#![cfg_attr(feature = "filters", doc = " ```no_run")]
+#![cfg_attr(not(feature = "filters"), doc = " ```ignore")]
+//! # use insta::assert_snapshot;
+//! # let event = "";
+//! assert_snapshot!(filters = [uuid], event, @"user [uuid] logged in");
+//! ```
+//!
+//! The same filters can be named in a metadata comment after the inline
+//! snapshot, which `cargo-insta` keeps when it updates the snapshot.  The
+//! comment is read from the source file when the test runs, so it is ignored
+//! if the sources are not available there.
+//!
+#![cfg_attr(feature = "filters", doc = " ```no_run")]
+#![cfg_attr(not(feature = "filters"), doc = " ```ignore")]
+//! # use insta::assert_snapshot;
+//! # let event = "";
+//! assert_snapshot!(event, @"user [uuid] logged in" /* insta: filters=uuid */);
+//! ```
+//!
 //! # Features
 //!
 //! The following features exist:
@@ -185,6 +216,28 @@
 //! * `filters`: enables support for filters
 //! * `glob`: enables support for globbing ([`glob!`])
 //! * `colors`: enables color output (enabled by default)
+//! * `blake3`: hashes the contents of binary and large snapshots with BLAKE3
+//!   instead of FNV-1a (enabled by default)
+//! * `sha256`: hashes the contents of binary and large snapshots with SHA-256
+//!   if `blake3` is disabled
+//! * `petgraph`: enables [`GraphSnapshot::from_petgraph`]
+//! * `macros`: enables table-driven tests with [`cases`]
+//! * `syn`: enables [`assert_parse_snapshot!`] for [`syn`](https://docs.rs/syn) syntax trees
+//! * `html`: enables [`assert_html_snapshot!`] for normalized HTML
+//! * `xml`: enables [`assert_xml_snapshot!`] for canonicalized XML
+//! * `cargo`: enables [`assert_cargo_manifest_snapshot!`] and
+//!   [`assert_cargo_lock_snapshot!`] for generated `Cargo.toml` and `Cargo.lock` files
+//! * `sql`: enables [`assert_sql_snapshot!`] for formatted SQL queries
+//! * `clap`: enables [`assert_clap_help_snapshot!`] for [`clap`](https://docs.rs/clap) command line interfaces
+//! * `protobuf`: enables [`assert_proto_snapshot!`] for [`protobuf`](https://docs.rs/protobuf) messages
+//! * `cbor`: enables [`assert_cbor_snapshot!`] for CBOR in diagnostic notation
+//! * `msgpack`: enables [`assert_msgpack_snapshot!`] for MessagePack in diagnostic notation
+//! * `archive`: enables [`assert_archive_snapshot!`] for the entries of zip and tar archives
+//! * `http`: enables [`assert_http_snapshot!`] for [`http`](https://docs.rs/http) responses
+//! * `tracing`: enables [`capture_tracing`] for [`tracing`](https://docs.rs/tracing) events
+//! * `schemars`: enables [`assert_schema_snapshot!`] for the JSON schemas of types from [`schemars`](https://docs.rs/schemars)
+//! * `graphql`: enables [`assert_graphql_snapshot!`] for normalized GraphQL documents and responses
+//! * `wasm`: enables [`assert_wasm_snapshot!`] for WebAssembly modules disassembled to WAT
 //!
 //! For legacy reasons the `json` and `yaml` features are enabled by default in
 //! limited capacity.  You will receive a deprecation warning if you are not
@@ -222,9 +275,34 @@
 //!   # also set by INSTA_OUTPUT
 //!   output: "diff" | "summary" | "minimal" | "none"
 //!   # also set by INSTA_UPDATE
-//!   update: "auto" | "new" | "always" | "no" | "unseen" | "force"
+//!   update: "auto" | "new" | "always" | "no" | "unseen" | "force" | "forbid"
 //!   # also set by INSTA_GLOB_FAIL_FAST
 //!   glob_fail_fast: true/false
+//!   # bytes of a diff printed per failure on CI, 0 for no limit
+//!   # also set by INSTA_CI_DIFF_BUDGET
+//!   ci_diff_budget: 16384
+//!   # columns of a tab when lining up inline snapshots indented with tabs
+//!   # also set by INSTA_INDENT_WIDTH
+//!   indent_width: 4
+//!   # write pending snapshots below `target/insta-pending` instead of
+//!   # next to the sources; `cargo insta review` moves them into place
+//!   # also set by INSTA_PENDING_IN_TARGET
+//!   pending_in_target: true/false
+//!   # how snapshot names become file names: "legacy" only replaces path
+//!   # separators, "portable" also replaces spaces, Unicode and characters
+//!   # that are not allowed on some platforms and shortens long names
+//!   # also set by INSTA_SANITIZE_NAMES
+//!   sanitize_names: "legacy" | "portable"
+//!   # how snapshot files of a module are laid out: "flat" names files after
+//!   # the full module path, "hashed" stores them in a short directory named
+//!   # after a hash of the module path, which keeps paths of deep module trees
+//!   # short enough for Windows
+//!   # also set by INSTA_SNAPSHOT_LAYOUT
+//!   snapshot_layout: "flat" | "hashed"
+//!   # snapshots whose mismatches print a warning instead of failing, named
+//!   # like their files as `<module>__<name>`, where `*` matches any text
+//!   quarantine:
+//!     - "my_crate__tests__flaky_*"
 //!
 //! # these are used by cargo insta test
 //! test:
@@ -250,6 +328,16 @@
 //!   # show a warning if undiscovered (ignored or hidden) snapshots are found.
 //!   # defaults to true but creates a performance hit.
 //!   warn_undiscovered: true / false
+//!   # put the arguments of a snapshot macro on separate lines if an accepted
+//!   # inline snapshot makes the line longer than rustfmt's `max_width`
+//!   wrap_long_inline_snapshots: true / false
+//!   # questions that have to be answered with "y" before snapshots are
+//!   # accepted whose file (or source file, for inline snapshots) matches
+//!   # the path relative to the workspace root, where `*` matches any text.
+//!   # every question is asked once per review, declining skips the snapshots
+//!   confirm:
+//!     - path: "tests/api/snapshots/*"
+//!       prompt: "Does this API change have a changelog entry?"
 //! ```
 //!
 //! # External Diff Tools
@@ -298,9 +386,15 @@
 
 #[macro_use]
 mod macros;
+mod ansi;
+#[cfg(unix)]
+mod capture;
 pub mod comparator;
 mod content;
+mod content_hash;
 mod env;
+mod graph;
+mod naming;
 #[doc(hidden)]
 pub mod output;
 mod runtime;
@@ -308,23 +402,124 @@ mod runtime;
 mod serialization;
 mod settings;
 mod snapshot;
+mod table;
 mod utils;
 
 #[cfg(feature = "redactions")]
 mod redaction;
 
+#[cfg(feature = "redactions")]
+mod volatile;
+
 #[cfg(feature = "filters")]
 mod filters;
 
+#[cfg(feature = "filters")]
+mod inline_meta;
+
+#[cfg(feature = "json")]
+mod json_schema;
+
 #[cfg(feature = "glob")]
 mod glob;
 
+#[cfg(feature = "syn")]
+mod syntax;
+
+#[cfg(feature = "html")]
+mod html;
+
+#[cfg(feature = "xml")]
+mod xml;
+
+#[cfg(feature = "cargo")]
+mod manifest;
+
+#[cfg(feature = "sql")]
+mod sql;
+
+#[cfg(feature = "protobuf")]
+mod proto;
+
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+mod diag;
+
+#[cfg(feature = "archive")]
+mod archive;
+
+#[cfg(feature = "http")]
+mod http_response;
+
+#[cfg(feature = "tracing")]
+mod tracing_capture;
+
+#[cfg(feature = "schemars")]
+mod type_schema;
+
+#[cfg(feature = "graphql")]
+mod graphql;
+
+#[cfg(feature = "wasm")]
+mod wasm;
+
+mod error_chain;
+
+mod metrics;
+
+#[cfg(feature = "clap")]
+mod cli_help;
+
+#[cfg(feature = "csv")]
+mod csv_table;
+
 #[cfg(test)]
 mod test;
 
-pub use crate::comparator::{Comparator, DefaultComparator};
+pub use crate::ansi::AnsiMode;
+#[cfg(unix)]
+#[cfg_attr(docsrs, doc(cfg(unix)))]
+pub use crate::capture::{capture_output, CapturedOutput};
+pub use crate::comparator::{
+    Comparator, DefaultComparator, FloatToleranceComparator, UnorderedMapComparator,
+};
+pub use crate::graph::{normalize_dot, GraphSnapshot};
+pub use crate::naming::stable_name;
+pub use crate::output::{diff, RenderedDiff};
+pub use crate::runtime::SnapshotOwner;
 pub use crate::settings::Settings;
 pub use crate::snapshot::{MetaData, Snapshot, TextSnapshotKind};
+#[cfg(feature = "tracing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tracing")))]
+pub use crate::tracing_capture::{capture_tracing, CapturedEvent, CapturedEvents};
+
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+pub use crate::runtime::InlineCase;
+
+/// Turns a function into one test per `#[case(...)]` attribute.
+///
+/// **Feature:** `macros` (disabled by default)
+///
+/// Every case names a value for each parameter of the function.  A value
+/// written as `@"..."` is an inline snapshot: its parameter is an
+/// [`InlineCase`] that can be passed to any snapshot assertion in place of the
+/// literal.  Like in the assertion macros it has to be the last argument.
+/// `cargo insta review` updates the literal in the attribute.
+///
+/// ```no_run
+/// #[insta::cases]
+/// #[case(input = "hello", expected = @"HELLO")]
+/// #[case(input = "wörld", expected = @"WÖRLD")]
+/// fn test_uppercase(input: &str, expected: insta::InlineCase) {
+///     insta::assert_snapshot!(input.to_uppercase(), @expected);
+/// }
+/// ```
+///
+/// The cases become tests named `case_1`, `case_2`, ... in a module with the
+/// name of the function.
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+pub use insta_macros::cases;
 
 /// Exposes some library internals.
 ///
@@ -339,6 +534,8 @@ pub mod internals {
     pub use crate::filters::Filters;
     pub use crate::runtime::AutoName;
     pub use crate::settings::SettingsBindDropGuard;
+    #[cfg(feature = "toml")]
+    pub use crate::settings::SettingsError;
     pub use crate::snapshot::{MetaData, SnapshotContents, TextSnapshotContents};
     #[cfg(feature = "redactions")]
     pub use crate::{
@@ -354,10 +551,13 @@ pub mod _cargo_insta_support {
     pub use crate::{
         content::Error as ContentError,
         env::{
-            get_pending_dir, Error as ToolConfigError, OutputBehavior, SnapshotUpdate, TestRunner,
-            ToolConfig, UnreferencedSnapshots,
+            get_pending_dir, matches_pattern, target_pending_dir, Error as ToolConfigError,
+            NameSanitization, OutputBehavior, SnapshotUpdate, TestRunner, ToolConfig,
+            UnreferencedSnapshots,
         },
+        naming::sanitize_snapshot_name,
         output::SnapshotPrinter,
+        runtime::predict_snapshot_file,
         snapshot::PendingInlineSnapshot,
         snapshot::SnapshotContents,
         snapshot::TextSnapshotContents,
@@ -368,28 +568,96 @@ pub mod _cargo_insta_support {
 
 // useful for redactions
 #[cfg(feature = "redactions")]
-pub use crate::redaction::{dynamic_redaction, rounded_redaction, sorted_redaction};
+pub use crate::redaction::{
+    decimal_redaction, dynamic_redaction, numbered_redaction, rounded_redaction,
+    sorted_by_redaction, sorted_redaction,
+};
 
 // these are here to make the macros work
 #[doc(hidden)]
 pub mod _macro_support {
+    pub use crate::ansi::render_ansi;
+    #[cfg(unix)]
+    pub use crate::capture::render_output;
     pub use crate::content::Content;
     pub use crate::env::{get_cargo_workspace, Workspace};
     pub use crate::runtime::{
-        assert_snapshot, with_allow_duplicates, AutoName, BinarySnapshotValue, InlineValue,
-        SnapshotValue,
+        assert_matches_snapshot_of, assert_snapshot, with_allow_duplicates, AutoName,
+        BinarySnapshotValue, InlineValue, IntoBinaryContent, SnapshotText, SnapshotValue,
     };
+    pub use crate::utils::{render_variants, wrap_lines};
     pub use core::{file, line, module_path};
     pub use std::{any, env, format, option_env, path, vec};
 
     #[cfg(feature = "serde")]
-    pub use crate::serialization::{serialize_value, SerializationFormat, SnapshotLocation};
+    pub use crate::serialization::{
+        assert_roundtrip, serialize_value, SerializationFormat, SnapshotLocation,
+    };
+
+    #[cfg(feature = "serde")]
+    pub use crate::table::with_cell_diff;
+
+    #[cfg(feature = "filters")]
+    pub use crate::filters::presets as filter_presets;
 
     #[cfg(feature = "glob")]
     pub use crate::glob::glob_exec;
 
+    #[cfg(feature = "syn")]
+    pub use crate::syntax::parse_debug;
+
+    #[cfg(feature = "html")]
+    pub use crate::html::normalize_html;
+
+    #[cfg(feature = "xml")]
+    pub use crate::xml::{normalize_xml, XmlSelector};
+
+    #[cfg(feature = "json")]
+    pub use crate::json_schema::JsonSchema;
+
+    #[cfg(feature = "cargo")]
+    pub use crate::manifest::{normalize_cargo_lock, normalize_cargo_manifest};
+
+    #[cfg(feature = "sql")]
+    pub use crate::sql::format_sql;
+
+    #[cfg(feature = "protobuf")]
+    pub use crate::proto::render_proto;
+
+    #[cfg(feature = "cbor")]
+    pub use crate::diag::{render_cbor, render_cbor_value};
+
+    #[cfg(feature = "msgpack")]
+    pub use crate::diag::{render_msgpack, render_msgpack_value};
+
+    #[cfg(feature = "archive")]
+    pub use crate::archive::render_archive;
+
+    #[cfg(feature = "http")]
+    pub use crate::http_response::render_http_response;
+
+    #[cfg(feature = "schemars")]
+    pub use crate::type_schema::schema_for;
+
+    #[cfg(feature = "graphql")]
+    pub use crate::graphql::format_graphql;
+
+    #[cfg(feature = "wasm")]
+    pub use crate::wasm::render_wasm;
+
+    pub use crate::error_chain::render_error_chain;
+
+    pub use crate::metrics::normalize_metrics;
+
+    #[cfg(feature = "clap")]
+    pub use crate::cli_help::render_clap_help;
+
+    #[cfg(feature = "csv")]
+    pub use crate::csv_table::normalize_csv;
+
     #[cfg(feature = "redactions")]
     pub use crate::{
         redaction::Redaction, redaction::Selector, serialization::serialize_value_redacted,
+        serialization::serialize_value_selected,
     };
 }
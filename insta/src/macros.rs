@@ -56,12 +56,99 @@ macro_rules! _get_workspace_root {
 /// the guide](https://insta.rs/docs/redactions/).
 ///
 /// The snapshot name is optional but can be provided as first argument.
+///
+/// CSV text, for instance the output of an export, can be asserted with
+/// `text = value`.  The text is parsed and stored as a table with aligned
+/// columns, and when the snapshot changes the diff realigns the old and the
+/// new table so that only the rows with changed cells are shown.  Columns
+/// can be redacted by the name in their header:
+///
+/// ```no_run
+/// # let export = "";
+/// insta::assert_csv_snapshot!(text = export, { "created_at" => "[date]" }, @"
+/// id | name | created_at
+/// 1  | Ada  | [date]
+/// ");
+/// ```
 #[cfg(feature = "csv")]
 #[cfg_attr(docsrs, doc(cfg(feature = "csv")))]
 #[macro_export]
 macro_rules! assert_csv_snapshot {
+    (text = $($arg:tt)*) => {
+        $crate::_with_assertion_context!(_assert_csv_text_snapshot!([]) $($arg)*)
+    };
+    ($name:expr, text = $($arg:tt)*) => {
+        $crate::_with_assertion_context!(_assert_csv_text_snapshot!([$name]) $($arg)*)
+    };
+    ($($arg:tt)*) => {
+        $crate::_with_assertion_context!(_assert_serialized_snapshot!(format=Csv,) $($arg)*);
+    };
+}
+
+// Parses CSV text into a table, with redactions of columns by their header.
+#[cfg(feature = "csv")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _assert_csv_text_snapshot {
+    ([$($name:expr)?] $value:expr, {$($k:expr => $v:expr),* $(,)?} $($arg:tt)*) => {{
+        let redactions = $crate::_macro_support::vec![$(($k, ToString::to_string(&$v)),)*];
+        $crate::_macro_support::with_cell_diff(|| {
+            $crate::_assert_snapshot_base!(
+                transform=|v| $crate::_macro_support::normalize_csv(v, &redactions),
+                $($name,)? $value $($arg)*
+            )
+        })
+    }};
+    ([$($name:expr)?] $($arg:tt)*) => {
+        $crate::_macro_support::with_cell_diff(|| {
+            $crate::_assert_snapshot_base!(
+                transform=|v| $crate::_macro_support::normalize_csv(v, &[]),
+                $($name,)? $($arg)*
+            )
+        })
+    };
+}
+
+/// Asserts a [`serde::Serialize`] snapshot as a text table.
+///
+/// **Feature:** any of `csv`, `json`, `ron`, `toml` or `yaml` (disabled by
+/// default)
+///
+/// The value is usually a sequence of structs, such as a `Vec` or a slice,
+/// and every item becomes a row with a column per field.  Columns are
+/// aligned, strings are written without quotes and nested values as compact
+/// JSON.  To assert an iterator, collect it into a `Vec` first.
+///
+/// ```no_run
+/// #[derive(serde::Serialize)]
+/// struct User {
+///     id: u32,
+///     name: &'static str,
+/// }
+///
+/// let users = vec![User { id: 1, name: "Ada" }, User { id: 42, name: "Grace" }];
+/// insta::assert_table_snapshot!(users, @"
+/// id | name
+/// 1  | Ada
+/// 42 | Grace
+/// ");
+/// ```
+///
+/// Like with [`assert_csv_snapshot!`](crate::assert_csv_snapshot!) text,
+/// the diff of a changed table realigns the old and the new table so that
+/// only the rows with changed cells are shown.  Redactions work like for
+/// [`assert_yaml_snapshot!`](crate::assert_yaml_snapshot!), with selectors
+/// such as `"[].created_at"`.
+///
+/// The snapshot name is optional but can be provided as first argument.
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+#[macro_export]
+macro_rules! assert_table_snapshot {
     ($($arg:tt)*) => {
-        $crate::_assert_serialized_snapshot!(format=Csv, $($arg)*);
+        $crate::_macro_support::with_cell_diff(|| {
+            $crate::_with_assertion_context!(_assert_serialized_snapshot!(format=Table,) $($arg)*)
+        })
     };
 }
 
@@ -91,7 +178,7 @@ macro_rules! assert_csv_snapshot {
 #[macro_export]
 macro_rules! assert_toml_snapshot {
     ($($arg:tt)*) => {
-        $crate::_assert_serialized_snapshot!(format=Toml, $($arg)*);
+        $crate::_with_assertion_context!(_assert_serialized_snapshot!(format=Toml,) $($arg)*);
     };
 }
 
@@ -135,6 +222,19 @@ macro_rules! assert_toml_snapshot {
 ///
 /// The replacement value can be a string, integer or any other primitive value.
 ///
+/// To snapshot only a part of a larger value, pass `select = selector` after
+/// the value.  The selector uses the same syntax as redactions.  A selector of
+/// keys and indexes only snapshots the selected value itself, others snapshot
+/// a sequence of all matched values:
+///
+#[cfg_attr(feature = "redactions", doc = " ```no_run")]
+#[cfg_attr(not(feature = "redactions"), doc = " ```ignore")]
+/// # use insta::*; use serde::Serialize;
+/// # #[derive(Serialize)] struct Value; let response = Value;
+/// assert_yaml_snapshot!(response, select = ".data.items");
+/// assert_yaml_snapshot!(response, select = ".data.items[].id", @"");
+/// ```
+///
 /// For inline usage the format is `(expression, @reference_value)` where the
 /// reference value must be a string literal.  If you make the initial snapshot
 /// just use an empty string (`@""`).
@@ -145,10 +245,85 @@ macro_rules! assert_toml_snapshot {
 #[macro_export]
 macro_rules! assert_yaml_snapshot {
     ($($arg:tt)*) => {
-        $crate::_assert_serialized_snapshot!(format=Yaml, $($arg)*);
+        $crate::_with_assertion_context!(_assert_serialized_snapshot!(format=Yaml,) $($arg)*);
+    };
+}
+
+/// Asserts that a value survives a serde round trip and snapshots it in YAML
+/// format.
+///
+/// **Feature:** `yaml`
+///
+/// The value is serialized, deserialized again and the assertion fails if
+/// the result is not equal to the value or does not serialize the same.
+/// This catches fields that are `#[serde(skip)]`ped or filled in with
+/// defaults on deserialization, which a snapshot of the serialized value
+/// alone does not show.  The value needs to implement
+/// [`serde::de::DeserializeOwned`], [`PartialEq`] and [`Debug`](std::fmt::Debug)
+/// in addition to [`serde::Serialize`].  Otherwise this works like
+/// [`assert_yaml_snapshot!`](crate::assert_yaml_snapshot!):
+///
+/// ```no_run
+/// # use insta::*; use serde::{Deserialize, Serialize};
+/// #[derive(Serialize, Deserialize, PartialEq, Debug)]
+/// struct Config {
+///     name: String,
+///     retries: u32,
+/// }
+///
+/// let config = Config { name: "api".into(), retries: 3 };
+/// assert_roundtrip_snapshot!(config, @r"
+/// name: api
+/// retries: 3
+/// ");
+/// ```
+///
+/// The value is read back from its serialized form directly rather than
+/// from YAML, so only the serde implementations of the type are checked.
+/// Redactions only apply to the snapshot, not to the round trip.
+///
+/// The snapshot name is optional but can be provided as first argument.
+#[cfg(feature = "yaml")]
+#[cfg_attr(docsrs, doc(cfg(feature = "yaml")))]
+#[macro_export]
+macro_rules! assert_roundtrip_snapshot {
+    ($($arg:tt)*) => {
+        $crate::_with_assertion_context!(_assert_roundtrip_snapshot!() $($arg)*);
     };
 }
 
+// Like `_assert_serialized_snapshot` but checks the round trip of the value
+// before it's serialized for the snapshot.
+#[cfg(feature = "yaml")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _assert_roundtrip_snapshot {
+    ($value:expr, $(match ..)? {$($k:expr => $v:expr),* $(,)?} $($arg:tt)*) => {{
+        let transform = |value| {
+            $crate::_macro_support::assert_roundtrip(value);
+            $crate::_prepare_snapshot_for_redaction!(value, {$($k => $v),*}, Yaml)
+        };
+        $crate::_assert_snapshot_base!(transform=transform, $value $($arg)*);
+    }};
+    ($name:expr, $value:expr, $(match ..)? {$($k:expr => $v:expr),* $(,)?} $(,)?) => {{
+        let transform = |value| {
+            $crate::_macro_support::assert_roundtrip(value);
+            $crate::_prepare_snapshot_for_redaction!(value, {$($k => $v),*}, Yaml)
+        };
+        $crate::_assert_snapshot_base!(transform=transform, $name, $value);
+    }};
+    ($($arg:tt)*) => {{
+        let transform = |value| {
+            $crate::_macro_support::assert_roundtrip(value);
+            $crate::_macro_support::serialize_value(
+                &value,
+                $crate::_macro_support::SerializationFormat::Yaml,
+            )
+        };
+        $crate::_assert_snapshot_base!(transform=transform, $($arg)*);
+    }};
+}
+
 /// Asserts a [`serde::Serialize`] snapshot in RON format.
 ///
 /// **Feature:** `ron` (disabled by default)
@@ -175,7 +350,7 @@ macro_rules! assert_yaml_snapshot {
 #[macro_export]
 macro_rules! assert_ron_snapshot {
     ($($arg:tt)*) => {
-        $crate::_assert_serialized_snapshot!(format=Ron, $($arg)*);
+        $crate::_with_assertion_context!(_assert_serialized_snapshot!(format=Ron,) $($arg)*);
     };
 }
 
@@ -200,12 +375,43 @@ macro_rules! assert_ron_snapshot {
 /// the guide](https://insta.rs/docs/redactions/).
 ///
 /// The snapshot name is optional but can be provided as first argument.
+///
+/// With `schema = ...` the serialized value is validated against a
+/// [JSON Schema](https://json-schema.org/) before it is compared, so a
+/// changed snapshot that breaks the contract fails even when it is accepted.
+/// The schema is either a path to a JSON or YAML file, relative to the
+/// directory of the test file, or a value such as a `serde_json::Value`.
+/// Redactions are applied after the validation.  The `$id` or path of the
+/// schema and a hash of it are stored in the `schema` field of the snapshot
+/// metadata.  The schema argument cannot be combined with a snapshot name.
+///
+/// ```no_run
+/// # use insta::*;
+/// # let user = ();
+/// assert_json_snapshot!(user, schema = "schemas/user.json", {
+///     ".created_at" => "[date]",
+/// });
+/// ```
+///
+/// The supported keywords are `type`, `enum`, `const`, `properties`,
+/// `required`, `additionalProperties`, `items`, `minItems`, `maxItems`,
+/// `uniqueItems`, `minLength`, `maxLength`, `minimum`, `maximum`,
+/// `exclusiveMinimum`, `exclusiveMaximum`, `allOf`, `anyOf`, `oneOf`, `not`
+/// and `$ref` to other parts of the same schema.  Other keywords are
+/// ignored.
 #[cfg(feature = "json")]
 #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
 #[macro_export]
 macro_rules! assert_json_snapshot {
+    ($value:expr, schema = $schema:expr $(, $($arg:tt)*)?) => {
+        $crate::_macro_support::JsonSchema::load(
+            &$schema,
+            &$crate::_get_workspace_root!().join($crate::_macro_support::path::Path::new(file!()).parent().unwrap()),
+        )
+        .bind(|| $crate::assert_json_snapshot!($value $(, $($arg)*)?))
+    };
     ($($arg:tt)*) => {
-        $crate::_assert_serialized_snapshot!(format=Json, $($arg)*);
+        $crate::_with_assertion_context!(_assert_serialized_snapshot!(format=Json,) $($arg)*);
     };
 }
 
@@ -236,7 +442,7 @@ macro_rules! assert_json_snapshot {
 #[macro_export]
 macro_rules! assert_compact_json_snapshot {
     ($($arg:tt)*) => {
-        $crate::_assert_serialized_snapshot!(format=JsonCompact, $($arg)*);
+        $crate::_with_assertion_context!(_assert_serialized_snapshot!(format=JsonCompact,) $($arg)*);
     };
 }
 
@@ -252,6 +458,21 @@ macro_rules! _assert_serialized_snapshot {
         };
         $crate::_assert_snapshot_base!(transform=transform, $value $($arg)*);
     }};
+    // If only a part of the value is selected, capture the selector and
+    // redactions and pass to `_assert_snapshot_base`
+    (format=$format:ident, $value:expr, select = $selector:expr $(, $(match ..)? {$($k:expr => $v:expr),* $(,)?})? $(, @$snapshot:literal)? $(,)?) => {{
+        let transform = |value| {
+            $crate::_prepare_snapshot_for_selection!(value, $selector, {$($($k => $v),*)?}, $format)
+        };
+        $crate::_assert_snapshot_base!(transform=transform, $value $(, @$snapshot)?);
+    }};
+    // If there's a name and a selector, capture and pass to `_assert_snapshot_base`
+    (format=$format:ident, $name:expr, $value:expr, select = $selector:expr $(, $(match ..)? {$($k:expr => $v:expr),* $(,)?})? $(,)?) => {{
+        let transform = |value| {
+            $crate::_prepare_snapshot_for_selection!(value, $selector, {$($($k => $v),*)?}, $format)
+        };
+        $crate::_assert_snapshot_base!(transform=transform, $name, $value);
+    }};
     // If there's a name, redaction expressions, and debug_expr, capture and pass all to `_assert_snapshot_base`
     (format=$format:ident, $name:expr, $value:expr, $(match ..)? {$($k:expr => $v:expr),* $(,)?}, $debug_expr:expr $(,)?) => {{
         let transform = |value| {
@@ -309,6 +530,90 @@ macro_rules! _prepare_snapshot_for_redaction {
     };
 }
 
+#[cfg(feature = "redactions")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _prepare_snapshot_for_selection {
+    ($value:expr, $selector:expr, {$($k:expr => $v:expr),*}, $format:ident) => {
+        {
+            let vec = $crate::_macro_support::vec![
+                $((
+                    $crate::_macro_support::Selector::parse($k).unwrap(),
+                    $crate::_macro_support::Redaction::from($v)
+                ),)*
+            ];
+            $crate::_macro_support::serialize_value_selected(
+                &$value,
+                $selector,
+                &vec,
+                $crate::_macro_support::SerializationFormat::$format,
+            )
+        }
+    }
+}
+
+#[cfg(not(feature = "redactions"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _prepare_snapshot_for_selection {
+    ($value:expr, $selector:expr, {$($k:expr => $v:expr),*}, $format:ident) => {
+        compile_error!(
+            "insta was compiled without redactions support. Enable the `redactions` feature."
+        )
+    };
+}
+
+/// Asserts that two [`serde::Serialize`] values produce the same snapshot.
+///
+/// **Feature:** `yaml`
+///
+/// Both values are serialized to YAML like in
+/// [`assert_yaml_snapshot!`](crate::assert_yaml_snapshot!), with the
+/// redactions, filters and other settings applied, and the results are
+/// compared with each other.  No snapshot is stored, and if the values
+/// differ the assertion fails with the same diff that failed snapshot
+/// assertions show.  This is useful for migration tests that compare the
+/// output of an old and a new code path:
+///
+#[cfg_attr(feature = "redactions", doc = " ```no_run")]
+#[cfg_attr(not(feature = "redactions"), doc = " ```ignore")]
+/// # use insta::*; use serde::Serialize;
+/// # #[derive(Serialize)] struct User; fn old_load() -> User { User } fn new_load() -> User { User }
+/// assert_matches_snapshot_of!(old_load(), new_load(), {
+///     ".id" => "[id]",
+/// });
+/// ```
+///
+/// The third argument is optional and can be an object expression for
+/// redactions, which are applied to both values.
+#[cfg(feature = "yaml")]
+#[cfg_attr(docsrs, doc(cfg(feature = "yaml")))]
+#[macro_export]
+macro_rules! assert_matches_snapshot_of {
+    ($left:expr, $right:expr, $(match ..)? {$($k:expr => $v:expr),* $(,)?} $(,)?) => {
+        $crate::_macro_support::assert_matches_snapshot_of(
+            &$crate::_prepare_snapshot_for_redaction!($left, {$($k => $v),*}, Yaml),
+            &$crate::_prepare_snapshot_for_redaction!($right, {$($k => $v),*}, Yaml),
+            stringify!($left),
+            stringify!($right),
+        )
+    };
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::_macro_support::assert_matches_snapshot_of(
+            &$crate::_macro_support::serialize_value(
+                &$left,
+                $crate::_macro_support::SerializationFormat::Yaml,
+            ),
+            &$crate::_macro_support::serialize_value(
+                &$right,
+                $crate::_macro_support::SerializationFormat::Yaml,
+            ),
+            stringify!($left),
+            stringify!($right),
+        )
+    };
+}
+
 /// Asserts a [`Debug`] snapshot.
 ///
 /// The value needs to implement the [`Debug`] trait.  This is useful for
@@ -319,7 +624,9 @@ macro_rules! _prepare_snapshot_for_redaction {
 #[macro_export]
 macro_rules! assert_debug_snapshot {
     ($($arg:tt)*) => {
-        $crate::_assert_snapshot_base!(transform=|v| $crate::_macro_support::format!("{:#?}", v), $($arg)*)
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::format!("{:#?}", v),) $($arg)*
+        )
     };
 }
 
@@ -333,142 +640,1224 @@ macro_rules! assert_debug_snapshot {
 #[macro_export]
 macro_rules! assert_compact_debug_snapshot {
     ($($arg:tt)*) => {
-        $crate::_assert_snapshot_base!(transform=|v| $crate::_macro_support::format!("{:?}", v), $($arg)*)
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::format!("{:?}", v),) $($arg)*
+        )
     };
 }
 
-// A helper macro which takes a closure as `transform`, and runs the closure on
-// the value. This allows us to implement other macros with a small wrapper. All
-// snapshot macros eventually call this macro.
-//
-// This macro handles optional trailing commas.
-#[doc(hidden)]
+/// Asserts a [`Debug`] snapshot of floating point values with a tolerance.
+///
+/// This works like [`assert_debug_snapshot!`](crate::assert_debug_snapshot!)
+/// but the numbers in the snapshot only have to match the reference within
+/// the given tolerance, as described for
+/// [`FloatToleranceComparator`](crate::FloatToleranceComparator).  The
+/// snapshot keeps the reference value, so results that differ in the last
+/// digits between platforms do not cause changes.
+///
+/// ```no_run
+/// insta::assert_float_snapshot!(0.1 + 0.2, tolerance = 1e-9, @"0.3");
+/// ```
+///
+/// The snapshot name is optional but can be provided as first argument.
+/// For serialized snapshots, use
+/// [`Settings::set_float_tolerance`](crate::Settings::set_float_tolerance)
+/// or `float_tolerance` in [`with_settings!`](crate::with_settings!).
 #[macro_export]
-macro_rules! _assert_snapshot_base {
-    // If there's an inline literal value, wrap the literal in a
-    // `ReferenceValue::Inline`, call self.
-    (transform=$transform:expr, $($arg:expr),*, @$snapshot:literal $(,)?) => {
-        $crate::_assert_snapshot_base!(
-            transform = $transform,
-            #[allow(clippy::needless_raw_string_hashes)]
-            $crate::_macro_support::InlineValue($snapshot),
-            $($arg),*
-        )
-    };
-    // If there's no debug_expr, use the stringified value, call self.
-    (transform=$transform:expr, $name:expr, $value:expr $(,)?) => {
-        $crate::_assert_snapshot_base!(transform = $transform, $name, $value, stringify!($value))
-    };
-    // If there's no name (and necessarily no debug expr), auto generate the
-    // name, call self.
-    (transform=$transform:expr, $value:expr $(,)?) => {
-        $crate::_assert_snapshot_base!(
-            transform = $transform,
-            $crate::_macro_support::AutoName,
-            $value
-        )
-    };
-    // The main macro body — every call to this macro should end up here.
-    (transform=$transform:expr, $name:expr, $value:expr, $debug_expr:expr $(,)?) => {
-        $crate::_macro_support::assert_snapshot(
-            (
-                $name,
-                #[allow(clippy::redundant_closure_call)]
-                $transform(&$value).as_str(),
-            ).into(),
-            $crate::_get_workspace_root!().as_path(),
-            $crate::_function_name!(),
-            $crate::_macro_support::module_path!(),
-            $crate::_macro_support::file!(),
-            $crate::_macro_support::line!(),
-            $debug_expr,
-        )
-        .unwrap()
-    };
+macro_rules! assert_float_snapshot {
+    ($value:expr, tolerance = $tolerance:expr $(, $($arg:tt)*)?) => {{
+        let mut settings = $crate::Settings::clone_current();
+        settings.set_float_tolerance($tolerance);
+        settings.bind(|| $crate::assert_debug_snapshot!($value $(, $($arg)*)?))
+    }};
+    ($name:expr, $value:expr, tolerance = $tolerance:expr $(, $($arg:tt)*)?) => {{
+        let mut settings = $crate::Settings::clone_current();
+        settings.set_float_tolerance($tolerance);
+        settings.bind(|| $crate::assert_debug_snapshot!($name, $value $(, $($arg)*)?))
+    }};
 }
 
-/// (Experimental)
-/// Asserts a binary snapshot in the form of a [`Vec<u8>`].
+/// Asserts a snapshot of an error and the chain of its sources.
 ///
-/// The contents get stored in a separate file next to the metadata file. The extension for this
-/// file must be passed as part of the name. For an implicit snapshot name just an extension can be
-/// passed starting with a `.`.
+/// The value is an error that implements [`std::error::Error`].  The
+/// snapshot holds its message followed by one line for every error returned
+/// by [`Error::source`](std::error::Error::source), so that the structure
+/// of wrapped errors is kept without the volatile details of their [`Debug`]
+/// representation:
 ///
-/// This feature is considered experimental: we may make incompatible changes for the next couple
-/// of versions after 1.41.
+/// ```no_run
+/// # let err = std::io::Error::new(std::io::ErrorKind::Other, "timed out");
+/// insta::assert_error_snapshot!(err, @r"
+/// failed to load config
+/// caused by: timed out
+/// ");
+/// ```
 ///
-/// Examples:
+/// Errors that only dereference to [`std::error::Error`], such as
+/// `anyhow::Error` and `Box<dyn Error>`, are passed with `err.as_ref()`.
 ///
-/// ```no_run
-/// // implicit name:
-/// insta::assert_binary_snapshot!(".txt", b"abcd".to_vec());
+/// A backtrace, or anything else that displays like
+/// [`std::backtrace::Backtrace`], can be added with `backtrace = value`.
+/// Its frames are written without line numbers, with paths relative to the
+/// workspace and other paths replaced by `[path]`, and frames of the standard
+/// library and of error handling libraries are left out:
 ///
-/// // named:
-/// insta::assert_binary_snapshot!("my_snapshot.bin", [0, 1, 2, 3].to_vec());
+/// ```no_run
+/// # let err = std::io::Error::new(std::io::ErrorKind::Other, "failed");
+/// let backtrace = std::backtrace::Backtrace::capture();
+/// insta::assert_error_snapshot!(err, backtrace = backtrace);
 /// ```
+///
+/// With `anyhow`, this is `backtrace = err.backtrace()`.
+///
+/// The snapshot name is optional but can be provided as first argument.
 #[macro_export]
-macro_rules! assert_binary_snapshot {
-    ($name_and_extension:expr, $value:expr $(,)?) => {
-        $crate::assert_binary_snapshot!($name_and_extension, $value, stringify!($value));
+macro_rules! assert_error_snapshot {
+    ($value:expr, backtrace = $backtrace:expr $(, $($arg:tt)*)?) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::render_error_chain(
+                v,
+                Some(ToString::to_string(&$backtrace)),
+                $crate::_get_workspace_root!().as_path(),
+            ),) $value $(, $($arg)*)?
+        )
     };
-
-    ($name_and_extension:expr, $value:expr, $debug_expr:expr $(,)?) => {
-        $crate::_macro_support::assert_snapshot(
-            $crate::_macro_support::BinarySnapshotValue {
-                name_and_extension: $name_and_extension,
-                content: $value,
-            }
-            .into(),
-            $crate::_get_workspace_root!().as_path(),
-            $crate::_function_name!(),
-            $crate::_macro_support::module_path!(),
-            $crate::_macro_support::file!(),
-            $crate::_macro_support::line!(),
-            $debug_expr,
+    ($name:expr, $value:expr, backtrace = $backtrace:expr $(, $($arg:tt)*)?) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::render_error_chain(
+                v,
+                Some(ToString::to_string(&$backtrace)),
+                $crate::_get_workspace_root!().as_path(),
+            ), $name,) $value $(, $($arg)*)?
         )
-        .unwrap()
     };
-}
-
-/// Asserts a [`Display`](std::fmt::Display) snapshot.
-///
-/// This is now deprecated, replaced by the more generic [`assert_snapshot!`](crate::assert_snapshot!)
-#[macro_export]
-#[deprecated = "use assert_snapshot!() instead"]
-macro_rules! assert_display_snapshot {
     ($($arg:tt)*) => {
-        $crate::assert_snapshot!($($arg)*)
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::render_error_chain(
+                v,
+                None,
+                $crate::_get_workspace_root!().as_path(),
+            ),) $($arg)*
+        )
     };
 }
 
-/// Asserts a [`String`] snapshot.
+/// Asserts a snapshot of metrics in the Prometheus text exposition format.
 ///
-/// This is the simplest of all assertion methods.
-/// It accepts any value that implements [`Display`](std::fmt::Display).
+/// The metrics, for instance the body of a `/metrics` endpoint, are
+/// normalized so that the snapshot does not depend on the order in which
+/// they were registered: families are sorted by name, samples by their
+/// labels and labels by their name.  Timestamps and comments other than
+/// `HELP` and `TYPE` are left out.
+///
+/// Values that change from run to run, such as durations, can be redacted
+/// by the name of the metric.  The name can contain `*` and a histogram or
+/// summary is also matched by the name of its family:
 ///
 /// ```no_run
-/// # use insta::*;
-/// // implicitly named
-/// assert_snapshot!("reference value to snapshot");
-/// // named
-/// assert_snapshot!("snapshot_name", "reference value to snapshot");
-/// // inline
-/// assert_snapshot!("reference value", @"reference value");
+/// # let metrics = "";
+/// insta::assert_metrics_snapshot!(metrics, { "*_seconds" => "[duration]" }, @r#"
+/// http_request_duration_seconds_bucket{le="0.1"} [duration]
+/// http_request_duration_seconds_bucket{le="+Inf"} [duration]
+/// http_request_duration_seconds_count [duration]
+/// http_request_duration_seconds_sum [duration]
+/// "#);
 /// ```
 ///
-/// Optionally a third argument can be given as an expression to be stringified
-/// as the debug expression.  For more information on this, check out
-/// <https://insta.rs/docs/snapshot-types/>.
+/// The snapshot name is optional but can be provided as first argument.
 #[macro_export]
-macro_rules! assert_snapshot {
+macro_rules! assert_metrics_snapshot {
     ($($arg:tt)*) => {
-        $crate::_assert_snapshot_base!(transform=|v| $crate::_macro_support::format!("{}", v), $($arg)*)
+        $crate::_with_assertion_context!(_assert_metrics_snapshot!() $($arg)*)
     };
 }
 
-/// Settings configuration macro.
-///
-/// This macro lets you bind some [`Settings`](crate::Settings) temporarily.  The first argument
+// Redactions are matched against metric names rather than selectors, so
+// they are taken here instead of by `_assert_snapshot_base`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _assert_metrics_snapshot {
+    (@base [$($name:expr)?] $value:expr, {$($k:expr => $v:expr),*} $($arg:tt)*) => {{
+        let redactions = $crate::_macro_support::vec![$(($k, ToString::to_string(&$v)),)*];
+        $crate::_assert_snapshot_base!(
+            transform=|v| $crate::_macro_support::normalize_metrics(v, &redactions),
+            $($name,)? $value $($arg)*
+        )
+    }};
+    ($value:expr, {$($k:expr => $v:expr),* $(,)?} $($arg:tt)*) => {
+        $crate::_assert_metrics_snapshot!(@base [] $value, {$($k => $v),*} $($arg)*)
+    };
+    ($name:expr, $value:expr, {$($k:expr => $v:expr),* $(,)?} $($arg:tt)*) => {
+        $crate::_assert_metrics_snapshot!(@base [$name] $value, {$($k => $v),*} $($arg)*)
+    };
+    ($($arg:tt)*) => {
+        $crate::_assert_snapshot_base!(
+            transform=|v| $crate::_macro_support::normalize_metrics(v, &[]),
+            $($arg)*
+        )
+    };
+}
+
+/// Asserts a snapshot of a [`syn`](https://docs.rs/syn) syntax tree.
+///
+/// **Feature:** `syn` (disabled by default)
+///
+/// The first argument is the syntax tree type to parse into, for instance
+/// `syn::ItemFn` or `syn::Expr`.  The value can be a string or anything else
+/// that displays as Rust tokens, such as a `proc_macro2::TokenStream` built
+/// with `quote!`.  The value is parsed into the given type and its [`Debug`]
+/// representation is snapshotted with `"{:#?}"`.  The assertion panics if the
+/// value does not parse.
+///
+/// ```no_run
+/// insta::assert_parse_snapshot!(syn::Expr, "a + b * 2");
+/// ```
+///
+/// The snapshot name is optional but can be provided as second argument.
+#[cfg(feature = "syn")]
+#[cfg_attr(docsrs, doc(cfg(feature = "syn")))]
+#[macro_export]
+macro_rules! assert_parse_snapshot {
+    ($ty:ty, $($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::parse_debug::<$ty, _>(v),) $($arg)*
+        )
+    };
+}
+
+/// Asserts a snapshot of HTML in a normalized form.
+///
+/// **Feature:** `html` (disabled by default)
+///
+/// The value can be a string or anything else that displays as HTML.  It is
+/// parsed with [html5ever](https://docs.rs/html5ever) and pretty printed with
+/// one element per line, attributes sorted by name and insignificant
+/// whitespace removed, so that reordered attributes or changed indentation
+/// in rendered templates do not change the snapshot.  Text within `<pre>`,
+/// `<textarea>`, `<script>` and `<style>` is kept as it is.
+///
+/// ```no_run
+/// insta::assert_html_snapshot!(r#"<a title="home" href="/">Home</a>"#, @r#"<a href="/" title="home">Home</a>"#);
+/// ```
+///
+/// The snapshot name is optional but can be provided as first argument.
+#[cfg(feature = "html")]
+#[cfg_attr(docsrs, doc(cfg(feature = "html")))]
+#[macro_export]
+macro_rules! assert_html_snapshot {
+    ($($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::normalize_html(v),) $($arg)*
+        )
+    };
+}
+
+/// Asserts a snapshot of XML in a canonical form.
+///
+/// **Feature:** `xml` (disabled by default)
+///
+/// The value can be a string or anything else that displays as XML.  It is
+/// parsed with [roxmltree](https://docs.rs/roxmltree) and pretty printed with
+/// one element per line, attributes sorted by name, comments and
+/// insignificant whitespace removed and all namespace declarations moved to
+/// the root element with one prefix per namespace.  Elements with
+/// `xml:space="preserve"` are kept as they are.  The assertion panics if the
+/// value is not well-formed XML.
+///
+/// ```no_run
+/// insta::assert_xml_snapshot!(r#"<a  y="2" x="1"> text </a>"#, @r#"<a x="1" y="2">text</a>"#);
+/// ```
+///
+/// The second argument to the macro can be an object expression for
+/// redaction in the form `{ selector => replacement }`.  Selectors are a
+/// subset of [XPath](https://www.w3.org/TR/xpath/): `/` selects children,
+/// `//` descendants at any depth, `*` any element and a last step of `@name`
+/// or `@*` attributes.  The content of selected elements and the value of
+/// selected attributes is replaced:
+///
+/// ```no_run
+/// # let response = "";
+/// insta::assert_xml_snapshot!(response, {
+///     "/Envelope/Header/Security" => "[credentials]",
+///     "//@timestamp" => "[timestamp]",
+/// });
+/// ```
+///
+/// The snapshot name is optional but can be provided as first argument.
+#[cfg(feature = "xml")]
+#[cfg_attr(docsrs, doc(cfg(feature = "xml")))]
+#[macro_export]
+macro_rules! assert_xml_snapshot {
+    ($($arg:tt)*) => {
+        $crate::_with_assertion_context!(_assert_xml_snapshot!() $($arg)*)
+    };
+}
+
+// Like `_assert_serialized_snapshot` but with XML selectors for redactions.
+#[cfg(feature = "xml")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _assert_xml_snapshot {
+    ($value:expr, $(match ..)? {$($k:expr => $v:expr),* $(,)?} $($arg:tt)*) => {{
+        let redactions = $crate::_macro_support::vec![
+            $(($crate::_macro_support::XmlSelector::parse($k), ToString::to_string(&$v)),)*
+        ];
+        $crate::_assert_snapshot_base!(
+            transform=|v| $crate::_macro_support::normalize_xml(v, &redactions),
+            $value $($arg)*
+        );
+    }};
+    ($name:expr, $value:expr, $(match ..)? {$($k:expr => $v:expr),* $(,)?} $($arg:tt)*) => {{
+        let redactions = $crate::_macro_support::vec![
+            $(($crate::_macro_support::XmlSelector::parse($k), ToString::to_string(&$v)),)*
+        ];
+        $crate::_assert_snapshot_base!(
+            transform=|v| $crate::_macro_support::normalize_xml(v, &redactions),
+            $name, $value $($arg)*
+        );
+    }};
+    ($($arg:tt)*) => {
+        $crate::_assert_snapshot_base!(
+            transform=|v| $crate::_macro_support::normalize_xml(v, &[]),
+            $($arg)*
+        )
+    };
+}
+
+/// Asserts a snapshot of a `Cargo.toml` in a normalized form.
+///
+/// **Feature:** `cargo` (disabled by default)
+///
+/// This is meant for crates that generate manifests, such as scaffolding
+/// tools and cargo plugins.  The value can be a string or anything else that
+/// displays as TOML.  Keys and tables are sorted by name, except that
+/// `[package]` and `[workspace]` come first, and comments and formatting are
+/// removed, so that the snapshot does not depend on the order in which the
+/// manifest was written.  The assertion panics if the value is not valid TOML.
+///
+/// ```no_run
+/// insta::assert_cargo_manifest_snapshot!("[dependencies]\nb = '1'\na = '2'", @r#"
+/// [dependencies]
+/// a = '2'
+/// b = '1'
+/// "#);
+/// ```
+///
+/// The snapshot name is optional but can be provided as first argument.
+#[cfg(feature = "cargo")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cargo")))]
+#[macro_export]
+macro_rules! assert_cargo_manifest_snapshot {
+    ($($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::normalize_cargo_manifest(v),) $($arg)*
+        )
+    };
+}
+
+/// Asserts a snapshot of a `Cargo.lock` in a normalized form.
+///
+/// **Feature:** `cargo` (disabled by default)
+///
+/// This works like [`assert_cargo_manifest_snapshot!`](crate::assert_cargo_manifest_snapshot!)
+/// but for lockfiles.  Packages and their dependencies are sorted, and
+/// checksums and the commit hashes of git sources are removed, so that the
+/// snapshot only changes when the resolved packages change.
+///
+/// ```no_run
+/// # let lockfile = "";
+/// insta::assert_cargo_lock_snapshot!(lockfile);
+/// ```
+///
+/// The snapshot name is optional but can be provided as first argument.
+#[cfg(feature = "cargo")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cargo")))]
+#[macro_export]
+macro_rules! assert_cargo_lock_snapshot {
+    ($($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::normalize_cargo_lock(v),) $($arg)*
+        )
+    };
+}
+
+/// Asserts a snapshot of a formatted SQL query.
+///
+/// **Feature:** `sql` (disabled by default)
+///
+/// The value can be a string or anything else that displays as SQL, such as
+/// the output of a query builder.  It is formatted with
+/// [sqlformat](https://docs.rs/sqlformat) with one clause per line and
+/// keywords in upper case, so that the snapshot is readable and does not
+/// depend on how the query was laid out.
+///
+/// ```no_run
+/// insta::assert_sql_snapshot!("select id from users where id = $1", @r"
+/// SELECT
+///   id
+/// FROM
+///   users
+/// WHERE
+///   id = $1
+/// ");
+/// ```
+///
+/// With a leading `normalize_params = true` argument, the placeholders `?`,
+/// `?N` and `$N` are renumbered as `$1`, `$2`, … in the order they appear,
+/// which keeps snapshots stable when the generated parameter numbers shift:
+///
+/// ```no_run
+/// # let query = "";
+/// insta::assert_sql_snapshot!(normalize_params = true, query);
+/// ```
+///
+/// The snapshot name is optional but can be provided as first argument.
+#[cfg(feature = "sql")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sql")))]
+#[macro_export]
+macro_rules! assert_sql_snapshot {
+    (normalize_params = $normalize:expr, $($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::format_sql(v, $normalize),) $($arg)*
+        )
+    };
+    ($($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::format_sql(v, false),) $($arg)*
+        )
+    };
+}
+
+/// Asserts a snapshot of a protobuf message in text format.
+///
+/// **Feature:** `protobuf` (disabled by default)
+///
+/// The value is a message generated by or loaded into
+/// [protobuf](https://docs.rs/protobuf).  It is written in the protobuf text
+/// format with fields in the order of their numbers and map entries in the
+/// order of their keys, so that encoded messages can be reviewed instead of
+/// their bytes.
+///
+/// ```no_run
+/// # let request = protobuf::well_known_types::empty::Empty::new();
+/// insta::assert_proto_snapshot!(request);
+/// ```
+///
+/// The second argument to the macro can be an object expression for
+/// redaction in the form `{ path => replacement }`.  Paths are field names
+/// separated by dots and cover all elements of repeated fields:
+///
+/// ```no_run
+/// # let response = protobuf::well_known_types::empty::Empty::new();
+/// insta::assert_proto_snapshot!(response, {
+///     "session.token" => "[token]",
+///     "users.created_at" => "[timestamp]",
+/// });
+/// ```
+///
+/// The snapshot name is optional but can be provided as first argument.
+#[cfg(feature = "protobuf")]
+#[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+#[macro_export]
+macro_rules! assert_proto_snapshot {
+    ($($arg:tt)*) => {
+        $crate::_with_assertion_context!(_assert_proto_snapshot!() $($arg)*)
+    };
+}
+
+// Like `_assert_serialized_snapshot` but with field paths for redactions.
+#[cfg(feature = "protobuf")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _assert_proto_snapshot {
+    ($value:expr, $(match ..)? {$($k:expr => $v:expr),* $(,)?} $($arg:tt)*) => {{
+        let redactions = $crate::_macro_support::vec![$(($k, ToString::to_string(&$v)),)*];
+        $crate::_assert_snapshot_base!(
+            transform=|v| $crate::_macro_support::render_proto(v, &redactions),
+            $value $($arg)*
+        );
+    }};
+    ($name:expr, $value:expr, $(match ..)? {$($k:expr => $v:expr),* $(,)?} $($arg:tt)*) => {{
+        let redactions = $crate::_macro_support::vec![$(($k, ToString::to_string(&$v)),)*];
+        $crate::_assert_snapshot_base!(
+            transform=|v| $crate::_macro_support::render_proto(v, &redactions),
+            $name, $value $($arg)*
+        );
+    }};
+    ($($arg:tt)*) => {
+        $crate::_assert_snapshot_base!(
+            transform=|v| $crate::_macro_support::render_proto(v, &[]),
+            $($arg)*
+        )
+    };
+}
+
+/// Asserts a snapshot of CBOR in diagnostic notation.
+///
+/// **Feature:** `cbor` (disabled by default)
+///
+/// The value is CBOR encoded data, such as a `Vec<u8>` or a `&[u8]`.  It is
+/// decoded and written in the diagnostic notation of
+/// [RFC 8949](https://www.rfc-editor.org/rfc/rfc8949#section-8), with one
+/// element of arrays and maps per line, so that changes of the encoded data
+/// can be reviewed in a diff.  The assertion panics if the value is not
+/// valid CBOR.
+///
+/// ```no_run
+/// insta::assert_cbor_snapshot!(b"\xa2\x61a\x01\x61b\x42\x01\x02", @r#"
+/// {
+///   "a": 1,
+///   "b": h'0102'
+/// }
+/// "#);
+/// ```
+///
+/// A [`serde::Serialize`] value can be asserted with `value = value`.  It is
+/// encoded with [ciborium](https://docs.rs/ciborium) first, so the snapshot
+/// shows the data a serde based encoder puts on the wire:
+///
+/// ```no_run
+/// # let message = ();
+/// insta::assert_cbor_snapshot!(value = message);
+/// ```
+///
+/// The snapshot name is optional but can be provided as first argument.
+#[cfg(feature = "cbor")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+#[macro_export]
+macro_rules! assert_cbor_snapshot {
+    (value = $($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::render_cbor_value(v),) $($arg)*
+        )
+    };
+    ($name:expr, value = $($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::render_cbor_value(v), $name,) $($arg)*
+        )
+    };
+    ($($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::render_cbor(v),) $($arg)*
+        )
+    };
+}
+
+/// Asserts a snapshot of MessagePack in diagnostic notation.
+///
+/// **Feature:** `msgpack` (disabled by default)
+///
+/// This works like [`assert_cbor_snapshot!`](crate::assert_cbor_snapshot!)
+/// but decodes [MessagePack](https://msgpack.org).  Binary data is written as
+/// `h'…'` and extension types as `ext(type, h'…')`.
+///
+/// ```no_run
+/// insta::assert_msgpack_snapshot!(b"\x92\x01\xa1a", @r#"
+/// [
+///   1,
+///   "a"
+/// ]
+/// "#);
+/// ```
+///
+/// With `value = value`, a [`serde::Serialize`] value is encoded with
+/// [rmp-serde](https://docs.rs/rmp-serde) first.  Structs are encoded as maps
+/// of their field names like with `rmp_serde::to_vec_named`; to assert data
+/// encoded differently, pass the bytes instead.
+///
+/// The snapshot name is optional but can be provided as first argument.
+#[cfg(feature = "msgpack")]
+#[cfg_attr(docsrs, doc(cfg(feature = "msgpack")))]
+#[macro_export]
+macro_rules! assert_msgpack_snapshot {
+    (value = $($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::render_msgpack_value(v),) $($arg)*
+        )
+    };
+    ($name:expr, value = $($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::render_msgpack_value(v), $name,) $($arg)*
+        )
+    };
+    ($($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::render_msgpack(v),) $($arg)*
+        )
+    };
+}
+
+/// Asserts a snapshot of the entries of a zip or tar archive.
+///
+/// **Feature:** `archive` (disabled by default)
+///
+/// The value is the archive, such as a `Vec<u8>` or a `&[u8]`.  Zip, tar and
+/// gzip compressed tar archives are supported and detected from their first
+/// bytes.  The snapshot lists the entries sorted by path with their
+/// permissions.  Text files of up to 4 KiB are followed by their contents
+/// and other files show their size and SHA-256 hash:
+///
+/// ```no_run
+/// # fn build_package() -> std::path::PathBuf { unimplemented!() }
+/// let archive = std::fs::read(build_package()).unwrap();
+/// insta::assert_archive_snapshot!(archive, @r"
+/// pkg/
+/// pkg/README (12 bytes, mode 644)
+///   Hello World
+/// pkg/bin/tool (5120 bytes, mode 755, sha256 9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08)
+/// pkg/current -> bin/tool
+/// ");
+/// ```
+///
+/// Modification times and the user and group that own the entries are left
+/// out, so the snapshot does not change when the archive is built at another
+/// time or by another user.  The assertion panics if the value is not a
+/// valid archive.
+///
+/// The snapshot name is optional but can be provided as first argument.
+#[cfg(feature = "archive")]
+#[cfg_attr(docsrs, doc(cfg(feature = "archive")))]
+#[macro_export]
+macro_rules! assert_archive_snapshot {
+    ($($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::render_archive(v),) $($arg)*
+        )
+    };
+}
+
+/// Asserts a snapshot of a normalized GraphQL document or response.
+///
+/// **Feature:** `graphql` (disabled by default)
+///
+/// The value can be a string or anything else that displays as GraphQL, such
+/// as an operation emitted by a code generator.  It is parsed with
+/// [graphql-parser](https://docs.rs/graphql-parser) and pretty-printed with
+/// the fields of selection sets and the arguments of fields sorted, so that
+/// the snapshot does not depend on the order or layout the operation was
+/// written in:
+///
+/// ```no_run
+/// insta::assert_graphql_snapshot!("{ user(id: 1) { name id } }", @r"
+/// {
+///   user(id: 1) {
+///     id
+///     name
+///   }
+/// }
+/// ");
+/// ```
+///
+/// Values that are JSON instead, such as the response to a request, are
+/// pretty-printed with the keys of objects sorted.  The assertion panics if
+/// the value is neither GraphQL nor JSON.
+///
+/// The snapshot name is optional but can be provided as first argument.
+#[cfg(feature = "graphql")]
+#[cfg_attr(docsrs, doc(cfg(feature = "graphql")))]
+#[macro_export]
+macro_rules! assert_graphql_snapshot {
+    ($($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::format_graphql(v),) $($arg)*
+        )
+    };
+}
+
+/// Asserts a snapshot of a WebAssembly module disassembled to WAT.
+///
+/// **Feature:** `wasm` (disabled by default)
+///
+/// The value is the binary module, such as a `Vec<u8>` or a `&[u8]`.  It is
+/// validated with [wasmparser](https://docs.rs/wasmparser) and printed in the
+/// WebAssembly text format with [wasmprinter](https://docs.rs/wasmprinter).
+/// Function names that are mangled Rust symbols are demangled without their
+/// hash, so the snapshot reads like the source and stays stable across
+/// rebuilds:
+///
+/// ```no_run
+/// # fn compile() -> Vec<u8> { unimplemented!() }
+/// insta::assert_wasm_snapshot!(compile(), @r#"
+/// (module
+///   (type (;0;) (func (param i32 i32) (result i32)))
+///   (func $mylib::add (;0;) (type 0) (param i32 i32) (result i32)
+///     local.get 0
+///     local.get 1
+///     i32.add
+///   )
+///   (export "add" (func $mylib::add))
+/// )
+/// "#);
+/// ```
+///
+/// The assertion panics if the value is not a valid WebAssembly module.
+///
+/// The snapshot name is optional but can be provided as first argument.
+#[cfg(feature = "wasm")]
+#[cfg_attr(docsrs, doc(cfg(feature = "wasm")))]
+#[macro_export]
+macro_rules! assert_wasm_snapshot {
+    ($($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::render_wasm(v),) $($arg)*
+        )
+    };
+}
+
+/// Asserts a snapshot of an [`http::Response`](https://docs.rs/http).
+///
+/// **Feature:** `http` (disabled by default)
+///
+/// The value is a response whose body can be read as bytes, such as a
+/// `Response<String>`, `Response<Vec<u8>>` or `Response<Bytes>`.  The
+/// snapshot has the status line, the headers sorted by name and the body.
+/// JSON bodies are pretty printed, other text is kept as it is and binary
+/// bodies are replaced with their size:
+///
+/// ```no_run
+/// # fn get(_: &str) -> http::Response<Vec<u8>> { unimplemented!() }
+/// insta::assert_http_snapshot!(get("/users/1"), @r#"
+/// HTTP/1.1 200 OK
+/// content-type: application/json
+/// date: [date]
+/// etag: [etag]
+/// set-cookie: session=[value]; Path=/; HttpOnly
+///
+/// {
+///   "id": 1,
+///   "name": "Ada"
+/// }
+/// "#);
+/// ```
+///
+/// The `date` and `etag` headers are always redacted, and so are the value
+/// and the expiry of cookies in `set-cookie` headers.  The second argument
+/// to the macro can redact more headers by their name, which also replaces
+/// the built-in redactions:
+///
+/// ```no_run
+/// # let response = http::Response::new("");
+/// insta::assert_http_snapshot!(response, {
+///     "x-request-id" => "[id]",
+///     "set-cookie" => "[cookie]",
+/// });
+/// ```
+///
+/// The snapshot name is optional but can be provided as first argument.
+#[cfg(feature = "http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+#[macro_export]
+macro_rules! assert_http_snapshot {
+    ($($arg:tt)*) => {
+        $crate::_with_assertion_context!(_assert_http_snapshot!() $($arg)*)
+    };
+}
+
+// Like `_assert_xml_snapshot` but with header names for redactions.
+#[cfg(feature = "http")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _assert_http_snapshot {
+    ($value:expr, {$($k:expr => $v:expr),* $(,)?} $($arg:tt)*) => {{
+        let redactions = $crate::_macro_support::vec![$(($k, ToString::to_string(&$v)),)*];
+        $crate::_assert_snapshot_base!(
+            transform=|v| $crate::_macro_support::render_http_response(v, &redactions),
+            $value $($arg)*
+        );
+    }};
+    ($name:expr, $value:expr, {$($k:expr => $v:expr),* $(,)?} $($arg:tt)*) => {{
+        let redactions = $crate::_macro_support::vec![$(($k, ToString::to_string(&$v)),)*];
+        $crate::_assert_snapshot_base!(
+            transform=|v| $crate::_macro_support::render_http_response(v, &redactions),
+            $name, $value $($arg)*
+        );
+    }};
+    ($($arg:tt)*) => {
+        $crate::_assert_snapshot_base!(
+            transform=|v| $crate::_macro_support::render_http_response(v, &[]),
+            $($arg)*
+        )
+    };
+}
+
+/// Asserts a snapshot of the JSON schema of a type.
+///
+/// **Feature:** `schemars` (disabled by default)
+///
+/// The schema of a type implementing [`schemars::JsonSchema`](https://docs.rs/schemars/0.8/schemars/trait.JsonSchema.html)
+/// is generated with the default settings of schemars and snapshotted as
+/// JSON.  Snapshotting the schemas of the types exposed by an API makes
+/// every change of the API contract show up in review, including changes
+/// that the snapshots of example values do not cover, such as new optional
+/// fields or enum variants.
+///
+/// ```no_run
+/// # #[derive(schemars::JsonSchema)]
+/// # struct User { id: u64 }
+/// insta::assert_schema_snapshot!(User);
+/// // named
+/// insta::assert_schema_snapshot!("user_schema", User);
+/// ```
+///
+/// The argument is a type rather than a value, and the snapshot name, if
+/// given, has to be a string literal.
+#[cfg(feature = "schemars")]
+#[cfg_attr(docsrs, doc(cfg(feature = "schemars")))]
+#[macro_export]
+macro_rules! assert_schema_snapshot {
+    ($($arg:tt)*) => {
+        $crate::_with_assertion_context!(@leading _assert_schema_snapshot!() $($arg)*)
+    };
+}
+
+// The type is not an expression, so the value and the debug expression are
+// passed to `_assert_snapshot_base` explicitly.
+#[cfg(feature = "schemars")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _assert_schema_snapshot {
+    ($name:literal, $ty:ty $(,)?) => {
+        $crate::_assert_schema_snapshot!(@base $name, $ty)
+    };
+    ($ty:ty, @$snapshot:literal $(,)?) => {
+        $crate::_assert_schema_snapshot!(
+            @base
+            #[allow(clippy::needless_raw_string_hashes)]
+            $crate::_macro_support::InlineValue($snapshot),
+            $ty
+        )
+    };
+    ($ty:ty $(,)?) => {
+        $crate::_assert_schema_snapshot!(@base $crate::_macro_support::AutoName, $ty)
+    };
+    (@base $name:expr, $ty:ty) => {
+        $crate::_assert_snapshot_base!(
+            transform = |v| $crate::_macro_support::serialize_value(
+                v,
+                $crate::_macro_support::SerializationFormat::Json,
+            ),
+            $name,
+            $crate::_macro_support::schema_for::<$ty>(),
+            stringify!($ty)
+        )
+    };
+}
+
+/// Asserts snapshots of the help of a [`clap`](https://docs.rs/clap) command
+/// and all of its subcommands.
+///
+/// **Feature:** `clap` (disabled by default)
+///
+/// The `--help` output of the command and of each subcommand, recursively,
+/// is asserted as a separate snapshot whose suffix is the path of the
+/// command, for instance `help@app-remote-add`.  Help is rendered with a
+/// terminal width of 100 columns, so the snapshots do not depend on the
+/// terminal the tests run in.  Hidden subcommands are skipped.
+///
+/// ```no_run
+/// # fn cli() -> clap::Command { clap::Command::new("app") }
+/// insta::assert_clap_help_snapshot!(cli());
+/// // named
+/// insta::assert_clap_help_snapshot!("help", cli());
+/// ```
+///
+/// With a derived parser, pass `<Cli as clap::CommandFactory>::command()`.
+#[cfg(feature = "clap")]
+#[cfg_attr(docsrs, doc(cfg(feature = "clap")))]
+#[macro_export]
+macro_rules! assert_clap_help_snapshot {
+    ($cmd:expr $(,)?) => {
+        $crate::assert_clap_help_snapshot!($crate::_macro_support::AutoName, $cmd)
+    };
+    ($name:expr, $cmd:expr $(,)?) => {{
+        for (path, invocation, help) in $crate::_macro_support::render_clap_help($cmd) {
+            let mut settings = $crate::Settings::clone_current();
+            settings.set_snapshot_suffix(path);
+            settings.bind(|| {
+                $crate::assert_snapshot!($name, help, &invocation);
+            });
+        }
+    }};
+}
+
+// Splits off an optional `context = {"key": value, ...}` argument and binds
+// it to the settings for the duration of the assertion.  The context is not
+// an expression, so it has to be removed before the remaining arguments are
+// parsed by the assertion macros.  It is recognized as the first argument,
+// or right after the value (and the name, and the redactions, if any) where
+// a settings-style argument goes.  Matching only these positions keeps the
+// expansion of long values cheap, all other arguments are passed on as they
+// are.  An `extension = "ext"` argument sets the snapshot extension and is
+// only recognized as the first argument.
+//
+// Macros whose arguments do not start with an expression, such as a type,
+// pass `@leading` to only look for the context as the first argument.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _with_assertion_context {
+    (@bind [$($key:literal : $value:expr),*] $macro:ident!($($arg:tt)*)) => {{
+        let mut settings = $crate::Settings::clone_current();
+        $(settings.add_context($key, $value);)*
+        settings.bind(|| $crate::$macro!($($arg)*))
+    }};
+    ($(@$leading:ident)? $macro:ident!($($prefix:tt)*) extension = $extension:literal $(, $($rest:tt)*)?) => {{
+        let mut settings = $crate::Settings::clone_current();
+        settings.set_snapshot_extension($extension);
+        settings.bind(|| $crate::_with_assertion_context!($(@$leading)? $macro!($($prefix)*) $($($rest)*)?))
+    }};
+    ($(@$leading:ident)? $macro:ident!($($prefix:tt)*) filters = [$($filter:ident),* $(,)?] $(, $($rest:tt)*)?) => {{
+        let mut settings = $crate::Settings::clone_current();
+        $(
+            let (regex, replacement) = $crate::_macro_support::filter_presets::$filter;
+            settings.add_filter(regex, replacement);
+        )*
+        settings.bind(|| $crate::_with_assertion_context!($(@$leading)? $macro!($($prefix)*) $($($rest)*)?))
+    }};
+    ($(@$leading:ident)? $macro:ident!($($prefix:tt)*) context = {$($key:literal : $value:expr),* $(,)?} $(, $($rest:tt)*)?) => {
+        $crate::_with_assertion_context!(@bind [$($key : $value),*] $macro!($($prefix)* $($($rest)*)?))
+    };
+    (@leading $macro:ident!($($prefix:tt)*) $($arg:tt)*) => {
+        $crate::$macro!($($prefix)* $($arg)*)
+    };
+    ($macro:ident!($($prefix:tt)*) $v:expr, context = {$($key:literal : $value:expr),* $(,)?} $(, $($rest:tt)*)?) => {
+        $crate::_with_assertion_context!(@bind [$($key : $value),*] $macro!($($prefix)* $v $(, $($rest)*)?))
+    };
+    ($macro:ident!($($prefix:tt)*) $v:expr, $(match $range:tt)? {$($r:tt)*}, context = {$($key:literal : $value:expr),* $(,)?} $(, $($rest:tt)*)?) => {
+        $crate::_with_assertion_context!(@bind [$($key : $value),*] $macro!($($prefix)* $v, $(match $range)? {$($r)*} $(, $($rest)*)?))
+    };
+    // redactions are not an expression, so they must not reach the arms
+    // below which parse the second argument as one
+    ($macro:ident!($($prefix:tt)*) $v:expr, $(match $range:tt)? {$($r:tt)*} $($rest:tt)*) => {
+        $crate::$macro!($($prefix)* $v, $(match $range)? {$($r)*} $($rest)*)
+    };
+    ($macro:ident!($($prefix:tt)*) $n:expr, $v:expr, context = {$($key:literal : $value:expr),* $(,)?} $(, $($rest:tt)*)?) => {
+        $crate::_with_assertion_context!(@bind [$($key : $value),*] $macro!($($prefix)* $n, $v $(, $($rest)*)?))
+    };
+    ($macro:ident!($($prefix:tt)*) $n:expr, $v:expr, $(match $range:tt)? {$($r:tt)*}, context = {$($key:literal : $value:expr),* $(,)?} $(, $($rest:tt)*)?) => {
+        $crate::_with_assertion_context!(@bind [$($key : $value),*] $macro!($($prefix)* $n, $v, $(match $range)? {$($r)*} $(, $($rest)*)?))
+    };
+    ($macro:ident!($($prefix:tt)*) $($arg:tt)*) => {
+        $crate::$macro!($($prefix)* $($arg)*)
+    };
+}
+
+// A helper macro which takes a closure as `transform`, and runs the closure on
+// the value. This allows us to implement other macros with a small wrapper. All
+// snapshot macros eventually call this macro.
+//
+// This macro handles optional trailing commas.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _assert_snapshot_base {
+    // If the reference value comes from a `#[case]` attribute, report the
+    // line of the attribute so that cargo-insta updates the value there.
+    (transform=$transform:expr, $value:expr, @$case:ident $(,)?) => {
+        $crate::_macro_support::assert_snapshot(
+            (
+                $crate::_macro_support::InlineValue($case.reference()),
+                #[allow(clippy::redundant_closure_call)]
+                $crate::_macro_support::SnapshotText::from(&$transform(&$value)),
+            ).into(),
+            $crate::_get_workspace_root!().as_path(),
+            $crate::_function_name!(),
+            $crate::_macro_support::module_path!(),
+            $crate::_macro_support::file!(),
+            $case.line(),
+            stringify!($value),
+        )
+        .unwrap()
+    };
+    // If there's an inline literal value, wrap the literal in a
+    // `ReferenceValue::Inline`, call self.
+    (transform=$transform:expr, $($arg:expr),*, @$snapshot:literal $(,)?) => {
+        $crate::_assert_snapshot_base!(
+            transform = $transform,
+            #[allow(clippy::needless_raw_string_hashes)]
+            $crate::_macro_support::InlineValue($snapshot),
+            $($arg),*
+        )
+    };
+    // If there's no debug_expr, use the stringified value, call self.
+    (transform=$transform:expr, $name:expr, $value:expr $(,)?) => {
+        $crate::_assert_snapshot_base!(transform = $transform, $name, $value, stringify!($value))
+    };
+    // If there's no name (and necessarily no debug expr), auto generate the
+    // name, call self.
+    (transform=$transform:expr, $value:expr $(,)?) => {
+        $crate::_assert_snapshot_base!(
+            transform = $transform,
+            $crate::_macro_support::AutoName,
+            $value
+        )
+    };
+    // The main macro body — every call to this macro should end up here.
+    (transform=$transform:expr, $name:expr, $value:expr, $debug_expr:expr $(,)?) => {
+        $crate::_macro_support::assert_snapshot(
+            (
+                $name,
+                #[allow(clippy::redundant_closure_call)]
+                $crate::_macro_support::SnapshotText::from(&$transform(&$value)),
+            ).into(),
+            $crate::_get_workspace_root!().as_path(),
+            $crate::_function_name!(),
+            $crate::_macro_support::module_path!(),
+            $crate::_macro_support::file!(),
+            $crate::_macro_support::line!(),
+            $debug_expr,
+        )
+        .unwrap()
+    };
+}
+
+/// (Experimental)
+/// Asserts a binary snapshot of anything that can be viewed as bytes, such as
+/// a [`Vec<u8>`] or a `&[u8]`.  Owned buffers are moved into the snapshot
+/// rather than copied.
+///
+/// The contents get stored in a separate file next to the metadata file. The extension for this
+/// file must be passed as part of the name. For an implicit snapshot name just an extension can be
+/// passed starting with a `.`.
+///
+/// This feature is considered experimental: we may make incompatible changes for the next couple
+/// of versions after 1.41.
+///
+/// Examples:
+///
+/// ```no_run
+/// // implicit name:
+/// insta::assert_binary_snapshot!(".txt", b"abcd");
+///
+/// // named:
+/// insta::assert_binary_snapshot!("my_snapshot.bin", vec![0, 1, 2, 3]);
+/// ```
+#[macro_export]
+macro_rules! assert_binary_snapshot {
+    ($name_and_extension:expr, $value:expr $(,)?) => {
+        $crate::assert_binary_snapshot!($name_and_extension, $value, stringify!($value));
+    };
+
+    ($name_and_extension:expr, $value:expr, $debug_expr:expr $(,)?) => {
+        $crate::_macro_support::assert_snapshot(
+            $crate::_macro_support::BinarySnapshotValue {
+                name_and_extension: $name_and_extension,
+                content: $crate::_macro_support::IntoBinaryContent::into_binary_content($value),
+            }
+            .into(),
+            $crate::_get_workspace_root!().as_path(),
+            $crate::_function_name!(),
+            $crate::_macro_support::module_path!(),
+            $crate::_macro_support::file!(),
+            $crate::_macro_support::line!(),
+            $debug_expr,
+        )
+        .unwrap()
+    };
+}
+
+/// Asserts a [`Display`](std::fmt::Display) snapshot, optionally wrapping
+/// long lines.
+///
+/// Without options this is the same as [`assert_snapshot!`](crate::assert_snapshot!).
+/// With a leading `width = N` argument, lines of the displayed value that are
+/// longer than `N` characters are wrapped at whitespace, which keeps long
+/// error messages and help texts reviewable and makes their snapshots
+/// independent of how the text was broken into lines.  Continuation lines
+/// keep the indentation of the line they continue.
+///
+/// ```no_run
+/// # use insta::*;
+/// # let err = "";
+/// assert_display_snapshot!(width = 80, err);
+/// ```
+#[macro_export]
+macro_rules! assert_display_snapshot {
+    (width = $width:expr, $($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::wrap_lines(
+                &$crate::_macro_support::format!("{}", v),
+                $width,
+            ),) $($arg)*
+        )
+    };
+    ($($arg:tt)*) => {
+        $crate::assert_snapshot!($($arg)*)
+    };
+}
+
+/// Asserts a snapshot of how every variant of an enum is rendered.
+///
+/// The value is an iterator over all variants, such as the one
+/// [strum](https://docs.rs/strum)'s `EnumIter` derive provides, and must be
+/// [`Clone`].  The function passed as `render` is called with a reference to
+/// every variant and the snapshot is a table with the [`Debug`](std::fmt::Debug)
+/// representation of each variant and the rendered value next to it.  This
+/// locks the behavior of every variant, for instance display names or error
+/// codes, and since a newly added variant adds a row to the table, the
+/// assertion fails until the snapshot is extended.
+///
+/// ```no_run
+/// # use insta::*;
+/// # #[derive(Debug, Clone, Copy)]
+/// # enum Status { Ok, NotFound }
+/// # impl Status { fn code(self) -> u16 { 0 } }
+/// # let all_statuses = vec![Status::Ok, Status::NotFound];
+/// assert_variants_snapshot!(render = |status| status.code(), all_statuses, @"
+/// Ok       | 200
+/// NotFound | 404
+/// ");
+/// ```
+///
+/// The snapshot name is optional but can be provided as first argument after
+/// `render`.
+#[macro_export]
+macro_rules! assert_variants_snapshot {
+    (render = $render:expr, $($arg:tt)*) => {
+        $crate::_with_assertion_context!(_assert_variants_snapshot!(render = $render,) $($arg)*)
+    };
+}
+
+// The table is rendered before it is passed on as the value, rather than in
+// `transform`, so that the type of the variants is known when the closure
+// passed as `render` is type checked.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _assert_variants_snapshot {
+    (render = $render:expr, $value:expr, @$case:ident $(,)?) => {
+        $crate::_assert_snapshot_base!(
+            transform = |v: &String| v.clone(),
+            $crate::_macro_support::render_variants(&$value, $render),
+            @$case
+        )
+    };
+    (render = $render:expr, $value:expr, @$snapshot:literal $(,)?) => {
+        $crate::_assert_snapshot_base!(
+            transform = |v: &String| v.clone(),
+            #[allow(clippy::needless_raw_string_hashes)]
+            $crate::_macro_support::InlineValue($snapshot),
+            $crate::_macro_support::render_variants(&$value, $render),
+            stringify!($value)
+        )
+    };
+    (render = $render:expr, $value:expr $(,)?) => {
+        $crate::_assert_variants_snapshot!(render = $render, $crate::_macro_support::AutoName, $value)
+    };
+    (render = $render:expr, $name:expr, $value:expr $(,)?) => {
+        $crate::_assert_snapshot_base!(
+            transform = |v: &String| v.clone(),
+            $name,
+            $crate::_macro_support::render_variants(&$value, $render),
+            stringify!($value)
+        )
+    };
+}
+
+/// Asserts a snapshot of terminal output with ANSI escape codes.
+///
+/// The value is displayed like in [`assert_snapshot!`](crate::assert_snapshot!)
+/// and its escape codes are handled according to
+/// [`Settings::set_ansi_mode`](crate::Settings::set_ansi_mode): by default
+/// they are stripped, with [`AnsiMode::Markup`](crate::AnsiMode::Markup)
+/// colors and text styles are written as tags such as `<red>…</red>`.
+/// Escape codes other than colors and styles, such as cursor movements and
+/// hyperlinks, are always removed.
+///
+/// ```no_run
+/// # use insta::*;
+/// assert_ansi_snapshot!("\x1b[31merror\x1b[0m: not found", @"error: not found");
+///
+/// with_settings!({ansi_mode => AnsiMode::Markup}, {
+///     assert_ansi_snapshot!("\x1b[31merror\x1b[0m: not found", @"<red>error</red>: not found");
+/// });
+/// ```
+///
+/// The snapshot name is optional but can be provided as first argument.
+#[macro_export]
+macro_rules! assert_ansi_snapshot {
+    ($($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::render_ansi(v),) $($arg)*
+        )
+    };
+}
+
+/// Asserts a snapshot of the output of a closure.
+///
+/// The closure is run with [`capture_output`](crate::capture_output) and the
+/// snapshot contains what it wrote to the standard output and to the
+/// standard error, each under a heading of its own.  See
+/// [`capture_output`](crate::capture_output) for which output can be
+/// captured.
+///
+/// ```no_run
+/// # use insta::*;
+/// use std::io::Write;
+///
+/// assert_output_snapshot!(|| {
+///     writeln!(std::io::stdout(), "done").unwrap();
+///     writeln!(std::io::stderr(), "warning: unused").unwrap();
+/// }, @r"
+/// ----- stdout -----
+/// done
+/// ----- stderr -----
+/// warning: unused
+/// ");
+/// ```
+///
+/// The snapshot name is optional but can be provided as first argument.
+///
+/// **Platform:** Unix only.
+#[cfg(unix)]
+#[cfg_attr(docsrs, doc(cfg(unix)))]
+#[macro_export]
+macro_rules! assert_output_snapshot {
+    ($($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|f| $crate::_macro_support::render_output(f),) $($arg)*
+        )
+    };
+}
+
+/// Asserts a [`String`] snapshot.
+///
+/// This is the simplest of all assertion methods.
+/// It accepts any value that implements [`Display`](std::fmt::Display).
+///
+/// ```no_run
+/// # use insta::*;
+/// // implicitly named
+/// assert_snapshot!("reference value to snapshot");
+/// // named
+/// assert_snapshot!("snapshot_name", "reference value to snapshot");
+/// // inline
+/// assert_snapshot!("reference value", @"reference value");
+/// ```
+///
+/// Optionally a third argument can be given as an expression to be stringified
+/// as the debug expression.  For more information on this, check out
+/// <https://insta.rs/docs/snapshot-types/>.
+///
+/// A leading `extension = "ext"` argument stores the snapshot as
+/// `name.snap.ext`, so that editors highlight its contents.  It can be passed
+/// to all assertion macros and is the same as
+/// [`Settings::set_snapshot_extension`](crate::Settings::set_snapshot_extension):
+///
+/// ```no_run
+/// # use insta::*;
+/// # let query = "";
+/// assert_snapshot!(extension = "sql", "users_query", query);
+/// ```
+#[macro_export]
+macro_rules! assert_snapshot {
+    ($($arg:tt)*) => {
+        $crate::_with_assertion_context!(
+            _assert_snapshot_base!(transform=|v| $crate::_macro_support::format!("{}", v),) $($arg)*
+        )
+    };
+}
+
+/// Settings configuration macro.
+///
+/// This macro lets you bind some [`Settings`](crate::Settings) temporarily.  The first argument
 /// takes key value pairs that should be set, and the second is the block to
 /// execute.  All settings can be set (`sort_maps => value` maps to `set_sort_maps(value)`).
 /// The exception are redactions, which can only be set to a vector this way.
@@ -506,6 +1895,30 @@ macro_rules! with_settings {
     }}
 }
 
+/// Creates a [`SnapshotOwner`](crate::SnapshotOwner) for the current location.
+///
+/// Pass it to shared test helpers which set it with
+/// [`Settings::set_snapshot_owner`](crate::Settings::set_snapshot_owner) so
+/// that the file snapshots they assert are stored and named as if they were
+/// asserted here.
+///
+/// ```no_run
+/// # fn check_output(output: &str, owner: insta::SnapshotOwner) {}
+/// check_output("output", insta::snapshot_owner!());
+/// ```
+#[macro_export]
+macro_rules! snapshot_owner {
+    () => {
+        $crate::SnapshotOwner::new(
+            $crate::_get_workspace_root!(),
+            $crate::_function_name!(),
+            $crate::_macro_support::module_path!(),
+            $crate::_macro_support::file!(),
+            $crate::_macro_support::line!(),
+        )
+    };
+}
+
 /// Executes a closure for all input files matching a glob.
 ///
 /// The closure is passed the path to the file.  You can use [`std::fs::read_to_string`]
@@ -0,0 +1,202 @@
+use std::fmt::Display;
+
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table, Value};
+
+/// Parses a `Cargo.toml` and formats it in a normalized form.
+///
+/// Keys are sorted within every table and tables are sorted by name, except
+/// that `[package]` and `[workspace]` come first.  Comments and formatting
+/// are removed.  Arrays keep their order since it is significant for
+/// instance in `workspace.members`.
+///
+/// Panics if `input` is not valid TOML.
+pub fn normalize_cargo_manifest<V: Display + ?Sized>(input: &V) -> String {
+    let mut doc = parse(input, "Cargo.toml");
+    normalize_table(doc.as_table_mut(), true, true, &mut 0);
+    finish(doc)
+}
+
+/// Parses a `Cargo.lock` and formats it in a normalized form.
+///
+/// Packages are sorted by name and version and their dependencies by name.
+/// Checksums and the commit hashes of git sources are removed, so that the
+/// snapshot does not change when a registry or repository is updated
+/// without affecting the resolved versions.  Comments and formatting are
+/// removed.
+///
+/// Panics if `input` is not valid TOML.
+pub fn normalize_cargo_lock<V: Display + ?Sized>(input: &V) -> String {
+    let mut doc = parse(input, "Cargo.lock");
+    // lockfiles before version 3 store the checksums in a table of their own
+    doc.remove("metadata");
+    if let Some(packages) = doc
+        .get_mut("package")
+        .and_then(Item::as_array_of_tables_mut)
+    {
+        let mut sorted = packages.iter().cloned().collect::<Vec<_>>();
+        sorted.sort_by_key(|package| {
+            ["name", "version", "source"].map(|key| {
+                package
+                    .get(key)
+                    .and_then(Item::as_str)
+                    .unwrap_or("")
+                    .to_string()
+            })
+        });
+        let mut rv = ArrayOfTables::new();
+        for mut package in sorted {
+            package.remove("checksum");
+            if let Some(source) = package.get_mut("source") {
+                if let Some(stripped) = source
+                    .as_str()
+                    .filter(|source| source.starts_with("git+"))
+                    .and_then(|source| source.split_once('#'))
+                    .map(|(source, _)| source.to_string())
+                {
+                    *source = toml_edit::value(stripped);
+                }
+            }
+            if let Some(dependencies) = package.get_mut("dependencies").and_then(Item::as_array_mut)
+            {
+                dependencies.sort_by_key(|dep| dep.as_str().unwrap_or("").to_string());
+            }
+            rv.push(package);
+        }
+        *packages = rv;
+    }
+    normalize_table(doc.as_table_mut(), false, true, &mut 0);
+    finish(doc)
+}
+
+fn parse<V: Display + ?Sized>(input: &V, kind: &str) -> DocumentMut {
+    let input = input.to_string();
+    match input.parse::<DocumentMut>() {
+        Ok(doc) => doc,
+        Err(err) => panic!("failed to parse snapshot value as {kind}: {err}\n\n{input}"),
+    }
+}
+
+fn finish(mut doc: DocumentMut) -> String {
+    doc.set_trailing("");
+    doc.to_string().trim().to_string()
+}
+
+/// Removes the formatting of `table` and its subtables and orders them by
+/// assigning increasing positions in the order they are visited.
+fn normalize_table(table: &mut Table, sort_keys: bool, root: bool, position: &mut isize) {
+    if !root {
+        table.decor_mut().clear();
+        table.set_position(*position);
+        *position += 1;
+    }
+    if sort_keys {
+        let rank = |key: &str| match key {
+            "package" if root => 0,
+            "workspace" if root => 1,
+            _ => 2,
+        };
+        table.sort_values_by(|a, _, b, _| (rank(a), a.get()).cmp(&(rank(b), b.get())));
+    }
+    table.fmt();
+    for (mut key, item) in table.iter_mut() {
+        toml_edit::KeyMut::fmt(&mut key);
+        match item {
+            Item::Table(table) => normalize_table(table, sort_keys, false, position),
+            Item::ArrayOfTables(tables) => {
+                for table in tables.iter_mut() {
+                    normalize_table(table, sort_keys, false, position);
+                }
+            }
+            Item::Value(Value::InlineTable(table)) => {
+                if sort_keys {
+                    table.sort_values();
+                }
+                table.fmt();
+            }
+            Item::Value(Value::Array(array)) => array.fmt(),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn test_normalize_cargo_manifest() {
+    let manifest = r#"
+[dependencies]
+serde = { version = "1",  features = ["derive"] }
+anyhow = "1.0"   # errors
+
+# the package
+[package]
+version = "0.1.0"
+name = "demo"
+
+[dev-dependencies]
+insta = "1"
+
+[workspace]
+members = ["b", "a"]
+"#;
+    assert_eq!(
+        normalize_cargo_manifest(manifest),
+        r#"[package]
+name = "demo"
+version = "0.1.0"
+
+[workspace]
+members = ["b", "a"]
+
+[dependencies]
+anyhow = "1.0"
+serde = { features = ["derive"], version = "1" }
+
+[dev-dependencies]
+insta = "1""#
+    );
+}
+
+#[test]
+fn test_normalize_cargo_lock() {
+    let lock = r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 3
+
+[[package]]
+name = "demo"
+version = "0.1.0"
+dependencies = [
+ "serde",
+ "anyhow",
+]
+
+[[package]]
+name = "anyhow"
+version = "1.0.86"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "b3d1d046238990b9cf5bcde22a3fb3584ee5cf65fb2765f454ed428c7a0063da"
+
+[[package]]
+name = "serde"
+version = "1.0.0"
+source = "git+https://github.com/serde-rs/serde?branch=master#0b4ab1a2f9b51b8f3b2f5a6e1a9e6b6d8c1f2e3a"
+"#;
+    assert_eq!(
+        normalize_cargo_lock(lock),
+        r#"version = 3
+
+[[package]]
+name = "anyhow"
+version = "1.0.86"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "demo"
+version = "0.1.0"
+dependencies = ["anyhow", "serde"]
+
+[[package]]
+name = "serde"
+version = "1.0.0"
+source = "git+https://github.com/serde-rs/serde?branch=master""#
+    );
+}
@@ -0,0 +1,212 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::env::matches_pattern;
+
+/// A metric family with its `HELP` and `TYPE` comments and its samples.
+#[derive(Debug, Default)]
+struct Family {
+    help: Option<String>,
+    kind: Option<String>,
+    samples: Vec<Sample>,
+}
+
+#[derive(Debug)]
+struct Sample {
+    name: String,
+    /// The labels as pairs of the name and the still escaped value.
+    labels: Vec<(String, String)>,
+    value: String,
+}
+
+/// Normalizes metrics in the Prometheus text exposition format.
+///
+/// Families are sorted by name and written with their `HELP` and `TYPE`
+/// comments first.  The samples of a family are sorted by name and labels,
+/// the labels of a sample by their name, and label values that are numbers,
+/// such as the `le` buckets of histograms, in numeric order.  Timestamps and
+/// other comments are left out.
+///
+/// The value of a sample is replaced by the replacement of the first
+/// `(pattern, replacement)` in `redactions` whose pattern matches the name
+/// of the sample or of its family.  In patterns `*` stands for any text.
+///
+/// Panics if a line is not a valid sample.
+pub fn normalize_metrics<V: Display + ?Sized>(input: &V, redactions: &[(&str, String)]) -> String {
+    let input = input.to_string();
+    let mut families = BTreeMap::<String, Family>::new();
+    let mut current = None::<String>;
+
+    for line in input.lines().map(str::trim).filter(|x| !x.is_empty()) {
+        if let Some(comment) = line.strip_prefix('#') {
+            let mut parts = comment.trim_start().splitn(3, ' ');
+            let (keyword, name) = match (parts.next(), parts.next()) {
+                (Some(keyword @ ("HELP" | "TYPE")), Some(name)) => (keyword, name),
+                _ => continue,
+            };
+            let rest = parts.next().unwrap_or("").trim().to_string();
+            let family = families.entry(name.to_string()).or_default();
+            if keyword == "HELP" {
+                family.help = Some(rest);
+            } else {
+                family.kind = Some(rest);
+            }
+            current = Some(name.to_string());
+            continue;
+        }
+
+        let sample = parse_sample(line)
+            .unwrap_or_else(|| panic!("failed to parse metrics sample: {line:?}\n\n{input}"));
+        // samples of histograms and summaries have suffixes after the name
+        // of their family
+        let family = match current {
+            Some(ref name) if sample.name.starts_with(name.as_str()) => name.clone(),
+            _ => sample.name.clone(),
+        };
+        families.entry(family).or_default().samples.push(sample);
+    }
+
+    let mut rv = String::new();
+    for (name, mut family) in families {
+        if let Some(help) = family.help {
+            rv.push_str(&format!("# HELP {name} {help}\n"));
+        }
+        if let Some(kind) = family.kind {
+            rv.push_str(&format!("# TYPE {name} {kind}\n"));
+        }
+        family.samples.sort_by(|a, b| {
+            a.name
+                .cmp(&b.name)
+                .then_with(|| cmp_labels(&a.labels, &b.labels))
+        });
+        for sample in family.samples {
+            rv.push_str(&sample.name);
+            if !sample.labels.is_empty() {
+                let labels = sample
+                    .labels
+                    .iter()
+                    .map(|(key, value)| format!("{key}=\"{value}\""))
+                    .collect::<Vec<_>>();
+                rv.push_str(&format!("{{{}}}", labels.join(",")));
+            }
+            let value = redactions
+                .iter()
+                .find(|(pattern, _)| {
+                    matches_pattern(pattern, &name) || matches_pattern(pattern, &sample.name)
+                })
+                .map_or(sample.value.as_str(), |x| x.1.as_str());
+            rv.push_str(&format!(" {value}\n"));
+        }
+    }
+    rv
+}
+
+/// Parses a line like `name{key="value",...} value [timestamp]`.
+fn parse_sample(line: &str) -> Option<Sample> {
+    let name_end = line.find(|c: char| c == '{' || c.is_whitespace())?;
+    let name = &line[..name_end];
+    let mut rest = &line[name_end..];
+    let mut labels = vec![];
+
+    if let Some(mut inner) = rest.strip_prefix('{') {
+        loop {
+            inner = inner.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+            if let Some(after) = inner.strip_prefix('}') {
+                rest = after;
+                break;
+            }
+            let eq = inner.find('=')?;
+            let key = inner[..eq].trim();
+            inner = inner[eq + 1..].trim_start().strip_prefix('"')?;
+            let mut escaped = false;
+            let end = inner.char_indices().find_map(|(idx, c)| {
+                match (escaped, c) {
+                    (false, '"') => return Some(idx),
+                    (false, '\\') => escaped = true,
+                    _ => escaped = false,
+                }
+                None
+            })?;
+            labels.push((key.to_string(), inner[..end].to_string()));
+            inner = &inner[end + 1..];
+        }
+    }
+
+    let value = rest.split_whitespace().next()?;
+    if name.is_empty() {
+        return None;
+    }
+    labels.sort_by(|a, b| a.0.cmp(&b.0));
+    Some(Sample {
+        name: name.to_string(),
+        labels,
+        value: value.to_string(),
+    })
+}
+
+fn cmp_labels(a: &[(String, String)], b: &[(String, String)]) -> Ordering {
+    for ((a_key, a_value), (b_key, b_value)) in a.iter().zip(b) {
+        let ord =
+            a_key
+                .cmp(b_key)
+                .then_with(|| match (a_value.parse::<f64>(), b_value.parse::<f64>()) {
+                    (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+                    _ => a_value.cmp(b_value),
+                });
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+#[test]
+fn test_normalize_metrics() {
+    let input = r#"
+# HELP requests_total Requests handled.
+# TYPE requests_total counter
+requests_total{status="500",method="GET"} 3 1700000000000
+requests_total{method="GET",status="200"} 1027
+# HELP request_duration_seconds Time spent on requests.
+# TYPE request_duration_seconds histogram
+request_duration_seconds_bucket{le="+Inf"} 144320
+request_duration_seconds_bucket{le="0.5"} 129389
+request_duration_seconds_bucket{le="0.05"} 24054
+request_duration_seconds_sum 53423.2
+request_duration_seconds_count 144320
+# a comment
+build_info{version="1.2.3", label="with \"quotes\""} 1
+"#;
+    crate::assert_snapshot!(normalize_metrics(input, &[]), @r#"
+    build_info{label="with \"quotes\"",version="1.2.3"} 1
+    # HELP request_duration_seconds Time spent on requests.
+    # TYPE request_duration_seconds histogram
+    request_duration_seconds_bucket{le="0.05"} 24054
+    request_duration_seconds_bucket{le="0.5"} 129389
+    request_duration_seconds_bucket{le="+Inf"} 144320
+    request_duration_seconds_count 144320
+    request_duration_seconds_sum 53423.2
+    # HELP requests_total Requests handled.
+    # TYPE requests_total counter
+    requests_total{method="GET",status="200"} 1027
+    requests_total{method="GET",status="500"} 3
+    "#);
+    crate::assert_snapshot!(
+        normalize_metrics(input, &[("*_count", "[n]".into()), ("*_seconds", "[duration]".into())]),
+        @r#"
+    build_info{label="with \"quotes\"",version="1.2.3"} 1
+    # HELP request_duration_seconds Time spent on requests.
+    # TYPE request_duration_seconds histogram
+    request_duration_seconds_bucket{le="0.05"} [duration]
+    request_duration_seconds_bucket{le="0.5"} [duration]
+    request_duration_seconds_bucket{le="+Inf"} [duration]
+    request_duration_seconds_count [n]
+    request_duration_seconds_sum [duration]
+    # HELP requests_total Requests handled.
+    # TYPE requests_total counter
+    requests_total{method="GET",status="200"} 1027
+    requests_total{method="GET",status="500"} 3
+    "#
+    );
+}
@@ -0,0 +1,139 @@
+use std::borrow::Cow;
+use std::fmt::Debug;
+
+use crate::env::NameSanitization;
+
+/// The longest readable part that [`stable_name`] keeps.
+const MAX_PREFIX_LEN: usize = 40;
+
+/// The longest snapshot name that portable file names keep, which leaves
+/// room for the module path within the limit of most file systems.
+const MAX_PORTABLE_NAME_LEN: usize = 150;
+
+/// Returns a deterministic snapshot suffix for the parameters of a test.
+///
+/// The name starts with a readable part made of the ASCII letters and
+/// digits of the [`Debug`] representation of `params`, with everything else
+/// replaced by `-` and cut to 40 characters.  It ends with a 64 bit hash of
+/// the full representation, so parameters that only differ in the part that
+/// was replaced or cut still get different names.  The hash is computed
+/// with FNV-1a and does not depend on the platform or the Rust version.
+///
+/// Pass several parameters as a tuple:
+///
+/// ```
+/// let suffix = insta::stable_name(&("en-US", "Grüße", 3));
+/// assert_eq!(suffix, "en-US-Gr-e-3-960059ceb3a5ea80");
+///
+/// insta::with_settings!({snapshot_suffix => suffix}, {
+///     // ...
+/// });
+/// ```
+///
+/// The name is only as stable as the [`Debug`] representation, so it
+/// should not be used with values such as hash maps whose representation
+/// changes between runs.
+pub fn stable_name<T: Debug + ?Sized>(params: &T) -> String {
+    let repr = format!("{params:?}");
+    let mut prefix = String::new();
+    for c in repr.chars() {
+        if c.is_ascii_alphanumeric() {
+            prefix.push(c);
+        } else if !prefix.is_empty() && !prefix.ends_with('-') {
+            prefix.push('-');
+        }
+        if prefix.len() >= MAX_PREFIX_LEN {
+            break;
+        }
+    }
+    let hash = format!("{:016x}", fnv1a(repr.as_bytes()));
+    match prefix.trim_end_matches('-') {
+        "" => hash,
+        prefix => format!("{prefix}-{hash}"),
+    }
+}
+
+/// Turns a snapshot name into the part of a file name it is stored in.
+///
+/// Path separators become `__` with either scheme.  Portable names also
+/// replace everything but ASCII letters, digits and `-_.@+` with `_`, and
+/// names longer than 150 bytes are cut and end with a hash of the full name.
+pub fn sanitize_snapshot_name(name: &str, scheme: NameSanitization) -> Cow<'_, str> {
+    let mut rv = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '/' | '\\' => rv.push_str("__"),
+            c if scheme == NameSanitization::Legacy => rv.push(c),
+            c if c.is_ascii_alphanumeric() || "-_.@+".contains(c) => rv.push(c),
+            _ => rv.push('_'),
+        }
+    }
+    if scheme == NameSanitization::Portable && rv.len() > MAX_PORTABLE_NAME_LEN {
+        rv.truncate(MAX_PORTABLE_NAME_LEN - 17);
+        rv = format!("{rv}-{:016x}", fnv1a(name.as_bytes()));
+    }
+    if rv == name {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(rv)
+    }
+}
+
+/// Returns the directory that the snapshots of a module are stored in with
+/// the hashed layout.
+///
+/// The name is the last component of the module path followed by a 32 bit
+/// hash of the full path, so that deep module trees get short paths.
+pub(crate) fn hashed_module_dir(module_path: &str) -> String {
+    let leaf = module_path.rsplit("::").next().unwrap_or(module_path);
+    format!("{leaf}-{:08x}", fnv1a(module_path.as_bytes()) as u32)
+}
+
+/// Hashes `bytes` with the 64 bit FNV-1a hash.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[test]
+fn test_stable_name() {
+    assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+    assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    assert_eq!(stable_name(&42), "42-07ee7e07b4b19223");
+    assert_eq!(stable_name(&()), "07e11f07b4a6665a");
+    assert_ne!(stable_name("a/b"), stable_name("a b"));
+    assert_eq!(stable_name(&"x".repeat(100)).len(), MAX_PREFIX_LEN + 1 + 16);
+}
+
+#[test]
+fn test_sanitize_snapshot_name() {
+    let name = "fixtures/héllo world: 1?";
+    assert_eq!(
+        sanitize_snapshot_name(name, NameSanitization::Legacy),
+        "fixtures__héllo world: 1?"
+    );
+    assert_eq!(
+        sanitize_snapshot_name(name, NameSanitization::Portable),
+        "fixtures__h_llo_world__1_"
+    );
+    assert!(matches!(
+        sanitize_snapshot_name("case@a-1.2", NameSanitization::Portable),
+        Cow::Borrowed(_)
+    ));
+    let name = "x".repeat(200);
+    let long = sanitize_snapshot_name(&name, NameSanitization::Portable);
+    assert_eq!(long.len(), MAX_PORTABLE_NAME_LEN);
+    assert_ne!(
+        long,
+        sanitize_snapshot_name(&"x".repeat(201), NameSanitization::Portable)
+    );
+}
+
+#[test]
+fn test_hashed_module_dir() {
+    let dir = hashed_module_dir("my_crate::parser::expressions::tests");
+    assert!(dir.starts_with("tests-"));
+    assert_eq!(dir.len(), "tests-".len() + 8);
+    assert_ne!(dir, hashed_module_dir("my_crate::lexer::tests"));
+}
@@ -1,11 +1,13 @@
 use std::borrow::Cow;
+use std::fmt::{self, Write};
 use std::process::Command;
-use std::{env, path::Path, time::Duration};
+use std::{env, fs, path::Path, time::Duration};
 
 use similar::{Algorithm, ChangeTag, TextDiff};
 
 use crate::content::yaml;
 use crate::snapshot::{MetaData, Snapshot, SnapshotContents};
+use crate::table::align_tables;
 use crate::utils::{format_rust_expression, style, term_width};
 
 /// Snapshot printer utility.
@@ -20,6 +22,7 @@ pub struct SnapshotPrinter<'a> {
     title: Option<&'a str>,
     line: Option<u32>,
     snapshot_file: Option<&'a Path>,
+    diff_budget: Option<(usize, &'a Path)>,
 }
 
 impl<'a> SnapshotPrinter<'a> {
@@ -39,6 +42,7 @@ impl<'a> SnapshotPrinter<'a> {
             title: None,
             line: None,
             snapshot_file: None,
+            diff_budget: None,
         }
     }
 
@@ -67,6 +71,12 @@ impl<'a> SnapshotPrinter<'a> {
         self.snapshot_file = file;
     }
 
+    /// Limits the printed diff to roughly `budget` bytes.  If the diff is
+    /// longer, it is written to `report_file` in full.
+    pub fn set_diff_budget(&mut self, budget: usize, report_file: &'a Path) {
+        self.diff_budget = Some((budget, report_file));
+    }
+
     pub fn print(&self) {
         if let Some(title) = self.title {
             let width = term_width();
@@ -199,6 +209,16 @@ impl<'a> SnapshotPrinter<'a> {
             let old_text = old.as_deref().unwrap_or("");
             let new_text = new.as_deref().unwrap_or("");
 
+            // with the same column widths on both sides, only the rows with
+            // changed cells show up in the diff of a table
+            let aligned;
+            let (old_text, new_text) = if self.new_snapshot.metadata().cell_diff {
+                aligned = align_tables(old_text, new_text);
+                (aligned.0.as_str(), aligned.1.as_str())
+            } else {
+                (old_text, new_text)
+            };
+
             // Check for external diff tool
             if let Ok(tool) = env::var("INSTA_DIFF_TOOL") {
                 if !tool.is_empty()
@@ -231,68 +251,56 @@ impl<'a> SnapshotPrinter<'a> {
 
             println!("────────────┬{:─^1$}", "", width.saturating_sub(13));
 
+            let mut rows = String::new();
+            let (changed, truncated) = write_diff_rows(
+                &diff,
+                newlines_matter,
+                width,
+                self.diff_budget.map(|(budget, _)| budget),
+                &mut rows,
+            );
             // This is to make sure that binary and text snapshots are never reported as being
             // equal (that would otherwise happen if the text snapshot is an empty string).
-            let mut has_changes = old.is_none() || new.is_none();
-
-            for (idx, group) in diff.grouped_ops(4).iter().enumerate() {
-                if idx > 0 {
-                    println!("┈┈┈┈┈┈┈┈┈┈┈┈┼{:┈^1$}", "", width.saturating_sub(13));
-                }
-                for op in group {
-                    for change in diff.iter_inline_changes(op) {
-                        match change.tag() {
-                            ChangeTag::Insert => {
-                                has_changes = true;
-                                print!(
-                                    "{:>5} {:>5} │{}",
-                                    "",
-                                    style(change.new_index().unwrap() + 1).cyan().dim().bold(),
-                                    style("+").green(),
-                                );
-                                for &(emphasized, change) in change.values() {
-                                    let change = render_invisible(change, newlines_matter);
-                                    if emphasized {
-                                        print!("{}", style(change).green().underlined());
-                                    } else {
-                                        print!("{}", style(change).green());
-                                    }
-                                }
-                            }
-                            ChangeTag::Delete => {
-                                has_changes = true;
-                                print!(
-                                    "{:>5} {:>5} │{}",
-                                    style(change.old_index().unwrap() + 1).cyan().dim(),
-                                    "",
-                                    style("-").red(),
-                                );
-                                for &(emphasized, change) in change.values() {
-                                    let change = render_invisible(change, newlines_matter);
-                                    if emphasized {
-                                        print!("{}", style(change).red().underlined());
-                                    } else {
-                                        print!("{}", style(change).red());
-                                    }
-                                }
-                            }
-                            ChangeTag::Equal => {
-                                print!(
-                                    "{:>5} {:>5} │ ",
-                                    style(change.old_index().unwrap() + 1).cyan().dim(),
-                                    style(change.new_index().unwrap() + 1).cyan().dim().bold(),
-                                );
-                                for &(_, change) in change.values() {
-                                    let change = render_invisible(change, newlines_matter);
-                                    print!("{}", style(change).dim());
-                                }
-                            }
-                        }
-                        if change.missing_newline() {
-                            println!();
-                        }
-                    }
-                }
+            let has_changes = changed || old.is_none() || new.is_none();
+            print!("{rows}");
+
+            if let (true, Some((budget, report_file))) = (truncated, self.diff_budget) {
+                let full_diff = diff
+                    .unified_diff()
+                    .context_radius(4)
+                    .header(self.old_snapshot_hint, self.new_snapshot_hint)
+                    .to_string();
+                let preview_file = report_file.with_extension("html");
+                let preview = render_diff_html(
+                    &diff,
+                    newlines_matter,
+                    &report_file
+                        .file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy(),
+                    budget,
+                );
+                let msg = match report_file
+                    .parent()
+                    .map_or(Ok(()), fs::create_dir_all)
+                    .and_then(|()| fs::write(report_file, full_diff))
+                    .and_then(|()| fs::write(&preview_file, preview))
+                {
+                    Ok(()) => format!(
+                        "diff truncated after {} bytes, full diff: {}, preview: {}",
+                        budget,
+                        report_file.display(),
+                        preview_file.display()
+                    ),
+                    Err(err) => format!(
+                        "diff truncated after {} bytes, could not write full diff to {}: {}",
+                        budget,
+                        report_file.display(),
+                        err
+                    ),
+                };
+                println!("┈┈┈┈┈┈┈┈┈┈┈┈┼{:┈^1$}", "", width.saturating_sub(13));
+                println!("{:>5} {:>5} │ {}", "", "", style(msg).yellow());
             }
 
             if !has_changes {
@@ -309,6 +317,278 @@ impl<'a> SnapshotPrinter<'a> {
     }
 }
 
+/// A diff between two texts, rendered the way failed assertions show it.
+///
+/// Returned by [`diff`].  Displays as a table with the line numbers of both
+/// texts, changed lines marked with `-` and `+` and the changed parts of
+/// lines highlighted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedDiff {
+    rendered: String,
+    has_changes: bool,
+    truncated: bool,
+}
+
+impl RenderedDiff {
+    /// Returns `true` if the texts are different.
+    pub fn has_changes(&self) -> bool {
+        self.has_changes
+    }
+
+    /// Returns `true` if the diff was cut short because it exceeded its
+    /// budget.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl fmt::Display for RenderedDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.rendered)
+    }
+}
+
+/// Computes and renders the diff between two texts.
+///
+/// This is the diff that failed assertions print, for use in assertion
+/// helpers and error messages of your own.  Lines are compared with the
+/// patience algorithm and changes within lines are highlighted if colors are
+/// enabled.  If `budget` is given, the diff is cut short after that many
+/// bytes of changed and context lines, as assertions do on CI with the
+/// `ci_diff_budget` setting.
+///
+/// ```
+/// let diff = insta::diff("a\nb\n", "a\nc\n", None);
+/// assert!(diff.has_changes());
+/// println!("{diff}");
+/// ```
+pub fn diff(old: &str, new: &str, budget: Option<usize>) -> RenderedDiff {
+    let width = term_width();
+    let diff = TextDiff::configure()
+        .algorithm(Algorithm::Patience)
+        .timeout(Duration::from_millis(500))
+        .diff_lines(old, new);
+
+    let mut rendered = format!("────────────┬{:─^1$}\n", "", width.saturating_sub(13));
+    let (has_changes, truncated) = write_diff_rows(
+        &diff,
+        newlines_matter(old, new),
+        width,
+        budget,
+        &mut rendered,
+    );
+    if let (true, Some(budget)) = (truncated, budget) {
+        writeln!(
+            rendered,
+            "┈┈┈┈┈┈┈┈┈┈┈┈┼{:┈^1$}",
+            "",
+            width.saturating_sub(13)
+        )
+        .unwrap();
+        let msg = format!("diff truncated after {budget} bytes");
+        writeln!(rendered, "{:>5} {:>5} │ {}", "", "", style(msg).yellow()).unwrap();
+    }
+    if !has_changes {
+        writeln!(
+            rendered,
+            "{:>5} {:>5} │{}",
+            "",
+            style("-").dim(),
+            style(" texts are matching").cyan(),
+        )
+        .unwrap();
+    }
+    write!(
+        rendered,
+        "────────────┴{:─^1$}",
+        "",
+        width.saturating_sub(13)
+    )
+    .unwrap();
+    RenderedDiff {
+        rendered,
+        has_changes,
+        truncated,
+    }
+}
+
+/// Writes the rows of a line diff with line numbers and highlighted changes.
+///
+/// Returns if there are changes, and if the rows were cut short because they
+/// exceed `budget` bytes.
+fn write_diff_rows<'a>(
+    diff: &'a TextDiff<'a, 'a, 'a, str>,
+    newlines_matter: bool,
+    width: usize,
+    budget: Option<usize>,
+    out: &mut String,
+) -> (bool, bool) {
+    let mut has_changes = false;
+    let mut written_bytes = 0;
+    for (idx, group) in diff.grouped_ops(4).iter().enumerate() {
+        if idx > 0 {
+            writeln!(out, "┈┈┈┈┈┈┈┈┈┈┈┈┼{:┈^1$}", "", width.saturating_sub(13)).unwrap();
+        }
+        for op in group {
+            for change in diff.iter_inline_changes(op) {
+                if let Some(budget) = budget {
+                    if written_bytes >= budget {
+                        return (has_changes, true);
+                    }
+                    written_bytes += change.values().iter().map(|(_, x)| x.len()).sum::<usize>();
+                }
+                match change.tag() {
+                    ChangeTag::Insert => {
+                        has_changes = true;
+                        write!(
+                            out,
+                            "{:>5} {:>5} │{}",
+                            "",
+                            style(change.new_index().unwrap() + 1).cyan().dim().bold(),
+                            style("+").green(),
+                        )
+                        .unwrap();
+                        for &(emphasized, change) in change.values() {
+                            let change = render_invisible(change, newlines_matter);
+                            if emphasized {
+                                write!(out, "{}", style(change).green().underlined()).unwrap();
+                            } else {
+                                write!(out, "{}", style(change).green()).unwrap();
+                            }
+                        }
+                    }
+                    ChangeTag::Delete => {
+                        has_changes = true;
+                        write!(
+                            out,
+                            "{:>5} {:>5} │{}",
+                            style(change.old_index().unwrap() + 1).cyan().dim(),
+                            "",
+                            style("-").red(),
+                        )
+                        .unwrap();
+                        for &(emphasized, change) in change.values() {
+                            let change = render_invisible(change, newlines_matter);
+                            if emphasized {
+                                write!(out, "{}", style(change).red().underlined()).unwrap();
+                            } else {
+                                write!(out, "{}", style(change).red()).unwrap();
+                            }
+                        }
+                    }
+                    ChangeTag::Equal => {
+                        write!(
+                            out,
+                            "{:>5} {:>5} │ ",
+                            style(change.old_index().unwrap() + 1).cyan().dim(),
+                            style(change.new_index().unwrap() + 1).cyan().dim().bold(),
+                        )
+                        .unwrap();
+                        for &(_, change) in change.values() {
+                            let change = render_invisible(change, newlines_matter);
+                            write!(out, "{}", style(change).dim()).unwrap();
+                        }
+                    }
+                }
+                if change.missing_newline() {
+                    out.push('\n');
+                }
+            }
+        }
+    }
+    (has_changes, false)
+}
+
+const HTML_DIFF_STYLE: &str = "\
+body { font-family: sans-serif; }
+table { border-collapse: collapse; font-family: monospace; }
+td { padding: 0 6px; vertical-align: top; }
+td pre { margin: 0; white-space: pre-wrap; }
+.line { color: #888; text-align: right; border-right: 1px solid #ccc; }
+.insert { color: #1a7f37; background: #e6ffec; }
+.delete { color: #cf222e; background: #ffebe9; }
+.equal { color: #666; }
+mark { color: inherit; background: none; text-decoration: underline; }
+.gap td { border-top: 1px dashed #ccc; }
+.truncated td { color: #9a6700; background: #fff8c5; }
+";
+
+/// Renders a line diff as an HTML page that looks like the diff printed to
+/// the terminal, so that reviewers of a report see the same changes
+/// highlighted within lines.
+///
+/// The whole diff is rendered, with a row marking where the terminal output
+/// was cut short after `budget` bytes.
+fn render_diff_html<'a>(
+    diff: &'a TextDiff<'a, 'a, 'a, str>,
+    newlines_matter: bool,
+    title: &str,
+    budget: usize,
+) -> String {
+    let title = escape_html(title);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <style>\n{HTML_DIFF_STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n<table>\n"
+    );
+    let mut written_bytes = 0;
+    let mut marked_budget = false;
+    for (idx, group) in diff.grouped_ops(4).iter().enumerate() {
+        if idx > 0 {
+            out.push_str("<tr class=\"gap\"><td colspan=\"4\"></td></tr>\n");
+        }
+        for op in group {
+            for change in diff.iter_inline_changes(op) {
+                if !marked_budget && written_bytes >= budget {
+                    writeln!(
+                        out,
+                        "<tr class=\"truncated\"><td colspan=\"4\">\
+                         diff truncated here after {budget} bytes</td></tr>"
+                    )
+                    .unwrap();
+                    marked_budget = true;
+                }
+                written_bytes += change.values().iter().map(|(_, x)| x.len()).sum::<usize>();
+                let (class, marker) = match change.tag() {
+                    ChangeTag::Insert => ("insert", "+"),
+                    ChangeTag::Delete => ("delete", "-"),
+                    ChangeTag::Equal => ("equal", ""),
+                };
+                write!(
+                    out,
+                    "<tr class=\"{class}\"><td class=\"line\">{}</td><td class=\"line\">{}</td>\
+                     <td>{marker}</td><td><pre>",
+                    change
+                        .old_index()
+                        .map_or(String::new(), |x| (x + 1).to_string()),
+                    change
+                        .new_index()
+                        .map_or(String::new(), |x| (x + 1).to_string()),
+                )
+                .unwrap();
+                for &(emphasized, value) in change.values() {
+                    let value = render_invisible(value, newlines_matter);
+                    let value = escape_html(value.trim_end_matches(&['\r', '\n'][..]));
+                    if emphasized && change.tag() != ChangeTag::Equal {
+                        write!(out, "<mark>{value}</mark>").unwrap();
+                    } else {
+                        out.push_str(&value);
+                    }
+                }
+                out.push_str("</pre></td></tr>\n");
+            }
+        }
+    }
+    out.push_str("</table>\n</body>\n</html>\n");
+    out
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 /// Prints the summary of a snapshot
 pub fn print_snapshot_summary(
     workspace_root: &Path,
@@ -440,6 +720,13 @@ fn print_info(metadata: &MetaData) {
         println!("{}", out.trim().strip_prefix("---").unwrap().trim_start());
         print_line(width);
     }
+    if !metadata.context().is_empty() {
+        println!("Context:");
+        for (key, value) in metadata.context() {
+            println!("  {}: {}", style(key).bold(), value);
+        }
+        print_line(width);
+    }
 }
 
 /// Encodes a path as an OSC-8 escape sequence. This makes it a clickable link in supported
@@ -474,14 +761,23 @@ pub fn invoke_external_diff_tool(
         }
     };
 
-    // Use snapshot file stem for naming (helps diff tools with syntax detection).
-    // Fall back to generic name - these are ephemeral temp files anyway.
-    let base_name = snapshot_file
-        .and_then(|p| p.file_stem())
+    // Use the snapshot file name for naming, and keep the extension of
+    // snapshots stored as `name.snap.sql` (helps diff tools with syntax
+    // detection).  Fall back to generic name - these are ephemeral temp
+    // files anyway.
+    let (base_name, extension) = match snapshot_file
+        .and_then(|p| p.file_name())
         .and_then(|s| s.to_str())
-        .unwrap_or("snapshot");
-    let old_path = dir.path().join(format!("{base_name}.old.snap"));
-    let new_path = dir.path().join(format!("{base_name}.new.snap"));
+        .and_then(|s| s.rsplit_once(".snap"))
+    {
+        Some((base_name, extension)) => match extension.strip_prefix('.') {
+            Some(extension) if !extension.is_empty() => (base_name, extension),
+            _ => (base_name, "snap"),
+        },
+        None => ("snapshot", "snap"),
+    };
+    let old_path = dir.path().join(format!("{base_name}.old.{extension}"));
+    let new_path = dir.path().join(format!("{base_name}.new.{extension}"));
 
     // Write old content
     if let Err(err) = std::fs::write(&old_path, old_content) {
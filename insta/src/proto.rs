@@ -0,0 +1,193 @@
+use std::cmp::Ordering;
+use std::fmt::Write;
+
+use protobuf::reflect::{MessageRef, ReflectFieldRef, ReflectValueRef};
+use protobuf::{MessageDyn, UnknownValueRef};
+
+/// Formats a protobuf message in the protobuf text format.
+///
+/// Fields are written in the order of their numbers, the entries of maps
+/// in the order of their keys, and fields that are not known to the message
+/// descriptor last, as `number: value`.  Strings keep their printable
+/// Unicode characters.
+///
+/// For every `(path, replacement)` in `redactions` the values of the field
+/// at `path` are replaced.  A path is made of field names separated by
+/// dots, such as `user.email`, and covers every element of repeated fields
+/// on the way.  The `key` and `value` of map entries are addressed like the
+/// fields of a message.
+pub fn render_proto(message: &dyn MessageDyn, redactions: &[(&str, String)]) -> String {
+    let mut rv = String::new();
+    write_message(&MessageRef::from(message), "", redactions, 0, &mut rv);
+    rv.trim_end().to_string()
+}
+
+fn write_message(
+    message: &MessageRef<'_>,
+    path: &str,
+    redactions: &[(&str, String)],
+    indent: usize,
+    out: &mut String,
+) {
+    let mut fields = message.descriptor_dyn().fields().collect::<Vec<_>>();
+    fields.sort_by_key(|field| field.number());
+    for field in fields {
+        let name = field.name();
+        let path = join_path(path, name);
+        match field.get_reflect(&**message) {
+            ReflectFieldRef::Optional(value) => {
+                if let Some(value) = value.value() {
+                    write_field(name, value, &path, redactions, indent, out);
+                }
+            }
+            ReflectFieldRef::Repeated(values) => {
+                for value in values {
+                    write_field(name, value, &path, redactions, indent, out);
+                }
+            }
+            ReflectFieldRef::Map(map) => {
+                let mut entries = map.into_iter().collect::<Vec<_>>();
+                entries.sort_by(|a, b| compare_keys(&a.0, &b.0));
+                for (key, value) in entries {
+                    if let Some(replacement) = find_redaction(&path, redactions) {
+                        writeln!(
+                            out,
+                            "{:indent$}{name}: {replacement:?}",
+                            "",
+                            indent = indent * 2
+                        )
+                        .unwrap();
+                        continue;
+                    }
+                    writeln!(out, "{:indent$}{name} {{", "", indent = indent * 2).unwrap();
+                    let key_path = join_path(&path, "key");
+                    write_field("key", key, &key_path, redactions, indent + 1, out);
+                    let value_path = join_path(&path, "value");
+                    write_field("value", value, &value_path, redactions, indent + 1, out);
+                    writeln!(out, "{:indent$}}}", "", indent = indent * 2).unwrap();
+                }
+            }
+        }
+    }
+
+    let mut unknown = message.unknown_fields_dyn().iter().collect::<Vec<_>>();
+    unknown.sort_by_key(|(number, _)| *number);
+    for (number, value) in unknown {
+        let value = match value {
+            UnknownValueRef::Fixed32(x) => x.to_string(),
+            UnknownValueRef::Fixed64(x) | UnknownValueRef::Varint(x) => x.to_string(),
+            UnknownValueRef::LengthDelimited(bytes) => quote_bytes(bytes),
+        };
+        writeln!(out, "{:indent$}{number}: {value}", "", indent = indent * 2).unwrap();
+    }
+}
+
+fn write_field(
+    name: &str,
+    value: ReflectValueRef<'_>,
+    path: &str,
+    redactions: &[(&str, String)],
+    indent: usize,
+    out: &mut String,
+) {
+    write!(out, "{:indent$}{name}", "", indent = indent * 2).unwrap();
+    if let Some(replacement) = find_redaction(path, redactions) {
+        writeln!(out, ": {replacement:?}").unwrap();
+        return;
+    }
+    match value {
+        ReflectValueRef::Message(message) => {
+            out.push_str(" {\n");
+            write_message(&message, path, redactions, indent + 1, out);
+            writeln!(out, "{:indent$}}}", "", indent = indent * 2).unwrap();
+        }
+        ReflectValueRef::Enum(descriptor, number) => match descriptor.value_by_number(number) {
+            Some(value) => writeln!(out, ": {}", value.name()).unwrap(),
+            None => writeln!(out, ": {number}").unwrap(),
+        },
+        ReflectValueRef::String(x) => writeln!(out, ": {x:?}").unwrap(),
+        ReflectValueRef::Bytes(x) => writeln!(out, ": {}", quote_bytes(x)).unwrap(),
+        ReflectValueRef::U32(x) => writeln!(out, ": {x}").unwrap(),
+        ReflectValueRef::U64(x) => writeln!(out, ": {x}").unwrap(),
+        ReflectValueRef::I32(x) => writeln!(out, ": {x}").unwrap(),
+        ReflectValueRef::I64(x) => writeln!(out, ": {x}").unwrap(),
+        ReflectValueRef::F32(x) => writeln!(out, ": {x}").unwrap(),
+        ReflectValueRef::F64(x) => writeln!(out, ": {x}").unwrap(),
+        ReflectValueRef::Bool(x) => writeln!(out, ": {x}").unwrap(),
+    }
+}
+
+fn join_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{path}.{name}")
+    }
+}
+
+fn find_redaction<'a>(path: &str, redactions: &'a [(&str, String)]) -> Option<&'a str> {
+    redactions
+        .iter()
+        .find(|(x, _)| *x == path)
+        .map(|(_, replacement)| replacement.as_str())
+}
+
+/// Orders map keys, which are integers, booleans or strings.
+fn compare_keys(a: &ReflectValueRef<'_>, b: &ReflectValueRef<'_>) -> Ordering {
+    fn as_int(value: &ReflectValueRef<'_>) -> Option<i128> {
+        match *value {
+            ReflectValueRef::U32(x) => Some(x.into()),
+            ReflectValueRef::U64(x) => Some(x.into()),
+            ReflectValueRef::I32(x) => Some(x.into()),
+            ReflectValueRef::I64(x) => Some(x.into()),
+            ReflectValueRef::Bool(x) => Some(x.into()),
+            _ => None,
+        }
+    }
+    match (as_int(a), as_int(b), a, b) {
+        (Some(a), Some(b), _, _) => a.cmp(&b),
+        (_, _, ReflectValueRef::String(a), ReflectValueRef::String(b)) => a.cmp(b),
+        _ => Ordering::Equal,
+    }
+}
+
+/// Quotes bytes like the protobuf text format, with printable ASCII kept
+/// and everything else escaped.
+fn quote_bytes(bytes: &[u8]) -> String {
+    let mut rv = String::from("\"");
+    for &byte in bytes {
+        match byte {
+            b'"' => rv.push_str("\\\""),
+            b'\\' => rv.push_str("\\\\"),
+            b'\n' => rv.push_str("\\n"),
+            b'\r' => rv.push_str("\\r"),
+            b'\t' => rv.push_str("\\t"),
+            0x20..=0x7e => rv.push(byte as char),
+            _ => write!(rv, "\\x{byte:02x}").unwrap(),
+        }
+    }
+    rv.push('"');
+    rv
+}
+
+#[test]
+fn test_render_proto_map() {
+    use protobuf::well_known_types::struct_::{Struct, Value};
+
+    let mut message = Struct::new();
+    for (key, number) in [("b", 2.0), ("a", 1.0), ("c", 3.5)] {
+        let mut value = Value::new();
+        value.set_number_value(number);
+        message.fields.insert(key.into(), value);
+    }
+    assert_eq!(
+        render_proto(&message, &[("fields.value.number_value", "[n]".into())]),
+        "fields {\n  key: \"a\"\n  value {\n    number_value: \"[n]\"\n  }\n}\n\
+         fields {\n  key: \"b\"\n  value {\n    number_value: \"[n]\"\n  }\n}\n\
+         fields {\n  key: \"c\"\n  value {\n    number_value: \"[n]\"\n  }\n}"
+    );
+    assert_eq!(
+        render_proto(&message, &[("fields", "[fields]".into())]),
+        "fields: \"[fields]\"\nfields: \"[fields]\"\nfields: \"[fields]\""
+    );
+}
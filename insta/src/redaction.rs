@@ -1,6 +1,8 @@
 use pest::Parser;
 use pest_derive::Parser;
 use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
 use std::fmt;
 
 use crate::content::Content;
@@ -140,6 +142,10 @@ where
 /// (which need to retain order) and sets (which should be given a stable order)
 /// look the same.
 ///
+/// The selector has to match the collection itself, so `.items` sorts a
+/// sequence while `.items[]` sorts each of its items.  To sort a collection
+/// after other redactions of it, chain them with [`Redaction::sorted`].
+///
 /// ```rust
 /// # use insta::{Settings, sorted_redaction};
 /// # let mut settings = Settings::new();
@@ -166,9 +172,118 @@ pub fn sorted_redaction() -> Redaction {
     dynamic_redaction(sort)
 }
 
+/// Creates a dynamic redaction that sorts the sequence at the selector by a
+/// key of its items.
+///
+/// The key is a selector that is applied to every item, so `.id` sorts a
+/// sequence of objects by their `id` field.  Items with equal keys, or
+/// without a value at the key, keep their order and items without a value
+/// at the key come first.  This makes unordered query results deterministic
+/// when their items cannot be compared as a whole.
+///
+/// ```rust
+/// # use insta::{Settings, sorted_by_redaction};
+/// # let mut settings = Settings::new();
+/// settings.add_redaction(".users", sorted_by_redaction(".id"));
+/// ```
+///
+/// # Panics
+///
+/// Panics if the key is not a valid selector.
+#[cfg_attr(docsrs, doc(cfg(feature = "redactions")))]
+pub fn sorted_by_redaction(key: &str) -> Redaction {
+    let key = Selector::parse(key).unwrap().make_static();
+    dynamic_redaction(move |mut value: Content, _path| {
+        if let Content::Seq(ref mut val) = value.resolve_inner_mut() {
+            let mut keyed = std::mem::take(val)
+                .into_iter()
+                .map(|item| (key.select(item.clone()), item))
+                .collect::<Vec<_>>();
+            keyed.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+            *val = keyed.into_iter().map(|(_, item)| item).collect();
+        }
+        value
+    })
+}
+
+thread_local! {
+    /// How many documents are being redacted on this thread, which is more
+    /// than one while settings redact a value the macros redacted before.
+    static NUMBERING_DEPTH: Cell<usize> = const { Cell::new(0) };
+    /// The values [`numbered_redaction`] replaced in the current document,
+    /// by label in the order they were first seen.
+    static NUMBERED_VALUES: RefCell<BTreeMap<String, Vec<Content>>> = RefCell::new(BTreeMap::new());
+}
+
+/// Runs `f` as the redaction of one document, so that the numbers of
+/// [`numbered_redaction`] start at one for it.  Nested calls belong to the
+/// outermost document.
+pub(crate) fn with_numbering_scope<R, F: FnOnce() -> R>(f: F) -> R {
+    struct Guard;
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            if NUMBERING_DEPTH.with(|x| x.replace(x.get() - 1)) == 1 {
+                NUMBERED_VALUES.with(|x| x.borrow_mut().clear());
+            }
+        }
+    }
+
+    if NUMBERING_DEPTH.with(|x| x.replace(x.get() + 1)) == 0 {
+        NUMBERED_VALUES.with(|x| x.borrow_mut().clear());
+    }
+    let _guard = Guard;
+    f()
+}
+
+/// Creates a redaction that replaces every distinct value with a numbered
+/// token such as `[id#1]`.
+///
+/// Equal values get the same number within a snapshot, so the snapshot
+/// still shows which entries refer to the same object when their UUIDs,
+/// addresses or connection ids change from run to run.  Values are numbered
+/// in the order they are redacted, starting at one for every snapshot, and
+/// missing values such as `None` are kept.  Redactions with the same label
+/// share their numbers, so `.id` and `.parent_id` can refer to the same
+/// objects:
+///
+/// ```rust
+/// # use insta::{Settings, numbered_redaction};
+/// # let mut settings = Settings::new();
+/// settings.add_redaction(".nodes[].id", numbered_redaction("id"));
+/// settings.add_redaction(".nodes[].parent_id", numbered_redaction("id"));
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "redactions")))]
+pub fn numbered_redaction(label: &str) -> Redaction {
+    let label = label.to_string();
+    dynamic_redaction(move |value: Content, _path: ContentPath| -> Content {
+        // an id in an `Option` refers to the same object as the bare id
+        let value = match value.resolve_inner() {
+            Content::None | Content::Unit => return value,
+            inner => inner.clone(),
+        };
+        let number = NUMBERED_VALUES.with(|x| {
+            let mut numbered = x.borrow_mut();
+            let values = numbered.entry(label.clone()).or_default();
+            match values.iter().position(|x| *x == value) {
+                Some(idx) => idx + 1,
+                None => {
+                    values.push(value);
+                    values.len()
+                }
+            }
+        });
+        Content::from(format!("[{label}#{number}]"))
+    })
+}
+
 /// Creates a redaction that rounds floating point numbers to a given
 /// number of decimal places.
 ///
+/// Floating point numbers nested in the value at the selector are rounded
+/// too, so `.points` rounds all coordinates of a sequence of points.  Numbers
+/// that round to zero are written as `0.0` even if they were negative.
+///
 /// ```rust
 /// # use insta::{Settings, rounded_redaction};
 /// # let mut settings = Settings::new();
@@ -176,18 +291,99 @@ pub fn sorted_redaction() -> Redaction {
 /// ```
 #[cfg_attr(docsrs, doc(cfg(feature = "redactions")))]
 pub fn rounded_redaction(decimals: usize) -> Redaction {
-    dynamic_redaction(move |value: Content, _path: ContentPath| -> Content {
-        let f = match value.resolve_inner() {
-            Content::F32(f) => *f as f64,
-            Content::F64(f) => *f,
-            _ => return value,
-        };
-        let x = 10f64.powf(decimals as f64);
-        Content::F64((f * x).round() / x)
+    let x = 10f64.powf(decimals as f64);
+    dynamic_redaction(move |mut value: Content, _path: ContentPath| -> Content {
+        value.walk(&mut |content| {
+            let f = match *content {
+                Content::F32(f) => f as f64,
+                Content::F64(f) => f,
+                _ => return true,
+            };
+            // adding zero turns a negative zero into a positive one
+            *content = Content::F64((f * x).round() / x + 0.0);
+            false
+        });
+        value
+    })
+}
+
+/// Creates a redaction that writes decimal numbers in a canonical form.
+///
+/// Types like `rust_decimal::Decimal` and `bigdecimal::BigDecimal`
+/// serialize to strings that keep their internal scale, so `1.50` and `1.5`
+/// show up as a snapshot change even though the values are equal.  String
+/// values at the selector, including nested ones, that hold a decimal number
+/// with a fraction or an exponent have trailing zeros, leading zeros and the
+/// exponent removed (`"1.500"` and `"15E-1"` both become `"1.5"`).  Strings
+/// holding plain integers are left alone.
+///
+/// Only the selected values are rewritten, as other strings such as version
+/// numbers can look like decimals too.
+///
+/// ```rust
+/// # use insta::{Settings, decimal_redaction};
+/// # let mut settings = Settings::new();
+/// settings.add_redaction(".invoice.total", decimal_redaction());
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "redactions")))]
+pub fn decimal_redaction() -> Redaction {
+    dynamic_redaction(|mut value: Content, _path: ContentPath| -> Content {
+        value.normalize_decimals();
+        value
     })
 }
 
 impl Redaction {
+    /// Chains another redaction after this one.
+    ///
+    /// The returned redaction passes the value at the selector through this
+    /// redaction and then through `next`, so several stages can be applied to
+    /// the same selector, for instance to redact the items of a sequence
+    /// before sorting it:
+    ///
+    /// ```rust
+    /// # use insta::{Settings, dynamic_redaction};
+    /// # use insta::internals::Content;
+    /// # let mut settings = Settings::new();
+    /// settings.add_redaction(
+    ///     ".paths",
+    ///     dynamic_redaction(|value, _| match value {
+    ///         Content::Seq(paths) => Content::Seq(
+    ///             paths
+    ///                 .iter()
+    ///                 .map(|x| x.as_str().unwrap_or_default().replace('\\', "/").into())
+    ///                 .collect(),
+    ///         ),
+    ///         value => value,
+    ///     })
+    ///     .then(insta::sorted_redaction()),
+    /// );
+    /// ```
+    pub fn then<R: Into<Redaction>>(self, next: R) -> Redaction {
+        let next = next.into();
+        Redaction::Dynamic(Box::new(move |value, path| {
+            next.redact(self.redact(value, path.0), path.0)
+        }))
+    }
+
+    /// Sorts the value after this redaction.
+    ///
+    /// This is a shortcut to `self.then(sorted_redaction())`.
+    pub fn sorted(self) -> Redaction {
+        self.then(sorted_redaction())
+    }
+
+    /// Sorts the sequence after this redaction by a key of its items.
+    ///
+    /// This is a shortcut to `self.then(sorted_by_redaction(key))`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not a valid selector.
+    pub fn sorted_by(self, key: &str) -> Redaction {
+        self.then(sorted_by_redaction(key))
+    }
+
     /// Performs the redaction of the value at the given path.
     fn redact(&self, value: Content, path: &[PathItem]) -> Content {
         match *self {
@@ -262,6 +458,49 @@ pub struct Selector<'a> {
     selectors: Vec<Vec<Segment<'a>>>,
 }
 
+impl fmt::Display for Segment<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Segment::DeepWildcard => write!(f, ".**"),
+            Segment::Wildcard => write!(f, ".*"),
+            Segment::Key(ref key) => {
+                let mut chars = key.chars();
+                if chars
+                    .next()
+                    .map_or(false, |c| c.is_alphabetic() || c == '_' || c == '$')
+                    && chars.all(|c| c.is_alphanumeric() || c == '_')
+                {
+                    write!(f, ".{key}")
+                } else {
+                    write!(f, "[\"{key}\"]")
+                }
+            }
+            Segment::Index(idx) => write!(f, "[{idx}]"),
+            Segment::Range(None, None) => write!(f, "[]"),
+            Segment::Range(Some(start), None) => write!(f, "[{start}:]"),
+            Segment::Range(None, Some(end)) => write!(f, "[:{end}]"),
+            Segment::Range(Some(start), Some(end)) => write!(f, "[{start}:{end}]"),
+        }
+    }
+}
+
+impl fmt::Display for Selector<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, segments) in self.selectors.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ")?;
+            }
+            if segments.is_empty() {
+                write!(f, ".")?;
+            }
+            for segment in segments {
+                write!(f, "{segment}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl<'a> Selector<'a> {
     pub fn parse(selector: &'a str) -> Result<Selector<'a>, SelectorParseError> {
         let pair = SelectParser::parse(Rule::selectors, selector)
@@ -427,6 +666,61 @@ impl<'a> Selector<'a> {
         false
     }
 
+    /// Returns `true` if the selector matches at most one location.
+    pub fn is_exact(&self) -> bool {
+        self.selectors.len() == 1
+            && self.selectors[0]
+                .iter()
+                .all(|x| matches!(x, Segment::Key(_) | Segment::Index(_)))
+    }
+
+    /// Returns all values matched by the selector in document order.
+    ///
+    /// The values nested within a matched value are not considered.
+    pub fn select(&self, value: Content) -> Vec<Content> {
+        let mut rv = vec![];
+        self.select_impl(value, &mut vec![], &mut rv);
+        rv
+    }
+
+    fn select_impl(&self, value: Content, path: &mut Vec<PathItem>, selected: &mut Vec<Content>) {
+        if self.is_match(path) {
+            selected.push(value);
+            return;
+        }
+        match value {
+            Content::Map(map) => {
+                for (key, value) in map {
+                    path.push(PathItem::Content(key));
+                    self.select_impl(value, path, selected);
+                    path.pop();
+                }
+            }
+            Content::Seq(seq)
+            | Content::Tuple(seq)
+            | Content::TupleStruct(_, seq)
+            | Content::TupleVariant(_, _, _, seq) => {
+                let len = seq.len();
+                for (idx, value) in seq.into_iter().enumerate() {
+                    path.push(PathItem::Index(idx as u64, len as u64));
+                    self.select_impl(value, path, selected);
+                    path.pop();
+                }
+            }
+            Content::Struct(_, fields) | Content::StructVariant(_, _, _, fields) => {
+                for (key, value) in fields {
+                    path.push(PathItem::Field(key));
+                    self.select_impl(value, path, selected);
+                    path.pop();
+                }
+            }
+            Content::NewtypeStruct(_, inner)
+            | Content::NewtypeVariant(_, _, _, inner)
+            | Content::Some(inner) => self.select_impl(*inner, path, selected),
+            _ => {}
+        }
+    }
+
     pub fn redact(&self, value: Content, redaction: &Redaction) -> Content {
         self.redact_impl(value, redaction, &mut vec![])
     }
@@ -545,3 +839,181 @@ fn test_range_checks() {
     assert_eq!(PathItem::Index(1, 10).range_check(Some(1), None), true);
     assert_eq!(PathItem::Index(9, 10).range_check(Some(1), None), true);
 }
+
+#[test]
+fn test_sorted_by_redaction() {
+    let item = |id: u64, name: &str| {
+        Content::Map(vec![
+            (Content::from("id"), Content::from(id)),
+            (Content::from("name"), Content::from(name)),
+        ])
+    };
+    let value = Content::Map(vec![(
+        Content::from("users"),
+        Content::Seq(vec![
+            item(3, "c"),
+            item(1, "a"),
+            Content::Unit,
+            item(2, "b"),
+        ]),
+    )]);
+
+    let sorted = Selector::parse(".users")
+        .unwrap()
+        .redact(value, &sorted_by_redaction(".id"));
+    assert_eq!(
+        sorted,
+        Content::Map(vec![(
+            Content::from("users"),
+            Content::Seq(vec![
+                Content::Unit,
+                item(1, "a"),
+                item(2, "b"),
+                item(3, "c")
+            ]),
+        )])
+    );
+}
+
+#[test]
+fn test_redaction_pipeline() {
+    let value = Content::Map(vec![(
+        Content::from("tags"),
+        Content::Seq(vec![
+            Content::from("b"),
+            Content::from("C"),
+            Content::from("a"),
+        ]),
+    )]);
+    let lowercase = dynamic_redaction(|value: Content, _path| match value {
+        Content::Seq(items) => Content::Seq(
+            items
+                .iter()
+                .map(|x| Content::from(x.as_str().unwrap().to_lowercase()))
+                .collect(),
+        ),
+        value => value,
+    });
+
+    let redacted = Selector::parse(".tags")
+        .unwrap()
+        .redact(value, &lowercase.sorted());
+    assert_eq!(
+        redacted,
+        Content::Map(vec![(
+            Content::from("tags"),
+            Content::Seq(vec![
+                Content::from("a"),
+                Content::from("b"),
+                Content::from("c")
+            ]),
+        )])
+    );
+}
+
+#[test]
+fn test_rounded_redaction() {
+    let value = Content::Map(vec![
+        (Content::from("x"), Content::from(1.0f64 / 3.0)),
+        (
+            Content::from("points"),
+            Content::Seq(vec![
+                Content::Tuple(vec![Content::from(0.25f32), Content::from(-0.0001f64)]),
+                Content::Some(Box::new(Content::from(2.0f64 / 3.0))),
+                Content::from("1.23456"),
+            ]),
+        ),
+    ]);
+
+    let rounded = Selector::parse(".points")
+        .unwrap()
+        .redact(value, &rounded_redaction(2));
+    // zeros compare equal regardless of their sign
+    let zero = Selector::parse(".points[0][1]")
+        .unwrap()
+        .select(rounded.clone());
+    assert!(zero[0].as_f64().unwrap().is_sign_positive());
+    assert_eq!(
+        rounded,
+        Content::Map(vec![
+            (Content::from("x"), Content::from(1.0f64 / 3.0)),
+            (
+                Content::from("points"),
+                Content::Seq(vec![
+                    Content::Tuple(vec![Content::F64(0.25), Content::F64(0.0)]),
+                    Content::Some(Box::new(Content::F64(0.67))),
+                    Content::from("1.23456"),
+                ]),
+            ),
+        ])
+    );
+}
+
+#[test]
+fn test_numbered_redaction() {
+    let node = |id: &str, parent: &str| {
+        Content::Map(vec![
+            (Content::from("id"), Content::from(id)),
+            (Content::from("parent"), Content::from(parent)),
+        ])
+    };
+    let nodes = |items| Content::Map(vec![(Content::from("nodes"), Content::Seq(items))]);
+    let value = nodes(vec![
+        node("f00", "a11"),
+        node("b22", "f00"),
+        node("c33", "f00"),
+    ]);
+    let redact = |value: Content| {
+        with_numbering_scope(|| {
+            let value = Selector::parse(".nodes[].id")
+                .unwrap()
+                .redact(value, &numbered_redaction("id"));
+            Selector::parse(".nodes[].parent")
+                .unwrap()
+                .redact(value, &numbered_redaction("id"))
+        })
+    };
+
+    let expected = nodes(vec![
+        node("[id#1]", "[id#4]"),
+        node("[id#2]", "[id#1]"),
+        node("[id#3]", "[id#1]"),
+    ]);
+    assert_eq!(redact(value.clone()), expected);
+    // every document is numbered from one
+    assert_eq!(redact(value), expected);
+}
+
+#[test]
+fn test_select() {
+    let value = Content::Map(vec![
+        (
+            Content::from("data"),
+            Content::Map(vec![(
+                Content::from("items"),
+                Content::Seq(vec![Content::from(1u64), Content::from(2u64)]),
+            )]),
+        ),
+        (Content::from("total"), Content::from(2u64)),
+    ]);
+
+    let selector = Selector::parse(".data.items[1]").unwrap();
+    assert!(selector.is_exact());
+    assert_eq!(selector.select(value.clone()), vec![Content::from(2u64)]);
+
+    let selector = Selector::parse(".data.items[], .total").unwrap();
+    assert!(!selector.is_exact());
+    assert_eq!(
+        selector.select(value.clone()),
+        vec![
+            Content::from(1u64),
+            Content::from(2u64),
+            Content::from(2u64)
+        ]
+    );
+
+    assert!(Selector::parse(".missing")
+        .unwrap()
+        .select(value)
+        .is_empty());
+}
@@ -11,15 +11,18 @@ use std::{borrow::Cow, env};
 
 use crate::settings::Settings;
 use crate::snapshot::{
-    MetaData, PendingInlineSnapshot, Snapshot, SnapshotContents, SnapshotKind, TextSnapshotContents,
+    pending_file_path, MetaData, PendingInlineSnapshot, Snapshot, SnapshotContents, SnapshotKind,
+    TextSnapshotContents,
 };
-use crate::utils::{path_to_storage, style};
+use crate::utils::{long_path, path_to_storage, style};
 use crate::{env::get_tool_config, output::SnapshotPrinter};
 use crate::{
     env::{
-        memoize_snapshot_file, pending_snapshot_path, snapshot_update_behavior, OutputBehavior,
+        get_pending_dir, memoize_assertion, memoize_failed_assertion, memoize_snapshot_file,
+        pending_snapshot_path, snapshot_update_behavior, OutputBehavior, SnapshotLayout,
         SnapshotUpdateBehavior, ToolConfig,
     },
+    naming::{hashed_module_dir, sanitize_snapshot_name},
     snapshot::TextSnapshotKind,
 };
 
@@ -29,7 +32,13 @@ static TEST_NAME_COUNTERS: Lazy<Mutex<BTreeMap<String, usize>>> =
     Lazy::new(|| Mutex::new(BTreeMap::new()));
 static TEST_NAME_CLASH_DETECTION: Lazy<Mutex<BTreeMap<String, bool>>> =
     Lazy::new(|| Mutex::new(BTreeMap::new()));
+static SNAPSHOT_FILE_NAMES: Lazy<Mutex<BTreeMap<PathBuf, (PathBuf, String)>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
 static INLINE_DUPLICATES: Lazy<Mutex<BTreeSet<String>>> = Lazy::new(|| Mutex::new(BTreeSet::new()));
+static PENDING_SECTION_SNAPSHOTS: Lazy<Mutex<BTreeSet<PathBuf>>> =
+    Lazy::new(|| Mutex::new(BTreeSet::new()));
+static ASSERTION_COUNTS: Lazy<Mutex<BTreeMap<String, usize>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
 
 thread_local! {
     static RECORDED_DUPLICATES: RefCell<Vec<BTreeMap<String, Snapshot>>> = RefCell::default()
@@ -69,6 +78,81 @@ pub struct AutoName;
 
 pub struct InlineValue<'a>(pub &'a str);
 
+/// The location that owns the file snapshots of assertions.
+///
+/// It is created with [`snapshot_owner!`](crate::snapshot_owner!) and set
+/// with [`Settings::set_snapshot_owner`].  File snapshots asserted while it
+/// is set are named and stored as if the assertion was made where the owner
+/// was created, which lets assertions in shared test helpers write snapshots
+/// into the tree of the crate calling them.  Inline snapshots are unaffected.
+#[derive(Debug, Clone)]
+pub struct SnapshotOwner {
+    workspace: Arc<PathBuf>,
+    function_name: &'static str,
+    module_path: &'static str,
+    file: &'static str,
+    line: u32,
+}
+
+impl SnapshotOwner {
+    #[doc(hidden)]
+    pub fn new(
+        workspace: Arc<PathBuf>,
+        function_name: &'static str,
+        module_path: &'static str,
+        file: &'static str,
+        line: u32,
+    ) -> SnapshotOwner {
+        SnapshotOwner {
+            workspace,
+            function_name,
+            module_path,
+            file,
+            line,
+        }
+    }
+
+    /// Returns the file the owner was created in.
+    pub fn file(&self) -> &'static str {
+        self.file
+    }
+
+    /// Returns the module the owner was created in.
+    pub fn module_path(&self) -> &'static str {
+        self.module_path
+    }
+}
+
+/// The reference value of a table-driven test case.
+///
+/// It is written as `@"..."` in a `#[case]` attribute of [`cases`](crate::cases)
+/// and passed to an assertion macro in place of an inline snapshot:
+/// `assert_snapshot!(value, @expected)`.
+#[cfg(feature = "macros")]
+#[derive(Debug, Clone, Copy)]
+pub struct InlineCase {
+    reference: &'static str,
+    line: u32,
+}
+
+#[cfg(feature = "macros")]
+impl InlineCase {
+    #[doc(hidden)]
+    pub fn new(reference: &'static str, line: u32) -> InlineCase {
+        InlineCase { reference, line }
+    }
+
+    /// Returns the reference value.
+    pub fn reference(&self) -> &'static str {
+        self.reference
+    }
+
+    /// Returns the line of the `#[case]` attribute holding the value.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+}
+
 /// The name of a snapshot, from which the path is derived.
 type SnapshotName<'a> = Option<Cow<'a, str>>;
 
@@ -77,6 +161,83 @@ pub struct BinarySnapshotValue<'a> {
     pub content: Vec<u8>,
 }
 
+/// Converts the values `assert_binary_snapshot!` accepts into the contents of
+/// the snapshot.
+///
+/// Owned buffers are moved into the snapshot, borrowed bytes are copied.
+pub trait IntoBinaryContent {
+    fn into_binary_content(self) -> Vec<u8>;
+}
+
+impl IntoBinaryContent for Vec<u8> {
+    fn into_binary_content(self) -> Vec<u8> {
+        self
+    }
+}
+
+impl IntoBinaryContent for Box<[u8]> {
+    fn into_binary_content(self) -> Vec<u8> {
+        self.into_vec()
+    }
+}
+
+impl IntoBinaryContent for String {
+    fn into_binary_content(self) -> Vec<u8> {
+        self.into_bytes()
+    }
+}
+
+impl<const N: usize> IntoBinaryContent for [u8; N] {
+    fn into_binary_content(self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+
+impl IntoBinaryContent for Cow<'_, [u8]> {
+    fn into_binary_content(self) -> Vec<u8> {
+        self.into_owned()
+    }
+}
+
+impl<T: AsRef<[u8]> + ?Sized> IntoBinaryContent for &T {
+    fn into_binary_content(self) -> Vec<u8> {
+        self.as_ref().to_vec()
+    }
+}
+
+/// The rendered value of a text snapshot assertion.
+pub struct SnapshotText<'a> {
+    content: &'a str,
+    /// Whether the value is serialized to YAML or JSON, for which redactions
+    /// of volatile values are suggested.
+    structured: bool,
+}
+
+impl<'a> From<&'a str> for SnapshotText<'a> {
+    fn from(content: &'a str) -> Self {
+        SnapshotText {
+            content,
+            structured: false,
+        }
+    }
+}
+
+impl<'a> From<&'a String> for SnapshotText<'a> {
+    fn from(content: &'a String) -> Self {
+        content.as_str().into()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a> From<&'a crate::serialization::SerializedValue> for SnapshotText<'a> {
+    fn from(value: &'a crate::serialization::SerializedValue) -> Self {
+        SnapshotText {
+            content: value.as_str(),
+            structured: value.is_structured(),
+        }
+    }
+}
+
 pub enum SnapshotValue<'a> {
     /// A text snapshot that gets stored along with the metadata in the same file.
     FileText {
@@ -84,6 +245,9 @@ pub enum SnapshotValue<'a> {
 
         /// The new generated value to compare against any previously approved content.
         content: &'a str,
+
+        /// Whether the value is serialized to YAML or JSON.
+        structured: bool,
     },
 
     /// An inline snapshot.
@@ -93,6 +257,9 @@ pub enum SnapshotValue<'a> {
 
         /// The new generated value to compare against any previously approved content.
         content: &'a str,
+
+        /// Whether the value is serialized to YAML or JSON.
+        structured: bool,
     },
 
     /// A binary snapshot that gets stored as a separate file next to the metadata file.
@@ -107,56 +274,68 @@ pub enum SnapshotValue<'a> {
     },
 }
 
-impl<'a> From<(AutoName, &'a str)> for SnapshotValue<'a> {
-    fn from((_, content): (AutoName, &'a str)) -> Self {
+impl<'a, T: Into<SnapshotText<'a>>> From<(AutoName, T)> for SnapshotValue<'a> {
+    fn from((_, text): (AutoName, T)) -> Self {
+        let text = text.into();
         SnapshotValue::FileText {
             name: None,
-            content,
+            content: text.content,
+            structured: text.structured,
         }
     }
 }
 
-impl<'a> From<(Option<String>, &'a str)> for SnapshotValue<'a> {
-    fn from((name, content): (Option<String>, &'a str)) -> Self {
+impl<'a, T: Into<SnapshotText<'a>>> From<(Option<String>, T)> for SnapshotValue<'a> {
+    fn from((name, text): (Option<String>, T)) -> Self {
+        let text = text.into();
         SnapshotValue::FileText {
             name: name.map(Cow::Owned),
-            content,
+            content: text.content,
+            structured: text.structured,
         }
     }
 }
 
-impl<'a> From<(String, &'a str)> for SnapshotValue<'a> {
-    fn from((name, content): (String, &'a str)) -> Self {
+impl<'a, T: Into<SnapshotText<'a>>> From<(String, T)> for SnapshotValue<'a> {
+    fn from((name, text): (String, T)) -> Self {
+        let text = text.into();
         SnapshotValue::FileText {
             name: Some(Cow::Owned(name)),
-            content,
+            content: text.content,
+            structured: text.structured,
         }
     }
 }
 
-impl<'a> From<(Option<&'a str>, &'a str)> for SnapshotValue<'a> {
-    fn from((name, content): (Option<&'a str>, &'a str)) -> Self {
+impl<'a, T: Into<SnapshotText<'a>>> From<(Option<&'a str>, T)> for SnapshotValue<'a> {
+    fn from((name, text): (Option<&'a str>, T)) -> Self {
+        let text = text.into();
         SnapshotValue::FileText {
             name: name.map(Cow::Borrowed),
-            content,
+            content: text.content,
+            structured: text.structured,
         }
     }
 }
 
-impl<'a> From<(&'a str, &'a str)> for SnapshotValue<'a> {
-    fn from((name, content): (&'a str, &'a str)) -> Self {
+impl<'a, T: Into<SnapshotText<'a>>> From<(&'a str, T)> for SnapshotValue<'a> {
+    fn from((name, text): (&'a str, T)) -> Self {
+        let text = text.into();
         SnapshotValue::FileText {
             name: Some(Cow::Borrowed(name)),
-            content,
+            content: text.content,
+            structured: text.structured,
         }
     }
 }
 
-impl<'a> From<(InlineValue<'a>, &'a str)> for SnapshotValue<'a> {
-    fn from((InlineValue(reference_content), content): (InlineValue<'a>, &'a str)) -> Self {
+impl<'a, T: Into<SnapshotText<'a>>> From<(InlineValue<'a>, T)> for SnapshotValue<'a> {
+    fn from((InlineValue(reference_content), text): (InlineValue<'a>, T)) -> Self {
+        let text = text.into();
         SnapshotValue::InlineText {
             reference_content,
-            content,
+            content: text.content,
+            structured: text.structured,
         }
     }
 }
@@ -259,41 +438,115 @@ fn get_snapshot_filename(
     snapshot_name: &str,
     cargo_workspace: &Path,
     is_doctest: bool,
+    is_text: bool,
+    tool_config: &ToolConfig,
 ) -> PathBuf {
     let root = Path::new(cargo_workspace);
     let base = Path::new(assertion_file);
     Settings::with(|settings| {
-        root.join(base.parent().unwrap())
-            .join(settings.snapshot_path())
-            .join({
-                use std::fmt::Write;
-                let mut f = String::new();
-                if settings.prepend_module_to_snapshot() {
-                    if is_doctest {
-                        write!(
-                            &mut f,
-                            "doctest_{}__",
-                            base.file_name()
-                                .unwrap()
-                                .to_string_lossy()
-                                .replace('.', "_")
-                        )
-                        .unwrap();
-                    } else {
-                        write!(&mut f, "{}__", module_path.replace("::", "__")).unwrap();
-                    }
+        let mut rv = root
+            .join(base.parent().unwrap())
+            .join(settings.snapshot_path());
+        let mut f = String::new();
+        if settings.prepend_module_to_snapshot() {
+            let module_path = if is_doctest {
+                Cow::Owned(format!(
+                    "doctest_{}",
+                    base.file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace('.', "_")
+                ))
+            } else {
+                Cow::Borrowed(module_path)
+            };
+            match tool_config.snapshot_layout() {
+                SnapshotLayout::Flat => {
+                    f.push_str(&module_path.replace("::", "__"));
+                    f.push_str("__");
                 }
-                write!(
-                    &mut f,
-                    "{}.snap",
-                    snapshot_name.replace(&['/', '\\'][..], "__")
-                )
-                .unwrap();
-                f
-            })
+                SnapshotLayout::Hashed => rv.push(hashed_module_dir(&module_path)),
+            }
+        }
+        f.push_str(&sanitize_snapshot_name(
+            snapshot_name,
+            tool_config.sanitize_names(),
+        ));
+        f.push_str(".snap");
+        // the extension only applies to text snapshots, binary snapshots
+        // store their contents in a file with an extension of its own
+        if let Some(extension) = settings.snapshot_extension().filter(|_| is_text) {
+            assert!(
+                extension != "new" && !extension.starts_with("new."),
+                "'{extension}' is not allowed as a snapshot extension"
+            );
+            assert!(
+                !extension.contains(['/', '\\']),
+                "snapshot extensions cannot contain path separators"
+            );
+            f.push('.');
+            f.push_str(extension);
+        }
+        rv.join(f)
     })
 }
 
+/// Predicts the file that the snapshot of an assertion in the test function
+/// `test_name` is stored in, with the settings bound on the calling thread.
+///
+/// `name` is the explicit name of the snapshot, if the assertion has one.
+/// The first unnamed assertion of a test is predicted; further ones get a
+/// counter appended to the name.
+#[cfg(feature = "_cargo_insta_internal")]
+pub fn predict_snapshot_file(
+    module_path: &str,
+    assertion_file: &str,
+    test_name: &str,
+    name: Option<&str>,
+    cargo_workspace: &Path,
+    tool_config: &ToolConfig,
+) -> PathBuf {
+    let name = name.unwrap_or_else(|| test_name.strip_prefix("test_").unwrap_or(test_name));
+    get_snapshot_filename(
+        module_path,
+        assertion_file,
+        &add_suffix_to_snapshot_name(Cow::Borrowed(name)),
+        cargo_workspace,
+        false,
+        true,
+        tool_config,
+    )
+}
+
+/// Panics if a snapshot with a different name was already stored in `file`,
+/// which happens when names only differ in characters that are replaced in
+/// file names, or in a file whose path only differs in case, which is the
+/// same file on case-insensitive file systems.
+fn detect_file_name_clash(file: &Path, name: &str) {
+    let mut file_names = SNAPSHOT_FILE_NAMES
+        .lock()
+        .unwrap_or_else(|x| x.into_inner());
+    let key = PathBuf::from(file.to_string_lossy().to_lowercase());
+    match file_names.get(&key) {
+        Some((other_file, other)) if other_file != file => panic!(
+            "Insta snapshot files '{}' and '{}' only differ in case, so they \
+             overwrite each other on case-insensitive file systems such as on \
+             macOS and Windows. Rename '{other}' or '{name}'.",
+            other_file.display(),
+            file.display()
+        ),
+        Some((_, other)) if other != name => panic!(
+            "Insta snapshot names '{other}' and '{name}' are both stored in '{}'. \
+             Rename one of them.",
+            file.display()
+        ),
+        Some(_) => {}
+        None => {
+            file_names.insert(key, (file.to_path_buf(), name.to_string()));
+        }
+    }
+}
+
 /// The context around a snapshot, such as the reference value, location, etc.
 /// (but not including the generated value). Responsible for saving the
 /// snapshot.
@@ -352,8 +605,11 @@ impl<'a> SnapshotAssertionContext<'a> {
                     &name,
                     workspace,
                     is_doctest,
+                    matches!(new_snapshot_value, SnapshotValue::FileText { .. }),
+                    &tool_config,
                 );
-                if fs::metadata(&file).is_ok() {
+                detect_file_name_clash(&file, &name);
+                if fs::metadata(long_path(&file)).is_ok() {
                     match Snapshot::from_file(&file) {
                         Ok(snapshot) => {
                             old_snapshot = Some(snapshot);
@@ -403,7 +659,10 @@ impl<'a> SnapshotAssertionContext<'a> {
                     module_path.replace("::", "__"),
                     None,
                     MetaData::default(),
-                    SnapshotContents::Text(TextSnapshotContents::from_inline_literal(contents)),
+                    SnapshotContents::Text(
+                        TextSnapshotContents::from_inline_literal(contents)
+                            .with_indent_width(tool_config.indent_width()),
+                    ),
                 ));
             }
         };
@@ -472,12 +731,17 @@ impl<'a> SnapshotAssertionContext<'a> {
                 },
                 assertion_line: Some(self.assertion_line),
                 description: settings.description().map(Into::into),
-                expression: if settings.omit_expression() {
+                // a file with sections holds the values of many expressions
+                expression: if settings.omit_expression() || settings.snapshot_section().is_some() {
                     None
                 } else {
                     Some(expr.to_string())
                 },
                 info: settings.info().map(ToOwned::to_owned),
+                context: settings.context().to_vec(),
+                cell_diff: settings.cell_diff(),
+                reference_hash: None,
+                schema: settings.json_schema_reference().map(Into::into),
                 input_file: settings
                     .input_file()
                     .and_then(|x| self.localize_path(x))
@@ -494,8 +758,8 @@ impl<'a> SnapshotAssertionContext<'a> {
         // around.
         if let Some(ref snapshot_file) = self.snapshot_file {
             let target_path = pending_snapshot_path(self.workspace, snapshot_file);
-            let new_file = target_path.with_extension("snap.new");
-            fs::remove_file(new_file).ok();
+            let new_file = pending_file_path(&target_path);
+            fs::remove_file(long_path(&new_file)).ok();
         }
 
         // and add a null pending snapshot to a pending snapshot file if needed
@@ -579,7 +843,7 @@ impl<'a> SnapshotAssertionContext<'a> {
         match snapshot_update {
             SnapshotUpdateBehavior::InPlace => {
                 if let Some(ref snapshot_file) = self.snapshot_file {
-                    new_snapshot.save(snapshot_file)?;
+                    check_writable(new_snapshot.save(snapshot_file), snapshot_file, false)?;
                     if should_print {
                         elog!(
                             "{} {}",
@@ -596,7 +860,11 @@ impl<'a> SnapshotAssertionContext<'a> {
                 if let Some(ref snapshot_file) = self.snapshot_file {
                     // File snapshot - use pending directory if set
                     let target_path = pending_snapshot_path(self.workspace, snapshot_file);
-                    let new_path = new_snapshot.save_new(&target_path)?;
+                    // lets `cargo-insta` notice if the reference changes before
+                    // the pending snapshot is accepted
+                    let new_snapshot = new_snapshot.with_reference_hash(snapshot_file);
+                    let new_path =
+                        check_writable(new_snapshot.save_new(&target_path), &target_path, true)?;
                     if should_print {
                         elog!(
                             "{} {}",
@@ -617,12 +885,16 @@ impl<'a> SnapshotAssertionContext<'a> {
                     // Inline snapshot - use pending directory if set
                     let pending_path = self.pending_snapshots_path.as_ref().unwrap();
                     let target_path = pending_snapshot_path(self.workspace, pending_path);
-                    PendingInlineSnapshot::new(
-                        Some(new_snapshot),
-                        self.old_snapshot.clone(),
-                        self.assertion_line,
-                    )
-                    .save(&target_path)?;
+                    check_writable(
+                        PendingInlineSnapshot::new(
+                            Some(new_snapshot),
+                            self.old_snapshot.clone(),
+                            self.assertion_line,
+                        )
+                        .save(&target_path),
+                        &target_path,
+                        true,
+                    )?;
                 }
             }
             SnapshotUpdateBehavior::NoUpdate => {}
@@ -631,6 +903,149 @@ impl<'a> SnapshotAssertionContext<'a> {
         Ok(snapshot_update)
     }
 
+    fn pending_section_path(&self) -> Option<PathBuf> {
+        let snapshot_file = self.snapshot_file.as_ref()?;
+        Some(pending_file_path(&pending_snapshot_path(
+            self.workspace,
+            snapshot_file,
+        )))
+    }
+
+    /// Returns the pending snapshot written by a failed section of the same
+    /// file earlier in this test run.
+    ///
+    /// Later sections are stored into it so that the pending snapshot holds
+    /// all changes to the file.  Pending snapshots left over from earlier runs
+    /// are ignored.
+    fn pending_section_snapshot(&self) -> Option<Snapshot> {
+        let path = self.pending_section_path()?;
+        let written = PENDING_SECTION_SNAPSHOTS
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .contains(&path);
+        if written {
+            Snapshot::from_file(&path).ok()
+        } else {
+            None
+        }
+    }
+
+    fn record_pending_section_snapshot(&self) {
+        if let Some(path) = self.pending_section_path() {
+            PENDING_SECTION_SNAPSHOTS
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .insert(path);
+        }
+    }
+
+    /// Returns the file the full diff is written to when the printed diff
+    /// exceeds the CI budget.
+    fn diff_report_path(&self, new_snapshot: &Snapshot) -> PathBuf {
+        let target_dir = env::var_os("CARGO_TARGET_DIR")
+            .map(|dir| self.workspace.join(dir))
+            .unwrap_or_else(|| self.workspace.join("target"));
+        let name = match self.snapshot_file {
+            Some(ref file) => file
+                .file_stem()
+                .map_or_else(String::new, |x| x.to_string_lossy().into_owned()),
+            None => format!(
+                "{}__{}",
+                self.module_path.replace("::", "__"),
+                new_snapshot.snapshot_name().unwrap_or("inline")
+            ),
+        };
+        target_dir
+            .join("insta-reports")
+            .join(format!("{name}.diff"))
+    }
+
+    /// Fails an assertion without a stored snapshot under
+    /// `INSTA_UPDATE=forbid`.
+    ///
+    /// Unlike other failures this ignores `INSTA_FORCE_PASS` and panics even
+    /// within `glob!`, since a missing snapshot means the run is not hermetic.
+    fn forbid_missing_snapshot(&self) -> ! {
+        let name = self.snapshot_name.as_deref().unwrap_or("inline snapshot");
+        let location = format!("{}:{}", self.assertion_file, self.assertion_line);
+        memoize_failed_assertion(name, &location);
+        panic!(
+            "snapshot assertion for '{}' at {} has no stored snapshot and \
+             INSTA_UPDATE=forbid does not allow creating one",
+            name, location
+        );
+    }
+
+    /// Returns why a snapshot is quarantined, or `None` if a mismatch fails
+    /// the assertion.
+    ///
+    /// Updates that accept snapshots in place still go ahead, so quarantined
+    /// snapshots can be fixed with `INSTA_UPDATE=always`.
+    fn quarantine_reason(&self) -> Option<String> {
+        if matches!(
+            self.tool_config.snapshot_update(),
+            crate::env::SnapshotUpdate::Always | crate::env::SnapshotUpdate::Force
+        ) {
+            return None;
+        }
+        if Settings::with(|settings| settings.flaky()) {
+            return Some("the `flaky` setting".into());
+        }
+        let name = self.snapshot_name.as_deref()?;
+        let key = format!("{}__{}", self.module_path.replace("::", "__"), name);
+        self.tool_config
+            .quarantined_by(&key)
+            .map(|pattern| format!("`{pattern}` in the insta config"))
+    }
+
+    /// Switches a snapshot with `xfail` to its known divergence, which is
+    /// stored next to the snapshot file with the `.xfail.snap` extension.
+    ///
+    /// Panics if the snapshot matches, since the divergence was fixed then.
+    fn switch_to_known_divergence(&mut self, matches_expected: bool) {
+        let name = self.snapshot_name.as_deref().unwrap_or("unnamed snapshot");
+        let snapshot_file = match self.snapshot_file {
+            Some(ref file) if self.old_snapshot.is_some() => file,
+            Some(_) => panic!(
+                "snapshot assertion for '{}' in line {} expects to fail, but there is no \
+                 stored snapshot of the expected value",
+                name, self.assertion_line
+            ),
+            None => panic!("xfail is only supported for snapshots stored in files"),
+        };
+        // `name.snap` and `name.snap.sql` become `name.xfail.snap` and
+        // `name.xfail.snap.sql`
+        let file_name = snapshot_file.file_name().unwrap().to_string_lossy();
+        let divergence_file = match file_name.rfind(".snap") {
+            Some(idx) => snapshot_file.with_file_name(format!(
+                "{}.xfail{}",
+                &file_name[..idx],
+                &file_name[idx..]
+            )),
+            None => snapshot_file.with_extension("xfail.snap"),
+        };
+        if matches_expected {
+            memoize_failed_assertion(
+                name,
+                &format!("{}:{}", self.assertion_file, self.assertion_line),
+            );
+            panic!(
+                "snapshot assertion for '{}' in line {} expects to fail but matches now; \
+                 remove `xfail` and {}",
+                name,
+                self.assertion_line,
+                divergence_file.display()
+            );
+        }
+        self.old_snapshot = if fs::metadata(long_path(&divergence_file)).is_ok() {
+            Snapshot::from_file(&divergence_file).ok()
+        } else {
+            None
+        };
+        self.snapshot_name = Some(Cow::Owned(format!("{name}.xfail")));
+        self.snapshot_file = Some(divergence_file);
+    }
+
     /// This prints the information about the snapshot
     fn print_snapshot_info(&self, new_snapshot: &Snapshot) {
         let mut printer =
@@ -639,6 +1054,11 @@ impl<'a> SnapshotAssertionContext<'a> {
         printer.set_snapshot_file(self.snapshot_file.as_deref());
         printer.set_title(Some("Snapshot Summary"));
         printer.set_show_info(true);
+        let report_file;
+        if let Some(budget) = self.tool_config.ci_diff_budget() {
+            report_file = self.diff_report_path(new_snapshot);
+            printer.set_diff_budget(budget, &report_file);
+        }
         match self.tool_config.output_behavior() {
             OutputBehavior::Summary => {
                 printer.print();
@@ -651,6 +1071,41 @@ impl<'a> SnapshotAssertionContext<'a> {
         }
     }
 
+    /// Suggests redactions if a YAML or JSON snapshot only changed in values
+    /// that usually differ between runs.
+    #[cfg(feature = "redactions")]
+    fn print_redaction_hints(&self, structured: bool, new_snapshot: &Snapshot) {
+        if !structured
+            || !matches!(
+                self.tool_config.output_behavior(),
+                OutputBehavior::Diff | OutputBehavior::Summary
+            )
+        {
+            return;
+        }
+        let old = self
+            .old_snapshot
+            .as_ref()
+            .and_then(|x| x.contents().as_text());
+        if let (Some(old), Some(new)) = (old, new_snapshot.contents().as_text()) {
+            if let Some(redactions) =
+                crate::volatile::suggest_redactions(&old.to_string(), &new.to_string())
+            {
+                println!(
+                    "{}",
+                    style(
+                        "hint: only values that likely differ between runs changed. \
+                         Redact them with:"
+                    )
+                    .yellow()
+                );
+                for (selector, replacement) in redactions {
+                    println!("  settings.add_redaction({selector:?}, {replacement:?});");
+                }
+            }
+        }
+    }
+
     /// Finalizes the assertion when the snapshot comparison fails, potentially
     /// panicking to fail the test
     fn finalize(&self, update_result: SnapshotUpdateBehavior) {
@@ -682,7 +1137,9 @@ impl<'a> SnapshotAssertionContext<'a> {
             );
         }
 
-        if update_result != SnapshotUpdateBehavior::InPlace && !self.tool_config.force_pass() {
+        let force_pass = self.tool_config.force_pass()
+            && self.tool_config.snapshot_update() != crate::env::SnapshotUpdate::Forbid;
+        if update_result != SnapshotUpdateBehavior::InPlace && !force_pass {
             if fail_fast && self.tool_config.output_behavior() != OutputBehavior::Nothing {
                 let msg = if env::var("INSTA_CARGO_INSTA") == Ok("1".to_string()) {
                     "Stopped on the first failure."
@@ -692,6 +1149,13 @@ impl<'a> SnapshotAssertionContext<'a> {
                 println!("{hint}", hint = style(msg).dim(),);
             }
 
+            if self.tool_config.output_behavior() != OutputBehavior::Nothing {
+                memoize_failed_assertion(
+                    self.snapshot_name.as_deref().unwrap_or("inline snapshot"),
+                    &format!("{}:{}", self.assertion_file, self.assertion_line),
+                );
+            }
+
             // if we are in glob mode, count the failures and print the
             // errors instead of panicking.  The glob will then panic at
             // the end.
@@ -773,6 +1237,30 @@ fn path_relative_from(path: &Path, base: &Path) -> Option<PathBuf> {
     }
 }
 
+/// Counts a snapshot assertion of the test `function_name`.
+///
+/// Panics if the test made more assertions than the assertion budget of the
+/// settings allows.
+fn count_assertion(function_name: &str, assertion_file: &str) {
+    let count = {
+        let mut counts = ASSERTION_COUNTS.lock().unwrap_or_else(|x| x.into_inner());
+        let count = counts.entry(function_name.to_string()).or_insert(0);
+        *count += 1;
+        *count
+    };
+    memoize_assertion(function_name, assertion_file);
+    if let Some(budget) = Settings::with(|settings| settings.assertion_budget()) {
+        if count > budget {
+            panic!(
+                "test '{}' made {} snapshot assertions, more than its budget of {}; \
+                 split it into smaller tests so that its snapshots can be reviewed \
+                 separately, or raise the budget with `Settings::set_assertion_budget`",
+                function_name, count, budget
+            );
+        }
+    }
+}
+
 fn prevent_inline_duplicate(function_name: &str, assertion_file: &str, assertion_line: u32) {
     let key = format!("{function_name}|{assertion_file}|{assertion_line}");
     let mut set = INLINE_DUPLICATES.lock().unwrap();
@@ -836,6 +1324,59 @@ where
     }
 }
 
+/// Turns a failure to write a snapshot because of missing permissions into a
+/// panic that explains how to store pending snapshots elsewhere.
+///
+/// `pending` is set when writing a pending snapshot, which is the only write
+/// that `INSTA_PENDING_DIR` can redirect.  Any other error is passed through.
+fn check_writable<T>(
+    result: Result<T, Box<dyn Error>>,
+    path: &Path,
+    pending: bool,
+) -> Result<T, Box<dyn Error>> {
+    let err = match result {
+        Ok(value) => return Ok(value),
+        Err(err) => err,
+    };
+    let io_err = match err.downcast_ref::<crate::content::Error>() {
+        Some(crate::content::Error::FileIo(io_err, _)) => Some(io_err),
+        _ => err.downcast_ref::<std::io::Error>(),
+    };
+    if !io_err.map_or(false, is_read_only_error) {
+        return Err(err);
+    }
+
+    let hint = match get_pending_dir() {
+        _ if !pending => "The snapshot is updated in place, which requires the snapshot file \
+                          to be writable.  Set INSTA_UPDATE=new to store a pending snapshot \
+                          instead."
+            .into(),
+        Some(pending_dir) => format!(
+            "INSTA_PENDING_DIR is set to {}, which is not writable either.",
+            pending_dir.display()
+        ),
+        None => "Snapshots cannot be written next to the tests, for instance because the \
+                 source tree is read-only.  Set INSTA_PENDING_DIR to a writable directory \
+                 to store pending snapshots there; `cargo insta review` reads them from the \
+                 same directory when run with the same INSTA_PENDING_DIR.  Alternatively \
+                 set INSTA_PENDING_IN_TARGET=1 to store them in the target directory."
+            .into(),
+    };
+    panic!(
+        "insta could not write the snapshot to {}: {}\n\n{}\n\nTo run the tests without \
+         writing any snapshots, set INSTA_UPDATE=no.",
+        path.display(),
+        io_err.unwrap(),
+        hint
+    );
+}
+
+fn is_read_only_error(err: &std::io::Error) -> bool {
+    // `ErrorKind::ReadOnlyFilesystem` is not available on our MSRV, so check
+    // for `EROFS` directly.
+    err.kind() == ErrorKind::PermissionDenied || (cfg!(unix) && err.raw_os_error() == Some(30))
+}
+
 /// This function is invoked from the macros to run the main assertion logic.
 ///
 /// This will create the assertion context, run the main logic to assert
@@ -852,7 +1393,45 @@ pub fn assert_snapshot(
     assertion_line: u32,
     expr: &str,
 ) -> Result<(), Box<dyn Error>> {
-    let ctx = SnapshotAssertionContext::prepare(
+    count_assertion(function_name, assertion_file);
+
+    // file snapshots of shared helpers belong to the owner set in the settings
+    let owner = match snapshot_value {
+        SnapshotValue::FileText { .. } | SnapshotValue::Binary { .. } => {
+            Settings::with(|settings| settings.snapshot_owner().cloned())
+        }
+        SnapshotValue::InlineText { .. } => None,
+    };
+    let (workspace, function_name, module_path, assertion_file, assertion_line) = match owner {
+        Some(ref owner) => (
+            owner.workspace.as_path(),
+            owner.function_name,
+            owner.module_path,
+            owner.file,
+            owner.line,
+        ),
+        None => (
+            workspace,
+            function_name,
+            module_path,
+            assertion_file,
+            assertion_line,
+        ),
+    };
+
+    #[cfg(feature = "redactions")]
+    let structured = matches!(
+        snapshot_value,
+        SnapshotValue::FileText {
+            structured: true,
+            ..
+        } | SnapshotValue::InlineText {
+            structured: true,
+            ..
+        }
+    );
+
+    let mut ctx = SnapshotAssertionContext::prepare(
         &snapshot_value,
         workspace,
         function_name,
@@ -861,7 +1440,11 @@ pub fn assert_snapshot(
         assertion_line,
     )?;
 
-    ctx.cleanup_previous_pending_binary_snapshots()?;
+    // with `INSTA_UPDATE=forbid` nothing may be written or removed
+    let forbid = ctx.tool_config.snapshot_update() == crate::env::SnapshotUpdate::Forbid;
+    if !forbid {
+        ctx.cleanup_previous_pending_binary_snapshots()?;
+    }
 
     let content = match snapshot_value {
         SnapshotValue::FileText { content, .. } | SnapshotValue::InlineText { content, .. } => {
@@ -869,12 +1452,28 @@ pub fn assert_snapshot(
             #[cfg(feature = "filters")]
             let content = Settings::with(|settings| settings.filters().apply_to(content));
 
+            // and those of a metadata comment after an inline snapshot
+            #[cfg(feature = "filters")]
+            let content = match ctx.snapshot_file {
+                None => match crate::inline_meta::inline_filters(
+                    workspace,
+                    assertion_file,
+                    assertion_line,
+                ) {
+                    Some(filters) => filters.apply_to(&content).into_owned().into(),
+                    None => content,
+                },
+                Some(_) => content,
+            };
+
             let kind = match ctx.snapshot_file {
                 Some(_) => TextSnapshotKind::File,
                 None => TextSnapshotKind::Inline,
             };
 
-            TextSnapshotContents::new(content.into(), kind).into()
+            TextSnapshotContents::new(content.into(), kind)
+                .with_indent_width(ctx.tool_config.indent_width())
+                .into()
         }
         SnapshotValue::Binary {
             content, extension, ..
@@ -894,6 +1493,25 @@ pub fn assert_snapshot(
 
     let new_snapshot = ctx.new_snapshot(content, expr);
 
+    // With a section only that part of the file is compared and diffed.  The
+    // full file is kept around to store the new section into.
+    let section = Settings::with(|settings| settings.snapshot_section().map(str::to_string));
+    let mut full_old_snapshot = None;
+    if let Some(ref section) = section {
+        assert!(
+            ctx.snapshot_file.is_some() && new_snapshot.as_text().is_some(),
+            "snapshot sections are only supported for text snapshots stored in files"
+        );
+        full_old_snapshot = ctx.old_snapshot.take();
+        ctx.old_snapshot = full_old_snapshot
+            .as_ref()
+            .and_then(|snapshot| snapshot.section(section));
+    }
+
+    if forbid && ctx.old_snapshot.is_none() {
+        ctx.forbid_missing_snapshot();
+    }
+
     // memoize the snapshot file if requested, as part of potentially removing unreferenced snapshots
     if let Some(ref snapshot_file) = ctx.snapshot_file {
         memoize_snapshot_file(snapshot_file);
@@ -908,38 +1526,122 @@ pub fn assert_snapshot(
         }
     });
 
-    let pass = Settings::with(|settings| {
-        ctx.old_snapshot
-            .as_ref()
-            .map(|x| {
-                if ctx.tool_config.require_full_match() {
-                    settings.comparator().matches_fully(x, &new_snapshot)
-                } else {
-                    settings.comparator().matches(x, &new_snapshot)
-                }
-            })
-            .unwrap_or(false)
-    });
+    let matches = |ctx: &SnapshotAssertionContext<'_>| {
+        Settings::with(|settings| {
+            ctx.old_snapshot
+                .as_ref()
+                .map(|x| {
+                    if ctx.tool_config.require_full_match() {
+                        settings.comparator().matches_fully(x, &new_snapshot)
+                    } else {
+                        settings.comparator().matches(x, &new_snapshot)
+                    }
+                })
+                .unwrap_or(false)
+        })
+    };
+    let mut pass = matches(&ctx);
+
+    // A snapshot that is expected to fail is asserted against its known
+    // divergence instead, which fails like any other snapshot if the value
+    // changes again.
+    if Settings::with(|settings| settings.xfail()) {
+        assert!(
+            section.is_none(),
+            "xfail is not supported for snapshot sections"
+        );
+        ctx.switch_to_known_divergence(pass);
+        if let Some(ref snapshot_file) = ctx.snapshot_file {
+            memoize_snapshot_file(snapshot_file);
+        }
+        pass = matches(&ctx);
+    }
 
     if pass {
-        ctx.cleanup_passing()?;
+        // another section of the file may have failed earlier in this run
+        if !forbid && (section.is_none() || ctx.pending_section_snapshot().is_none()) {
+            ctx.cleanup_passing()?;
+        }
 
         if matches!(
             ctx.tool_config.snapshot_update(),
             crate::env::SnapshotUpdate::Force
         ) {
+            let new_snapshot = match section {
+                Some(ref section) => {
+                    new_snapshot.into_section_of(full_old_snapshot.as_ref(), section)
+                }
+                None => new_snapshot,
+            };
             ctx.update_snapshot(new_snapshot)?;
         }
     // otherwise print information and update snapshots.
     } else {
         ctx.print_snapshot_info(&new_snapshot);
-        let update_result = ctx.update_snapshot(new_snapshot)?;
+        #[cfg(feature = "redactions")]
+        ctx.print_redaction_hints(structured, &new_snapshot);
+        if let Some(reason) = ctx.quarantine_reason() {
+            elog!(
+                "{}: snapshot assertion for '{}' in line {} failed but is quarantined by {}",
+                style("warning").yellow().bold(),
+                ctx.snapshot_name.as_deref().unwrap_or("unnamed snapshot"),
+                ctx.assertion_line,
+                reason
+            );
+            return Ok(());
+        }
+        let update_result = match section {
+            Some(ref section) => {
+                let pending = ctx.pending_section_snapshot();
+                let base = pending.as_ref().or(full_old_snapshot.as_ref());
+                let update_result =
+                    ctx.update_snapshot(new_snapshot.into_section_of(base, section))?;
+                if update_result == SnapshotUpdateBehavior::NewFile {
+                    ctx.record_pending_section_snapshot();
+                }
+                update_result
+            }
+            None => ctx.update_snapshot(new_snapshot)?,
+        };
         ctx.finalize(update_result);
     }
 
     Ok(())
 }
 
+/// Returns how many bytes of a diff assertions without a snapshot file print
+/// on CI, from the insta config of the workspace the tests run in.
+pub(crate) fn ci_diff_budget() -> Option<usize> {
+    env::var("INSTA_WORKSPACE_ROOT")
+        .or_else(|_| env::var("CARGO_MANIFEST_DIR"))
+        .ok()
+        .and_then(|workspace| get_tool_config(Path::new(&workspace)).ci_diff_budget())
+}
+
+/// This function is invoked from `assert_matches_snapshot_of!` to compare two
+/// rendered values with each other instead of with a stored snapshot.
+///
+/// The filters of the settings are applied to both values and they are
+/// compared like snapshots are, so differences in trailing whitespace do not
+/// count.
+pub fn assert_matches_snapshot_of(left: &str, right: &str, left_expr: &str, right_expr: &str) {
+    #[cfg(feature = "filters")]
+    let (left, right) = Settings::with(|settings| {
+        (
+            settings.filters().apply_to(left),
+            settings.filters().apply_to(right),
+        )
+    });
+    let left = TextSnapshotContents::new(left.into(), TextSnapshotKind::File);
+    let right = TextSnapshotContents::new(right.into(), TextSnapshotKind::File);
+    if left.matches_latest(&right) {
+        return;
+    }
+
+    let diff = crate::output::diff(&left.to_string(), &right.to_string(), ci_diff_budget());
+    panic!("snapshot of `{right_expr}` does not match snapshot of `{left_expr}`:\n{diff}");
+}
+
 #[allow(rustdoc::private_doc_tests)]
 /// Test snapshots in doctests.
 ///
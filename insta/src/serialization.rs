@@ -1,4 +1,4 @@
-use serde::{de::value::Error as ValueError, Serialize};
+use serde::{de::value::Error as ValueError, de::DeserializeOwned, Serialize};
 #[cfg(feature = "ron")]
 use std::borrow::Cow;
 #[cfg(feature = "toml")]
@@ -13,6 +13,7 @@ use crate::{
     settings::Settings,
 };
 
+#[derive(Clone, Copy)]
 pub enum SerializationFormat {
     #[cfg(feature = "csv")]
     Csv,
@@ -23,6 +24,7 @@ pub enum SerializationFormat {
     Yaml,
     Json,
     JsonCompact,
+    Table,
 }
 
 #[derive(Debug)]
@@ -31,11 +33,45 @@ pub enum SnapshotLocation {
     File,
 }
 
+/// A value serialized for a snapshot, with the format it is serialized in.
+pub struct SerializedValue {
+    content: String,
+    format: SerializationFormat,
+}
+
+impl SerializedValue {
+    pub fn as_str(&self) -> &str {
+        &self.content
+    }
+
+    /// Returns `true` if the value is YAML or JSON, whose parts selectors of
+    /// redactions can name.
+    pub fn is_structured(&self) -> bool {
+        matches!(
+            self.format,
+            SerializationFormat::Yaml
+                | SerializationFormat::Json
+                | SerializationFormat::JsonCompact
+        )
+    }
+}
+
+impl std::ops::Deref for SerializedValue {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.content
+    }
+}
+
 pub fn serialize_content(mut content: Content, format: SerializationFormat) -> String {
     content = Settings::with(|settings| {
         if settings.sort_maps() {
             content.sort_maps();
         }
+        if let Some(precision) = settings.normalize_datetimes() {
+            content.normalize_datetimes(precision);
+        }
         #[cfg(feature = "redactions")]
         {
             content = settings.apply_redactions(content);
@@ -47,6 +83,7 @@ pub fn serialize_content(mut content: Content, format: SerializationFormat) -> S
         SerializationFormat::Yaml => yaml::to_string(&content)[4..].to_string(),
         SerializationFormat::Json => json::to_string_pretty(&content),
         SerializationFormat::JsonCompact => json::to_string_compact(&content),
+        SerializationFormat::Table => crate::table::render_content_table(&content),
         #[cfg(feature = "csv")]
         SerializationFormat::Csv => {
             let mut buf = Vec::with_capacity(128);
@@ -190,10 +227,60 @@ pub fn serialize_content(mut content: Content, format: SerializationFormat) -> S
     }
 }
 
-pub fn serialize_value<S: Serialize>(s: &S, format: SerializationFormat) -> String {
+/// Serializes a value into [`Content`] and applies the serialization
+/// overrides of the current settings.
+fn to_content<S: Serialize>(s: &S) -> Content {
     let serializer = ContentSerializer::<ValueError>::new();
     let content = Serialize::serialize(s, serializer).unwrap();
-    serialize_content(content, format)
+    #[cfg(feature = "json")]
+    crate::json_schema::validate_current(&content);
+    Settings::with(|settings| settings.apply_serialization_overrides(content))
+}
+
+pub fn serialize_value<S: Serialize>(s: &S, format: SerializationFormat) -> SerializedValue {
+    SerializedValue {
+        content: serialize_content(to_content(s), format),
+        format,
+    }
+}
+
+/// Serializes a value, deserializes it again and panics if the result is not
+/// equal to the value or does not serialize the same.
+///
+/// The value is read back from the serialized content directly, so this
+/// finds fields that are skipped or defaulted on one side only, not the
+/// limitations of a particular format.
+pub fn assert_roundtrip<T>(value: &T)
+where
+    T: Serialize + DeserializeOwned + PartialEq + std::fmt::Debug,
+{
+    let serialize = |value: &T| Serialize::serialize(value, ContentSerializer::<ValueError>::new());
+    let content = serialize(value).unwrap();
+    let roundtrip = match T::deserialize(content.clone()) {
+        Ok(roundtrip) => roundtrip,
+        Err(err) => panic!("failed to deserialize the serialized value: {err}"),
+    };
+    let roundtrip_content = serialize(&roundtrip).unwrap();
+    if roundtrip_content != content {
+        panic!(
+            "the value serializes differently after a round trip:\n{}",
+            crate::output::diff(
+                &yaml::to_string(&content),
+                &yaml::to_string(&roundtrip_content),
+                crate::runtime::ci_diff_budget(),
+            )
+        );
+    }
+    if roundtrip != *value {
+        panic!(
+            "the value is not equal to itself after a round trip:\n{}",
+            crate::output::diff(
+                &format!("{value:#?}"),
+                &format!("{roundtrip:#?}"),
+                crate::runtime::ci_diff_budget(),
+            )
+        );
+    }
 }
 
 #[cfg(feature = "redactions")]
@@ -201,13 +288,53 @@ pub fn serialize_value_redacted<S: Serialize>(
     s: &S,
     redactions: &[(crate::redaction::Selector, crate::redaction::Redaction)],
     format: SerializationFormat,
-) -> String {
-    let serializer = ContentSerializer::<ValueError>::new();
-    let mut content = Serialize::serialize(s, serializer).unwrap();
-    for (selector, redaction) in redactions {
-        content = selector.redact(content, redaction);
-    }
-    serialize_content(content, format)
+) -> SerializedValue {
+    crate::redaction::with_numbering_scope(|| {
+        let mut content = to_content(s);
+        for (selector, redaction) in redactions {
+            content = selector.redact(content, redaction);
+        }
+        SerializedValue {
+            content: serialize_content(content, format),
+            format,
+        }
+    })
+}
+
+/// Serializes only the part of a value matched by `selector`.
+///
+/// Redactions are applied to the full value first, so their selectors are
+/// relative to the root.  An exact selector (keys and indexes only) yields
+/// the selected value itself, all others a sequence of the matched values.
+#[cfg(feature = "redactions")]
+pub fn serialize_value_selected<S: Serialize>(
+    s: &S,
+    selector: &str,
+    redactions: &[(crate::redaction::Selector, crate::redaction::Redaction)],
+    format: SerializationFormat,
+) -> SerializedValue {
+    let parsed = crate::redaction::Selector::parse(selector)
+        .unwrap_or_else(|err| panic!("invalid selector {selector:?} at column {}", err.column()));
+    crate::redaction::with_numbering_scope(|| {
+        let mut content = to_content(s);
+        for (selector, redaction) in redactions {
+            content = selector.redact(content, redaction);
+        }
+        let mut selected = parsed.select(content);
+        let content = if parsed.is_exact() {
+            assert!(
+                !selected.is_empty(),
+                "selector {selector:?} did not match any value"
+            );
+            selected.remove(0)
+        } else {
+            Content::Seq(selected)
+        };
+        SerializedValue {
+            content: serialize_content(content, format),
+            format,
+        }
+    })
 }
 
 #[test]
@@ -1,6 +1,7 @@
 #[cfg(feature = "serde")]
 use serde::{de::value::Error as ValueError, Serialize};
 use std::cell::RefCell;
+use std::fmt;
 use std::future::Future;
 use std::mem;
 use std::path::{Path, PathBuf};
@@ -8,14 +9,21 @@ use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
 
-use crate::comparator::Comparator;
+use crate::ansi::AnsiMode;
+use crate::comparator::{Comparator, FloatToleranceComparator};
 use crate::content::Content;
 #[cfg(feature = "serde")]
 use crate::content::ContentSerializer;
 #[cfg(feature = "filters")]
 use crate::filters::Filters;
+#[cfg(feature = "json")]
+use crate::json_schema::JsonSchema;
 #[cfg(feature = "redactions")]
-use crate::redaction::{dynamic_redaction, sorted_redaction, ContentPath, Redaction, Selector};
+use crate::redaction::{
+    decimal_redaction, dynamic_redaction, sorted_by_redaction, sorted_redaction, ContentPath,
+    Redaction, Selector,
+};
+use crate::runtime::SnapshotOwner;
 
 thread_local!(static CURRENT_SETTINGS: RefCell<Settings> = RefCell::new(Settings::new()));
 
@@ -41,50 +49,84 @@ impl<'a> From<Vec<(&'a str, Redaction)>> for Redactions {
 impl Redactions {
     /// Applies all redactions to the given content.
     pub(crate) fn apply_to_content(&self, mut content: Content) -> Content {
-        for (selector, redaction) in self.0.iter() {
-            content = selector.redact(content, redaction);
-        }
-        content
+        crate::redaction::with_numbering_scope(|| {
+            for (selector, redaction) in self.0.iter() {
+                content = selector.redact(content, redaction);
+            }
+            content
+        })
     }
 }
 
+/// A function that replaces the serialized content of a type.
+#[cfg(feature = "serde")]
+type SerializationOverride = Rc<dyn Fn(Content) -> Content>;
+
 #[doc(hidden)]
 pub struct ActualSettings {
     pub sort_maps: bool,
+    pub normalize_datetimes: Option<u8>,
     pub snapshot_path: PathBuf,
     pub snapshot_suffix: String,
+    pub snapshot_extension: String,
+    pub snapshot_section: String,
     pub input_file: Option<PathBuf>,
     pub description: Option<String>,
     pub info: Option<Content>,
+    pub context: Vec<(String, String)>,
     pub omit_expression: bool,
+    pub flaky: bool,
+    pub xfail: bool,
+    pub assertion_budget: Option<usize>,
     pub prepend_module_to_snapshot: bool,
+    pub snapshot_owner: Option<SnapshotOwner>,
+    pub ansi_mode: AnsiMode,
+    pub cell_diff: bool,
     pub comparator: Box<dyn Comparator>,
+    #[cfg(feature = "serde")]
+    pub serialization_overrides: Vec<(String, SerializationOverride)>,
     #[cfg(feature = "redactions")]
     pub redactions: Redactions,
     #[cfg(feature = "filters")]
     pub filters: Filters,
     #[cfg(feature = "glob")]
     pub allow_empty_glob: bool,
+    #[cfg(feature = "json")]
+    pub json_schema: Option<std::sync::Arc<JsonSchema>>,
 }
 
 impl Clone for ActualSettings {
     fn clone(&self) -> Self {
         ActualSettings {
             sort_maps: self.sort_maps,
+            normalize_datetimes: self.normalize_datetimes,
             snapshot_path: self.snapshot_path.clone(),
             snapshot_suffix: self.snapshot_suffix.clone(),
+            snapshot_extension: self.snapshot_extension.clone(),
+            snapshot_section: self.snapshot_section.clone(),
             input_file: self.input_file.clone(),
             description: self.description.clone(),
             info: self.info.clone(),
+            context: self.context.clone(),
             omit_expression: self.omit_expression,
+            flaky: self.flaky,
+            xfail: self.xfail,
+            assertion_budget: self.assertion_budget,
             prepend_module_to_snapshot: self.prepend_module_to_snapshot,
+            snapshot_owner: self.snapshot_owner.clone(),
+            ansi_mode: self.ansi_mode,
+            cell_diff: self.cell_diff,
             comparator: self.comparator.dyn_clone(),
+            #[cfg(feature = "serde")]
+            serialization_overrides: self.serialization_overrides.clone(),
             #[cfg(feature = "redactions")]
             redactions: self.redactions.clone(),
             #[cfg(feature = "filters")]
             filters: self.filters.clone(),
             #[cfg(feature = "glob")]
             allow_empty_glob: self.allow_empty_glob,
+            #[cfg(feature = "json")]
+            json_schema: self.json_schema.clone(),
         }
     }
 }
@@ -94,6 +136,10 @@ impl ActualSettings {
         self.sort_maps = value;
     }
 
+    pub fn normalize_datetimes(&mut self, precision: Option<u8>) {
+        self.normalize_datetimes = precision.map(|x| x.min(9));
+    }
+
     pub fn snapshot_path<P: AsRef<Path>>(&mut self, path: P) {
         self.snapshot_path = path.as_ref().to_path_buf();
     }
@@ -102,6 +148,15 @@ impl ActualSettings {
         self.snapshot_suffix = suffix.into();
     }
 
+    pub fn snapshot_extension<I: Into<String>>(&mut self, extension: I) {
+        let extension = extension.into();
+        self.snapshot_extension = extension.trim_start_matches('.').to_string();
+    }
+
+    pub fn snapshot_section<I: Into<String>>(&mut self, section: I) {
+        self.snapshot_section = section.into();
+    }
+
     pub fn input_file<P: AsRef<Path>>(&mut self, p: P) {
         self.input_file = Some(p.as_ref().to_path_buf());
     }
@@ -130,18 +185,46 @@ impl ActualSettings {
         self.info = Some(content.to_owned());
     }
 
+    pub fn add_context<K: Into<String>, V: fmt::Display>(&mut self, key: K, value: V) {
+        self.context.push((key.into(), value.to_string()));
+    }
+
     pub fn omit_expression(&mut self, value: bool) {
         self.omit_expression = value;
     }
 
+    pub fn flaky(&mut self, value: bool) {
+        self.flaky = value;
+    }
+
+    pub fn xfail(&mut self, value: bool) {
+        self.xfail = value;
+    }
+
+    pub fn assertion_budget(&mut self, budget: Option<usize>) {
+        self.assertion_budget = budget;
+    }
+
     pub fn prepend_module_to_snapshot(&mut self, value: bool) {
         self.prepend_module_to_snapshot = value;
     }
 
+    pub fn snapshot_owner(&mut self, owner: SnapshotOwner) {
+        self.snapshot_owner = Some(owner);
+    }
+
+    pub fn ansi_mode(&mut self, value: AnsiMode) {
+        self.ansi_mode = value;
+    }
+
     pub fn comparator(&mut self, value: Box<dyn Comparator>) {
         self.comparator = value;
     }
 
+    pub fn float_tolerance(&mut self, tolerance: f64) {
+        self.comparator = Box::new(FloatToleranceComparator::new(tolerance));
+    }
+
     #[cfg(feature = "redactions")]
     pub fn redactions<R: Into<Redactions>>(&mut self, r: R) {
         self.redactions = r.into();
@@ -196,20 +279,34 @@ impl Default for Settings {
         Settings {
             inner: Rc::new(ActualSettings {
                 sort_maps: false,
+                normalize_datetimes: None,
                 snapshot_path: "snapshots".into(),
                 snapshot_suffix: "".into(),
+                snapshot_extension: "".into(),
+                snapshot_section: "".into(),
                 input_file: None,
                 description: None,
                 info: None,
+                context: Vec::new(),
                 omit_expression: false,
+                flaky: false,
+                xfail: false,
+                assertion_budget: None,
                 prepend_module_to_snapshot: true,
+                snapshot_owner: None,
+                ansi_mode: AnsiMode::Strip,
+                cell_diff: false,
                 comparator: Box::new(crate::comparator::DefaultComparator),
+                #[cfg(feature = "serde")]
+                serialization_overrides: Vec::new(),
                 #[cfg(feature = "redactions")]
                 redactions: Redactions::default(),
                 #[cfg(feature = "filters")]
                 filters: Filters::default(),
                 #[cfg(feature = "glob")]
                 allow_empty_glob: false,
+                #[cfg(feature = "json")]
+                json_schema: None,
             }),
         }
     }
@@ -241,6 +338,11 @@ impl Settings {
     /// non-ordered collections like [`HashMap`](std::collections::HashMap) and
     /// [`HashSet`](std::collections::HashSet).
     ///
+    /// Sequences are sorted by their items as a whole.  To only sort some
+    /// sequences, or to sort sequences of objects by a key, use
+    /// [`Settings::sort_selector`] and [`Settings::sort_selector_by`]
+    /// instead.
+    ///
     /// Note that this only applies to snapshots that undergo serialization
     /// (eg: does not work for [`assert_debug_snapshot!`](crate::assert_debug_snapshot!)).
     ///
@@ -254,6 +356,34 @@ impl Settings {
         self.inner.sort_maps
     }
 
+    /// Normalizes timestamps to UTC before serialization.
+    ///
+    /// When set, every string value that is an RFC 3339 timestamp with an
+    /// offset (which is how `chrono` and `time` serialize their zoned date
+    /// times) is converted to UTC and written as `YYYY-MM-DDTHH:MM:SS.fffZ`
+    /// with the given number of fractional second digits (at most 9).  This
+    /// keeps snapshots from depending on the time zone of the machine that
+    /// produced them.  Naive timestamps without an offset are left alone.
+    ///
+    /// ```rust
+    /// # use insta::Settings;
+    /// let mut settings = Settings::clone_current();
+    /// settings.set_normalize_datetimes(Some(3));
+    /// ```
+    ///
+    /// Like [`Settings::set_sort_maps`] this only applies to snapshots that
+    /// undergo serialization.
+    ///
+    /// The default value is `None`.
+    pub fn set_normalize_datetimes(&mut self, precision: Option<u8>) {
+        self._private_inner_mut().normalize_datetimes(precision);
+    }
+
+    /// Returns the current datetime normalization precision.
+    pub fn normalize_datetimes(&self) -> Option<u8> {
+        self.inner.normalize_datetimes
+    }
+
     /// Disables prepending of modules to the snapshot filename.
     ///
     /// By default, the filename of a snapshot is `<module>__<name>.snap`.
@@ -270,6 +400,43 @@ impl Settings {
         self.inner.prepend_module_to_snapshot
     }
 
+    /// Stores file snapshots as if they were asserted at another location.
+    ///
+    /// When a shared test helper crate makes the assertions, its snapshots
+    /// end up next to the helper and are named after the helper's module and
+    /// function.  Setting an owner created with
+    /// [`snapshot_owner!`](crate::snapshot_owner!) in the calling test makes
+    /// them land in the calling crate instead, named as if the test had made
+    /// the assertion itself:
+    ///
+    /// ```rust
+    /// pub fn check_output(output: &str, owner: insta::SnapshotOwner) {
+    ///     insta::with_settings!({snapshot_owner => owner}, {
+    ///         insta::assert_snapshot!(output);
+    ///     });
+    /// }
+    ///
+    /// # fn test_output() {
+    /// // in the calling test
+    /// check_output("output", insta::snapshot_owner!());
+    /// # }
+    /// ```
+    ///
+    /// Inline snapshots are always stored where the assertion is.
+    pub fn set_snapshot_owner(&mut self, owner: SnapshotOwner) {
+        self._private_inner_mut().snapshot_owner(owner);
+    }
+
+    /// Removes the snapshot owner.
+    pub fn remove_snapshot_owner(&mut self) {
+        self._private_inner_mut().snapshot_owner = None;
+    }
+
+    /// Returns the current snapshot owner.
+    pub fn snapshot_owner(&self) -> Option<&SnapshotOwner> {
+        self.inner.snapshot_owner.as_ref()
+    }
+
     /// Allows the [`glob!`] macro to succeed if it matches no files.
     ///
     /// By default, the glob macro will fail the test if it does not find
@@ -313,6 +480,75 @@ impl Settings {
         }
     }
 
+    /// Sets an extension that is appended to the file names of snapshots.
+    ///
+    /// With the extension `"sql"` a snapshot is stored as `name.snap.sql`
+    /// instead of `name.snap`, so that editors highlight its contents as SQL.
+    /// External diff tools set with `INSTA_DIFF_TOOL` get files with the
+    /// extension too, so that they can highlight the diff.  A leading `.` is
+    /// ignored.  The extension can also be passed to the assertion macros:
+    ///
+    /// ```no_run
+    /// # let query = "";
+    /// insta::assert_snapshot!(extension = "sql", "users_query", query);
+    /// ```
+    ///
+    /// The extension only applies to text snapshots stored in files.
+    pub fn set_snapshot_extension<I: Into<String>>(&mut self, extension: I) {
+        self._private_inner_mut().snapshot_extension(extension);
+    }
+
+    /// Removes the snapshot extension.
+    pub fn remove_snapshot_extension(&mut self) {
+        self.set_snapshot_extension("");
+    }
+
+    /// Returns the current snapshot extension.
+    pub fn snapshot_extension(&self) -> Option<&str> {
+        if self.inner.snapshot_extension.is_empty() {
+            None
+        } else {
+            Some(&self.inner.snapshot_extension)
+        }
+    }
+
+    /// Sets the section of the snapshot file to assert against.
+    ///
+    /// File snapshots with a section store their value under a `--- section ---`
+    /// header so that several related assertions can share one snapshot file
+    /// which is reviewed as a single document.  Only the section is compared
+    /// and shown in the diff; the other sections of the file are kept as they
+    /// are.  Lines of a value that look like section headers are stored with
+    /// a leading backslash.
+    ///
+    /// ```rust,no_run
+    /// # use insta::{assert_debug_snapshot, with_settings};
+    /// # let value = vec![1, 2, 3];
+    /// with_settings!({snapshot_section => "summary"}, {
+    ///     assert_debug_snapshot!("report", value.len());
+    /// });
+    /// with_settings!({snapshot_section => "details"}, {
+    ///     assert_debug_snapshot!("report", value);
+    /// });
+    /// ```
+    pub fn set_snapshot_section<I: Into<String>>(&mut self, section: I) {
+        self._private_inner_mut().snapshot_section(section);
+    }
+
+    /// Removes the snapshot section.
+    pub fn remove_snapshot_section(&mut self) {
+        self.set_snapshot_section("");
+    }
+
+    /// Returns the current snapshot section.
+    pub fn snapshot_section(&self) -> Option<&str> {
+        if self.inner.snapshot_section.is_empty() {
+            None
+        } else {
+            Some(&self.inner.snapshot_section)
+        }
+    }
+
     /// Sets the input file reference.
     ///
     /// This value is completely unused by the snapshot testing system, but it
@@ -397,6 +633,58 @@ impl Settings {
         self.inner.info.as_ref()
     }
 
+    /// Attaches a contextual key/value pair to snapshot assertions.
+    ///
+    /// Context is meant for values that explain a particular run, such as a
+    /// request ID or the fixture that was loaded.  It is printed when an
+    /// assertion fails and stored with pending snapshots so it shows up in
+    /// `cargo insta review`, but unlike [`Self::set_info`] it is never
+    /// written to the accepted snapshot.  The assertion macros accept it
+    /// directly, either as the first argument or right after the value and
+    /// its redactions:
+    ///
+    /// ```no_run
+    /// # let (output, request_id) = ("", 42);
+    /// insta::assert_snapshot!(output, context = {"request_id": request_id});
+    /// ```
+    pub fn add_context<K: Into<String>, V: fmt::Display>(&mut self, key: K, value: V) {
+        self._private_inner_mut().add_context(key, value);
+    }
+
+    /// Removes all context.
+    pub fn clear_context(&mut self) {
+        self._private_inner_mut().context.clear();
+    }
+
+    /// Returns the current context.
+    pub fn context(&self) -> &[(String, String)] {
+        &self.inner.context
+    }
+
+    /// Returns whether snapshots are tables that are realigned for diffs.
+    pub(crate) fn cell_diff(&self) -> bool {
+        self.inner.cell_diff
+    }
+
+    /// Returns the JSON schema that serialized values are validated against.
+    #[cfg(feature = "json")]
+    pub(crate) fn json_schema(&self) -> Option<&std::sync::Arc<JsonSchema>> {
+        self.inner.json_schema.as_ref()
+    }
+
+    /// Returns the reference of the JSON schema that is stored in the
+    /// metadata of snapshots.
+    pub(crate) fn json_schema_reference(&self) -> Option<&str> {
+        #[cfg(feature = "json")]
+        {
+            self.inner.json_schema.as_ref().map(|x| x.reference())
+        }
+        #[cfg(not(feature = "json"))]
+        {
+            None
+        }
+    }
+
     /// Returns the current info
     pub fn has_info(&self) -> bool {
         self.inner.info.is_some()
@@ -417,11 +705,117 @@ impl Settings {
         self._private_inner_mut().comparator = value;
     }
 
+    /// Lets numbers in snapshots differ from the reference by `tolerance`.
+    ///
+    /// This replaces the active [`Comparator`] with a
+    /// [`FloatToleranceComparator`].  Snapshots still store the reference
+    /// value, and are only updated once a number changes by more than the
+    /// tolerance.
+    pub fn set_float_tolerance(&mut self, tolerance: f64) {
+        self._private_inner_mut().float_tolerance(tolerance);
+    }
+
     /// Returns true if expressions are omitted from snapshots.
     pub fn omit_expression(&self) -> bool {
         self.inner.omit_expression
     }
 
+    /// Quarantines the snapshots asserted with these settings.
+    ///
+    /// A quarantined snapshot that does not match prints its diff and a
+    /// warning, but does not fail the test and writes no pending snapshot.
+    /// This is meant for snapshots that are known to be flaky until they are
+    /// fixed; `cargo insta stats --quarantined` lists them.  Snapshots can
+    /// also be quarantined with the `quarantine` list in `insta.yaml`.
+    ///
+    /// ```rust
+    /// insta::with_settings!({flaky => true}, {
+    ///     insta::assert_snapshot!("value that changes between runs", @"value that changes between runs");
+    /// });
+    /// ```
+    ///
+    /// The default value is `false`.
+    pub fn set_flaky(&mut self, value: bool) {
+        self._private_inner_mut().flaky(value);
+    }
+
+    /// Returns true if snapshots are quarantined.
+    pub fn flaky(&self) -> bool {
+        self.inner.flaky
+    }
+
+    /// Marks the snapshots asserted with these settings as expected to fail.
+    ///
+    /// This is meant for known bugs, such as in a dependency, that make a
+    /// value differ from the snapshot of the correct value.  The value is then
+    /// compared with a second snapshot of the known divergence instead, which
+    /// is stored next to the snapshot file with the `.xfail.snap` extension
+    /// and is reviewed like any other snapshot.  The assertion passes as long
+    /// as the value matches the known divergence, and fails if it diverges in
+    /// another way or matches the expected snapshot again, which means the bug
+    /// was fixed and `xfail` can be removed.
+    ///
+    /// ```rust,no_run
+    /// # fn parse_with_upstream_bug() -> &'static str { "" }
+    /// insta::with_settings!({xfail => true}, {
+    ///     insta::assert_snapshot!("parsed", parse_with_upstream_bug());
+    /// });
+    /// ```
+    ///
+    /// Only snapshots stored in files can be expected to fail.  The default
+    /// value is `false`.
+    pub fn set_xfail(&mut self, value: bool) {
+        self._private_inner_mut().xfail(value);
+    }
+
+    /// Returns true if snapshots are expected to fail.
+    pub fn xfail(&self) -> bool {
+        self.inner.xfail
+    }
+
+    /// Limits the number of snapshot assertions a single test may make.
+    ///
+    /// A test that makes more assertions than the budget fails with a hint to
+    /// split it up.  Tests with many snapshots are hard to review since their
+    /// snapshots change together, and a failure only shows the first
+    /// mismatch.  The budget is usually set for a whole test binary or module
+    /// with [`Settings::bind_to_scope`]:
+    ///
+    /// ```rust
+    /// let mut settings = insta::Settings::clone_current();
+    /// settings.set_assertion_budget(Some(20));
+    /// let _guard = settings.bind_to_scope();
+    /// ```
+    ///
+    /// The assertions of a test are counted by its function name, regardless
+    /// of the settings they were made with.  The default value is `None`,
+    /// which does not limit the assertions.
+    pub fn set_assertion_budget(&mut self, budget: Option<usize>) {
+        self._private_inner_mut().assertion_budget(budget);
+    }
+
+    /// Returns the number of snapshot assertions a test may make.
+    pub fn assertion_budget(&self) -> Option<usize> {
+        self.inner.assertion_budget
+    }
+
+    /// Sets how [`assert_ansi_snapshot!`](crate::assert_ansi_snapshot!)
+    /// handles ANSI escape codes.
+    ///
+    /// With [`AnsiMode::Strip`] only the text is kept, with
+    /// [`AnsiMode::Markup`] colors and text styles are written as tags such
+    /// as `<red>…</red>` so that they can be reviewed too.
+    ///
+    /// The default value is [`AnsiMode::Strip`].
+    pub fn set_ansi_mode(&mut self, value: AnsiMode) {
+        self._private_inner_mut().ansi_mode(value);
+    }
+
+    /// Returns the current ANSI escape code handling.
+    pub fn ansi_mode(&self) -> AnsiMode {
+        self.inner.ansi_mode
+    }
+
     /// Registers redactions that should be applied.
     ///
     /// This can be useful if redactions must be shared across multiple
@@ -469,6 +863,24 @@ impl Settings {
         self.add_redaction(selector, sorted_redaction());
     }
 
+    /// A special redaction that writes decimal numbers in a canonical form.
+    ///
+    /// This is a shortcut to `add_redaction(selector, decimal_redaction())`.
+    #[cfg(feature = "redactions")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "redactions")))]
+    pub fn normalize_decimal_selector(&mut self, selector: &str) {
+        self.add_redaction(selector, decimal_redaction());
+    }
+
+    /// A special redaction that sorts a sequence by a key of its items.
+    ///
+    /// This is a shortcut to `add_redaction(selector, sorted_by_redaction(key))`.
+    #[cfg(feature = "redactions")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "redactions")))]
+    pub fn sort_selector_by(&mut self, selector: &str, key: &str) {
+        self.add_redaction(selector, sorted_by_redaction(key));
+    }
+
     /// Replaces the currently set redactions.
     ///
     /// The default set is empty.
@@ -492,6 +904,73 @@ impl Settings {
         self.inner.redactions.apply_to_content(content)
     }
 
+    /// Registers an override for how values of a type are serialized.
+    ///
+    /// Some types serialize in a way that is not stable between runs or
+    /// versions, for instance types from other crates that contain
+    /// timestamps or internal handles.  Instead of wrapping them in newtypes
+    /// everywhere, an override can replace their serialized value in all
+    /// snapshots.  The override is selected by the name the type uses with
+    /// [`serde`], which is the name of the struct or enum unless it is
+    /// renamed, and is passed the serialized value as [`Content`]:
+    ///
+    /// ```rust
+    /// # use insta::Settings;
+    /// use insta::internals::Content;
+    /// # let mut settings = Settings::new();
+    /// settings.add_serialization_override("SystemTime", |_| "[time]");
+    /// settings.add_serialization_override("Duration", |value| match value {
+    ///     Content::Struct(_, fields) => Content::from(format!("{}s", fields[0].1.as_u64().unwrap())),
+    ///     value => value,
+    /// });
+    /// ```
+    ///
+    /// Overrides apply to structs, tuple structs, unit structs, newtypes and
+    /// enums, but not to types that serialize as primitives such as strings.
+    /// They are applied right after serialization, before redactions, sorting
+    /// and normalization.  The value an override returns is not overridden
+    /// again.
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn add_serialization_override<I, F>(&mut self, name: &str, func: F)
+    where
+        I: Into<Content>,
+        F: Fn(Content) -> I + 'static,
+    {
+        self._private_inner_mut()
+            .serialization_overrides
+            .push((name.to_string(), Rc::new(move |c| func(c).into())));
+    }
+
+    /// Removes all serialization overrides.
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn clear_serialization_overrides(&mut self) {
+        self._private_inner_mut().serialization_overrides.clear();
+    }
+
+    /// Applies the serialization overrides to content.
+    #[cfg(feature = "serde")]
+    pub(crate) fn apply_serialization_overrides(&self, mut content: Content) -> Content {
+        let overrides = &self.inner.serialization_overrides;
+        if overrides.is_empty() {
+            return content;
+        }
+        content.walk(&mut |content| {
+            let func = content
+                .type_name()
+                .and_then(|name| overrides.iter().find(|(n, _)| n == name));
+            match func {
+                Some((_, func)) => {
+                    *content = func(mem::replace(content, Content::Unit));
+                    false
+                }
+                None => true,
+            }
+        });
+        content
+    }
+
     /// Adds a new filter.
     ///
     /// Filters are similar to redactions but are applied as regex onto the final snapshot
@@ -511,6 +990,20 @@ impl Settings {
     /// settings.add_filter(r"\b[[:xdigit:]]{32}\b", "[UID]");
     /// # }
     /// ```
+    ///
+    /// The replacement can refer to the groups of the regex as `$1`, or as
+    /// `$name` for named groups, to keep parts of the match.  This turns
+    /// `took 123ms` into `took [DURATION]ms`:
+    ///
+    /// ```rust
+    /// # use insta::Settings;
+    /// # let mut settings = Settings::new();
+    /// settings.add_filter(r"took \d+(?P<unit>ms|s)\b", "took [DURATION]$unit");
+    /// ```
+    ///
+    /// A reference that is followed by a letter, digit or underscore must be
+    /// written in braces, as in `${1}ms`, and `$$` is a literal `$`.  This
+    /// panics if the replacement refers to a group the regex does not have.
     #[cfg(feature = "filters")]
     #[cfg_attr(docsrs, doc(cfg(feature = "filters")))]
     pub fn add_filter<S: Into<String>>(&mut self, regex: &str, replacement: S) {
@@ -526,6 +1019,33 @@ impl Settings {
         self._private_inner_mut().filters(filters);
     }
 
+    /// Adds filters for values that differ between machines and users.
+    ///
+    /// These replace the temporary directory with `[TEMP_DIR]`, the home
+    /// directory with `[HOME]`, the host name with `[HOSTNAME]`, the name of
+    /// the user with `[USER]`, and process ids after a `pid` label, as in
+    /// `pid=4242`, with `[PID]`.  Paths in the temporary and home directory
+    /// are written with `/` as separator on all platforms.
+    ///
+    /// The values are read when this is called.  The host and user names are
+    /// replaced wherever they appear as a word, so a user named like a word
+    /// that the snapshot contains is replaced there too.
+    ///
+    /// ```rust
+    /// # use insta::Settings;
+    /// let mut settings = Settings::clone_current();
+    /// settings.add_standard_filters();
+    /// settings.bind(|| {
+    ///     let path = std::env::temp_dir().join("out").join("report.txt");
+    ///     insta::assert_snapshot!(path.display(), @"[TEMP_DIR]/out/report.txt");
+    /// });
+    /// ```
+    #[cfg(feature = "filters")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "filters")))]
+    pub fn add_standard_filters(&mut self) {
+        self._private_inner_mut().filters.add_standard();
+    }
+
     /// Removes all filters.
     #[cfg(feature = "filters")]
     #[cfg_attr(docsrs, doc(cfg(feature = "filters")))]
@@ -649,6 +1169,267 @@ impl Settings {
     }
 }
 
+/// An error from reading settings with [`Settings::from_toml`].
+#[cfg(feature = "toml")]
+#[cfg_attr(docsrs, doc(cfg(feature = "toml")))]
+#[derive(Debug)]
+pub struct SettingsError(String);
+
+#[cfg(feature = "toml")]
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid insta settings: {}", self.0)
+    }
+}
+
+#[cfg(feature = "toml")]
+impl std::error::Error for SettingsError {}
+
+#[cfg(feature = "toml")]
+#[cfg_attr(docsrs, doc(cfg(feature = "toml")))]
+impl Settings {
+    /// Writes the settings as TOML.
+    ///
+    /// This writes the settings that are commonly shared between tests:
+    /// sorting and normalization, the snapshot path and suffix, the module
+    /// prefix, expression omission, the ANSI mode and the description, as
+    /// well as filters and redactions.  Redactions are only written if they
+    /// replace a value with a string, number or boolean; dynamic redactions
+    /// such as [`sorted_redaction`](crate::sorted_redaction) cannot be
+    /// written.  Per-test settings like the info, context and input file are
+    /// skipped.
+    ///
+    /// ```rust
+    /// # use insta::Settings;
+    /// let mut settings = Settings::new();
+    /// settings.set_sort_maps(true);
+    /// let toml = settings.to_toml();
+    /// assert!(toml.contains("sort_maps = true"));
+    /// ```
+    pub fn to_toml(&self) -> String {
+        use toml_edit::{value, DocumentMut};
+
+        let mut doc = DocumentMut::new();
+        doc["sort_maps"] = value(self.sort_maps());
+        if let Some(precision) = self.normalize_datetimes() {
+            doc["normalize_datetimes"] = value(i64::from(precision));
+        }
+        doc["snapshot_path"] = value(self.snapshot_path().to_string_lossy().into_owned());
+        if let Some(suffix) = self.snapshot_suffix() {
+            doc["snapshot_suffix"] = value(suffix);
+        }
+        if let Some(extension) = self.snapshot_extension() {
+            doc["snapshot_extension"] = value(extension);
+        }
+        doc["prepend_module_to_snapshot"] = value(self.prepend_module_to_snapshot());
+        doc["omit_expression"] = value(self.omit_expression());
+        doc["ansi_mode"] = value(self.ansi_mode().as_str());
+        if let Some(description) = self.description() {
+            doc["description"] = value(description);
+        }
+        #[cfg(feature = "glob")]
+        {
+            doc["allow_empty_glob"] = value(self.allow_empty_glob());
+        }
+
+        #[cfg(feature = "filters")]
+        {
+            let mut filters = toml_edit::ArrayOfTables::new();
+            for (regex, replacement) in self.filters().iter() {
+                let mut table = toml_edit::Table::new();
+                table["regex"] = value(regex);
+                table["replacement"] = value(replacement);
+                filters.push(table);
+            }
+            if !filters.is_empty() {
+                doc["filters"] = toml_edit::Item::ArrayOfTables(filters);
+            }
+        }
+
+        #[cfg(feature = "redactions")]
+        {
+            let mut redactions = toml_edit::ArrayOfTables::new();
+            for (selector, redaction) in &self.inner.redactions.0 {
+                let replacement = match **redaction {
+                    Redaction::Static(ref content) => match content.resolve_inner() {
+                        Content::String(s) => value(s.as_str()),
+                        Content::Bool(b) => value(*b),
+                        Content::F32(f) => value(f64::from(*f)),
+                        Content::F64(f) => value(*f),
+                        other => match other.as_i64() {
+                            Some(n) => value(n),
+                            None => continue,
+                        },
+                    },
+                    Redaction::Dynamic(_) => continue,
+                };
+                let mut table = toml_edit::Table::new();
+                table["selector"] = value(selector.to_string());
+                table["replacement"] = replacement;
+                redactions.push(table);
+            }
+            if !redactions.is_empty() {
+                doc["redactions"] = toml_edit::Item::ArrayOfTables(redactions);
+            }
+        }
+
+        doc.to_string()
+    }
+
+    /// Reads settings written by [`to_toml`](Settings::to_toml).
+    ///
+    /// Settings missing from the TOML keep their defaults, so settings read
+    /// from the same file are identical no matter which crate reads them.
+    /// This makes it possible to keep the redactions and filters of a
+    /// workspace in one file that the tests of all crates load:
+    ///
+    /// ```rust
+    /// # use insta::Settings;
+    /// let settings = Settings::from_toml(
+    ///     r#"
+    /// sort_maps = true
+    /// snapshot_path = "snapshots"
+    /// "#,
+    /// )
+    /// .unwrap();
+    /// settings.bind(|| {
+    ///     // runs with the shared settings
+    /// });
+    /// ```
+    ///
+    /// Filters and redactions are lists of tables with a `regex` or
+    /// `selector` and a `replacement`, and require the `filters` and
+    /// `redactions` features respectively.  Unknown settings are an error.
+    pub fn from_toml(toml: &str) -> Result<Settings, SettingsError> {
+        let doc = toml
+            .parse::<toml_edit::DocumentMut>()
+            .map_err(|err| SettingsError(err.to_string()))?;
+        let mut settings = Settings::new();
+        for (key, item) in doc.iter() {
+            match key {
+                "sort_maps" => settings.set_sort_maps(toml_bool(key, item)?),
+                "normalize_datetimes" => {
+                    let precision = item
+                        .as_integer()
+                        .and_then(|x| u8::try_from(x).ok())
+                        .filter(|x| *x <= 9)
+                        .ok_or_else(|| {
+                            SettingsError(format!("`{key}` must be an integer from 0 to 9"))
+                        })?;
+                    settings.set_normalize_datetimes(Some(precision));
+                }
+                "snapshot_path" => settings.set_snapshot_path(toml_str(key, item)?),
+                "snapshot_suffix" => settings.set_snapshot_suffix(toml_str(key, item)?),
+                "snapshot_extension" => settings.set_snapshot_extension(toml_str(key, item)?),
+                "prepend_module_to_snapshot" => {
+                    settings.set_prepend_module_to_snapshot(toml_bool(key, item)?)
+                }
+                "omit_expression" => settings.set_omit_expression(toml_bool(key, item)?),
+                "ansi_mode" => {
+                    let mode = AnsiMode::parse(toml_str(key, item)?).ok_or_else(|| {
+                        SettingsError(format!("`{key}` must be \"strip\" or \"markup\""))
+                    })?;
+                    settings.set_ansi_mode(mode);
+                }
+                "description" => settings.set_description(toml_str(key, item)?),
+                #[cfg(feature = "glob")]
+                "allow_empty_glob" => settings.set_allow_empty_glob(toml_bool(key, item)?),
+                #[cfg(feature = "filters")]
+                "filters" => {
+                    for table in toml_tables(key, item)?.iter() {
+                        let regex = toml_str(
+                            "filters.regex",
+                            table.get("regex").unwrap_or(&toml_edit::Item::None),
+                        )?;
+                        let replacement = toml_str(
+                            "filters.replacement",
+                            table.get("replacement").unwrap_or(&toml_edit::Item::None),
+                        )?;
+                        settings
+                            ._private_inner_mut()
+                            .filters
+                            .try_add(regex, replacement)
+                            .map_err(|err| SettingsError(format!("invalid filter: {err}")))?;
+                    }
+                }
+                #[cfg(feature = "redactions")]
+                "redactions" => {
+                    for table in toml_tables(key, item)?.iter() {
+                        let selector = toml_str(
+                            "redactions.selector",
+                            table.get("selector").unwrap_or(&toml_edit::Item::None),
+                        )?;
+                        let selector = Selector::parse(selector).map_err(|err| {
+                            SettingsError(format!(
+                                "invalid selector {selector:?} at column {}",
+                                err.column()
+                            ))
+                        })?;
+                        let replacement = match table
+                            .get("replacement")
+                            .and_then(toml_edit::Item::as_value)
+                        {
+                            Some(toml_edit::Value::String(s)) => Content::from(s.value().as_str()),
+                            Some(toml_edit::Value::Integer(n)) => Content::from(*n.value()),
+                            Some(toml_edit::Value::Float(f)) => Content::from(*f.value()),
+                            Some(toml_edit::Value::Boolean(b)) => Content::from(*b.value()),
+                            _ => {
+                                return Err(SettingsError(
+                                    "`redactions.replacement` must be a string, number or \
+                                     boolean"
+                                        .into(),
+                                ))
+                            }
+                        };
+                        settings._private_inner_mut().redactions.0.push((
+                            selector.make_static(),
+                            Rc::new(Redaction::Static(replacement)),
+                        ));
+                    }
+                }
+                #[cfg(not(feature = "filters"))]
+                "filters" => {
+                    return Err(SettingsError(
+                        "filters require the `filters` feature".into(),
+                    ))
+                }
+                #[cfg(not(feature = "redactions"))]
+                "redactions" => {
+                    return Err(SettingsError(
+                        "redactions require the `redactions` feature".into(),
+                    ))
+                }
+                _ => return Err(SettingsError(format!("unknown setting `{key}`"))),
+            }
+        }
+        Ok(settings)
+    }
+}
+
+#[cfg(feature = "toml")]
+fn toml_bool(key: &str, item: &toml_edit::Item) -> Result<bool, SettingsError> {
+    item.as_bool()
+        .ok_or_else(|| SettingsError(format!("`{key}` must be a boolean")))
+}
+
+#[cfg(feature = "toml")]
+fn toml_str<'a>(key: &str, item: &'a toml_edit::Item) -> Result<&'a str, SettingsError> {
+    item.as_str()
+        .ok_or_else(|| SettingsError(format!("`{key}` must be a string")))
+}
+
+/// Returns a list of tables, written either as `[[key]]` tables or as an
+/// array of inline tables.
+#[cfg(all(feature = "toml", any(feature = "filters", feature = "redactions")))]
+fn toml_tables(
+    key: &str,
+    item: &toml_edit::Item,
+) -> Result<toml_edit::ArrayOfTables, SettingsError> {
+    item.clone()
+        .into_array_of_tables()
+        .map_err(|_| SettingsError(format!("`{key}` must be a list of tables")))
+}
+
 /// Returned from [`Settings::bind_to_scope`]
 ///
 /// This type is not shareable between threads:
@@ -1,7 +1,9 @@
 use crate::{
     content::{self, json, yaml, Content},
+    content_hash::{hash_contents, verify_hash},
     elog,
-    utils::style,
+    env::DEFAULT_INDENT_WIDTH,
+    utils::{long_path, style},
 };
 use once_cell::sync::Lazy;
 use std::env;
@@ -167,6 +169,18 @@ pub struct MetaData {
     pub(crate) info: Option<Content>,
     /// Reference to the input file.
     pub(crate) input_file: Option<String>,
+    /// The name and hash of the JSON schema the value was validated against.
+    pub(crate) schema: Option<String>,
+    /// Contextual key/value pairs of the assertion.  Like `assertion_line`
+    /// these are only kept in pending snapshots.
+    pub(crate) context: Vec<(String, String)>,
+    /// Whether the snapshot is a table whose rows are realigned for diffs.
+    /// Like `context` this is only kept in pending snapshots.
+    pub(crate) cell_diff: bool,
+    /// The hash of the contents of the reference snapshot when a pending
+    /// snapshot was written, or `none` if there was no reference.  Like
+    /// `context` this is only kept in pending snapshots.
+    pub(crate) reference_hash: Option<String>,
     /// The type of the snapshot (string or binary).
     pub(crate) snapshot_kind: SnapshotKind,
 }
@@ -214,6 +228,26 @@ impl MetaData {
         self.input_file.as_deref()
     }
 
+    /// Returns the name and hash of the JSON schema the value was validated
+    /// against.
+    pub fn schema(&self) -> Option<&str> {
+        self.schema.as_deref()
+    }
+
+    /// Returns the context attached to the assertion.
+    pub fn context(&self) -> &[(String, String)] {
+        &self.context
+    }
+
+    /// Returns the hash of the reference snapshot when this pending snapshot
+    /// was written, which is `none` if there was no reference.
+    ///
+    /// If it differs from [`Snapshot::contents_hash`] of the current
+    /// reference, the reference changed after the test ran.
+    pub fn reference_hash(&self) -> Option<&str> {
+        self.reference_hash.as_deref()
+    }
+
     fn from_content(content: Content) -> Result<MetaData, Box<dyn Error>> {
         if let Content::Map(map) = content {
             let mut source = None;
@@ -222,6 +256,10 @@ impl MetaData {
             let mut expression = None;
             let mut info = None;
             let mut input_file = None;
+            let mut schema = None;
+            let mut context = Vec::new();
+            let mut cell_diff = false;
+            let mut reference_hash = None;
             let mut snapshot_type = TmpSnapshotKind::Text;
             let mut extension = None;
 
@@ -238,6 +276,17 @@ impl MetaData {
                     Some("expression") => expression = value.as_str().map(Into::into),
                     Some("info") if !value.is_nil() => info = Some(value),
                     Some("input_file") => input_file = value.as_str().map(Into::into),
+                    Some("schema") => schema = value.as_str().map(Into::into),
+                    Some("context") => {
+                        if let Content::Map(items) = value {
+                            context = items
+                                .into_iter()
+                                .filter_map(|(k, v)| Some((k.as_str()?.into(), v.as_str()?.into())))
+                                .collect();
+                        }
+                    }
+                    Some("cell_diff") => cell_diff = value.as_bool().unwrap_or(false),
+                    Some("reference_hash") => reference_hash = value.as_str().map(Into::into),
                     Some("snapshot_kind") => {
                         snapshot_type = match value.as_str() {
                             Some("binary") => TmpSnapshotKind::Binary,
@@ -258,6 +307,10 @@ impl MetaData {
                 expression,
                 info,
                 input_file,
+                schema,
+                context,
+                cell_diff,
+                reference_hash,
                 snapshot_kind: match snapshot_type {
                     TmpSnapshotKind::Text => SnapshotKind::Text,
                     TmpSnapshotKind::Binary => SnapshotKind::Binary {
@@ -290,6 +343,27 @@ impl MetaData {
         if let Some(input_file) = self.input_file.as_deref() {
             fields.push(("input_file", Content::from(input_file)));
         }
+        if let Some(schema) = self.schema.as_deref() {
+            fields.push(("schema", Content::from(schema)));
+        }
+        if !self.context.is_empty() {
+            fields.push((
+                "context",
+                Content::Map(
+                    self.context
+                        .iter()
+                        .map(|(k, v)| (Content::from(k.as_str()), Content::from(v.as_str())))
+                        .collect(),
+                ),
+            ));
+        }
+
+        if self.cell_diff {
+            fields.push(("cell_diff", Content::from(true)));
+        }
+        if let Some(reference_hash) = self.reference_hash.as_deref() {
+            fields.push(("reference_hash", Content::from(reference_hash)));
+        }
 
         match self.snapshot_kind {
             SnapshotKind::Text => {}
@@ -312,9 +386,16 @@ impl MetaData {
         // `--require-full-match` is experimental and we're working on making
         // inline & file snapshots more coherent, I'm leaving this as is for
         // now.
-        if self.assertion_line.is_some() {
+        if self.assertion_line.is_some()
+            || !self.context.is_empty()
+            || self.cell_diff
+            || self.reference_hash.is_some()
+        {
             let mut rv = self.clone();
             rv.assertion_line = None;
+            rv.context.clear();
+            rv.cell_diff = false;
+            rv.reference_hash = None;
             Cow::Owned(rv)
         } else {
             Cow::Borrowed(self)
@@ -340,7 +421,7 @@ pub struct Snapshot {
 impl Snapshot {
     /// Loads a snapshot from a file.
     pub fn from_file(p: &Path) -> Result<Snapshot, Box<dyn Error>> {
-        let mut f = BufReader::new(fs::File::open(p)?);
+        let mut f = BufReader::new(fs::File::open(long_path(p))?);
         let mut buf = String::new();
 
         f.read_line(&mut buf)?;
@@ -398,15 +479,11 @@ impl Snapshot {
                     buf.push_str(&line);
                 }
 
-                TextSnapshotContents {
-                    contents: buf,
-                    kind: TextSnapshotKind::File,
-                }
-                .into()
+                TextSnapshotContents::new(buf, TextSnapshotKind::File).into()
             }
             SnapshotKind::Binary { ref extension } => {
                 let path = build_binary_path(extension, p);
-                let contents = fs::read(path)?;
+                let contents = fs::read(long_path(&path))?;
 
                 SnapshotContents::Binary(Rc::new(contents))
             }
@@ -436,6 +513,31 @@ impl Snapshot {
         }
     }
 
+    /// Creates a text snapshot that did not come from an assertion.
+    ///
+    /// `cargo insta` uses this for snapshots it produces itself, such as
+    /// macro expansions.
+    #[doc(hidden)]
+    #[cfg(feature = "_cargo_insta_internal")]
+    pub fn from_text(
+        module_name: String,
+        snapshot_name: String,
+        source: Option<String>,
+        expression: Option<String>,
+        contents: String,
+    ) -> Snapshot {
+        Snapshot::from_components(
+            module_name,
+            Some(snapshot_name),
+            MetaData {
+                source,
+                expression,
+                ..MetaData::default()
+            },
+            TextSnapshotContents::new(contents, TextSnapshotKind::File).into(),
+        )
+    }
+
     #[cfg(feature = "_cargo_insta_internal")]
     fn from_content(content: Content, kind: TextSnapshotKind) -> Result<Snapshot, Box<dyn Error>> {
         if let Content::Map(map) = content {
@@ -451,13 +553,13 @@ impl Snapshot {
                     Some("metadata") => metadata = Some(MetaData::from_content(value)?),
                     Some("snapshot") => {
                         snapshot = Some(
-                            TextSnapshotContents {
-                                contents: value
+                            TextSnapshotContents::new(
+                                value
                                     .as_str()
                                     .ok_or(content::Error::UnexpectedDataType)?
                                     .to_string(),
                                 kind,
-                            }
+                            )
                             .into(),
                         );
                     }
@@ -517,6 +619,118 @@ impl Snapshot {
         self.snapshot.as_text()
     }
 
+    /// Returns a hash of the contents, which ignores the metadata and the
+    /// differences that snapshot comparisons ignore.
+    ///
+    /// Small text snapshots are hashed with FNV-1a.  Binary and large
+    /// snapshots are hashed with BLAKE3 with the `blake3` feature, which is
+    /// enabled by default, with SHA-256 with only the `sha256` feature, and
+    /// with FNV-1a otherwise.  Hashes other than FNV-1a are prefixed with the
+    /// name of the algorithm, as in `blake3:…`.
+    pub fn contents_hash(&self) -> String {
+        match self.snapshot {
+            SnapshotContents::Text(ref contents) => {
+                hash_contents(contents.to_string().as_bytes(), true)
+            }
+            SnapshotContents::Binary(ref contents) => hash_contents(contents, false),
+        }
+    }
+
+    /// Returns whether `hash`, as returned by [`Snapshot::contents_hash`]
+    /// of this or another version of insta, is the hash of the contents.
+    ///
+    /// Returns `None` if the hash was made with an algorithm that is not
+    /// enabled.
+    pub fn matches_contents_hash(&self, hash: &str) -> Option<bool> {
+        match self.snapshot {
+            SnapshotContents::Text(ref contents) => {
+                verify_hash(hash, contents.to_string().as_bytes())
+            }
+            SnapshotContents::Binary(ref contents) => verify_hash(hash, contents),
+        }
+    }
+
+    /// Records the hash of the reference snapshot at `path` in the metadata
+    /// of this pending snapshot.
+    pub(crate) fn with_reference_hash(mut self, path: &Path) -> Snapshot {
+        self.metadata.reference_hash = if fs::metadata(long_path(path)).is_err() {
+            Some("none".into())
+        } else {
+            // a reference that cannot be parsed is reported when it's loaded
+            Snapshot::from_file(path).ok().map(|x| x.contents_hash())
+        };
+        self
+    }
+
+    /// Sets a field of the metadata, or removes it if `value` is `None`, and
+    /// returns whether the metadata changed.
+    ///
+    /// `description`, `expression` and `input_file` can be set, and these
+    /// as well as `info` and `schema` removed.
+    #[cfg(feature = "_cargo_insta_internal")]
+    pub fn set_metadata_field(&mut self, field: &str, value: Option<&str>) -> Result<bool, String> {
+        let md = &mut self.metadata;
+        let slot = match (field, value) {
+            ("description", _) => &mut md.description,
+            ("expression", _) => &mut md.expression,
+            ("input_file", _) => &mut md.input_file,
+            ("schema", None) => &mut md.schema,
+            ("info", None) => return Ok(md.info.take().is_some()),
+            (_, Some(_)) => return Err(format!("the metadata field `{field}` cannot be set")),
+            (_, None) => return Err(format!("the metadata field `{field}` cannot be removed")),
+        };
+        let value = value.map(str::to_string);
+        let changed = *slot != value;
+        *slot = value;
+        Ok(changed)
+    }
+
+    /// Returns this snapshot with its contents replaced by `contents`, for
+    /// instance by the result of a merge.
+    #[cfg(feature = "_cargo_insta_internal")]
+    pub fn with_text(&self, contents: String) -> Snapshot {
+        Snapshot {
+            snapshot: TextSnapshotContents::new(contents, TextSnapshotKind::File).into(),
+            ..self.clone()
+        }
+    }
+
+    /// Returns this snapshot with tabs in its inline contents counting as
+    /// `width` columns.
+    #[cfg(feature = "_cargo_insta_internal")]
+    pub fn with_indent_width(self, width: usize) -> Snapshot {
+        match self.snapshot {
+            SnapshotContents::Text(text) => Snapshot {
+                snapshot: text.with_indent_width(width).into(),
+                ..self
+            },
+            SnapshotContents::Binary(_) => self,
+        }
+    }
+
+    /// Returns a snapshot with only the named section of the contents.
+    pub(crate) fn section(&self, name: &str) -> Option<Snapshot> {
+        let section = self.as_text()?.section(name)?;
+        Some(Snapshot {
+            snapshot: section.into(),
+            ..self.clone()
+        })
+    }
+
+    /// Returns this snapshot with its contents stored as the named section
+    /// of the contents of `base`.
+    pub(crate) fn into_section_of(self, base: Option<&Snapshot>, name: &str) -> Snapshot {
+        let text = self.as_text().expect("sections require text snapshots");
+        let contents = match base.and_then(Snapshot::as_text) {
+            Some(base) => base.with_section(name, text),
+            None => TextSnapshotContents::new(String::new(), text.kind).with_section(name, text),
+        };
+        Snapshot {
+            snapshot: contents.into(),
+            ..self
+        }
+    }
+
     fn serialize_snapshot(&self, md: &MetaData) -> String {
         let mut buf = yaml::to_string(&md.as_content());
         buf.push_str("---\n");
@@ -534,15 +748,16 @@ impl Snapshot {
     // `.snap` file.
     fn save_with_metadata(&self, path: &Path, md: &MetaData) -> Result<(), Box<dyn Error>> {
         if let Some(folder) = path.parent() {
-            fs::create_dir_all(folder)?;
+            fs::create_dir_all(long_path(folder))?;
         }
 
         let serialized_snapshot = self.serialize_snapshot(md);
-        fs::write(path, serialized_snapshot)
+        fs::write(long_path(path), serialized_snapshot)
             .map_err(|e| content::Error::FileIo(e, path.to_path_buf()))?;
 
         if let SnapshotContents::Binary(ref contents) = self.snapshot {
-            fs::write(self.build_binary_path(path).unwrap(), &**contents)
+            let binary_path = self.build_binary_path(path).unwrap();
+            fs::write(long_path(&binary_path), &**contents)
                 .map_err(|e| content::Error::FileIo(e, path.to_path_buf()))?;
         }
 
@@ -568,9 +783,7 @@ impl Snapshot {
     ///
     /// The path of the new snapshot file is returned.
     pub(crate) fn save_new(&self, path: &Path) -> Result<PathBuf, Box<dyn Error>> {
-        // TODO: should we be the actual extension here rather than defaulting
-        // to the standard `.snap`?
-        let new_path = path.to_path_buf().with_extension("snap.new");
+        let new_path = pending_file_path(path);
         self.save_with_metadata(&new_path, &self.metadata)?;
         Ok(new_path)
     }
@@ -593,6 +806,7 @@ pub enum SnapshotContents {
 pub struct TextSnapshotContents {
     contents: String,
     pub kind: TextSnapshotKind,
+    indent_width: usize,
 }
 
 impl From<TextSnapshotContents> for SnapshotContents {
@@ -621,7 +835,18 @@ impl TextSnapshotContents {
         // it would avoid allocating a new `String` when we get the normalized
         // versions, which we may do a few times. (We want to store the
         // unnormalized version because it allows us to use `matches_fully`.)
-        TextSnapshotContents { contents, kind }
+        TextSnapshotContents {
+            contents,
+            kind,
+            indent_width: DEFAULT_INDENT_WIDTH,
+        }
+    }
+
+    /// Sets the number of columns a tab counts as when lining up the lines of
+    /// an inline snapshot.
+    pub fn with_indent_width(mut self, width: usize) -> TextSnapshotContents {
+        self.indent_width = width.max(1);
+        self
     }
 
     /// Matches another snapshot without any normalization
@@ -735,7 +960,7 @@ impl TextSnapshotContents {
 
     fn normalize(&self) -> String {
         let kind_specific_normalization = match self.kind {
-            TextSnapshotKind::Inline => normalize_inline(&self.contents),
+            TextSnapshotKind::Inline => normalize_inline(&self.contents, self.indent_width),
             TextSnapshotKind::File => self.contents.clone(),
         };
         // Then this we do for both kinds
@@ -814,6 +1039,102 @@ impl fmt::Display for TextSnapshotContents {
     }
 }
 
+/// Returns the name of a section header line of the form `--- name ---`.
+fn section_header(line: &str) -> Option<&str> {
+    line.strip_prefix("--- ")
+        .and_then(|x| x.strip_suffix(" ---"))
+        .filter(|x| !x.is_empty())
+}
+
+/// Returns whether `line` is a section header behind any number of
+/// backslashes, which lines of the contents that look like headers are
+/// escaped with.
+fn is_escaped_section_header(line: &str) -> bool {
+    section_header(line.trim_start_matches('\\')).is_some()
+}
+
+/// Splits snapshot contents into `(name, contents)` pairs at lines of the form
+/// `--- name ---`.  Text before the first section header gets an empty name.
+///
+/// Lines of the contents that look like headers are stored with an extra
+/// leading backslash by [`join_sections`], which is removed here.
+fn split_sections(contents: &str) -> Vec<(String, String)> {
+    let mut rv = vec![(String::new(), String::new())];
+    for line in contents.lines() {
+        match section_header(line) {
+            Some(name) => rv.push((name.to_string(), String::new())),
+            None => {
+                let section = &mut rv.last_mut().unwrap().1;
+                if is_escaped_section_header(line) {
+                    section.push_str(&line[1..]);
+                } else {
+                    section.push_str(line);
+                }
+                section.push('\n');
+            }
+        }
+    }
+    for (_, section) in rv.iter_mut() {
+        section.truncate(section.trim_end().len());
+    }
+    if rv[0].1.is_empty() {
+        rv.remove(0);
+    }
+    rv
+}
+
+/// Joins `(name, contents)` pairs under section headers, the inverse of
+/// [`split_sections`].
+fn join_sections(sections: &[(String, String)]) -> String {
+    let mut rv = String::new();
+    for (section_name, contents) in sections {
+        if !rv.is_empty() {
+            rv.push_str("\n\n");
+        }
+        if !section_name.is_empty() {
+            rv.push_str(&format!("--- {section_name} ---\n"));
+        }
+        for (idx, line) in contents.lines().enumerate() {
+            if idx > 0 {
+                rv.push('\n');
+            }
+            if is_escaped_section_header(line) {
+                rv.push('\\');
+            }
+            rv.push_str(line);
+        }
+    }
+    rv
+}
+
+impl TextSnapshotContents {
+    /// Returns the contents of the named section.
+    pub(crate) fn section(&self, name: &str) -> Option<TextSnapshotContents> {
+        split_sections(&self.to_string())
+            .into_iter()
+            .find(|(section_name, _)| section_name == name)
+            .map(|(_, contents)| {
+                TextSnapshotContents::new(contents, self.kind).with_indent_width(self.indent_width)
+            })
+    }
+
+    /// Returns a copy of the contents with the named section replaced by
+    /// `value`.  New sections are appended at the end.
+    pub(crate) fn with_section(&self, name: &str, value: &TextSnapshotContents) -> Self {
+        let mut sections = split_sections(&self.to_string());
+        let value = value.to_string();
+        match sections
+            .iter_mut()
+            .find(|(section_name, _)| section_name == name)
+        {
+            Some(section) => section.1 = value,
+            None => sections.push((name.to_string(), value)),
+        }
+        TextSnapshotContents::new(join_sections(&sections), self.kind)
+            .with_indent_width(self.indent_width)
+    }
+}
+
 impl PartialEq for SnapshotContents {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
@@ -834,6 +1155,15 @@ impl PartialEq for SnapshotContents {
     }
 }
 
+/// Returns the path of the pending snapshot for the snapshot file `path`,
+/// such as `name.snap.new` for `name.snap` and `name.snap.sql.new` for
+/// `name.snap.sql`.
+pub(crate) fn pending_file_path(path: &Path) -> PathBuf {
+    let mut rv = path.as_os_str().to_os_string();
+    rv.push(".new");
+    rv.into()
+}
+
 fn build_binary_path(extension: &str, path: impl Into<PathBuf>) -> PathBuf {
     let path = path.into();
     let mut new_extension = path.extension().unwrap().to_os_string();
@@ -876,37 +1206,74 @@ fn leading_space(value: &str) -> String {
         .collect::<String>()
 }
 
-fn min_indentation(snapshot: &str) -> String {
+/// Returns the number of columns `indentation` spans, with tabs advancing to
+/// the next multiple of `tab_width`.
+fn indentation_width(indentation: &str, tab_width: usize) -> usize {
+    indentation.chars().fold(0, |column, c| match c {
+        '\t' => column + tab_width - column % tab_width,
+        _ => column + 1,
+    })
+}
+
+fn min_indentation(snapshot: &str, tab_width: usize) -> String {
     let lines = snapshot.trim_end().lines();
 
+    // Lines are compared by their width rather than their length so that a
+    // snapshot with some lines indented by tabs and others by spaces lines up.
     lines
         .filter(|l| !l.is_empty())
         .map(leading_space)
-        .min_by(|a, b| a.len().cmp(&b.len()))
+        .min_by_key(|x| indentation_width(x, tab_width))
         .unwrap_or("".into())
 }
 
+/// Removes `columns` columns of indentation from the start of `line`.
+///
+/// A tab which only partially falls into the removed indentation is replaced
+/// by the spaces that remain of it.
+fn strip_indentation(line: &str, columns: usize, tab_width: usize) -> Cow<'_, str> {
+    let mut column = 0;
+    for (idx, c) in line.char_indices() {
+        if column >= columns {
+            return Cow::Borrowed(&line[idx..]);
+        }
+        column = match c {
+            ' ' => column + 1,
+            '\t' => column + tab_width - column % tab_width,
+            _ => return Cow::Borrowed(&line[idx..]),
+        };
+        if column > columns {
+            let rest = &line[idx + c.len_utf8()..];
+            return Cow::Owned(format!("{}{}", " ".repeat(column - columns), rest));
+        }
+    }
+    Cow::Borrowed("")
+}
+
 /// Normalize snapshot value, which we apply to both generated and literal
 /// snapshots. Remove excess indentation, excess ending whitespace and coerce
-/// newlines to `\n`.
-fn normalize_inline(snapshot: &str) -> String {
+/// newlines to `\n`.  A tab counts as `tab_width` columns.
+fn normalize_inline(snapshot: &str, tab_width: usize) -> String {
     // If it's a single line string, then we don't do anything.
     if snapshot.trim_end().lines().count() <= 1 {
         return snapshot.trim_end().to_string();
     }
 
-    let indentation = min_indentation(snapshot);
+    let columns = indentation_width(&min_indentation(snapshot, tab_width), tab_width);
     snapshot
         .lines()
-        .map(|l| l.get(indentation.len()..).unwrap_or(""))
-        .collect::<Vec<&str>>()
+        .map(|l| strip_indentation(l, columns, tab_width))
+        .collect::<Vec<_>>()
         .join("\n")
 }
 
 #[test]
 fn test_normalize_inline_snapshot() {
     fn normalized_of_literal(snapshot: &str) -> String {
-        normalize_inline(&TextSnapshotContents::from_inline_literal(snapshot).contents)
+        normalize_inline(
+            &TextSnapshotContents::from_inline_literal(snapshot).contents,
+            4,
+        )
     }
 
     use similar_asserts::assert_eq;
@@ -1019,14 +1386,14 @@ a
 /// Extracts the module and snapshot name from a snapshot path
 fn names_of_path(path: &Path) -> (String, String) {
     // The final part of the snapshot file name is the test name; the
-    // initial parts are the module name
-    let parts: Vec<&str> = path
-        .file_stem()
-        .unwrap()
-        .to_str()
-        .unwrap_or("")
-        .rsplitn(2, "__")
-        .collect();
+    // initial parts are the module name.  Snapshots with an extension are
+    // named like `name.snap.sql`.
+    let file_name = path.file_name().unwrap().to_str().unwrap_or("");
+    let stem = match file_name.rfind(".snap.") {
+        Some(idx) => &file_name[..idx],
+        None => path.file_stem().unwrap().to_str().unwrap_or(""),
+    };
+    let parts: Vec<&str> = stem.rsplitn(2, "__").collect();
 
     match parts.as_slice() {
         [snapshot_name, module_name] => (snapshot_name.to_string(), module_name.to_string()),
@@ -1061,6 +1428,14 @@ fn test_names_of_path() {
     )
     "#
     );
+    assert_debug_snapshot!(
+        names_of_path(Path::new("/src/snapshots/insta_tests__query.snap.sql")), @r#"
+    (
+        "query",
+        "insta_tests",
+    )
+    "#
+    );
 }
 
 /// legacy format - retain so old snapshots still work
@@ -1332,6 +1707,7 @@ fn test_min_indentation() {
    1
    2
    ",
+            4,
         ),
         "   ".to_string()
     );
@@ -1340,7 +1716,8 @@ fn test_min_indentation() {
         min_indentation(
             "
             1
-    2"
+    2",
+            4
         ),
         "    ".to_string()
     );
@@ -1350,7 +1727,8 @@ fn test_min_indentation() {
             "
             1
             2
-    "
+    ",
+            4
         ),
         "            ".to_string()
     );
@@ -1360,7 +1738,8 @@ fn test_min_indentation() {
             "
    1
    2
-"
+",
+            4
         ),
         "   ".to_string()
     );
@@ -1369,12 +1748,13 @@ fn test_min_indentation() {
         min_indentation(
             "
         a
-    "
+    ",
+            4
         ),
         "        ".to_string()
     );
 
-    assert_eq!(min_indentation(""), "".to_string());
+    assert_eq!(min_indentation("", 4), "".to_string());
 
     assert_eq!(
         min_indentation(
@@ -1382,7 +1762,8 @@ fn test_min_indentation() {
     a
     b
 c
-    "
+    ",
+            4
         ),
         "".to_string()
     );
@@ -1391,7 +1772,7 @@ c
         min_indentation(
             "
 a
-    "
+    ", 4
         ),
         "".to_string()
     );
@@ -1399,7 +1780,7 @@ a
     assert_eq!(
         min_indentation(
             "
-    a"
+    a", 4
         ),
         "    ".to_string()
     );
@@ -1407,7 +1788,7 @@ a
     assert_eq!(
         min_indentation(
             "a
-  a"
+  a", 4
         ),
         "".to_string()
     );
@@ -1416,7 +1797,7 @@ a
         normalize_inline(
             "
 			1
-	2"
+	2", 4
         ),
         "
 		1
@@ -1428,7 +1809,8 @@ a
             "
 	  	  1
 	  	  2
-    "
+    ",
+            4
         ),
         "
 1
@@ -1445,56 +1827,69 @@ fn test_min_indentation_additional() {
    1
    2
 ";
-    assert_eq!(min_indentation(t), "   ".to_string());
+    assert_eq!(min_indentation(t, 4), "   ".to_string());
 
     let t = "
         a
     ";
-    assert_eq!(min_indentation(t), "        ".to_string());
+    assert_eq!(min_indentation(t, 4), "        ".to_string());
 
     let t = "";
-    assert_eq!(min_indentation(t), "".to_string());
+    assert_eq!(min_indentation(t, 4), "".to_string());
 
     let t = "
     a
     b
 c
     ";
-    assert_eq!(min_indentation(t), "".to_string());
+    assert_eq!(min_indentation(t, 4), "".to_string());
 
     let t = "
 a";
-    assert_eq!(min_indentation(t), "".to_string());
+    assert_eq!(min_indentation(t, 4), "".to_string());
 
     let t = "
     a";
-    assert_eq!(min_indentation(t), "    ".to_string());
+    assert_eq!(min_indentation(t, 4), "    ".to_string());
 
     let t = "a
   a";
-    assert_eq!(min_indentation(t), "".to_string());
+    assert_eq!(min_indentation(t, 4), "".to_string());
 
     let t = "
  	1
  	2
     ";
-    assert_eq!(min_indentation(t), " 	".to_string());
+    assert_eq!(min_indentation(t, 4), " 	".to_string());
 
     let t = "
   	  	  	1
   	2";
-    assert_eq!(min_indentation(t), "  	".to_string());
+    assert_eq!(min_indentation(t, 4), "  	".to_string());
 
     let t = "
 			1
 	2";
-    assert_eq!(min_indentation(t), "	".to_string());
+    assert_eq!(min_indentation(t, 4), "	".to_string());
+}
+
+#[test]
+fn test_normalize_inline_mixed_indentation() {
+    use similar_asserts::assert_eq;
+
+    // a tab and four spaces span the same columns
+    assert_eq!(normalize_inline("\n\t\ta\n    \tb\n\t", 4), "\na\nb\n");
+    assert_eq!(normalize_inline("\n\ta\n      b\n\t", 4), "\na\n  b\n");
+    // the part of a tab that is not removed is kept as spaces
+    assert_eq!(normalize_inline("\n  a\n\tb", 4), "\na\n  b");
+    // with wider tabs the same lines no longer line up
+    assert_eq!(normalize_inline("\n\t\ta\n    \tb\n\t", 8), "\n\ta\nb\n");
 }
 
 #[test]
 fn test_inline_snapshot_value_newline() {
     // https://github.com/mitsuhiko/insta/issues/39
-    assert_eq!(normalize_inline("\n"), "");
+    assert_eq!(normalize_inline("\n", 4), "");
 }
 
 #[test]
@@ -1553,3 +1948,90 @@ fn test_empty_lines() {
     final line
     ");
 }
+
+#[test]
+fn test_context_not_persisted() {
+    let metadata = MetaData {
+        assertion_line: Some(42),
+        context: vec![("request_id".into(), "42".into())],
+        cell_diff: true,
+        reference_hash: Some("none".into()),
+        ..MetaData::default()
+    };
+    let content = yaml::parse_str(
+        &yaml::to_string(&metadata.as_content()),
+        Path::new("snapshot.snap"),
+    )
+    .unwrap();
+    let parsed = MetaData::from_content(content).unwrap();
+    assert_eq!(parsed.context(), metadata.context());
+    assert!(parsed.cell_diff);
+    assert_eq!(parsed.reference_hash(), Some("none"));
+    let trimmed = metadata.trim_for_persistence();
+    assert!(trimmed.context().is_empty());
+    assert_eq!(trimmed.assertion_line, None);
+    assert!(!trimmed.cell_diff);
+    assert_eq!(trimmed.reference_hash(), None);
+}
+
+#[test]
+fn test_snapshot_sections() {
+    let contents = TextSnapshotContents::new("".into(), TextSnapshotKind::File);
+    let summary = TextSnapshotContents::new("3 items\n".into(), TextSnapshotKind::File);
+    let details = TextSnapshotContents::new("a\n\nb".into(), TextSnapshotKind::File);
+    let contents = contents
+        .with_section("summary", &summary)
+        .with_section("details", &details);
+    assert_snapshot!(contents.to_string(), @"
+    --- summary ---
+    3 items
+
+    --- details ---
+    a
+
+    b
+    ");
+    assert_eq!(contents.section("details").unwrap().to_string(), "a\n\nb");
+    assert!(contents.section("missing").is_none());
+
+    let summary = TextSnapshotContents::new("4 items".into(), TextSnapshotKind::File);
+    let contents = contents.with_section("summary", &summary);
+    assert_eq!(contents.section("summary").unwrap().to_string(), "4 items");
+    assert_eq!(contents.section("details").unwrap().to_string(), "a\n\nb");
+}
+
+#[test]
+fn test_snapshot_sections_escape_headers() {
+    let contents = TextSnapshotContents::new("".into(), TextSnapshotKind::File);
+    let log = TextSnapshotContents::new(
+        "start\n--- fake ---\n\\--- escaped ---\nend".into(),
+        TextSnapshotKind::File,
+    );
+    let summary = TextSnapshotContents::new("ok".into(), TextSnapshotKind::File);
+    let contents = contents
+        .with_section("log", &log)
+        .with_section("summary", &summary);
+    assert_snapshot!(contents.to_string(), @r"
+    --- log ---
+    start
+    \--- fake ---
+    \\--- escaped ---
+    end
+
+    --- summary ---
+    ok
+    ");
+    assert_eq!(
+        contents.section("log").unwrap().to_string(),
+        log.to_string()
+    );
+    assert!(contents.section("fake").is_none());
+
+    // unchanged sections keep their escapes
+    let summary = TextSnapshotContents::new("still ok".into(), TextSnapshotKind::File);
+    let contents = contents.with_section("summary", &summary);
+    assert_eq!(
+        contents.section("log").unwrap().to_string(),
+        log.to_string()
+    );
+}
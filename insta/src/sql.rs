@@ -0,0 +1,114 @@
+use std::fmt::Display;
+
+use sqlformat::{FormatOptions, QueryParams};
+
+/// Formats the SQL query `input` with one clause per line and keywords in
+/// upper case.
+///
+/// With `normalize_params` the parameter placeholders `?`, `?N` and `$N`
+/// are renumbered as `$1`, `$2`, … in the order they first appear, so that
+/// the snapshot does not depend on the placeholder style or the numbering a
+/// query builder chose.  Placeholders that are the same in the query stay
+/// the same, every `?` gets a number of its own.
+pub fn format_sql<V: Display + ?Sized>(input: &V, normalize_params: bool) -> String {
+    let mut query = input.to_string();
+    if normalize_params {
+        query = renumber_params(&query);
+    }
+    let options = FormatOptions {
+        uppercase: true,
+        ..FormatOptions::default()
+    };
+    sqlformat::format(&query, &QueryParams::None, options)
+}
+
+/// Renumbers the placeholders outside of strings, quoted identifiers and
+/// comments.
+fn renumber_params(query: &str) -> String {
+    let mut rv = String::with_capacity(query.len());
+    let mut seen: Vec<(String, usize)> = vec![];
+    let mut count = 0;
+    let mut chars = query.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                rv.push(c);
+                for (_, next) in chars.by_ref() {
+                    rv.push(next);
+                    if next == c {
+                        break;
+                    }
+                }
+            }
+            '-' if query[idx..].starts_with("--") => {
+                let end = query[idx..].find('\n').map_or(query.len(), |x| idx + x);
+                rv.push_str(&query[idx..end]);
+                while chars.peek().map_or(false, |&(next, _)| next < end) {
+                    chars.next();
+                }
+            }
+            '/' if query[idx..].starts_with("/*") => {
+                let end = query[idx + 2..]
+                    .find("*/")
+                    .map_or(query.len(), |x| idx + x + 4);
+                rv.push_str(&query[idx..end]);
+                while chars.peek().map_or(false, |&(next, _)| next < end) {
+                    chars.next();
+                }
+            }
+            '?' | '$' => {
+                let mut digits = String::new();
+                while let Some(&(_, d)) = chars.peek().filter(|(_, d)| d.is_ascii_digit()) {
+                    digits.push(d);
+                    chars.next();
+                }
+                if digits.is_empty() && c == '$' {
+                    rv.push(c);
+                    continue;
+                }
+                let number = if digits.is_empty() {
+                    count += 1;
+                    count
+                } else {
+                    let key = format!("{c}{digits}");
+                    match seen.iter().find(|(x, _)| *x == key) {
+                        Some(&(_, number)) => number,
+                        None => {
+                            count += 1;
+                            seen.push((key, count));
+                            count
+                        }
+                    }
+                };
+                rv.push_str(&format!("${number}"));
+            }
+            c => rv.push(c),
+        }
+    }
+    rv
+}
+
+#[test]
+fn test_format_sql() {
+    assert_eq!(
+        format_sql(
+            "select id, name from users where id = $1 and org = $2",
+            false
+        ),
+        "SELECT\n  id,\n  name\nFROM\n  users\nWHERE\n  id = $1\n  AND org = $2"
+    );
+}
+
+#[test]
+fn test_renumber_params() {
+    assert_eq!(
+        renumber_params("a = $3 and b = $7 or c = $3"),
+        "a = $1 and b = $2 or c = $1"
+    );
+    assert_eq!(renumber_params("a = ? and b = ?"), "a = $1 and b = $2");
+    assert_eq!(renumber_params("a = ?2 and b = ?1"), "a = $1 and b = $2");
+    assert_eq!(
+        renumber_params("a = '?' and \"$1\" = $9 -- $5\nand b = ? /* ? */"),
+        "a = '?' and \"$1\" = $1 -- $5\nand b = $2 /* ? */"
+    );
+}
@@ -0,0 +1,81 @@
+use std::fmt::Display;
+
+/// Parses `input` into the syntax tree `T` and returns its pretty printed
+/// [`Debug`] representation.
+///
+/// Panics if `input` does not parse as `T`.
+pub fn parse_debug<T, V>(input: &V) -> String
+where
+    T: syn::parse::Parse + std::fmt::Debug,
+    V: Display + ?Sized,
+{
+    let input = input.to_string();
+    match syn::parse_str::<T>(&input) {
+        Ok(tree) => strip_spans(&format!("{tree:#?}")),
+        Err(err) => panic!(
+            "failed to parse snapshot value as {}: {}\n\n{}",
+            std::any::type_name::<T>(),
+            err,
+            input
+        ),
+    }
+}
+
+/// Removes span locations from the debug output of a syntax tree.
+///
+/// When another crate in the build enables the `span-locations` feature of
+/// proc-macro2, tokens print the byte offsets of their spans and identifiers
+/// print as structs.  The offsets change with everything parsed before, so
+/// they are dropped and identifiers are written as without the feature.
+fn strip_spans(debug: &str) -> String {
+    let lines = debug
+        .lines()
+        .filter(|line| !is_span_field(line))
+        .collect::<Vec<_>>();
+    let mut rv = String::new();
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line = lines[idx];
+        if let (Some(prefix), Some(sym), Some(close)) = (
+            line.strip_suffix("Ident {"),
+            lines
+                .get(idx + 1)
+                .and_then(|x| x.split_once("sym: "))
+                .filter(|(indent, _)| indent.trim().is_empty()),
+            lines
+                .get(idx + 2)
+                .and_then(|x| x.split_once('}'))
+                .filter(|(indent, _)| indent.trim().is_empty()),
+        ) {
+            rv.push_str(&format!(
+                "{prefix}Ident(\n{}{}\n{}){}\n",
+                sym.0, sym.1, close.0, close.1
+            ));
+            idx += 3;
+        } else {
+            rv.push_str(line);
+            rv.push('\n');
+            idx += 1;
+        }
+    }
+    rv.pop();
+    rv
+}
+
+fn is_span_field(line: &str) -> bool {
+    line.trim()
+        .split_once(": bytes(")
+        .map_or(false, |(field, rest)| {
+            field.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && (rest.ends_with("),") || rest.ends_with(')'))
+        })
+}
+
+#[test]
+fn test_strip_spans() {
+    let debug = "Expr::Path {\n    ident: Ident {\n        sym: a,\n        span: bytes(1..2),\n    },\n    punct: Punct {\n        char: '+',\n        span: bytes(3..4),\n    },\n}";
+    assert_eq!(
+        strip_spans(debug),
+        "Expr::Path {\n    ident: Ident(\n        a,\n    ),\n    punct: Punct {\n        char: '+',\n    },\n}"
+    );
+}
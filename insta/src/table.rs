@@ -0,0 +1,204 @@
+#[cfg(feature = "serde")]
+use crate::content::{json, Content};
+#[cfg(feature = "serde")]
+use crate::settings::Settings;
+
+/// Escapes a cell so that it stays on one line and a `|` in it is not taken
+/// for the end of the cell.
+#[cfg(feature = "serde")]
+pub(crate) fn escape_cell(cell: &str) -> String {
+    let mut rv = String::with_capacity(cell.len());
+    for c in cell.chars() {
+        match c {
+            '\\' => rv.push_str("\\\\"),
+            '|' => rv.push_str("\\|"),
+            '\n' => rv.push_str("\\n"),
+            '\r' => rv.push_str("\\r"),
+            c => rv.push(c),
+        }
+    }
+    rv
+}
+
+/// Formats rows of escaped cells as lines with the cells separated by `|`
+/// and every column padded to the width of its widest cell.
+#[cfg(feature = "serde")]
+pub(crate) fn render_table(rows: &[Vec<String>]) -> String {
+    render_rows(rows, &column_widths(rows.iter()))
+}
+
+/// Formats serialized items as a table with a column per field.
+///
+/// Every element of a sequence is a row, and the columns are the fields of
+/// structs, the keys of maps or the positions in tuples, in the order they
+/// first appear.  Strings are written without quotes, missing fields and
+/// `None` as empty cells and nested values as compact JSON.
+#[cfg(feature = "serde")]
+pub(crate) fn render_content_table(content: &Content) -> String {
+    let items = content
+        .as_slice()
+        .unwrap_or_else(|| std::slice::from_ref(content));
+    let mut columns: Vec<String> = vec![];
+    let rows = items
+        .iter()
+        .map(|item| {
+            let fields = match item.resolve_inner() {
+                Content::Struct(_, fields) | Content::StructVariant(_, _, _, fields) => fields
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), format_cell(value)))
+                    .collect(),
+                Content::Map(entries) => entries
+                    .iter()
+                    .map(|(key, value)| (format_cell(key), format_cell(value)))
+                    .collect(),
+                Content::Seq(values)
+                | Content::Tuple(values)
+                | Content::TupleStruct(_, values)
+                | Content::TupleVariant(_, _, _, values) => values
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, value)| (idx.to_string(), format_cell(value)))
+                    .collect(),
+                _ => vec![("value".to_string(), format_cell(item))],
+            };
+            for (key, _) in &fields {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+            fields
+        })
+        .collect::<Vec<Vec<(String, String)>>>();
+
+    let mut table = vec![columns.iter().map(|x| escape_cell(x)).collect::<Vec<_>>()];
+    for row in rows {
+        table.push(
+            columns
+                .iter()
+                .map(|column| match row.iter().find(|(key, _)| key == column) {
+                    Some((_, value)) => escape_cell(value),
+                    None => String::new(),
+                })
+                .collect(),
+        );
+    }
+    render_table(&table)
+}
+
+#[cfg(feature = "serde")]
+fn format_cell(value: &Content) -> String {
+    match value {
+        Content::Some(inner) | Content::NewtypeStruct(_, inner) => format_cell(inner),
+        Content::None | Content::Unit => String::new(),
+        Content::String(x) => x.clone(),
+        Content::Char(x) => x.to_string(),
+        Content::UnitStruct(name) => name.to_string(),
+        Content::UnitVariant(_, _, variant) => variant.to_string(),
+        other => json::to_string_compact(other),
+    }
+}
+
+/// Runs `f` with settings that mark the snapshot as a table, so that its
+/// columns are realigned when it is compared in a diff.
+#[cfg(feature = "serde")]
+pub fn with_cell_diff<R, F: FnOnce() -> R>(f: F) -> R {
+    let mut settings = Settings::clone_current();
+    settings._private_inner_mut().cell_diff = true;
+    settings.bind(f)
+}
+
+/// Realigns two tables formatted by [`render_table`] to the same column
+/// widths.
+///
+/// When a cell gets wider than the other cells of its column, the padding
+/// of every row changes.  With the same widths on both sides, only the rows
+/// whose cells changed differ in a diff.
+pub(crate) fn align_tables(old: &str, new: &str) -> (String, String) {
+    let old_rows = old.lines().map(split_row).collect::<Vec<_>>();
+    let new_rows = new.lines().map(split_row).collect::<Vec<_>>();
+    let widths = column_widths(old_rows.iter().chain(new_rows.iter()));
+    (
+        render_rows(&old_rows, &widths),
+        render_rows(&new_rows, &widths),
+    )
+}
+
+fn column_widths<'a>(rows: impl Iterator<Item = &'a Vec<String>>) -> Vec<usize> {
+    let mut widths = Vec::new();
+    for row in rows {
+        for (idx, cell) in row.iter().enumerate() {
+            let width = cell.chars().count();
+            match widths.get_mut(idx) {
+                Some(max) if *max < width => *max = width,
+                Some(_) => {}
+                None => widths.push(width),
+            }
+        }
+    }
+    widths
+}
+
+fn render_rows(rows: &[Vec<String>], widths: &[usize]) -> String {
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join(" | ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Splits a line of a table into its cells, leaving them escaped.
+fn split_row(line: &str) -> Vec<String> {
+    let mut cells = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        let cell = cells.last_mut().unwrap();
+        match c {
+            '\\' => {
+                cell.push(c);
+                cell.extend(chars.next());
+            }
+            '|' => cells.push(String::new()),
+            c => cell.push(c),
+        }
+    }
+    cells.iter().map(|cell| cell.trim().to_string()).collect()
+}
+
+#[test]
+fn test_align_tables() {
+    let old = "id | name\n1  | Al\n2  | a\\|b";
+    let new = "id  | name\n100 | Al\n2   | a\\|b";
+    let (old, new) = align_tables(old, new);
+    assert_eq!(old, "id  | name\n1   | Al\n2   | a\\|b");
+    assert_eq!(new, "id  | name\n100 | Al\n2   | a\\|b");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_render_content_table() {
+    let row = |id: u32, name: Option<&str>, tags: Vec<Content>| {
+        let mut fields = vec![("id", Content::from(id))];
+        if let Some(name) = name {
+            fields.push(("name", Content::Some(Box::new(Content::from(name)))));
+        }
+        fields.push(("tags", Content::Seq(tags)));
+        Content::Struct("Row", fields)
+    };
+    let content = Content::Seq(vec![
+        row(1, Some("a|b"), vec![]),
+        row(22, None, vec![Content::from("x"), Content::from(2u8)]),
+    ]);
+    assert_eq!(
+        render_content_table(&content),
+        "id | name | tags\n\
+         1  | a\\|b | []\n\
+         22 |      | [\"x\", 2]"
+    );
+}
@@ -0,0 +1,195 @@
+use std::fmt::{self, Write};
+use std::sync::Mutex;
+
+use tracing_core::field::{Field, Visit};
+use tracing_core::span::{Attributes, Id, Record};
+use tracing_core::subscriber::Interest;
+use tracing_core::{dispatcher, Dispatch, Event, Level, LevelFilter, Metadata, Subscriber};
+
+/// An event recorded by [`capture_tracing`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedEvent {
+    /// The level of the event.
+    pub level: Level,
+    /// The target of the event, usually the module it was emitted in.
+    pub target: String,
+    /// The spans the event was emitted in, outermost first, formatted as
+    /// `name{field=value}`.
+    pub spans: Vec<String>,
+    /// The message of the event.
+    pub message: String,
+    /// The other fields of the event, formatted as `field=value`.
+    pub fields: Vec<String>,
+}
+
+impl fmt::Display for CapturedEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ", self.level)?;
+        for span in &self.spans {
+            write!(f, "{span}: ")?;
+        }
+        write!(f, "{}: {}", self.target, self.message)?;
+        for field in &self.fields {
+            write!(f, " {field}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The events recorded by [`capture_tracing`].
+///
+/// Displays as one line per event, without timestamps and thread ids.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CapturedEvents {
+    /// The events in the order they were emitted.
+    pub events: Vec<CapturedEvent>,
+}
+
+impl fmt::Display for CapturedEvents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, event) in self.events.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{event}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `f` and records the [`tracing`](https://docs.rs/tracing) events it
+/// emits at `level` or above.
+///
+/// **Feature:** `tracing` (disabled by default)
+///
+/// A subscriber is installed for the current thread while `f` runs, so
+/// events of other threads are not recorded.  The recorded events display
+/// as one line per event with the level, the spans the event is in, the
+/// target, the message and the fields.  Timestamps and thread ids are left
+/// out, so the output can be snapshotted as it is:
+///
+/// ```no_run
+/// let events = insta::capture_tracing(tracing::Level::INFO, || {
+///     let _span = tracing::info_span!("request", id = 7).entered();
+///     tracing::info!(target: "app::db", rows = 3, "query done");
+///     tracing::debug!(target: "app::db", "not recorded");
+/// });
+/// insta::assert_snapshot!(events, @"INFO request{id=7}: app::db: query done rows=3");
+/// ```
+pub fn capture_tracing<F: FnOnce()>(level: Level, f: F) -> CapturedEvents {
+    let subscriber = CaptureSubscriber {
+        level,
+        state: Mutex::new(State::default()),
+    };
+    let dispatch = Dispatch::new(subscriber);
+    dispatcher::with_default(&dispatch, f);
+    let subscriber = dispatch
+        .downcast_ref::<CaptureSubscriber>()
+        .expect("capture subscriber");
+    let events = std::mem::take(&mut subscriber.state.lock().unwrap().events);
+    CapturedEvents { events }
+}
+
+struct CaptureSubscriber {
+    level: Level,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    /// The formatted spans, indexed by their id minus one.
+    spans: Vec<String>,
+    /// The ids of the entered spans, innermost last.
+    stack: Vec<u64>,
+    events: Vec<CapturedEvent>,
+}
+
+impl Subscriber for CaptureSubscriber {
+    fn register_callsite(&self, _metadata: &'static Metadata<'static>) -> Interest {
+        // other subscribers may be interested in callsites this one is not
+        Interest::sometimes()
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= &self.level
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(LevelFilter::from_level(self.level))
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut fields = FieldVisitor::default();
+        span.record(&mut fields);
+        let mut formatted = span.metadata().name().to_string();
+        if !fields.fields.is_empty() {
+            write!(formatted, "{{{}}}", fields.fields.join(" ")).unwrap();
+        }
+        let mut state = self.state.lock().unwrap();
+        state.spans.push(formatted);
+        Id::from_u64(state.spans.len() as u64)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut fields = FieldVisitor::default();
+        values.record(&mut fields);
+        if fields.fields.is_empty() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        if let Some(formatted) = state.spans.get_mut(span.into_u64() as usize - 1) {
+            match formatted.strip_suffix('}') {
+                Some(rest) => *formatted = format!("{rest} {}}}", fields.fields.join(" ")),
+                None => write!(formatted, "{{{}}}", fields.fields.join(" ")).unwrap(),
+            }
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = FieldVisitor::default();
+        event.record(&mut fields);
+        let metadata = event.metadata();
+        let mut state = self.state.lock().unwrap();
+        let spans = state
+            .stack
+            .iter()
+            .map(|id| state.spans[*id as usize - 1].clone())
+            .collect();
+        state.events.push(CapturedEvent {
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            spans,
+            message: fields.message,
+            fields: fields.fields,
+        });
+    }
+
+    fn enter(&self, span: &Id) {
+        self.state.lock().unwrap().stack.push(span.into_u64());
+    }
+
+    fn exit(&self, span: &Id) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(idx) = state.stack.iter().rposition(|id| *id == span.into_u64()) {
+            state.stack.remove(idx);
+        }
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: Vec<String>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        } else {
+            self.fields.push(format!("{}={value:?}", field.name()));
+        }
+    }
+}
@@ -0,0 +1,8 @@
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+
+/// Generates the JSON schema of a type with the default settings of
+/// schemars.
+pub fn schema_for<T: JsonSchema>() -> RootSchema {
+    schemars::schema_for!(T)
+}
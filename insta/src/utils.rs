@@ -1,6 +1,7 @@
 use std::{
     borrow::Cow,
     env,
+    fmt::{Debug, Display},
     io::Write,
     path::Path,
     process::{Command, Stdio},
@@ -74,6 +75,65 @@ pub fn path_to_storage(path: &Path) -> String {
     }
 }
 
+/// The length from which Windows rejects paths that are not verbatim.
+#[cfg(windows)]
+const MAX_PATH: usize = 260;
+
+/// Returns a path that file operations accept even if it is longer than
+/// `MAX_PATH` on Windows.
+///
+/// Long absolute paths are turned into verbatim paths (`\\?\C:\...`),
+/// which Windows does not limit in length.  Other paths, and all paths on
+/// other platforms, are returned as they are.
+pub fn long_path(path: &Path) -> Cow<'_, Path> {
+    #[cfg(windows)]
+    {
+        if path.as_os_str().len() >= MAX_PATH {
+            if let Some(verbatim) = path.to_str().and_then(verbatim_path) {
+                return Cow::Owned(verbatim.into());
+            }
+        }
+    }
+    Cow::Borrowed(path)
+}
+
+/// Converts an absolute Windows path into a verbatim path.
+///
+/// Verbatim paths are not normalized by Windows, so separators are made
+/// backslashes and `.` and `..` are resolved here.
+#[cfg(any(windows, test))]
+fn verbatim_path(path: &str) -> Option<String> {
+    if path.starts_with(r"\\?\") {
+        return None;
+    }
+    let path = path.replace('/', "\\");
+    let (mut rv, rest) = if let Some(rest) = path.strip_prefix(r"\\") {
+        (String::from(r"\\?\UNC"), rest)
+    } else if path.as_bytes().get(1) == Some(&b':')
+        && path.as_bytes().get(2) == Some(&b'\\')
+        && path.as_bytes()[0].is_ascii_alphabetic()
+    {
+        (format!(r"\\?\{}", &path[..2]), &path[3..])
+    } else {
+        return None;
+    };
+    let mut parts = vec![];
+    for part in rest.split('\\') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    for part in parts {
+        rv.push('\\');
+        rv.push_str(part);
+    }
+    Some(rv)
+}
+
 /// Tries to format a given rust expression with rustfmt
 pub fn format_rust_expression(value: &str) -> Cow<'_, str> {
     const PREFIX: &str = "const x:() = ";
@@ -108,6 +168,72 @@ pub fn format_rust_expression(value: &str) -> Cow<'_, str> {
     Cow::Borrowed(value)
 }
 
+/// Wraps the lines of `text` at whitespace so that they are at most `width`
+/// characters long.
+///
+/// Continuation lines keep the indentation of the line they continue, and
+/// words longer than `width` are put on a line of their own.  Whitespace
+/// between the words of a wrapped line is collapsed and trailing whitespace
+/// is removed from all lines.
+pub fn wrap_lines(text: &str, width: usize) -> String {
+    let mut rv = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.chars().count() <= width {
+            rv.push(line.to_string());
+            continue;
+        }
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let mut current = indent.to_string();
+        for word in line.split_whitespace() {
+            if current.len() == indent.len() {
+                current.push_str(word);
+            } else if current.chars().count() + 1 + word.chars().count() <= width {
+                current.push(' ');
+                current.push_str(word);
+            } else {
+                rv.push(std::mem::replace(&mut current, format!("{indent}{word}")));
+            }
+        }
+        rv.push(current);
+    }
+    rv.join("\n")
+}
+
+/// Renders one row per item of `variants` with the [`Debug`] representation
+/// of the item and what `render` returns for it, aligned in two columns.
+///
+/// Lines after the first of a multi-line value are indented to the second
+/// column.
+pub fn render_variants<I, F, R>(variants: &I, render: F) -> String
+where
+    I: IntoIterator + Clone,
+    I::Item: Debug,
+    F: Fn(&I::Item) -> R,
+    R: Display,
+{
+    let rows = variants
+        .clone()
+        .into_iter()
+        .map(|variant| (format!("{variant:?}"), render(&variant).to_string()))
+        .collect::<Vec<_>>();
+    let width = rows
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut rv = Vec::new();
+    for (name, value) in &rows {
+        let mut lines = value.lines();
+        let first = lines.next().unwrap_or("");
+        rv.push(format!("{name:width$} | {first}").trim_end().to_string());
+        for line in lines {
+            rv.push(format!("{:width$} | {line}", "").trim_end().to_string());
+        }
+    }
+    rv.join("\n")
+}
+
 #[cfg(feature = "_cargo_insta_internal")]
 pub fn get_cargo() -> std::ffi::OsString {
     let cargo = env::var_os("CARGO");
@@ -125,3 +251,32 @@ fn test_format_rust_expression() {
     assert_snapshot!(format_rust_expression(r#"  "aoe😄""#), @r#""aoe😄""#);
     assert_snapshot!(format_rust_expression("😄😄😄😄😄"), @"😄😄😄😄😄")
 }
+
+#[test]
+fn test_wrap_lines() {
+    assert_eq!(
+        wrap_lines(
+            "error: the value   is far too long to fit\n  --> indented line that wraps around\nshort  ",
+            20
+        ),
+        "error: the value is\nfar too long to fit\n  --> indented line\n  that wraps around\nshort"
+    );
+    assert_eq!(
+        wrap_lines("a https://example.com/a/very/long/url b", 10),
+        "a\nhttps://example.com/a/very/long/url\nb"
+    );
+}
+
+#[test]
+fn test_verbatim_path() {
+    assert_eq!(
+        verbatim_path(r"C:\work\crate\src/./snapshots\..\snapshots\a.snap").as_deref(),
+        Some(r"\\?\C:\work\crate\src\snapshots\a.snap")
+    );
+    assert_eq!(
+        verbatim_path(r"\\server\share\crate\a.snap").as_deref(),
+        Some(r"\\?\UNC\server\share\crate\a.snap")
+    );
+    assert_eq!(verbatim_path(r"\\?\C:\a.snap"), None);
+    assert_eq!(verbatim_path(r"src\a.snap"), None);
+}
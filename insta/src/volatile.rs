@@ -0,0 +1,161 @@
+use std::path::Path;
+
+use crate::content::{yaml, Content};
+
+/// Finds redactions for the differences between two YAML or JSON snapshots.
+///
+/// Returns the selectors and their replacements if both snapshots have the
+/// same structure and only differ in values that look like timestamps or
+/// UUIDs.  Sequence indexes are written as `[]` so that a selector covers
+/// all items of a sequence.
+pub(crate) fn suggest_redactions(old: &str, new: &str) -> Option<Vec<(String, &'static str)>> {
+    // JSON is a subset of YAML, so the YAML parser reads both formats
+    let old = yaml::parse_str(old, Path::new("")).ok()?;
+    let new = yaml::parse_str(new, Path::new("")).ok()?;
+    let mut rv = vec![];
+    if walk(&old, &new, &mut String::new(), &mut rv) && !rv.is_empty() {
+        Some(rv)
+    } else {
+        None
+    }
+}
+
+/// Compares `old` and `new` at `selector`, returning `false` as soon as a
+/// difference cannot be redacted.
+fn walk(
+    old: &Content,
+    new: &Content,
+    selector: &mut String,
+    rv: &mut Vec<(String, &'static str)>,
+) -> bool {
+    let len = selector.len();
+    match (old, new) {
+        (Content::Map(old), Content::Map(new)) => {
+            old.len() == new.len()
+                && old
+                    .iter()
+                    .zip(new)
+                    .all(|((old_key, old_value), (new_key, new_value))| {
+                        match old_key.as_str() {
+                            Some(key) if old_key == new_key && push_key(selector, key) => {}
+                            _ => return false,
+                        }
+                        let redactable = walk(old_value, new_value, selector, rv);
+                        selector.truncate(len);
+                        redactable
+                    })
+        }
+        (Content::Seq(old), Content::Seq(new)) => {
+            selector.push_str("[]");
+            let redactable = old.len() == new.len()
+                && old
+                    .iter()
+                    .zip(new)
+                    .all(|(old, new)| walk(old, new, selector, rv));
+            selector.truncate(len);
+            redactable
+        }
+        (old, new) if old == new => true,
+        (old, new) => match (
+            old.as_str().and_then(replacement_for),
+            new.as_str().and_then(replacement_for),
+        ) {
+            (Some(old), Some(new)) if old == new => {
+                let selector = if selector.is_empty() {
+                    ".".to_string()
+                } else {
+                    selector.clone()
+                };
+                if !rv.iter().any(|(x, _)| *x == selector) {
+                    rv.push((selector, new));
+                }
+                true
+            }
+            _ => false,
+        },
+    }
+}
+
+/// Appends the selector segment for a map key, returning `false` for keys
+/// that cannot be written in a selector.
+fn push_key(selector: &mut String, key: &str) -> bool {
+    let mut chars = key.chars();
+    if chars
+        .next()
+        .map_or(false, |c| c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+    {
+        selector.push('.');
+        selector.push_str(key);
+        true
+    } else if !key.contains(['"', '\\']) {
+        selector.push_str(&format!("[\"{key}\"]"));
+        true
+    } else {
+        false
+    }
+}
+
+/// Returns the redaction for values that usually change between runs.
+fn replacement_for(value: &str) -> Option<&'static str> {
+    if is_timestamp(value) {
+        Some("[timestamp]")
+    } else if is_uuid(value) {
+        Some("[uuid]")
+    } else {
+        None
+    }
+}
+
+fn is_timestamp(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes.len() >= 19
+        && bytes[..19]
+            .iter()
+            .zip(b"0000-00-00T00:00:00")
+            .all(|(&c, &p)| match p {
+                b'0' => c.is_ascii_digit(),
+                b'T' => c == b'T' || c == b' ',
+                _ => c == p,
+            })
+        && bytes[19..]
+            .iter()
+            .all(|c| c.is_ascii_digit() || b".:+-Z".contains(c))
+}
+
+fn is_uuid(value: &str) -> bool {
+    value.len() == 36
+        && value.bytes().enumerate().all(|(idx, c)| match idx {
+            8 | 13 | 18 | 23 => c == b'-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+#[test]
+fn test_suggest_redactions() {
+    let old = "id: 67e55044-10b1-426f-9247-bb680e5fe0c8\nname: a\nitems:\n  - created_at: \"2024-01-02T03:04:05Z\"\n  - created_at: \"2024-01-02T03:04:06Z\"\n\"the key\": 2024-01-02 03:04:05.123+01:00\n";
+    let new = "id: 1c6bd4b0-02ad-4f14-9f5e-5a3c1a7b6b55\nname: a\nitems:\n  - created_at: \"2025-06-07T08:09:10Z\"\n  - created_at: \"2025-06-07T08:09:11Z\"\n\"the key\": 2025-06-07 08:09:10.456+01:00\n";
+    assert_eq!(
+        suggest_redactions(old, new).unwrap(),
+        [
+            (".id".to_string(), "[uuid]"),
+            (".items[].created_at".to_string(), "[timestamp]"),
+            ("[\"the key\"]".to_string(), "[timestamp]"),
+        ]
+    );
+
+    let json_old = "{\n  \"at\": \"2024-01-02T03:04:05Z\"\n}";
+    let json_new = "{\n  \"at\": \"2024-01-02T03:04:06Z\"\n}";
+    assert_eq!(
+        suggest_redactions(json_old, json_new).unwrap(),
+        [(".at".to_string(), "[timestamp]")]
+    );
+
+    // other changes cannot be redacted away
+    assert_eq!(
+        suggest_redactions(old, &new.replace("name: a", "name: b")),
+        None
+    );
+    assert_eq!(suggest_redactions(old, old), None);
+    assert_eq!(suggest_redactions("- 1\n- 2\n", "- 1\n"), None);
+}
@@ -0,0 +1,63 @@
+use rustc_demangle::try_demangle;
+
+/// Disassembles the WebAssembly module `input` to the WAT text format.
+///
+/// Function names from the name section that are mangled Rust symbols are
+/// demangled without their hash, so that the snapshot reads like the source
+/// and does not change when only the hashes do.  Demangled names that are
+/// not valid WAT identifiers are quoted, as in `$"<T as Trait>::f"`.
+///
+/// Panics if `input` is not a valid WebAssembly module.
+pub fn render_wasm<B: AsRef<[u8]> + ?Sized>(input: &B) -> String {
+    let bytes = input.as_ref();
+    if let Err(err) = wasmparser::Validator::new().validate_all(bytes) {
+        panic!("failed to read snapshot value as WebAssembly: {err}");
+    }
+    let wat = wasmprinter::print_bytes(bytes)
+        .unwrap_or_else(|err| panic!("failed to disassemble WebAssembly: {err}"));
+    demangle_ids(&wat)
+}
+
+/// Returns whether `c` may appear in a WAT identifier.
+fn is_id_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-./:<=>?@\\^_`|~".contains(c)
+}
+
+/// Replaces the identifiers in `wat` that are mangled Rust symbols with
+/// their demangled names.
+fn demangle_ids(wat: &str) -> String {
+    let mut rv = String::with_capacity(wat.len());
+    let mut rest = wat;
+    while let Some(idx) = rest.find('$') {
+        rv.push_str(&rest[..idx + 1]);
+        rest = &rest[idx + 1..];
+        let end = rest.find(|c| !is_id_char(c)).unwrap_or(rest.len());
+        let id = &rest[..end];
+        match try_demangle(id) {
+            Ok(symbol) => {
+                let name = format!("{symbol:#}");
+                if name.chars().all(is_id_char) {
+                    rv.push_str(&name);
+                } else {
+                    rv.push_str(&format!("{name:?}"));
+                }
+            }
+            Err(_) => rv.push_str(id),
+        }
+        rest = &rest[end..];
+    }
+    rv.push_str(rest);
+    rv
+}
+
+#[test]
+fn test_demangle_ids() {
+    assert_eq!(
+        demangle_ids("(func $_ZN4core3fmt5write17h0123456789abcdefE (;0;)\n  call $main)"),
+        "(func $core::fmt::write (;0;)\n  call $main)"
+    );
+    assert_eq!(
+        demangle_ids("call $_ZN60_$LT$alloc..string..String$u20$as$u20$core..fmt..Display$GT$3fmt17h0123456789abcdefE"),
+        "call $\"<alloc::string::String as core::fmt::Display>::fmt\""
+    );
+}
@@ -0,0 +1,488 @@
+use std::fmt::Display;
+
+use roxmltree::{Document, Node, NodeType, ParsingOptions};
+
+/// The namespace of the `xml:` prefix, which is never declared.
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// A path selecting elements or attributes of an XML document.
+///
+/// The syntax is a small subset of [XPath](https://www.w3.org/TR/xpath/):
+/// steps are separated by `/` to select children and by `//` to select
+/// descendants at any depth, `*` matches any element and a last step of
+/// `@name` or `@*` selects attributes instead of elements.  Names without a
+/// prefix match elements in any namespace, names with a prefix match the
+/// prefix the element has in the snapshot.
+#[derive(Debug, Clone)]
+pub struct XmlSelector {
+    steps: Vec<Step>,
+    attribute: Option<NameTest>,
+}
+
+#[derive(Debug, Clone)]
+struct Step {
+    descendant: bool,
+    test: NameTest,
+}
+
+#[derive(Debug, Clone)]
+enum NameTest {
+    Any,
+    Name {
+        prefix: Option<String>,
+        local: String,
+    },
+}
+
+impl NameTest {
+    fn parse(name: &str) -> Option<NameTest> {
+        let valid = |x: &str| {
+            !x.is_empty()
+                && x.chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+        };
+        if name == "*" {
+            return Some(NameTest::Any);
+        }
+        let (prefix, local) = match name.split_once(':') {
+            Some((prefix, local)) => (Some(prefix), local),
+            None => (None, name),
+        };
+        if !valid(local) || !prefix.map_or(true, valid) {
+            return None;
+        }
+        Some(NameTest::Name {
+            prefix: prefix.map(str::to_string),
+            local: local.to_string(),
+        })
+    }
+
+    fn matches(&self, name: &QName) -> bool {
+        match self {
+            NameTest::Any => true,
+            NameTest::Name { prefix, local } => {
+                *local == name.local
+                    && prefix
+                        .as_ref()
+                        .map_or(true, |prefix| name.prefix.as_deref() == Some(prefix))
+            }
+        }
+    }
+}
+
+impl XmlSelector {
+    /// Parses a selector such as `/Envelope/Header//Token` or `//@id`.
+    ///
+    /// Panics if the selector is not valid.
+    pub fn parse(selector: &str) -> XmlSelector {
+        let fail = |reason: &str| -> ! { panic!("invalid XML selector {selector:?}: {reason}") };
+        if !selector.starts_with('/') {
+            fail("selectors must start with / or //");
+        }
+        let mut steps = vec![];
+        let mut attribute = None;
+        let mut rest = selector;
+        while !rest.is_empty() {
+            if attribute.is_some() {
+                fail("attributes can only be selected by the last step");
+            }
+            let descendant = rest.starts_with("//");
+            rest = rest.trim_start_matches('/');
+            let end = rest.find('/').unwrap_or(rest.len());
+            let name = &rest[..end];
+            rest = &rest[end..];
+            if let Some(name) = name.strip_prefix('@') {
+                if descendant {
+                    steps.push(Step {
+                        descendant,
+                        test: NameTest::Any,
+                    });
+                }
+                attribute =
+                    Some(NameTest::parse(name).unwrap_or_else(|| fail("invalid attribute name")));
+            } else {
+                let test = NameTest::parse(name).unwrap_or_else(|| fail("invalid element name"));
+                steps.push(Step { descendant, test });
+            }
+        }
+        XmlSelector { steps, attribute }
+    }
+
+    fn matches_path(&self, path: &[QName]) -> bool {
+        fn matches(steps: &[Step], path: &[QName]) -> bool {
+            match steps.split_first() {
+                None => path.is_empty(),
+                Some((step, rest)) if step.descendant => (0..path.len())
+                    .any(|idx| step.test.matches(&path[idx]) && matches(rest, &path[idx + 1..])),
+                Some((step, rest)) => {
+                    !path.is_empty() && step.test.matches(&path[0]) && matches(rest, &path[1..])
+                }
+            }
+        }
+        matches(&self.steps, path)
+    }
+}
+
+/// The name of an element or attribute with its normalized prefix.
+#[derive(Debug, Clone)]
+struct QName {
+    prefix: Option<String>,
+    local: String,
+}
+
+impl Display for QName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.prefix {
+            Some(ref prefix) => write!(f, "{prefix}:{}", self.local),
+            None => write!(f, "{}", self.local),
+        }
+    }
+}
+
+/// Parses `input` as XML and pretty prints it in a canonical form.
+///
+/// Namespace declarations are moved to the root element and every namespace
+/// gets a single prefix, the first one it is declared with.  Attributes are
+/// sorted by name, comments are removed, whitespace in text is collapsed and
+/// text that is only whitespace is dropped, except within elements with
+/// `xml:space="preserve"`, which are written on a single line as they are.
+/// Every element goes on its own line, indented by its depth, unless its
+/// only child is text.
+///
+/// The content of elements and the values of attributes matched by a
+/// selector in `redactions` are replaced with the redaction.
+///
+/// Panics if `input` is not well-formed XML.
+pub fn normalize_xml<V: Display + ?Sized>(
+    input: &V,
+    redactions: &[(XmlSelector, String)],
+) -> String {
+    let input = input.to_string();
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    let doc = match Document::parse_with_options(&input, options) {
+        Ok(doc) => doc,
+        Err(err) => panic!("failed to parse snapshot value as XML: {err}\n\n{input}"),
+    };
+    let mut writer = Writer {
+        out: String::new(),
+        namespaces: vec![],
+        redactions,
+    };
+    writer.assign_prefixes(&doc);
+    for child in doc.root().children() {
+        writer.write_node(child, &mut vec![], 0, false);
+    }
+    writer.out.pop();
+    writer.out
+}
+
+struct Writer<'a> {
+    out: String,
+    /// The namespace URIs with their prefixes in the order they were seen.
+    namespaces: Vec<(String, Option<String>)>,
+    redactions: &'a [(XmlSelector, String)],
+}
+
+impl Writer<'_> {
+    /// Gives every namespace in the document a prefix.
+    ///
+    /// Elements keep the default namespace they are in unless an element of
+    /// the document is in no namespace, since the default namespace is only
+    /// declared on the root element.  Prefixes bound to more than one
+    /// namespace get a number appended.
+    fn assign_prefixes(&mut self, doc: &Document) {
+        let allow_default = doc
+            .descendants()
+            .all(|node| !node.is_element() || element_namespace(node).is_some());
+        for node in doc.descendants().filter(Node::is_element) {
+            if let Some(uri) = element_namespace(node) {
+                self.assign_prefix(node, uri, allow_default);
+            }
+            for attr in node.attributes() {
+                if let Some(uri) = attr.namespace() {
+                    self.assign_prefix(node, uri, false);
+                }
+            }
+        }
+    }
+
+    fn assign_prefix(&mut self, node: Node, uri: &str, allow_default: bool) {
+        if uri == XML_NAMESPACE
+            || self
+                .namespaces
+                .iter()
+                .any(|(u, prefix)| u == uri && (allow_default || prefix.is_some()))
+        {
+            return;
+        }
+        let declared = node
+            .namespaces()
+            .filter(|ns| ns.uri() == uri)
+            .map(|ns| ns.name())
+            .collect::<Vec<_>>();
+        let prefix = if allow_default
+            && declared.contains(&None)
+            && !self.namespaces.iter().any(|(_, prefix)| prefix.is_none())
+        {
+            None
+        } else {
+            let base = declared.into_iter().flatten().next().unwrap_or("ns");
+            let taken = |prefix: &str| {
+                prefix == "xml"
+                    || self
+                        .namespaces
+                        .iter()
+                        .any(|(_, p)| p.as_deref() == Some(prefix))
+            };
+            let mut prefix = base.to_string();
+            let mut idx = 1;
+            while taken(&prefix) {
+                prefix = format!("{base}{idx}");
+                idx += 1;
+            }
+            Some(prefix)
+        };
+        self.namespaces.push((uri.to_string(), prefix));
+    }
+
+    fn qname(&self, uri: Option<&str>, local: &str, is_attribute: bool) -> QName {
+        let prefix = match uri {
+            None => None,
+            Some(XML_NAMESPACE) => Some("xml".to_string()),
+            Some(uri) => self
+                .namespaces
+                .iter()
+                .filter(|(u, _)| u == uri)
+                .map(|(_, prefix)| prefix.clone())
+                .find(|prefix| !is_attribute || prefix.is_some())
+                .flatten(),
+        };
+        QName {
+            prefix,
+            local: local.to_string(),
+        }
+    }
+
+    fn redaction(&self, path: &[QName], attribute: Option<&QName>) -> Option<&str> {
+        self.redactions
+            .iter()
+            .find(|(selector, _)| match (&selector.attribute, attribute) {
+                (None, None) => selector.matches_path(path),
+                (Some(test), Some(attribute)) => {
+                    test.matches(attribute) && selector.matches_path(path)
+                }
+                _ => false,
+            })
+            .map(|(_, redaction)| redaction.as_str())
+    }
+
+    fn write_node(&mut self, node: Node, path: &mut Vec<QName>, depth: usize, preserve: bool) {
+        let (indent, newline) = if preserve {
+            (String::new(), "")
+        } else {
+            ("  ".repeat(depth), "\n")
+        };
+        match node.node_type() {
+            NodeType::Root | NodeType::Comment => {}
+            NodeType::PI => {
+                let pi = node.pi().unwrap();
+                match pi.value {
+                    Some(value) => self
+                        .out
+                        .push_str(&format!("{indent}<?{} {value}?>{newline}", pi.target)),
+                    None => self
+                        .out
+                        .push_str(&format!("{indent}<?{}?>{newline}", pi.target)),
+                }
+            }
+            NodeType::Text => {
+                let text = node.text().unwrap_or("");
+                if preserve {
+                    self.out.push_str(&escape(text, false));
+                } else {
+                    let text = collapse_whitespace(text);
+                    if !text.is_empty() {
+                        self.out
+                            .push_str(&format!("{indent}{}{newline}", escape(&text, false)));
+                    }
+                }
+            }
+            NodeType::Element => {
+                let tag = self.qname(element_namespace(node), node.tag_name().name(), false);
+                path.push(tag.clone());
+                let preserve = match node.attribute((XML_NAMESPACE, "space")) {
+                    Some(space) => space == "preserve",
+                    None => preserve,
+                };
+
+                let mut open = format!("{indent}<{tag}");
+                if node.parent().map_or(false, |parent| parent.is_root()) {
+                    let mut namespaces = self.namespaces.clone();
+                    namespaces.sort_by(|a, b| a.1.cmp(&b.1));
+                    for (uri, prefix) in namespaces {
+                        let name = match prefix {
+                            Some(prefix) => format!("xmlns:{prefix}"),
+                            None => "xmlns".to_string(),
+                        };
+                        open.push_str(&format!(" {name}=\"{}\"", escape(&uri, true)));
+                    }
+                }
+                let mut attrs = node
+                    .attributes()
+                    .map(|attr| {
+                        let name = self.qname(attr.namespace(), attr.name(), true);
+                        let value = self
+                            .redaction(path, Some(&name))
+                            .unwrap_or_else(|| attr.value())
+                            .to_string();
+                        (name.to_string(), value)
+                    })
+                    .collect::<Vec<_>>();
+                attrs.sort();
+                for (name, value) in &attrs {
+                    open.push_str(&format!(" {name}=\"{}\"", escape(value, true)));
+                }
+
+                if let Some(redaction) = self.redaction(path, None) {
+                    let redaction = escape(redaction, false);
+                    self.out
+                        .push_str(&format!("{open}>{redaction}</{tag}>{newline}"));
+                    path.pop();
+                    return;
+                }
+
+                let children = node
+                    .children()
+                    .filter(|child| match child.node_type() {
+                        NodeType::Comment => false,
+                        NodeType::Text if !preserve => {
+                            !collapse_whitespace(child.text().unwrap_or("")).is_empty()
+                        }
+                        _ => true,
+                    })
+                    .collect::<Vec<_>>();
+                match children[..] {
+                    [] => self.out.push_str(&format!("{open}/>{newline}")),
+                    [child] if child.is_text() && !preserve => {
+                        let text = collapse_whitespace(child.text().unwrap_or(""));
+                        let text = escape(&text, false);
+                        self.out.push_str(&format!("{open}>{text}</{tag}>\n"));
+                    }
+                    _ if preserve => {
+                        self.out.push_str(&format!("{open}>"));
+                        for child in children {
+                            self.write_node(child, path, 0, true);
+                        }
+                        self.out.push_str(&format!("</{tag}>{newline}"));
+                    }
+                    _ => {
+                        self.out.push_str(&format!("{open}>\n"));
+                        for child in children {
+                            self.write_node(child, path, depth + 1, false);
+                        }
+                        self.out.push_str(&format!("{indent}</{tag}>\n"));
+                    }
+                }
+                path.pop();
+            }
+        }
+    }
+}
+
+/// Returns the namespace of an element, where `xmlns=""` means none.
+fn element_namespace<'a>(node: Node<'a, '_>) -> Option<&'a str> {
+    node.tag_name().namespace().filter(|uri| !uri.is_empty())
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn escape(text: &str, attribute: bool) -> String {
+    let mut rv = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => rv.push_str("&amp;"),
+            '<' => rv.push_str("&lt;"),
+            '>' if !attribute => rv.push_str("&gt;"),
+            '"' if attribute => rv.push_str("&quot;"),
+            c => rv.push(c),
+        }
+    }
+    rv
+}
+
+#[test]
+fn test_normalize_xml() {
+    let xml = r#"<?xml version="1.0"?>
+<!-- request -->
+<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope">
+    <soap:Body>
+        <GetUser xmlns="urn:users"  version="2" id="7">
+            <Name>  Jane
+              Doe </Name>
+            <Bio xml:space="preserve"><b>  a  </b> b </Bio>
+            <Empty></Empty>
+        </GetUser>
+    </soap:Body>
+</soap:Envelope>"#;
+    assert_eq!(
+        normalize_xml(xml, &[]),
+        r#"<soap:Envelope xmlns="urn:users" xmlns:soap="http://www.w3.org/2003/05/soap-envelope">
+  <soap:Body>
+    <GetUser id="7" version="2">
+      <Name>Jane Doe</Name>
+      <Bio xml:space="preserve"><b>  a  </b> b </Bio>
+      <Empty/>
+    </GetUser>
+  </soap:Body>
+</soap:Envelope>"#
+    );
+
+    // prefixes and declarations do not matter
+    assert_eq!(
+        normalize_xml(
+            r#"<a:x xmlns:a="urn:a"><b:y xmlns:b="urn:a" b:k="v"/></a:x>"#,
+            &[]
+        ),
+        normalize_xml(r#"<a:x xmlns:a="urn:a"><a:y a:k="v"/></a:x>"#, &[]),
+    );
+
+    // unqualified elements keep the default namespace undeclared
+    assert_eq!(
+        normalize_xml(r#"<x xmlns="urn:a"><y xmlns=""/></x>"#, &[]),
+        r#"<ns:x xmlns:ns="urn:a">
+  <y/>
+</ns:x>"#
+    );
+}
+
+#[test]
+fn test_xml_redactions() {
+    let xml = r#"<r><token id="1">abc</token><item id="2"><token>def</token></item></r>"#;
+    let redactions = [
+        (XmlSelector::parse("/r/token"), "[token]".to_string()),
+        (XmlSelector::parse("//@id"), "[id]".to_string()),
+    ];
+    assert_eq!(
+        normalize_xml(xml, &redactions),
+        r#"<r>
+  <token id="[id]">[token]</token>
+  <item id="[id]">
+    <token>def</token>
+  </item>
+</r>"#
+    );
+
+    let redactions = [(XmlSelector::parse("//item/*"), "[..]".to_string())];
+    assert!(normalize_xml(xml, &redactions).contains("<token>[..]</token>"));
+}
+
+#[test]
+#[should_panic(expected = "attributes can only be selected by the last step")]
+fn test_xml_selector_attribute_not_last() {
+    XmlSelector::parse("/a/@b/c");
+}
@@ -0,0 +1,6 @@
+---
+source: insta/tests/test_settings.rs
+expression: "\"SELECT id\\nFROM users\""
+---
+SELECT id
+FROM users
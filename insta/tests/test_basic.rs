@@ -2,7 +2,6 @@
 use insta::assert_json_snapshot;
 #[cfg(feature = "yaml")]
 use insta::assert_yaml_snapshot;
-#[allow(deprecated)]
 use insta::{assert_debug_snapshot, assert_display_snapshot, assert_snapshot};
 use std::fmt;
 
@@ -81,20 +80,29 @@ impl fmt::Display for TestDisplay {
 }
 
 #[test]
-#[allow(deprecated)]
 fn test_display() {
     let td = TestDisplay;
     assert_display_snapshot!("display", td);
 }
 
 #[test]
-#[allow(deprecated)]
 fn test_unnamed_display() {
     let td = TestDisplay;
     assert_display_snapshot!(td);
     assert_display_snapshot!("whatever");
 }
 
+#[test]
+fn test_display_width() {
+    let message = "error: could not find the configuration file in the current directory or any of its parents\n  help: create one with `init`";
+    assert_display_snapshot!(width = 40, message, @"
+    error: could not find the configuration
+    file in the current directory or any of
+    its parents
+      help: create one with `init`
+    ");
+}
+
 #[cfg(feature = "json")]
 #[test]
 fn test_u128_json() {
@@ -135,3 +143,650 @@ fn test_trailing_crlf_inline() {
     baz
     ");
 }
+
+#[cfg(feature = "syn")]
+#[test]
+fn test_parse_snapshot() {
+    insta::assert_parse_snapshot!(syn::Expr, "a + 1", @"
+    Expr::Binary {
+        attrs: [],
+        left: Expr::Path {
+            attrs: [],
+            qself: None,
+            path: Path {
+                leading_colon: None,
+                segments: [
+                    PathSegment {
+                        ident: Ident(
+                            a,
+                        ),
+                        arguments: PathArguments::None,
+                    },
+                ],
+            },
+        },
+        op: BinOp::Add(
+            Plus,
+        ),
+        right: Expr::Lit {
+            attrs: [],
+            lit: Lit::Int {
+                token: 1,
+            },
+        },
+    }
+    ");
+}
+
+#[cfg(feature = "syn")]
+#[test]
+#[should_panic(expected = "failed to parse snapshot value as")]
+fn test_parse_snapshot_invalid() {
+    insta::assert_parse_snapshot!(syn::ItemFn, "fn (", @"");
+}
+
+#[cfg(feature = "html")]
+#[test]
+fn test_html_snapshot() {
+    let html = r#"<ul class="nav"   id="menu"><li><a title="Home" href="/">Home</a></li>
+        <li><a href="/about">About   us</a></li></ul>"#;
+    insta::assert_html_snapshot!(html, @r#"
+    <ul class="nav" id="menu">
+      <li>
+        <a href="/" title="Home">Home</a>
+      </li>
+      <li>
+        <a href="/about">About us</a>
+      </li>
+    </ul>
+    "#);
+}
+
+#[cfg(feature = "clap")]
+#[test]
+fn test_clap_help() {
+    let cli = clap::Command::new("app")
+        .about("Manages things")
+        .arg(
+            clap::Arg::new("verbose")
+                .long("verbose")
+                .short('v')
+                .help("Prints more"),
+        )
+        .subcommand(
+            clap::Command::new("remote")
+                .about("Manages remotes")
+                .subcommand(
+                    clap::Command::new("add")
+                        .about("Adds a remote")
+                        .arg(clap::Arg::new("url").required(true)),
+                ),
+        )
+        .subcommand(clap::Command::new("debug").hide(true));
+    insta::assert_clap_help_snapshot!(cli);
+}
+
+#[cfg(feature = "xml")]
+#[test]
+fn test_xml_snapshot() {
+    let xml = r#"<env:Envelope xmlns:env="http://www.w3.org/2003/05/soap-envelope">
+  <env:Header><Token xmlns="urn:auth">secret</Token></env:Header>
+  <env:Body>
+    <!-- generated -->
+    <Reply xmlns="urn:users" id="42" version="2"><Name>Jane</Name></Reply>
+  </env:Body>
+</env:Envelope>"#;
+    insta::assert_xml_snapshot!(xml, {
+        "//Token" => "[token]",
+        "/Envelope/Body/Reply/@id" => "[id]",
+    }, @r#"
+    <env:Envelope xmlns="urn:auth" xmlns:env="http://www.w3.org/2003/05/soap-envelope" xmlns:ns="urn:users">
+      <env:Header>
+        <Token>[token]</Token>
+      </env:Header>
+      <env:Body>
+        <ns:Reply id="[id]" version="2">
+          <ns:Name>Jane</ns:Name>
+        </ns:Reply>
+      </env:Body>
+    </env:Envelope>
+    "#);
+}
+
+#[cfg(feature = "cargo")]
+#[test]
+fn test_cargo_manifest_snapshot() {
+    let manifest = r#"
+[dev-dependencies]
+insta = "1"
+
+[package]
+edition = "2021"
+name = "generated"   # from the template
+version = "0.1.0"
+"#;
+    insta::assert_cargo_manifest_snapshot!(manifest, @r#"
+    [package]
+    edition = "2021"
+    name = "generated"
+    version = "0.1.0"
+
+    [dev-dependencies]
+    insta = "1"
+    "#);
+}
+
+#[cfg(feature = "sql")]
+#[test]
+fn test_sql_snapshot() {
+    let query = "select u.id, u.name from users u join orgs o on o.id = u.org_id where o.slug = $4 and u.active = $5 order by u.name";
+    insta::assert_sql_snapshot!(normalize_params = true, query, @r"
+    SELECT
+      u.id,
+      u.name
+    FROM
+      users u
+      JOIN orgs o ON o.id = u.org_id
+    WHERE
+      o.slug = $1
+      AND u.active = $2
+    ORDER BY
+      u.name
+    ");
+}
+
+#[test]
+fn test_ansi_snapshot() {
+    let output = "\x1b[1m\x1b[31merror\x1b[0m: could not find `\x1b[36mCargo.toml\x1b[0m`";
+    insta::assert_ansi_snapshot!(output, @"error: could not find `Cargo.toml`");
+    insta::with_settings!({ansi_mode => insta::AnsiMode::Markup}, {
+        insta::assert_ansi_snapshot!(output, @"<bold><red>error</red></bold>: could not find `<cyan>Cargo.toml</cyan>`");
+    });
+}
+
+#[test]
+fn test_variants_snapshot() {
+    #[derive(Debug, Clone, Copy)]
+    enum Level {
+        Info,
+        Warning,
+        Error,
+    }
+
+    impl fmt::Display for Level {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Level::Info => write!(f, "info"),
+                Level::Warning => write!(f, "warning"),
+                Level::Error => write!(f, "error\n(aborts the build)"),
+            }
+        }
+    }
+
+    let levels = [Level::Info, Level::Warning, Level::Error];
+    insta::assert_variants_snapshot!(render = |level| level.to_string(), levels, @"
+    Info    | info
+    Warning | warning
+    Error   | error
+            | (aborts the build)
+    ");
+}
+
+#[cfg(feature = "protobuf")]
+#[test]
+fn test_proto_snapshot() {
+    use protobuf::descriptor::field_descriptor_proto::{Label, Type};
+    use protobuf::descriptor::{DescriptorProto, FieldDescriptorProto, FileDescriptorProto};
+
+    let mut file = FileDescriptorProto::new();
+    file.set_name("users.proto".into());
+    file.set_package("example".into());
+    let mut message = DescriptorProto::new();
+    message.set_name("User".into());
+    for (number, name, ty) in [(2, "name", Type::TYPE_STRING), (1, "id", Type::TYPE_UINT64)] {
+        let mut field = FieldDescriptorProto::new();
+        field.set_name(name.into());
+        field.set_number(number);
+        field.set_label(Label::LABEL_OPTIONAL);
+        field.set_type(ty);
+        field.set_json_name(format!("{name}Json"));
+        message.field.push(field);
+    }
+    file.message_type.push(message);
+
+    insta::assert_proto_snapshot!(file, { "message_type.field.json_name" => "[json]" }, @r#"
+    name: "users.proto"
+    package: "example"
+    message_type {
+      name: "User"
+      field {
+        name: "name"
+        number: 2
+        label: LABEL_OPTIONAL
+        type: TYPE_STRING
+        json_name: "[json]"
+      }
+      field {
+        name: "id"
+        number: 1
+        label: LABEL_OPTIONAL
+        type: TYPE_UINT64
+        json_name: "[json]"
+      }
+    }
+    "#);
+}
+
+#[test]
+fn test_error_snapshot() {
+    use std::{error::Error, fmt, io};
+
+    #[derive(Debug)]
+    struct ConfigError(io::Error);
+
+    impl fmt::Display for ConfigError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "failed to load config")
+        }
+    }
+
+    impl Error for ConfigError {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    let err = ConfigError(io::Error::new(io::ErrorKind::Other, "timed out"));
+    insta::assert_error_snapshot!(err, @r"
+    failed to load config
+    caused by: timed out
+    ");
+
+    let boxed: Box<dyn Error> = Box::new(err);
+    insta::assert_error_snapshot!(
+        boxed.as_ref(),
+        backtrace = "disabled backtrace",
+        @r"
+    failed to load config
+    caused by: timed out
+
+    stack backtrace: disabled backtrace
+    "
+    );
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn test_cbor_snapshot() {
+    #[derive(serde::Serialize)]
+    struct Event {
+        id: u32,
+        tags: Vec<&'static str>,
+        payload: Option<f64>,
+    }
+
+    insta::assert_cbor_snapshot!(b"\xc1\x1a\x51\x4b\x67\xb0", @"1(1363896240)");
+    insta::assert_cbor_snapshot!(value = Event { id: 7, tags: vec!["new"], payload: None }, @r#"
+    {
+      "id": 7,
+      "tags": [
+        "new"
+      ],
+      "payload": null
+    }
+    "#);
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn test_msgpack_snapshot() {
+    #[derive(serde::Serialize)]
+    struct Event {
+        id: u32,
+        ratio: f32,
+    }
+
+    insta::assert_msgpack_snapshot!(vec![0x92, 0xc4, 0x02, 0xca, 0xfe, 0xc2], @"
+    [
+      h'cafe',
+      false
+    ]
+    ");
+    insta::assert_msgpack_snapshot!(value = Event { id: 7, ratio: 0.5 }, @r#"
+    {
+      "id": 7,
+      "ratio": 0.5
+    }
+    "#);
+}
+
+#[cfg(feature = "archive")]
+#[test]
+fn test_archive_snapshot() {
+    // writes a ustar header followed by the padded contents
+    fn tar_entry(path: &str, mode: &str, contents: &[u8]) -> Vec<u8> {
+        let mut header = [0u8; 512];
+        header[..path.len()].copy_from_slice(path.as_bytes());
+        header[100..108].copy_from_slice(format!("{mode}\0").as_bytes());
+        header[124..136].copy_from_slice(format!("{:011o}\0", contents.len()).as_bytes());
+        header[136..148].copy_from_slice(b"14723572000\0");
+        header[148..156].copy_from_slice(b"        ");
+        header[156] = b'0';
+        header[257..265].copy_from_slice(b"ustar\x0000");
+        let checksum = header.iter().map(|&x| u32::from(x)).sum::<u32>();
+        header[148..156].copy_from_slice(format!("{checksum:06o}\0 ").as_bytes());
+        let mut rv = header.to_vec();
+        rv.extend_from_slice(contents);
+        rv.resize(512 + (contents.len() + 511) / 512 * 512, 0);
+        rv
+    }
+
+    let mut archive = tar_entry("pkg/README", "0000644", b"Hello World\n");
+    archive.extend(tar_entry("pkg/logo.png", "0000600", b"\x89PNG"));
+    archive.extend([0; 1024]);
+    insta::assert_archive_snapshot!(archive, @"
+    pkg/README (12 bytes, mode 644)
+      Hello World
+    pkg/logo.png (4 bytes, mode 600, sha256 0f4636c78f65d3639ece5a064b5ae753e3408614a14fb18ab4d7540d2c248543)
+    ");
+}
+
+#[cfg(feature = "graphql")]
+#[test]
+fn test_graphql_snapshot() {
+    insta::assert_graphql_snapshot!(
+        "query Users { users(first: 10, after: null) { name id } }",
+        @"
+    query Users {
+      users(after: null, first: 10) {
+        id
+        name
+      }
+    }
+    "
+    );
+    insta::assert_graphql_snapshot!(
+        r#"{"data": {"users": [{"name": "Ada", "id": 2}, {"name": "Bob", "id": 1}]}}"#,
+        @r#"
+    {
+      "data": {
+        "users": [
+          {
+            "id": 2,
+            "name": "Ada"
+          },
+          {
+            "id": 1,
+            "name": "Bob"
+          }
+        ]
+      }
+    }
+    "#
+    );
+}
+
+#[cfg(feature = "wasm")]
+#[test]
+fn test_wasm_snapshot() {
+    fn section(id: u8, contents: &[u8]) -> Vec<u8> {
+        let mut rv = vec![id, contents.len() as u8];
+        rv.extend_from_slice(contents);
+        rv
+    }
+
+    let symbol = b"_ZN5mylib3add17h0123456789abcdefE";
+    let mut names = vec![1, 0, symbol.len() as u8];
+    names.extend_from_slice(symbol);
+    let mut name_section = b"\x04name".to_vec();
+    name_section.extend(section(1, &names));
+
+    let mut module = b"\0asm\x01\0\0\0".to_vec();
+    module.extend(section(1, &[1, 0x60, 2, 0x7f, 0x7f, 1, 0x7f]));
+    module.extend(section(3, &[1, 0]));
+    module.extend(section(7, &[1, 3, b'a', b'd', b'd', 0, 0]));
+    module.extend(section(10, &[1, 7, 0, 0x20, 0, 0x20, 1, 0x6a, 0x0b]));
+    module.extend(section(0, &name_section));
+    insta::assert_wasm_snapshot!(module, @r#"
+    (module
+      (type (;0;) (func (param i32 i32) (result i32)))
+      (func $mylib::add (;0;) (type 0) (param i32 i32) (result i32)
+        local.get 0
+        local.get 1
+        i32.add
+      )
+      (export "add" (func $mylib::add))
+    )
+    "#);
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn test_http_snapshot() {
+    let response = http::Response::builder()
+        .status(200)
+        .header("content-type", "application/json")
+        .header("date", "Tue, 15 Nov 1994 08:12:31 GMT")
+        .header("x-request-id", "8d2e0c51")
+        .body(r#"{"id":1,"name":"Ada"}"#)
+        .unwrap();
+    insta::assert_http_snapshot!(response, { "x-request-id" => "[id]" }, @r#"
+    HTTP/1.1 200 OK
+    content-type: application/json
+    date: [date]
+    x-request-id: [id]
+
+    {
+      "id": 1,
+      "name": "Ada"
+    }
+    "#);
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_capture_tracing() {
+    let events = insta::capture_tracing(tracing::Level::DEBUG, || {
+        tracing::info!(target: "app", "starting");
+        let span = tracing::info_span!("request", id = 7, user = tracing::field::Empty);
+        let _guard = span.enter();
+        span.record("user", "ada");
+        tracing::debug!(target: "app::db", rows = 3, "query done");
+        tracing::trace!("not recorded");
+        tracing::warn!(target: "app", elapsed = ?std::time::Duration::from_millis(5), "slow");
+    });
+    insta::assert_snapshot!(events, @r#"
+    INFO app: starting
+    DEBUG request{id=7 user="ada"}: app::db: query done rows=3
+    WARN request{id=7 user="ada"}: app: slow elapsed=5ms
+    "#);
+}
+
+#[test]
+fn test_diff() {
+    let diff = insta::diff("a\nb\nc\n", "a\nx\nc\n", None);
+    assert!(diff.has_changes());
+    assert!(!diff.is_truncated());
+    let rows = diff
+        .to_string()
+        .lines()
+        .filter(|line| line.contains('│'))
+        .collect::<Vec<_>>()
+        .join("\n");
+    assert_snapshot!(rows, @r"
+        1     1 │ a
+        2       │-b
+              2 │+x
+        3     3 │ c
+    ");
+
+    assert!(!insta::diff("a\n", "a\n", None).has_changes());
+
+    let diff = insta::diff("a\nb\nc\n", "x\ny\nz\n", Some(2));
+    assert!(diff.is_truncated());
+    assert!(diff.to_string().contains("diff truncated after 2 bytes"));
+}
+
+#[test]
+fn test_long_expression() {
+    // every token of the value used to cost a step of macro recursion
+    insta::assert_debug_snapshot!(
+        1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1,
+        @"70"
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_json_schema() {
+    use std::collections::BTreeMap;
+
+    #[derive(serde::Serialize)]
+    struct User {
+        id: u32,
+        name: &'static str,
+    }
+
+    assert_json_snapshot!(User { id: 1, name: "Ada" }, schema = "schemas/user.json");
+
+    let err = std::panic::catch_unwind(|| {
+        assert_json_snapshot!(User { id: 0, name: "" }, schema = "schemas/user.json", @"");
+    })
+    .unwrap_err();
+    let message = err.downcast_ref::<String>().unwrap();
+    assert!(message.starts_with("value does not match JSON schema user@"));
+    assert!(message.contains("  .id: "));
+    assert!(message.contains("  .name: "));
+
+    let schema = BTreeMap::from([("type", "array")]);
+    assert_json_snapshot!(vec![1, 2], schema = schema, @r"
+    [
+      1,
+      2
+    ]
+    ");
+}
+
+#[cfg(feature = "schemars")]
+#[test]
+fn test_schema_snapshot() {
+    #[allow(dead_code)]
+    #[derive(schemars::JsonSchema)]
+    struct User {
+        id: u32,
+        name: String,
+        email: Option<String>,
+    }
+
+    insta::assert_schema_snapshot!(User, @r#"
+    {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "title": "User",
+      "type": "object",
+      "required": [
+        "id",
+        "name"
+      ],
+      "properties": {
+        "email": {
+          "type": [
+            "string",
+            "null"
+          ]
+        },
+        "id": {
+          "type": "integer",
+          "format": "uint32",
+          "minimum": 0.0
+        },
+        "name": {
+          "type": "string"
+        }
+      }
+    }
+    "#);
+}
+
+#[cfg(feature = "schemars")]
+#[test]
+fn test_schema_snapshot_extension() {
+    // the type is not an expression, so only a leading `extension` is parsed
+    insta::assert_schema_snapshot!(extension = "json", Option<u8>, @r#"
+    {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "title": "Nullable_uint8",
+      "type": [
+        "integer",
+        "null"
+      ],
+      "format": "uint8",
+      "minimum": 0.0
+    }
+    "#);
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_roundtrip_snapshot() {
+    use insta::assert_roundtrip_snapshot;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Role {
+        Admin,
+        Guest { until: Option<u64> },
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct User {
+        id: u32,
+        name: String,
+        roles: Vec<Role>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        email: Option<String>,
+    }
+
+    let user = User {
+        id: 42,
+        name: "Ada".into(),
+        roles: vec![Role::Admin, Role::Guest { until: Some(7) }],
+        email: None,
+    };
+    assert_roundtrip_snapshot!(user, @r"
+    id: 42
+    name: Ada
+    roles:
+      - Admin
+      - Guest:
+          until: 7
+    ");
+    assert_roundtrip_snapshot!(user, { ".id" => "[id]" }, @r#"
+    id: "[id]"
+    name: Ada
+    roles:
+      - Admin
+      - Guest:
+          until: 7
+    "#);
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Session {
+        user: u32,
+        #[serde(skip)]
+        token: String,
+    }
+
+    let err = std::panic::catch_unwind(|| {
+        let session = Session {
+            user: 1,
+            token: "secret".into(),
+        };
+        assert_roundtrip_snapshot!(session, @"user: 1");
+    })
+    .unwrap_err();
+    let message = err.downcast_ref::<String>().unwrap();
+    assert!(
+        message.starts_with("the value is not equal to itself after a round trip:"),
+        "{message}"
+    );
+}
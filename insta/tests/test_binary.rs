@@ -30,3 +30,9 @@ fn test_multipart_extension() {
 fn test_named() {
     insta::assert_binary_snapshot!("name.json", b"null".to_vec());
 }
+
+#[test]
+fn test_slice() {
+    let payload = b"\x89PNG\r\n".to_vec();
+    insta::assert_binary_snapshot!(".png", &payload[..]);
+}
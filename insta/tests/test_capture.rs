@@ -0,0 +1,25 @@
+#![cfg(unix)]
+use std::io::Write;
+
+use insta::{assert_output_snapshot, capture_output};
+
+#[test]
+fn test_capture_output() {
+    let output = capture_output(|| {
+        writeln!(std::io::stdout(), "to stdout").unwrap();
+        writeln!(std::io::stderr(), "to stderr").unwrap();
+    });
+    assert_eq!(output.stdout, "to stdout\n");
+    assert_eq!(output.stderr, "to stderr\n");
+
+    assert_output_snapshot!(|| {
+        writeln!(std::io::stdout(), "first line\nsecond line").unwrap();
+        writeln!(std::io::stderr(), "warning: something happened").unwrap();
+    }, @r###"
+    ----- stdout -----
+    first line
+    second line
+    ----- stderr -----
+    warning: something happened
+    "###);
+}
@@ -118,6 +118,60 @@ fn test_csv_inline_multiple_values() {
     "###);
 }
 
+#[cfg(feature = "csv")]
+#[test]
+fn test_csv_text_inline() {
+    let export = "id,username,last_login\n\
+                  1453,mehmed-doe,2024-03-01T10:00:00Z\n\
+                  1455,\"doe, mehmed\",2024-03-02T11:30:00Z\n";
+    assert_csv_snapshot!(text = export, { "last_login" => "[date]" }, @"
+    id   | username    | last_login
+    1453 | mehmed-doe  | [date]
+    1455 | doe, mehmed | [date]
+    ");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_table_inline() {
+    #[derive(serde::Serialize)]
+    enum Role {
+        Admin,
+        Guest,
+    }
+
+    #[derive(serde::Serialize)]
+    struct User {
+        id: u32,
+        name: &'static str,
+        role: Role,
+        email: Option<&'static str>,
+        tags: Vec<&'static str>,
+    }
+
+    let users = vec![
+        User {
+            id: 1,
+            name: "Ada Lovelace",
+            role: Role::Admin,
+            email: Some("ada@example.com"),
+            tags: vec!["math"],
+        },
+        User {
+            id: 1455,
+            name: "Guest",
+            role: Role::Guest,
+            email: None,
+            tags: vec![],
+        },
+    ];
+    insta::assert_table_snapshot!(users, @r#"
+    id   | name         | role  | email           | tags
+    1    | Ada Lovelace | Admin | ada@example.com | ["math"]
+    1455 | Guest        | Guest |                 | []
+    "#);
+}
+
 #[cfg(feature = "ron")]
 #[test]
 fn test_ron_inline() {
@@ -317,3 +371,54 @@ fn test_indentation() {
     "
     );
 }
+
+#[test]
+fn test_float_tolerance_inline() {
+    insta::assert_float_snapshot!(0.1 + 0.2, tolerance = 1e-9, @"0.3");
+    insta::assert_float_snapshot!([1.0 / 3.0, 2e10 + 1e-6], tolerance = 1e-9, @"
+    [
+        0.333333333,
+        20000000000.0,
+    ]
+    ");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_float_tolerance_settings() {
+    insta::with_settings!({float_tolerance => 1e-9}, {
+        assert_compact_json_snapshot!(vec![0.1 + 0.2], @"[0.3]");
+    });
+}
+
+#[cfg(feature = "filters")]
+#[test]
+fn test_filter_presets() {
+    let event = "user 67e55044-10b1-426f-9247-bb680e5fe0c8 at 2024-05-01T10:00:00Z";
+    assert_snapshot!(filters = [uuid, timestamp], event, @"user [uuid] at [timestamp]");
+    assert_snapshot!(
+        filters = [timestamp],
+        "created 2024-05-01 10:00:00",
+        @"created [timestamp]"
+    );
+    // the filters only apply to their assertion
+    assert_snapshot!(event, @"user 67e55044-10b1-426f-9247-bb680e5fe0c8 at 2024-05-01T10:00:00Z");
+}
+
+#[cfg(feature = "filters")]
+#[test]
+fn test_inline_metadata_comment() {
+    let event = "user 67e55044-10b1-426f-9247-bb680e5fe0c8 at 2024-05-01T10:00:00Z";
+    assert_snapshot!(event, @"user [uuid] at [timestamp]" /* insta: filters=uuid,timestamp */);
+    assert_snapshot!(
+        "created 2024-05-01 10:00:00",
+        @"created [timestamp]" /* insta: filters=timestamp */
+    );
+
+    // with code before the assertion on the same line
+    #[rustfmt::skip]
+    fn same_line(event: &str) {
+        let _ = event.len(); assert_snapshot!(event, @"user [uuid] at 2024-05-01T10:00:00Z" /* insta: filters=uuid */);
+    }
+    same_line(event);
+}
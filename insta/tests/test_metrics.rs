@@ -0,0 +1,54 @@
+use insta::assert_metrics_snapshot;
+
+const METRICS: &str = r#"
+# HELP http_requests_total Requests handled.
+# TYPE http_requests_total counter
+http_requests_total{status="500",method="POST"} 2
+http_requests_total{method="GET",status="200"} 1027 1700000000000
+# HELP http_request_duration_seconds Time spent on requests.
+# TYPE http_request_duration_seconds histogram
+http_request_duration_seconds_bucket{le="+Inf"} 1029
+http_request_duration_seconds_bucket{le="0.1"} 1001
+http_request_duration_seconds_sum 31.25
+http_request_duration_seconds_count 1029
+"#;
+
+#[test]
+fn test_metrics() {
+    assert_metrics_snapshot!(METRICS, @r#"
+    # HELP http_request_duration_seconds Time spent on requests.
+    # TYPE http_request_duration_seconds histogram
+    http_request_duration_seconds_bucket{le="0.1"} 1001
+    http_request_duration_seconds_bucket{le="+Inf"} 1029
+    http_request_duration_seconds_count 1029
+    http_request_duration_seconds_sum 31.25
+    # HELP http_requests_total Requests handled.
+    # TYPE http_requests_total counter
+    http_requests_total{method="GET",status="200"} 1027
+    http_requests_total{method="POST",status="500"} 2
+    "#);
+}
+
+#[test]
+fn test_metrics_redacted() {
+    assert_metrics_snapshot!(METRICS, {
+        "http_request_duration_seconds" => "[duration]",
+        "http_requests_total" => "[count]",
+    }, @r#"
+    # HELP http_request_duration_seconds Time spent on requests.
+    # TYPE http_request_duration_seconds histogram
+    http_request_duration_seconds_bucket{le="0.1"} [duration]
+    http_request_duration_seconds_bucket{le="+Inf"} [duration]
+    http_request_duration_seconds_count [duration]
+    http_request_duration_seconds_sum [duration]
+    # HELP http_requests_total Requests handled.
+    # TYPE http_requests_total counter
+    http_requests_total{method="GET",status="200"} [count]
+    http_requests_total{method="POST",status="500"} [count]
+    "#);
+}
+
+#[test]
+fn test_metrics_named() {
+    assert_metrics_snapshot!("metrics_named", METRICS, { "*_seconds" => "[duration]" });
+}
@@ -522,6 +522,88 @@ fn test_ordering_newtype_set() {
     );
 }
 
+#[cfg(feature = "json")]
+#[test]
+fn test_ordering_by_key() {
+    #[derive(Debug, Serialize)]
+    pub struct User {
+        id: u64,
+        username: &'static str,
+    }
+
+    let users: std::collections::HashMap<&str, User> = [
+        (
+            "z",
+            User {
+                id: 3,
+                username: "zoe",
+            },
+        ),
+        (
+            "a",
+            User {
+                id: 1,
+                username: "ada",
+            },
+        ),
+        (
+            "j",
+            User {
+                id: 2,
+                username: "jane",
+            },
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let users = users.into_values().collect::<Vec<_>>();
+
+    let mut settings = insta::Settings::new();
+    settings.sort_selector_by(".users", ".id");
+    settings.bind(|| {
+        assert_json_snapshot!(std::collections::BTreeMap::from([("users", &users)]), @r#"
+        {
+          "users": [
+            {
+              "id": 1,
+              "username": "ada"
+            },
+            {
+              "id": 2,
+              "username": "jane"
+            },
+            {
+              "id": 3,
+              "username": "zoe"
+            }
+          ]
+        }
+        "#);
+    });
+
+    assert_json_snapshot!(std::collections::BTreeMap::from([("users", &users)]), {
+        ".users" => insta::sorted_by_redaction(".username"),
+        ".users[].id" => "[id]",
+    }, @r#"
+    {
+      "users": [
+        {
+          "id": "[id]",
+          "username": "ada"
+        },
+        {
+          "id": "[id]",
+          "username": "jane"
+        },
+        {
+          "id": "[id]",
+          "username": "zoe"
+        }
+      ]
+    }
+    "#);
+}
+
 #[cfg(feature = "json")]
 #[test]
 fn test_rounded_redaction() {
@@ -650,3 +732,172 @@ fn test_metadata_raw_info_no_redaction() {
         assert_yaml_snapshot!("metadata_raw_info_no_redaction", &vec![1, 2, 3]);
     });
 }
+
+#[derive(Serialize)]
+pub struct Response {
+    data: ResponseData,
+}
+
+#[derive(Serialize)]
+pub struct ResponseData {
+    total: u32,
+    items: Vec<Item>,
+}
+
+#[derive(Serialize)]
+pub struct Item {
+    id: u32,
+    secret: String,
+}
+
+fn response() -> Response {
+    Response {
+        data: ResponseData {
+            total: 2,
+            items: vec![
+                Item {
+                    id: 1,
+                    secret: "a".into(),
+                },
+                Item {
+                    id: 2,
+                    secret: "b".into(),
+                },
+            ],
+        },
+    }
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_select() {
+    assert_json_snapshot!(response(), select = ".data.items[0]", @r#"
+    {
+      "id": 1,
+      "secret": "a"
+    }
+    "#);
+    assert_json_snapshot!(response(), select = ".data.items[].id", @r"
+    [
+      1,
+      2
+    ]
+    ");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_select_with_redactions() {
+    assert_json_snapshot!(response(), select = ".data.items", {
+        ".data.items[].secret" => "[secret]",
+    }, @r#"
+    [
+      {
+        "id": 1,
+        "secret": "[secret]"
+      },
+      {
+        "id": 2,
+        "secret": "[secret]"
+      }
+    ]
+    "#);
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_select_named() {
+    assert_yaml_snapshot!("select_named", response(), select = ".data.total");
+}
+
+#[cfg(feature = "json")]
+#[test]
+#[should_panic(expected = "did not match any value")]
+fn test_select_no_match() {
+    assert_json_snapshot!(response(), select = ".data.missing", @"");
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_matches_snapshot_of() {
+    #[derive(Serialize)]
+    struct Old {
+        id: u32,
+        name: &'static str,
+    }
+
+    #[derive(Serialize)]
+    struct New {
+        id: String,
+        name: String,
+    }
+
+    let old = Old { id: 1, name: "Ada" };
+    let new = New {
+        id: "a9f0".into(),
+        name: "Ada".into(),
+    };
+    insta::assert_matches_snapshot_of!(old, new, {
+        ".id" => "[id]",
+    });
+
+    let err = std::panic::catch_unwind(|| {
+        insta::assert_matches_snapshot_of!(vec![1, 2, 3], vec![1, 4, 3]);
+    })
+    .unwrap_err();
+    let message = err.downcast_ref::<String>().unwrap();
+    assert!(message
+        .starts_with("snapshot of `vec![1, 4, 3]` does not match snapshot of `vec![1, 2, 3]`:"));
+    assert!(insta::diff("- 1\n- 2\n- 3\n", "- 1\n- 4\n- 3\n", None)
+        .to_string()
+        .lines()
+        .all(|line| message.contains(line)));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_numbered_redaction() {
+    #[derive(Serialize)]
+    pub struct Node {
+        id: &'static str,
+        parent: Option<&'static str>,
+    }
+
+    let nodes = vec![
+        Node {
+            id: "7d0a",
+            parent: None,
+        },
+        Node {
+            id: "e31c",
+            parent: Some("7d0a"),
+        },
+        Node {
+            id: "42bf",
+            parent: Some("7d0a"),
+        },
+    ];
+
+    let mut settings = insta::Settings::new();
+    settings.add_redaction("[].parent", insta::numbered_redaction("id"));
+    settings.bind(|| {
+        assert_json_snapshot!(nodes, {
+            "[].id" => insta::numbered_redaction("id"),
+        }, @r#"
+        [
+          {
+            "id": "[id#1]",
+            "parent": null
+          },
+          {
+            "id": "[id#2]",
+            "parent": "[id#1]"
+          },
+          {
+            "id": "[id#3]",
+            "parent": "[id#1]"
+          }
+        ]
+        "#);
+    });
+}
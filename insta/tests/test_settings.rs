@@ -1,10 +1,10 @@
-#[cfg(feature = "json")]
-use insta::assert_json_snapshot;
 #[cfg(feature = "yaml")]
 use insta::assert_yaml_snapshot;
+#[cfg(feature = "json")]
+use insta::{assert_compact_json_snapshot, assert_json_snapshot};
 use similar_asserts::assert_eq;
 
-use insta::{assert_debug_snapshot, with_settings, Settings};
+use insta::{assert_debug_snapshot, assert_snapshot, with_settings, Settings};
 
 #[cfg(feature = "yaml")]
 #[test]
@@ -157,6 +157,87 @@ fn test_sort_maps_sorts_hashset() {
     });
 }
 
+#[cfg(feature = "json")]
+#[test]
+fn test_normalize_datetimes() {
+    with_settings!({normalize_datetimes => Some(3)}, {
+        assert_json_snapshot!(
+            vec!["2024-06-01T08:15:30.123456+02:00", "2024-06-01T06:15:30Z", "2024-06-01"],
+            @r###"
+        [
+          "2024-06-01T06:15:30.123Z",
+          "2024-06-01T06:15:30.000Z",
+          "2024-06-01"
+        ]
+        "###
+        );
+    });
+}
+
+#[cfg(all(feature = "json", feature = "redactions"))]
+#[test]
+fn test_normalize_decimals() {
+    #[derive(serde::Serialize)]
+    struct Release {
+        prices: Vec<&'static str>,
+        version: &'static str,
+    }
+
+    let mut settings = Settings::new();
+    settings.normalize_decimal_selector(".prices");
+    settings.bind(|| {
+        // the version looks like a decimal but is not selected
+        assert_json_snapshot!(
+            Release {
+                prices: vec!["10.500", "1.05E+1", "0.000", "0042"],
+                version: "1.10",
+            },
+            @r###"
+        {
+          "prices": [
+            "10.5",
+            "10.5",
+            "0",
+            "0042"
+          ],
+          "version": "1.10"
+        }
+        "###
+        );
+    });
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_serialization_overrides() {
+    use insta::internals::Content;
+    use std::time::{Duration, SystemTime};
+
+    #[derive(serde::Serialize)]
+    struct Job {
+        started: SystemTime,
+        took: Duration,
+    }
+
+    let mut settings = Settings::clone_current();
+    settings.add_serialization_override("SystemTime", |_| "[time]");
+    settings.add_serialization_override("Duration", |value| match value {
+        Content::Struct(_, fields) => Content::from(format!("{}s", fields[0].1.as_u64().unwrap())),
+        value => value,
+    });
+    settings.bind(|| {
+        assert_json_snapshot!(Job {
+            started: SystemTime::now(),
+            took: Duration::from_secs(3),
+        }, @r#"
+        {
+          "started": "[time]",
+          "took": "3s"
+        }
+        "#);
+    });
+}
+
 #[test]
 fn test_with_settings_inherit() {
     with_settings!({sort_maps => true}, {
@@ -167,3 +248,202 @@ fn test_with_settings_inherit() {
         });
     });
 }
+
+#[test]
+fn test_assertion_context() {
+    let request_id = 42;
+    assert_snapshot!("hello", context = {"request_id": request_id}, @"hello");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_assertion_context_serialized() {
+    assert_compact_json_snapshot!(
+        vec![1, 2],
+        context = {"fixture": "input.json", "attempt": 1,},
+        @"[1, 2]"
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_assertion_context_after_redactions() {
+    assert_compact_json_snapshot!(
+        vec![1, 2],
+        {"[0]" => "[first]"},
+        context = {"fixture": "input.json"},
+        @r#"["[first]", 2]"#
+    );
+}
+
+#[test]
+fn test_add_context() {
+    let mut settings = Settings::new();
+    settings.add_context("request_id", 42);
+    settings.add_context("fixture", "input.json");
+    assert_eq!(
+        settings.context(),
+        &[
+            ("request_id".to_string(), "42".to_string()),
+            ("fixture".to_string(), "input.json".to_string())
+        ]
+    );
+    settings.clear_context();
+    assert!(settings.context().is_empty());
+}
+
+#[test]
+fn test_snapshot_sections() {
+    let values = vec![3, 1, 2];
+    with_settings!({snapshot_section => "summary"}, {
+        assert_snapshot!("sections", format!("{} values", values.len()));
+    });
+    with_settings!({snapshot_section => "details"}, {
+        assert_debug_snapshot!("sections", values);
+    });
+}
+
+#[cfg(all(
+    feature = "toml",
+    feature = "filters",
+    feature = "redactions",
+    feature = "json"
+))]
+#[test]
+fn test_settings_toml_roundtrip() {
+    let mut settings = Settings::new();
+    settings.set_sort_maps(true);
+    settings.set_snapshot_suffix("shared");
+    settings.set_ansi_mode(insta::AnsiMode::Markup);
+    settings.add_filter(r"\d{4}-\d{2}-\d{2}", "[date]");
+    settings.add_redaction(".id", "[id]");
+    settings.add_redaction(r#"["full name"]"#, "[name]");
+    settings.sort_selector(".tags");
+
+    let toml = settings.to_toml();
+    assert_snapshot!(toml, @r#"
+    sort_maps = true
+    snapshot_path = "snapshots"
+    snapshot_suffix = "shared"
+    prepend_module_to_snapshot = true
+    omit_expression = false
+    ansi_mode = "markup"
+    allow_empty_glob = false
+
+    [[filters]]
+    regex = '\d{4}-\d{2}-\d{2}'
+    replacement = "[date]"
+
+    [[redactions]]
+    selector = ".id"
+    replacement = "[id]"
+
+    [[redactions]]
+    selector = '["full name"]'
+    replacement = "[name]"
+    "#);
+
+    let loaded = Settings::from_toml(&toml).unwrap();
+    assert_eq!(loaded.to_toml(), toml);
+
+    #[derive(serde::Serialize)]
+    struct User {
+        id: u32,
+        #[serde(rename = "full name")]
+        full_name: &'static str,
+        joined: &'static str,
+    }
+    let user = User {
+        id: 42,
+        full_name: "Jane Doe",
+        joined: "2024-01-02",
+    };
+    loaded.bind(|| {
+        assert_compact_json_snapshot!(user, @r#"{"id": "[id]", "full name": "[name]", "joined": "[date]"}"#);
+    });
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_settings_from_toml_errors() {
+    let err = |toml: &str| Settings::from_toml(toml).err().unwrap().to_string();
+    assert_eq!(
+        err("sort_maps = \"yes\""),
+        "invalid insta settings: `sort_maps` must be a boolean"
+    );
+    assert_eq!(
+        err("sort_map = true"),
+        "invalid insta settings: unknown setting `sort_map`"
+    );
+    assert_eq!(
+        err("normalize_datetimes = 12"),
+        "invalid insta settings: `normalize_datetimes` must be an integer from 0 to 9"
+    );
+}
+
+#[test]
+fn test_flaky() {
+    // the mismatch is reported as a warning instead of failing the test
+    insta::with_settings!({flaky => true}, {
+        insta::assert_snapshot!("changed", @"original");
+    });
+}
+
+#[test]
+fn test_xfail() {
+    // compared with `test_settings__xfail.xfail.snap` since the value does
+    // not match `test_settings__xfail.snap` yet
+    insta::with_settings!({xfail => true}, {
+        insta::assert_snapshot!("1 + 1 = 3");
+    });
+}
+
+mod helpers {
+    pub fn check_output(output: &str, owner: insta::SnapshotOwner) {
+        insta::with_settings!({snapshot_owner => owner}, {
+            insta::assert_snapshot!(output);
+        });
+    }
+}
+
+#[test]
+fn test_snapshot_owner() {
+    // stored as `test_settings__snapshot_owner.snap` rather than
+    // `test_settings__helpers__check_output.snap`
+    helpers::check_output("owned by the caller", insta::snapshot_owner!());
+}
+
+#[test]
+fn test_assertion_budget() {
+    insta::with_settings!({assertion_budget => Some(2)}, {
+        insta::assert_snapshot!("first", @"first");
+        insta::assert_snapshot!("second", @"second");
+    });
+}
+
+#[test]
+#[should_panic(expected = "made 3 snapshot assertions, more than its budget of 2")]
+fn test_assertion_budget_exceeded() {
+    insta::with_settings!({assertion_budget => Some(2)}, {
+        insta::assert_snapshot!("first", @"first");
+        insta::assert_snapshot!("second", @"second");
+        insta::assert_snapshot!("third", @"third");
+    });
+}
+
+#[test]
+fn test_snapshot_extension() {
+    // stored as `test_settings__snapshot_extension.snap.sql`
+    insta::assert_snapshot!(extension = "sql", "SELECT id\nFROM users");
+}
+
+#[test]
+fn test_snapshot_extension_long_value() {
+    // the value is not searched for more arguments token by token
+    insta::assert_snapshot!(
+        extension = "txt",
+        (1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 1).to_string(),
+        context = {"terms": 70},
+        @"70"
+    );
+}